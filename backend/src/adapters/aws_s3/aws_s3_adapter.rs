@@ -1,16 +1,21 @@
 use std::sync::Arc;
 
 use super::{GenericStorageError, KVStorageAdapter};
-use crate::model::{ConfigDetails, YakManApiKey};
+use crate::adapters::MAX_CONCURRENT_OBJECT_READS;
+use crate::model::{
+    ConfigDetails, YakManApiKey, YakManApiKeyUsage, YakManAuditLogEntry, YakManLoginAttempts,
+    YakManNotificationDelivery, YakManScheduledApply, YakManServiceAccount, YakManTotp,
+};
 use crate::model::{
     ConfigInstanceRevision, LabelType, YakManConfig, YakManPassword, YakManPasswordResetLink,
-    YakManProject, YakManProjectDetails, YakManSnapshotLock, YakManTeam, YakManTeamDetails,
-    YakManUser, YakManUserDetails,
+    YakManProject, YakManProjectDetails, YakManRevokedRefreshToken, YakManSnapshotLock, YakManTeam,
+    YakManTeamDetails, YakManUser, YakManUserDetails,
 };
 use async_trait::async_trait;
 use aws_config::BehaviorVersion;
 use aws_sdk_s3 as s3;
 use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt, TryStreamExt};
 use s3::primitives::ByteStream;
 use tokio::io::AsyncReadExt;
 
@@ -471,6 +476,373 @@ impl KVStorageAdapter for AwsS3StorageAdapter {
         return Ok(());
     }
 
+    async fn get_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError> {
+        let dir = self.get_notification_deliveries_dir();
+        let mut deliveries: Vec<YakManNotificationDelivery> = vec![];
+        for data in self
+            .get_objects_with_prefix(&format!("{dir}/{project_id}/"))
+            .await?
+        {
+            deliveries.push(serde_json::from_str(&data)?);
+        }
+        return Ok(deliveries);
+    }
+
+    async fn save_notification_delivery(
+        &self,
+        project_id: &str,
+        delivery: &YakManNotificationDelivery,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_notification_deliveries_dir();
+        let path = format!("{dir}/{project_id}/{}.json", delivery.id);
+        let data = serde_json::to_string(&delivery)?;
+        self.put_object(&path, data).await?;
+        Ok(())
+    }
+
+    async fn delete_notification_delivery(
+        &self,
+        project_id: &str,
+        delivery_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_notification_deliveries_dir();
+        let path: String = format!("{dir}/{project_id}/{delivery_id}.json");
+        self.delete_object(&path).await?;
+        return Ok(());
+    }
+
+    async fn delete_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_notification_deliveries_dir();
+        for key in self
+            .list_keys_with_prefix(&format!("{dir}/{project_id}/"))
+            .await?
+        {
+            self.delete_object(&key).await?;
+        }
+        return Ok(());
+    }
+
+    async fn get_pending_notification_deliveries(
+        &self,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError> {
+        let dir = self.get_pending_notification_deliveries_dir();
+        let mut deliveries: Vec<YakManNotificationDelivery> = vec![];
+        for data in self.get_objects_with_prefix(&format!("{dir}/")).await? {
+            deliveries.push(serde_json::from_str(&data)?);
+        }
+        return Ok(deliveries);
+    }
+
+    async fn save_pending_notification_delivery(
+        &self,
+        delivery: &YakManNotificationDelivery,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_pending_notification_deliveries_dir();
+        let path = format!("{dir}/{}.json", delivery.id);
+        let data = serde_json::to_string(&delivery)?;
+        self.put_object(&path, data).await?;
+        Ok(())
+    }
+
+    async fn delete_pending_notification_delivery(
+        &self,
+        delivery_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_pending_notification_deliveries_dir();
+        let path: String = format!("{dir}/{delivery_id}.json");
+        self.delete_object(&path).await?;
+        return Ok(());
+    }
+
+    async fn get_notification_delivery_lock(
+        &self,
+    ) -> Result<YakManSnapshotLock, GenericStorageError> {
+        let path = self.get_notification_delivery_lock_file_path();
+        if let Some(data) = self.get_object_as_option(&path).await? {
+            let lock: YakManSnapshotLock = serde_json::from_str(&data)?;
+            return Ok(lock);
+        }
+        return Ok(YakManSnapshotLock::unlocked());
+    }
+
+    async fn save_notification_delivery_lock(
+        &self,
+        lock: &YakManSnapshotLock,
+    ) -> Result<(), GenericStorageError> {
+        let data = serde_json::to_string(lock)?;
+        let data_file_path = self.get_notification_delivery_lock_file_path();
+        self.put_object(&data_file_path, data).await?;
+        Ok(())
+    }
+
+    async fn get_audit_log(
+        &self,
+        day: &str,
+    ) -> Result<Vec<YakManAuditLogEntry>, GenericStorageError> {
+        let dir = self.get_audit_log_dir();
+        let mut entries: Vec<YakManAuditLogEntry> = vec![];
+        for data in self
+            .get_objects_with_prefix(&format!("{dir}/{day}/"))
+            .await?
+        {
+            entries.push(serde_json::from_str(&data)?);
+        }
+        return Ok(entries);
+    }
+
+    async fn save_audit_log_entry(
+        &self,
+        day: &str,
+        entry: &YakManAuditLogEntry,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_audit_log_dir();
+        let path = format!("{dir}/{day}/{}.json", entry.id);
+        let data = serde_json::to_string(&entry)?;
+        self.put_object(&path, data).await?;
+        Ok(())
+    }
+
+    async fn get_service_accounts(&self) -> Result<Vec<YakManServiceAccount>, GenericStorageError> {
+        let path = self.get_service_accounts_file_path();
+        if let Some(data) = self.get_object_as_option(&path).await? {
+            let service_accounts: Vec<YakManServiceAccount> = serde_json::from_str(&data)?;
+            return Ok(service_accounts);
+        }
+        return Ok(vec![]);
+    }
+
+    async fn save_service_accounts(
+        &self,
+        service_accounts: &[YakManServiceAccount],
+    ) -> Result<(), GenericStorageError> {
+        let data = serde_json::to_string(service_accounts)?;
+        let data_file_path = self.get_service_accounts_file_path();
+        self.put_object(&data_file_path, data).await?;
+        Ok(())
+    }
+
+    async fn get_scheduled_applies(
+        &self,
+    ) -> Result<Vec<YakManScheduledApply>, GenericStorageError> {
+        let dir = self.get_scheduled_applies_dir();
+        let mut scheduled_applies: Vec<YakManScheduledApply> = vec![];
+        for data in self.get_objects_with_prefix(&format!("{dir}/")).await? {
+            scheduled_applies.push(serde_json::from_str(&data)?);
+        }
+        return Ok(scheduled_applies);
+    }
+
+    async fn get_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<Option<YakManScheduledApply>, GenericStorageError> {
+        let dir = self.get_scheduled_applies_dir();
+        let path = format!("{dir}/{config_id}/{instance}.json");
+        if let Some(data) = self.get_object_as_option(&path).await? {
+            let scheduled: YakManScheduledApply = serde_json::from_str(&data)?;
+            return Ok(Some(scheduled));
+        }
+        return Ok(None);
+    }
+
+    async fn save_scheduled_apply(
+        &self,
+        scheduled: &YakManScheduledApply,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_scheduled_applies_dir();
+        let path = format!("{dir}/{}/{}.json", scheduled.config_id, scheduled.instance);
+        let data = serde_json::to_string(&scheduled)?;
+        self.put_object(&path, data).await?;
+        Ok(())
+    }
+
+    async fn delete_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_scheduled_applies_dir();
+        let path = format!("{dir}/{config_id}/{instance}.json");
+        self.delete_object(&path).await?;
+        Ok(())
+    }
+
+    async fn get_scheduled_apply_lock(&self) -> Result<YakManSnapshotLock, GenericStorageError> {
+        let path = self.get_scheduled_apply_lock_file_path();
+        if let Some(data) = self.get_object_as_option(&path).await? {
+            let lock: YakManSnapshotLock = serde_json::from_str(&data)?;
+            return Ok(lock);
+        }
+        return Ok(YakManSnapshotLock::unlocked());
+    }
+
+    async fn save_scheduled_apply_lock(
+        &self,
+        lock: &YakManSnapshotLock,
+    ) -> Result<(), GenericStorageError> {
+        let data = serde_json::to_string(lock)?;
+        let data_file_path = self.get_scheduled_apply_lock_file_path();
+        self.put_object(&data_file_path, data).await?;
+        Ok(())
+    }
+
+    async fn delete_user_details(&self, user_id: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_user_dir();
+        let path: String = format!("{dir}/{user_id}.json");
+        self.delete_object(&path).await?;
+        return Ok(());
+    }
+
+    async fn delete_password(&self, email_hash: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_password_dir();
+        let path: String = format!("{dir}/{email_hash}.json");
+        self.delete_object(&path).await?;
+        return Ok(());
+    }
+
+    async fn get_login_attempts(
+        &self,
+        key: &str,
+    ) -> Result<Option<YakManLoginAttempts>, GenericStorageError> {
+        let dir = self.get_login_attempts_dir();
+        let path = format!("{dir}/{key}.json");
+        if let Some(data) = self.get_object_as_option(&path).await? {
+            let attempts: YakManLoginAttempts = serde_json::from_str(&data)?;
+            return Ok(Some(attempts));
+        }
+        return Ok(None);
+    }
+
+    async fn save_login_attempts(
+        &self,
+        key: &str,
+        attempts: &YakManLoginAttempts,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_login_attempts_dir();
+        let path = format!("{dir}/{key}.json");
+        let data = serde_json::to_string(&attempts)?;
+        self.put_object(&path, data).await?;
+        Ok(())
+    }
+
+    async fn delete_login_attempts(&self, key: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_login_attempts_dir();
+        let path = format!("{dir}/{key}.json");
+        self.delete_object(&path).await?;
+        Ok(())
+    }
+
+    async fn get_api_key_usage(
+        &self,
+        api_key_id: &str,
+    ) -> Result<Option<YakManApiKeyUsage>, GenericStorageError> {
+        let dir = self.get_api_key_usage_dir();
+        let path = format!("{dir}/{api_key_id}.json");
+        if let Some(data) = self.get_object_as_option(&path).await? {
+            let usage: YakManApiKeyUsage = serde_json::from_str(&data)?;
+            return Ok(Some(usage));
+        }
+        return Ok(None);
+    }
+
+    async fn save_api_key_usage(
+        &self,
+        api_key_id: &str,
+        usage: &YakManApiKeyUsage,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_api_key_usage_dir();
+        let path = format!("{dir}/{api_key_id}.json");
+        let data = serde_json::to_string(&usage)?;
+        self.put_object(&path, data).await?;
+        Ok(())
+    }
+
+    async fn delete_api_key_usage(&self, api_key_id: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_api_key_usage_dir();
+        let path = format!("{dir}/{api_key_id}.json");
+        self.delete_object(&path).await?;
+        Ok(())
+    }
+
+    async fn get_revoked_refresh_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<YakManRevokedRefreshToken>, GenericStorageError> {
+        let dir = self.get_revoked_refresh_tokens_dir();
+        let path = format!("{dir}/{token_hash}.json");
+        if let Some(data) = self.get_object_as_option(&path).await? {
+            let revoked_token: YakManRevokedRefreshToken = serde_json::from_str(&data)?;
+            return Ok(Some(revoked_token));
+        }
+        return Ok(None);
+    }
+
+    async fn save_revoked_refresh_token(
+        &self,
+        token_hash: &str,
+        revoked_token: &YakManRevokedRefreshToken,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_revoked_refresh_tokens_dir();
+        let path = format!("{dir}/{token_hash}.json");
+        let data = serde_json::to_string(&revoked_token)?;
+        self.put_object(&path, data).await?;
+        Ok(())
+    }
+
+    async fn delete_expired_revoked_refresh_tokens(
+        &self,
+        now: i64,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_revoked_refresh_tokens_dir();
+        let keys = self.list_keys_with_prefix(&format!("{dir}/")).await?;
+
+        return stream::iter(keys)
+            .map(|key| async move {
+                if let Some(data) = self.get_object_as_option(&key).await? {
+                    let revoked_token: YakManRevokedRefreshToken = serde_json::from_str(&data)?;
+                    if revoked_token.expires_at <= now {
+                        self.delete_object(&key).await?;
+                    }
+                }
+                return Ok::<(), GenericStorageError>(());
+            })
+            .buffer_unordered(MAX_CONCURRENT_OBJECT_READS)
+            .try_collect()
+            .await;
+    }
+
+    async fn get_totp(&self, user_id: &str) -> Result<Option<YakManTotp>, GenericStorageError> {
+        let dir = self.get_totp_dir();
+        let path = format!("{dir}/{user_id}.json");
+        if let Some(data) = self.get_object_as_option(&path).await? {
+            let totp: YakManTotp = serde_json::from_str(&data)?;
+            return Ok(Some(totp));
+        }
+        return Ok(None);
+    }
+
+    async fn save_totp(&self, user_id: &str, totp: &YakManTotp) -> Result<(), GenericStorageError> {
+        let dir = self.get_totp_dir();
+        let path = format!("{dir}/{user_id}.json");
+        let data = serde_json::to_string(&totp)?;
+        self.put_object(&path, data).await?;
+        Ok(())
+    }
+
+    async fn delete_totp(&self, user_id: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_totp_dir();
+        let path = format!("{dir}/{user_id}.json");
+        self.delete_object(&path).await?;
+        Ok(())
+    }
+
     async fn get_snapshot_lock(&self) -> Result<YakManSnapshotLock, GenericStorageError> {
         let path = self.get_snapshot_lock_file_path();
         let content = self
@@ -598,6 +970,26 @@ impl AwsS3StorageAdapter {
         return format!("{yakman_dir}/revisions");
     }
 
+    fn get_service_accounts_file_path(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/service-accounts.json");
+    }
+
+    fn get_scheduled_applies_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/scheduled-applies");
+    }
+
+    fn get_scheduled_apply_lock_file_path(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/scheduled-apply-lock.json");
+    }
+
+    fn get_notification_delivery_lock_file_path(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/notification-delivery-lock.json");
+    }
+
     fn get_api_key_file_path(&self) -> String {
         let yakman_dir = self.get_yakman_dir();
         return format!("{yakman_dir}/api-keys.json");
@@ -638,6 +1030,41 @@ impl AwsS3StorageAdapter {
         return format!("{yakman_dir}/passwords");
     }
 
+    fn get_notification_deliveries_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/notification-deliveries");
+    }
+
+    fn get_pending_notification_deliveries_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/pending-notification-deliveries");
+    }
+
+    fn get_audit_log_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/audit-log");
+    }
+
+    fn get_api_key_usage_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/api-key-usage");
+    }
+
+    fn get_revoked_refresh_tokens_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/revoked-refresh-tokens");
+    }
+
+    fn get_login_attempts_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/login-attempts");
+    }
+
+    fn get_totp_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/totp");
+    }
+
     fn get_password_reset_link_dir(&self) -> String {
         let yakman_dir = self.get_yakman_dir();
         return format!("{yakman_dir}/password-reset-links");
@@ -729,6 +1156,42 @@ impl AwsS3StorageAdapter {
         return Ok(Some(string));
     }
 
+    async fn list_keys_with_prefix(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<String>, GenericStorageError> {
+        let mut keys: Vec<String> = vec![];
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(prefix)
+            .into_paginator()
+            .send();
+        while let Some(page) = pages.next().await {
+            for object in page?.contents() {
+                if let Some(key) = object.key() {
+                    keys.push(key.to_string());
+                }
+            }
+        }
+        return Ok(keys);
+    }
+
+    /// Lists the keys under the prefix, then fetches the objects concurrently
+    async fn get_objects_with_prefix(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<String>, GenericStorageError> {
+        let keys = self.list_keys_with_prefix(prefix).await?;
+        let objects: Vec<Option<String>> = stream::iter(keys)
+            .map(|key| async move { self.get_object_as_option(&key).await })
+            .buffer_unordered(MAX_CONCURRENT_OBJECT_READS)
+            .try_collect()
+            .await?;
+        return Ok(objects.into_iter().flatten().collect());
+    }
+
     fn not_found() -> GenericStorageError {
         GenericStorageError::new(
             "object found".to_string(),
@@ -1,15 +1,20 @@
 use std::borrow::Cow;
 
 use super::{GenericStorageError, KVStorageAdapter};
-use crate::model::{ConfigDetails, YakManApiKey};
+use crate::adapters::MAX_CONCURRENT_OBJECT_READS;
+use crate::model::{
+    ConfigDetails, YakManApiKey, YakManApiKeyUsage, YakManAuditLogEntry, YakManLoginAttempts,
+    YakManNotificationDelivery, YakManScheduledApply, YakManServiceAccount, YakManTotp,
+};
 use crate::model::{
     ConfigInstanceRevision, LabelType, YakManConfig, YakManPassword, YakManPasswordResetLink,
-    YakManProject, YakManProjectDetails, YakManSnapshotLock, YakManTeam, YakManTeamDetails,
-    YakManUser, YakManUserDetails,
+    YakManProject, YakManProjectDetails, YakManRevokedRefreshToken, YakManSnapshotLock, YakManTeam,
+    YakManTeamDetails, YakManUser, YakManUserDetails,
 };
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt, TryStreamExt};
 use google_cloud_storage::{
     client::{Client, ClientConfig},
     http::objects::{
@@ -479,6 +484,408 @@ impl KVStorageAdapter for GoogleCloudStorageAdapter {
         return Ok(());
     }
 
+    async fn get_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError> {
+        let dir = self.get_notification_deliveries_dir();
+        let mut deliveries: Vec<YakManNotificationDelivery> = vec![];
+        for content in self
+            .get_objects_with_prefix(&format!("{dir}/{project_id}/"))
+            .await?
+        {
+            deliveries.push(serde_json::from_str(&content)?);
+        }
+        return Ok(deliveries);
+    }
+
+    async fn save_notification_delivery(
+        &self,
+        project_id: &str,
+        delivery: &YakManNotificationDelivery,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_notification_deliveries_dir();
+        let path: String = format!("{dir}/{project_id}/{}.json", delivery.id);
+
+        let data: String = serde_json::to_string(&delivery)?;
+
+        self.put_object(&path, data).await?;
+        return Ok(());
+    }
+
+    async fn delete_notification_delivery(
+        &self,
+        project_id: &str,
+        delivery_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_notification_deliveries_dir();
+        let path: String = format!("{dir}/{project_id}/{delivery_id}.json");
+        self.delete_object(&path).await?;
+        return Ok(());
+    }
+
+    async fn delete_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_notification_deliveries_dir();
+        for name in self
+            .list_names_with_prefix(&format!("{dir}/{project_id}/"))
+            .await?
+        {
+            self.delete_object(&name).await?;
+        }
+        return Ok(());
+    }
+
+    async fn get_pending_notification_deliveries(
+        &self,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError> {
+        let dir = self.get_pending_notification_deliveries_dir();
+        let mut deliveries: Vec<YakManNotificationDelivery> = vec![];
+        for content in self.get_objects_with_prefix(&format!("{dir}/")).await? {
+            deliveries.push(serde_json::from_str(&content)?);
+        }
+        return Ok(deliveries);
+    }
+
+    async fn save_pending_notification_delivery(
+        &self,
+        delivery: &YakManNotificationDelivery,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_pending_notification_deliveries_dir();
+        let path: String = format!("{dir}/{}.json", delivery.id);
+
+        let data: String = serde_json::to_string(&delivery)?;
+
+        self.put_object(&path, data).await?;
+        return Ok(());
+    }
+
+    async fn delete_pending_notification_delivery(
+        &self,
+        delivery_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_pending_notification_deliveries_dir();
+        let path: String = format!("{dir}/{delivery_id}.json");
+        self.delete_object(&path).await?;
+        return Ok(());
+    }
+
+    async fn get_notification_delivery_lock(
+        &self,
+    ) -> Result<YakManSnapshotLock, GenericStorageError> {
+        let path = self.get_notification_delivery_lock_file_path();
+
+        if let Ok(content) = self.get_object(&path).await {
+            let data: YakManSnapshotLock = serde_json::from_str(&content)?;
+            return Ok(data);
+        }
+
+        return Ok(YakManSnapshotLock::unlocked());
+    }
+
+    async fn save_notification_delivery_lock(
+        &self,
+        lock: &YakManSnapshotLock,
+    ) -> Result<(), GenericStorageError> {
+        let data = serde_json::to_string(lock)?;
+        let data_file_path = self.get_notification_delivery_lock_file_path();
+        self.put_object(&data_file_path, data).await?;
+        Ok(())
+    }
+
+    async fn get_audit_log(
+        &self,
+        day: &str,
+    ) -> Result<Vec<YakManAuditLogEntry>, GenericStorageError> {
+        let dir = self.get_audit_log_dir();
+        let mut entries: Vec<YakManAuditLogEntry> = vec![];
+        for content in self
+            .get_objects_with_prefix(&format!("{dir}/{day}/"))
+            .await?
+        {
+            entries.push(serde_json::from_str(&content)?);
+        }
+        return Ok(entries);
+    }
+
+    async fn save_audit_log_entry(
+        &self,
+        day: &str,
+        entry: &YakManAuditLogEntry,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_audit_log_dir();
+        let path: String = format!("{dir}/{day}/{}.json", entry.id);
+
+        let data: String = serde_json::to_string(&entry)?;
+
+        self.put_object(&path, data).await?;
+        return Ok(());
+    }
+
+    async fn get_service_accounts(&self) -> Result<Vec<YakManServiceAccount>, GenericStorageError> {
+        let path = self.get_service_accounts_file_path();
+
+        if let Ok(content) = self.get_object(&path).await {
+            let data: Vec<YakManServiceAccount> = serde_json::from_str(&content)?;
+            return Ok(data);
+        }
+
+        return Ok(vec![]);
+    }
+
+    async fn save_service_accounts(
+        &self,
+        service_accounts: &[YakManServiceAccount],
+    ) -> Result<(), GenericStorageError> {
+        let data = serde_json::to_string(service_accounts)?;
+        let data_file_path = self.get_service_accounts_file_path();
+        self.put_object(&data_file_path, data).await?;
+        Ok(())
+    }
+
+    async fn get_scheduled_applies(
+        &self,
+    ) -> Result<Vec<YakManScheduledApply>, GenericStorageError> {
+        let dir = self.get_scheduled_applies_dir();
+        let mut scheduled_applies: Vec<YakManScheduledApply> = vec![];
+        for content in self.get_objects_with_prefix(&format!("{dir}/")).await? {
+            scheduled_applies.push(serde_json::from_str(&content)?);
+        }
+        return Ok(scheduled_applies);
+    }
+
+    async fn get_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<Option<YakManScheduledApply>, GenericStorageError> {
+        let dir = self.get_scheduled_applies_dir();
+        let path = format!("{dir}/{config_id}/{instance}.json");
+
+        if let Ok(content) = self.get_object(&path).await {
+            let data: YakManScheduledApply = serde_json::from_str(&content)?;
+            return Ok(Some(data));
+        }
+
+        return Ok(None);
+    }
+
+    async fn save_scheduled_apply(
+        &self,
+        scheduled: &YakManScheduledApply,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_scheduled_applies_dir();
+        let path: String = format!("{dir}/{}/{}.json", scheduled.config_id, scheduled.instance);
+
+        let data: String = serde_json::to_string(&scheduled)?;
+
+        self.put_object(&path, data).await?;
+        return Ok(());
+    }
+
+    async fn delete_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_scheduled_applies_dir();
+        let path: String = format!("{dir}/{config_id}/{instance}.json");
+
+        self.delete_object(&path).await?;
+        return Ok(());
+    }
+
+    async fn get_scheduled_apply_lock(&self) -> Result<YakManSnapshotLock, GenericStorageError> {
+        let path = self.get_scheduled_apply_lock_file_path();
+
+        if let Ok(content) = self.get_object(&path).await {
+            let data: YakManSnapshotLock = serde_json::from_str(&content)?;
+            return Ok(data);
+        }
+
+        return Ok(YakManSnapshotLock::unlocked());
+    }
+
+    async fn save_scheduled_apply_lock(
+        &self,
+        lock: &YakManSnapshotLock,
+    ) -> Result<(), GenericStorageError> {
+        let data = serde_json::to_string(lock)?;
+        let data_file_path = self.get_scheduled_apply_lock_file_path();
+        self.put_object(&data_file_path, data).await?;
+        Ok(())
+    }
+
+    async fn delete_user_details(&self, user_id: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_user_dir();
+        let path: String = format!("{dir}/{user_id}.json");
+        self.delete_object(&path).await?;
+        return Ok(());
+    }
+
+    async fn delete_password(&self, email_hash: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_password_dir();
+        let path: String = format!("{dir}/{email_hash}.json");
+        self.delete_object(&path).await?;
+        return Ok(());
+    }
+
+    async fn get_login_attempts(
+        &self,
+        key: &str,
+    ) -> Result<Option<YakManLoginAttempts>, GenericStorageError> {
+        let dir = self.get_login_attempts_dir();
+        let path = format!("{dir}/{key}.json");
+
+        if let Ok(content) = self.get_object(&path).await {
+            let data: YakManLoginAttempts = serde_json::from_str(&content)?;
+            return Ok(Some(data));
+        }
+
+        return Ok(None);
+    }
+
+    async fn save_login_attempts(
+        &self,
+        key: &str,
+        attempts: &YakManLoginAttempts,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_login_attempts_dir();
+        let path: String = format!("{dir}/{key}.json");
+
+        let data: String = serde_json::to_string(&attempts)?;
+
+        self.put_object(&path, data).await?;
+        return Ok(());
+    }
+
+    async fn delete_login_attempts(&self, key: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_login_attempts_dir();
+        let path: String = format!("{dir}/{key}.json");
+
+        self.delete_object(&path).await?;
+        return Ok(());
+    }
+
+    async fn get_api_key_usage(
+        &self,
+        api_key_id: &str,
+    ) -> Result<Option<YakManApiKeyUsage>, GenericStorageError> {
+        let dir = self.get_api_key_usage_dir();
+        let path = format!("{dir}/{api_key_id}.json");
+
+        if let Ok(content) = self.get_object(&path).await {
+            let data: YakManApiKeyUsage = serde_json::from_str(&content)?;
+            return Ok(Some(data));
+        }
+
+        return Ok(None);
+    }
+
+    async fn save_api_key_usage(
+        &self,
+        api_key_id: &str,
+        usage: &YakManApiKeyUsage,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_api_key_usage_dir();
+        let path: String = format!("{dir}/{api_key_id}.json");
+
+        let data: String = serde_json::to_string(&usage)?;
+
+        self.put_object(&path, data).await?;
+        return Ok(());
+    }
+
+    async fn delete_api_key_usage(&self, api_key_id: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_api_key_usage_dir();
+        let path: String = format!("{dir}/{api_key_id}.json");
+
+        self.delete_object(&path).await?;
+        return Ok(());
+    }
+
+    async fn get_revoked_refresh_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<YakManRevokedRefreshToken>, GenericStorageError> {
+        let dir = self.get_revoked_refresh_tokens_dir();
+        let path = format!("{dir}/{token_hash}.json");
+
+        if let Ok(content) = self.get_object(&path).await {
+            let data: YakManRevokedRefreshToken = serde_json::from_str(&content)?;
+            return Ok(Some(data));
+        }
+
+        return Ok(None);
+    }
+
+    async fn save_revoked_refresh_token(
+        &self,
+        token_hash: &str,
+        revoked_token: &YakManRevokedRefreshToken,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_revoked_refresh_tokens_dir();
+        let path: String = format!("{dir}/{token_hash}.json");
+
+        let data: String = serde_json::to_string(&revoked_token)?;
+
+        self.put_object(&path, data).await?;
+        return Ok(());
+    }
+
+    async fn delete_expired_revoked_refresh_tokens(
+        &self,
+        now: i64,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_revoked_refresh_tokens_dir();
+        let names = self.list_names_with_prefix(&format!("{dir}/")).await?;
+
+        return stream::iter(names)
+            .map(|name| async move {
+                let content = self.get_object(&name).await?;
+                let revoked_token: YakManRevokedRefreshToken = serde_json::from_str(&content)?;
+                if revoked_token.expires_at <= now {
+                    self.delete_object(&name).await?;
+                }
+                return Ok::<(), GenericStorageError>(());
+            })
+            .buffer_unordered(MAX_CONCURRENT_OBJECT_READS)
+            .try_collect()
+            .await;
+    }
+
+    async fn get_totp(&self, user_id: &str) -> Result<Option<YakManTotp>, GenericStorageError> {
+        let dir = self.get_totp_dir();
+        let path = format!("{dir}/{user_id}.json");
+
+        if let Ok(content) = self.get_object(&path).await {
+            let data: YakManTotp = serde_json::from_str(&content)?;
+            return Ok(Some(data));
+        }
+
+        return Ok(None);
+    }
+
+    async fn save_totp(&self, user_id: &str, totp: &YakManTotp) -> Result<(), GenericStorageError> {
+        let dir = self.get_totp_dir();
+        let path: String = format!("{dir}/{user_id}.json");
+
+        let data: String = serde_json::to_string(&totp)?;
+
+        self.put_object(&path, data).await?;
+        return Ok(());
+    }
+
+    async fn delete_totp(&self, user_id: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_totp_dir();
+        let path: String = format!("{dir}/{user_id}.json");
+
+        self.delete_object(&path).await?;
+        return Ok(());
+    }
+
     async fn get_snapshot_lock(&self) -> Result<YakManSnapshotLock, GenericStorageError> {
         let path = self.get_snapshot_lock_file_path();
         let content = self.get_object(&path).await?;
@@ -585,6 +992,26 @@ impl GoogleCloudStorageAdapter {
         return format!("{yakman_dir}/revisions");
     }
 
+    fn get_service_accounts_file_path(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/service-accounts.json");
+    }
+
+    fn get_scheduled_applies_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/scheduled-applies");
+    }
+
+    fn get_scheduled_apply_lock_file_path(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/scheduled-apply-lock.json");
+    }
+
+    fn get_notification_delivery_lock_file_path(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/notification-delivery-lock.json");
+    }
+
     fn get_api_key_file_path(&self) -> String {
         let yakman_dir = self.get_yakman_dir();
         return format!("{yakman_dir}/api-keys.json");
@@ -625,6 +1052,41 @@ impl GoogleCloudStorageAdapter {
         return format!("{yakman_dir}/passwords");
     }
 
+    fn get_notification_deliveries_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/notification-deliveries");
+    }
+
+    fn get_pending_notification_deliveries_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/pending-notification-deliveries");
+    }
+
+    fn get_audit_log_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/audit-log");
+    }
+
+    fn get_api_key_usage_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/api-key-usage");
+    }
+
+    fn get_revoked_refresh_tokens_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/revoked-refresh-tokens");
+    }
+
+    fn get_login_attempts_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/login-attempts");
+    }
+
+    fn get_totp_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/totp");
+    }
+
     fn get_password_reset_link_dir(&self) -> String {
         let yakman_dir = self.get_yakman_dir();
         return format!("{yakman_dir}/password-reset-links");
@@ -709,6 +1171,46 @@ impl GoogleCloudStorageAdapter {
         return Ok(String::from_utf8(obj)?);
     }
 
+    async fn list_names_with_prefix(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<String>, GenericStorageError> {
+        let mut names: Vec<String> = vec![];
+        let mut page_token = None;
+        loop {
+            let req = ListObjectsRequest {
+                bucket: self.bucket.to_string(),
+                prefix: Some(prefix.to_string()),
+                page_token: page_token,
+                ..Default::default()
+            };
+            let res = self.client.list_objects(&req).await?;
+
+            for obj in res.items.unwrap_or_default() {
+                names.push(obj.name);
+            }
+
+            page_token = res.next_page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+        return Ok(names);
+    }
+
+    /// Lists the objects under the prefix, then downloads them concurrently
+    async fn get_objects_with_prefix(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<String>, GenericStorageError> {
+        let names = self.list_names_with_prefix(prefix).await?;
+        return stream::iter(names)
+            .map(|name| async move { self.get_object(&name).await })
+            .buffer_unordered(MAX_CONCURRENT_OBJECT_READS)
+            .try_collect()
+            .await;
+    }
+
     pub async fn from_env() -> Result<GoogleCloudStorageAdapter> {
         let config = ClientConfig::default().with_auth().await?;
         let client = Client::new(config);
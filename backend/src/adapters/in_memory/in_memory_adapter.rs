@@ -7,9 +7,11 @@ use serde::de::DeserializeOwned;
 
 use super::{GenericStorageError, KVStorageAdapter};
 use crate::model::{
-    ConfigDetails, ConfigInstanceRevision, LabelType, YakManApiKey, YakManConfig, YakManPassword,
-    YakManPasswordResetLink, YakManProject, YakManProjectDetails, YakManSnapshotLock, YakManTeam,
-    YakManTeamDetails, YakManUser, YakManUserDetails,
+    ConfigDetails, ConfigInstanceRevision, LabelType, YakManApiKey, YakManApiKeyUsage,
+    YakManAuditLogEntry, YakManConfig, YakManLoginAttempts, YakManNotificationDelivery,
+    YakManPassword, YakManPasswordResetLink, YakManProject, YakManProjectDetails,
+    YakManRevokedRefreshToken, YakManScheduledApply, YakManServiceAccount, YakManSnapshotLock,
+    YakManTeam, YakManTeamDetails, YakManTotp, YakManUser, YakManUserDetails,
 };
 
 /// This adapter is meant for development and testing not real world use.
@@ -342,6 +344,346 @@ impl KVStorageAdapter for InMemoryStorageAdapter {
         return Ok(());
     }
 
+    async fn get_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError> {
+        let prefix = self.get_notification_delivery_key(project_id, "");
+        let storage = self.storage.lock().await;
+        let mut deliveries = vec![];
+        for (key, value) in storage.iter() {
+            if key.starts_with(&prefix) {
+                deliveries.push(serde_json::from_str(value)?);
+            }
+        }
+        return Ok(deliveries);
+    }
+
+    async fn save_notification_delivery(
+        &self,
+        project_id: &str,
+        delivery: &YakManNotificationDelivery,
+    ) -> Result<(), GenericStorageError> {
+        self.insert(
+            self.get_notification_delivery_key(project_id, &delivery.id),
+            serde_json::to_string(&delivery)?,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn delete_notification_delivery(
+        &self,
+        project_id: &str,
+        delivery_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        self.remove(&self.get_notification_delivery_key(project_id, delivery_id))
+            .await;
+        return Ok(());
+    }
+
+    async fn delete_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let prefix = self.get_notification_delivery_key(project_id, "");
+        self.storage
+            .lock()
+            .await
+            .retain(|key, _| !key.starts_with(&prefix));
+        return Ok(());
+    }
+
+    async fn get_pending_notification_deliveries(
+        &self,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError> {
+        let prefix = self.get_pending_notification_delivery_key("");
+        let storage = self.storage.lock().await;
+        let mut deliveries = vec![];
+        for (key, value) in storage.iter() {
+            if key.starts_with(&prefix) {
+                deliveries.push(serde_json::from_str(value)?);
+            }
+        }
+        return Ok(deliveries);
+    }
+
+    async fn save_pending_notification_delivery(
+        &self,
+        delivery: &YakManNotificationDelivery,
+    ) -> Result<(), GenericStorageError> {
+        self.insert(
+            self.get_pending_notification_delivery_key(&delivery.id),
+            serde_json::to_string(&delivery)?,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn delete_pending_notification_delivery(
+        &self,
+        delivery_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        self.remove(&self.get_pending_notification_delivery_key(delivery_id))
+            .await;
+        return Ok(());
+    }
+
+    async fn get_notification_delivery_lock(
+        &self,
+    ) -> Result<YakManSnapshotLock, GenericStorageError> {
+        return Ok(self
+            .get_optional_data(&self.get_notification_delivery_lock_key())
+            .await?
+            .unwrap_or(YakManSnapshotLock::unlocked()));
+    }
+
+    async fn save_notification_delivery_lock(
+        &self,
+        lock: &YakManSnapshotLock,
+    ) -> Result<(), GenericStorageError> {
+        self.insert(
+            self.get_notification_delivery_lock_key(),
+            serde_json::to_string(&lock)?,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn get_audit_log(
+        &self,
+        day: &str,
+    ) -> Result<Vec<YakManAuditLogEntry>, GenericStorageError> {
+        let prefix = self.get_audit_log_key(day, "");
+        let storage = self.storage.lock().await;
+        let mut entries = vec![];
+        for (key, value) in storage.iter() {
+            if key.starts_with(&prefix) {
+                entries.push(serde_json::from_str(value)?);
+            }
+        }
+        return Ok(entries);
+    }
+
+    async fn save_audit_log_entry(
+        &self,
+        day: &str,
+        entry: &YakManAuditLogEntry,
+    ) -> Result<(), GenericStorageError> {
+        self.insert(
+            self.get_audit_log_key(day, &entry.id),
+            serde_json::to_string(&entry)?,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn get_service_accounts(&self) -> Result<Vec<YakManServiceAccount>, GenericStorageError> {
+        return Ok(self
+            .get_optional_data(&self.get_service_accounts_key())
+            .await?
+            .unwrap_or_default());
+    }
+
+    async fn save_service_accounts(
+        &self,
+        service_accounts: &[YakManServiceAccount],
+    ) -> Result<(), GenericStorageError> {
+        self.insert(
+            self.get_service_accounts_key(),
+            serde_json::to_string(&service_accounts)?,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn get_scheduled_applies(
+        &self,
+    ) -> Result<Vec<YakManScheduledApply>, GenericStorageError> {
+        let prefix = format!("{}_", self.get_scheduled_applies_key());
+        let storage = self.storage.lock().await;
+        let mut scheduled_applies = vec![];
+        for (key, value) in storage.iter() {
+            if key.starts_with(&prefix) {
+                scheduled_applies.push(serde_json::from_str(value)?);
+            }
+        }
+        return Ok(scheduled_applies);
+    }
+
+    async fn get_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<Option<YakManScheduledApply>, GenericStorageError> {
+        return self
+            .get_optional_data(&self.get_scheduled_apply_key(config_id, instance))
+            .await;
+    }
+
+    async fn save_scheduled_apply(
+        &self,
+        scheduled: &YakManScheduledApply,
+    ) -> Result<(), GenericStorageError> {
+        self.insert(
+            self.get_scheduled_apply_key(&scheduled.config_id, &scheduled.instance),
+            serde_json::to_string(&scheduled)?,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn delete_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<(), GenericStorageError> {
+        self.remove(&self.get_scheduled_apply_key(config_id, instance))
+            .await;
+        return Ok(());
+    }
+
+    async fn get_scheduled_apply_lock(&self) -> Result<YakManSnapshotLock, GenericStorageError> {
+        return Ok(self
+            .get_optional_data(&self.get_scheduled_apply_lock_key())
+            .await?
+            .unwrap_or(YakManSnapshotLock::unlocked()));
+    }
+
+    async fn save_scheduled_apply_lock(
+        &self,
+        lock: &YakManSnapshotLock,
+    ) -> Result<(), GenericStorageError> {
+        self.insert(
+            self.get_scheduled_apply_lock_key(),
+            serde_json::to_string(&lock)?,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn delete_user_details(&self, user_id: &str) -> Result<(), GenericStorageError> {
+        self.remove(&self.get_user_key(user_id)).await;
+        return Ok(());
+    }
+
+    async fn delete_password(&self, email_hash: &str) -> Result<(), GenericStorageError> {
+        self.remove(&self.get_password_key(email_hash)).await;
+        return Ok(());
+    }
+
+    async fn get_login_attempts(
+        &self,
+        key: &str,
+    ) -> Result<Option<YakManLoginAttempts>, GenericStorageError> {
+        return self
+            .get_optional_data(&self.get_login_attempts_key(key))
+            .await;
+    }
+
+    async fn save_login_attempts(
+        &self,
+        key: &str,
+        attempts: &YakManLoginAttempts,
+    ) -> Result<(), GenericStorageError> {
+        self.insert(
+            self.get_login_attempts_key(key),
+            serde_json::to_string(&attempts)?,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn delete_login_attempts(&self, key: &str) -> Result<(), GenericStorageError> {
+        self.remove(&self.get_login_attempts_key(key)).await;
+        Ok(())
+    }
+
+    async fn get_api_key_usage(
+        &self,
+        api_key_id: &str,
+    ) -> Result<Option<YakManApiKeyUsage>, GenericStorageError> {
+        return self
+            .get_optional_data(&self.get_api_key_usage_key(api_key_id))
+            .await;
+    }
+
+    async fn save_api_key_usage(
+        &self,
+        api_key_id: &str,
+        usage: &YakManApiKeyUsage,
+    ) -> Result<(), GenericStorageError> {
+        self.insert(
+            self.get_api_key_usage_key(api_key_id),
+            serde_json::to_string(&usage)?,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn delete_api_key_usage(&self, api_key_id: &str) -> Result<(), GenericStorageError> {
+        self.remove(&self.get_api_key_usage_key(api_key_id)).await;
+        Ok(())
+    }
+
+    async fn get_revoked_refresh_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<YakManRevokedRefreshToken>, GenericStorageError> {
+        return self
+            .get_optional_data(&self.get_revoked_refresh_token_key(token_hash))
+            .await;
+    }
+
+    async fn save_revoked_refresh_token(
+        &self,
+        token_hash: &str,
+        revoked_token: &YakManRevokedRefreshToken,
+    ) -> Result<(), GenericStorageError> {
+        self.insert(
+            self.get_revoked_refresh_token_key(token_hash),
+            serde_json::to_string(&revoked_token)?,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn delete_expired_revoked_refresh_tokens(
+        &self,
+        now: i64,
+    ) -> Result<(), GenericStorageError> {
+        let prefix = self.get_revoked_refresh_token_key("");
+        let mut storage = self.storage.lock().await;
+        let mut expired_keys = vec![];
+        for (key, value) in storage.iter() {
+            if key.starts_with(&prefix) {
+                let revoked_token: YakManRevokedRefreshToken = serde_json::from_str(value)?;
+                if revoked_token.expires_at <= now {
+                    expired_keys.push(key.clone());
+                }
+            }
+        }
+        for key in expired_keys {
+            storage.remove(&key);
+        }
+        return Ok(());
+    }
+
+    async fn get_totp(&self, user_id: &str) -> Result<Option<YakManTotp>, GenericStorageError> {
+        return self.get_optional_data(&self.get_totp_key(user_id)).await;
+    }
+
+    async fn save_totp(&self, user_id: &str, totp: &YakManTotp) -> Result<(), GenericStorageError> {
+        self.insert(self.get_totp_key(user_id), serde_json::to_string(&totp)?)
+            .await;
+        Ok(())
+    }
+
+    async fn delete_totp(&self, user_id: &str) -> Result<(), GenericStorageError> {
+        self.remove(&self.get_totp_key(user_id)).await;
+        Ok(())
+    }
+
     async fn get_snapshot_lock(&self) -> Result<YakManSnapshotLock, GenericStorageError> {
         let storage = self.storage.lock().await;
         let projects = storage.get(&self.get_snapshot_lock_key()).unwrap();
@@ -488,6 +830,18 @@ impl InMemoryStorageAdapter {
         "TEAMS".to_string()
     }
 
+    fn get_service_accounts_key(&self) -> String {
+        return "SERVICE_ACCOUNTS".to_string();
+    }
+
+    fn get_scheduled_apply_lock_key(&self) -> String {
+        return "SCHEDULED_APPLY_LOCK".to_string();
+    }
+
+    fn get_notification_delivery_lock_key(&self) -> String {
+        return "NOTIFICATION_DELIVERY_LOCK".to_string();
+    }
+
     fn get_api_keys_key(&self) -> String {
         return "API_KEYS".to_string();
     }
@@ -524,10 +878,47 @@ impl InMemoryStorageAdapter {
         return format!("PASSWORDS_{email_hash}");
     }
 
+    fn get_api_key_usage_key(&self, api_key_id: &str) -> String {
+        return format!("API_KEY_USAGE_{api_key_id}");
+    }
+
+    fn get_revoked_refresh_token_key(&self, token_hash: &str) -> String {
+        return format!("REVOKED_REFRESH_TOKENS_{token_hash}");
+    }
+
+    fn get_login_attempts_key(&self, key: &str) -> String {
+        return format!("LOGIN_ATTEMPTS_{key}");
+    }
+
+    fn get_totp_key(&self, user_id: &str) -> String {
+        return format!("TOTP_{user_id}");
+    }
+
     fn get_password_reset_link_key(&self, id: &str) -> String {
         return format!("PASSWORD_RESET_LINK_{id}");
     }
 
+    fn get_notification_delivery_key(&self, project_id: &str, delivery_id: &str) -> String {
+        return format!("NOTIFICATION_DELIVERIES_{project_id}_{delivery_id}");
+    }
+
+    fn get_scheduled_applies_key(&self) -> String {
+        return "SCHEDULED_APPLIES".to_string();
+    }
+
+    fn get_scheduled_apply_key(&self, config_id: &str, instance: &str) -> String {
+        let scheduled_applies_key = self.get_scheduled_applies_key();
+        return format!("{scheduled_applies_key}_{config_id}_{instance}");
+    }
+
+    fn get_pending_notification_delivery_key(&self, delivery_id: &str) -> String {
+        return format!("PENDING_NOTIFICATION_DELIVERIES_{delivery_id}");
+    }
+
+    fn get_audit_log_key(&self, day: &str, entry_id: &str) -> String {
+        return format!("AUDIT_LOG_{day}_{entry_id}");
+    }
+
     pub fn new() -> InMemoryStorageAdapter {
         return InMemoryStorageAdapter {
             storage: Arc::new(Mutex::new(HashMap::new())),
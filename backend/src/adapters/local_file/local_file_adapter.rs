@@ -8,9 +8,11 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 
 use crate::model::{
-    ConfigDetails, ConfigInstanceRevision, LabelType, YakManApiKey, YakManConfig, YakManPassword,
-    YakManPasswordResetLink, YakManProject, YakManProjectDetails, YakManSnapshotLock, YakManTeam,
-    YakManTeamDetails, YakManUser, YakManUserDetails,
+    ConfigDetails, ConfigInstanceRevision, LabelType, YakManApiKey, YakManApiKeyUsage,
+    YakManAuditLogEntry, YakManConfig, YakManLoginAttempts, YakManNotificationDelivery,
+    YakManPassword, YakManPasswordResetLink, YakManProject, YakManProjectDetails,
+    YakManRevokedRefreshToken, YakManScheduledApply, YakManServiceAccount, YakManSnapshotLock,
+    YakManTeam, YakManTeamDetails, YakManTotp, YakManUser, YakManUserDetails,
 };
 
 use super::{GenericStorageError, KVStorageAdapter};
@@ -275,6 +277,75 @@ impl KVStorageAdapter for LocalFileStorageAdapter {
             ));
         }
 
+        let api_key_usage_dir = self.get_api_key_usage_dir();
+        if !Path::new(&api_key_usage_dir).is_dir() {
+            log::info!("Creating {}", api_key_usage_dir);
+            fs::create_dir(&api_key_usage_dir).expect(&format!(
+                "Failed to create api key usage dir: {}",
+                api_key_usage_dir
+            ));
+        }
+
+        let revoked_refresh_tokens_dir = self.get_revoked_refresh_tokens_dir();
+        if !Path::new(&revoked_refresh_tokens_dir).is_dir() {
+            log::info!("Creating {}", revoked_refresh_tokens_dir);
+            fs::create_dir(&revoked_refresh_tokens_dir).expect(&format!(
+                "Failed to create revoked refresh tokens dir: {}",
+                revoked_refresh_tokens_dir
+            ));
+        }
+
+        let login_attempts_dir = self.get_login_attempts_dir();
+        if !Path::new(&login_attempts_dir).is_dir() {
+            log::info!("Creating {}", login_attempts_dir);
+            fs::create_dir(&login_attempts_dir).expect(&format!(
+                "Failed to create login attempts dir: {}",
+                login_attempts_dir
+            ));
+        }
+
+        let totp_dir = self.get_totp_dir();
+        if !Path::new(&totp_dir).is_dir() {
+            log::info!("Creating {}", totp_dir);
+            fs::create_dir(&totp_dir).expect(&format!("Failed to create TOTP dir: {}", totp_dir));
+        }
+
+        let notification_deliveries_dir = self.get_notification_deliveries_dir();
+        if !Path::new(&notification_deliveries_dir).is_dir() {
+            log::info!("Creating {}", notification_deliveries_dir);
+            fs::create_dir(&notification_deliveries_dir).expect(&format!(
+                "Failed to create notification deliveries dir: {}",
+                notification_deliveries_dir
+            ));
+        }
+
+        let scheduled_applies_dir = self.get_scheduled_applies_dir();
+        if !Path::new(&scheduled_applies_dir).is_dir() {
+            log::info!("Creating {}", scheduled_applies_dir);
+            fs::create_dir(&scheduled_applies_dir).expect(&format!(
+                "Failed to create scheduled applies dir: {}",
+                scheduled_applies_dir
+            ));
+        }
+
+        let pending_notification_deliveries_dir = self.get_pending_notification_deliveries_dir();
+        if !Path::new(&pending_notification_deliveries_dir).is_dir() {
+            log::info!("Creating {}", pending_notification_deliveries_dir);
+            fs::create_dir(&pending_notification_deliveries_dir).expect(&format!(
+                "Failed to create pending notification deliveries dir: {}",
+                pending_notification_deliveries_dir
+            ));
+        }
+
+        let audit_log_dir = self.get_audit_log_dir();
+        if !Path::new(&audit_log_dir).is_dir() {
+            log::info!("Creating {}", audit_log_dir);
+            fs::create_dir(&audit_log_dir).expect(&format!(
+                "Failed to create audit log dir: {}",
+                audit_log_dir
+            ));
+        }
+
         let snapshot_dir = self.get_yakman_snapshot_dir();
         if !Path::new(&snapshot_dir).is_dir() {
             log::info!("Creating {}", snapshot_dir);
@@ -559,6 +630,425 @@ impl KVStorageAdapter for LocalFileStorageAdapter {
         return Ok(());
     }
 
+    async fn get_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError> {
+        let dir = format!("{}/{project_id}", self.get_notification_deliveries_dir());
+        let mut deliveries: Vec<YakManNotificationDelivery> = vec![];
+
+        if let Ok(files) = fs::read_dir(dir) {
+            for file in files {
+                let content = fs::read_to_string(file?.path())?;
+                deliveries.push(serde_json::from_str(&content)?);
+            }
+        }
+
+        return Ok(deliveries);
+    }
+
+    async fn save_notification_delivery(
+        &self,
+        project_id: &str,
+        delivery: &YakManNotificationDelivery,
+    ) -> Result<(), GenericStorageError> {
+        let dir = format!("{}/{project_id}", self.get_notification_deliveries_dir());
+        fs::create_dir_all(&dir)?;
+        let path = format!("{dir}/{}.json", delivery.id);
+        let data: String = serde_json::to_string(&delivery)?;
+        let mut data_file = File::create(path)?;
+        Write::write_all(&mut data_file, data.as_bytes())?;
+        return Ok(());
+    }
+
+    async fn delete_notification_delivery(
+        &self,
+        project_id: &str,
+        delivery_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_notification_deliveries_dir();
+        remove_file(format!("{dir}/{project_id}/{delivery_id}.json"))?;
+        return Ok(());
+    }
+
+    async fn delete_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let dir = format!("{}/{project_id}", self.get_notification_deliveries_dir());
+        if Path::new(&dir).is_dir() {
+            fs::remove_dir_all(dir)?;
+        }
+        return Ok(());
+    }
+
+    async fn get_pending_notification_deliveries(
+        &self,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError> {
+        let dir = self.get_pending_notification_deliveries_dir();
+        let mut deliveries: Vec<YakManNotificationDelivery> = vec![];
+
+        if let Ok(files) = fs::read_dir(dir) {
+            for file in files {
+                let content = fs::read_to_string(file?.path())?;
+                deliveries.push(serde_json::from_str(&content)?);
+            }
+        }
+
+        return Ok(deliveries);
+    }
+
+    async fn save_pending_notification_delivery(
+        &self,
+        delivery: &YakManNotificationDelivery,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_pending_notification_deliveries_dir();
+        let path = format!("{dir}/{}.json", delivery.id);
+        let data: String = serde_json::to_string(&delivery)?;
+        let mut data_file = File::create(path)?;
+        Write::write_all(&mut data_file, data.as_bytes())?;
+        return Ok(());
+    }
+
+    async fn delete_pending_notification_delivery(
+        &self,
+        delivery_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_pending_notification_deliveries_dir();
+        let path = format!("{dir}/{delivery_id}.json");
+        if Path::new(&path).exists() {
+            remove_file(path)?;
+        }
+        return Ok(());
+    }
+
+    async fn get_notification_delivery_lock(
+        &self,
+    ) -> Result<YakManSnapshotLock, GenericStorageError> {
+        let path = self.get_notification_delivery_lock_file_path();
+
+        if let Ok(content) = fs::read_to_string(path) {
+            let data: YakManSnapshotLock = serde_json::from_str(&content)?;
+            return Ok(data);
+        }
+
+        return Ok(YakManSnapshotLock::unlocked());
+    }
+
+    async fn save_notification_delivery_lock(
+        &self,
+        lock: &YakManSnapshotLock,
+    ) -> Result<(), GenericStorageError> {
+        let data = serde_json::to_string(lock)?;
+        let data_file_path = self.get_notification_delivery_lock_file_path();
+        let mut data_file = File::create(data_file_path)?;
+        Write::write_all(&mut data_file, data.as_bytes())?;
+        Ok(())
+    }
+
+    async fn get_audit_log(
+        &self,
+        day: &str,
+    ) -> Result<Vec<YakManAuditLogEntry>, GenericStorageError> {
+        let dir = self.get_audit_log_dir();
+        let mut entries: Vec<YakManAuditLogEntry> = vec![];
+
+        if let Ok(files) = fs::read_dir(format!("{dir}/{day}")) {
+            for file in files {
+                let content = fs::read_to_string(file?.path())?;
+                entries.push(serde_json::from_str(&content)?);
+            }
+        }
+
+        return Ok(entries);
+    }
+
+    async fn save_audit_log_entry(
+        &self,
+        day: &str,
+        entry: &YakManAuditLogEntry,
+    ) -> Result<(), GenericStorageError> {
+        let dir = format!("{}/{day}", self.get_audit_log_dir());
+        fs::create_dir_all(&dir)?;
+        let path = format!("{dir}/{}.json", entry.id);
+        let data: String = serde_json::to_string(&entry)?;
+        let mut data_file = File::create_new(path)?;
+        Write::write_all(&mut data_file, data.as_bytes())?;
+        return Ok(());
+    }
+
+    async fn get_service_accounts(&self) -> Result<Vec<YakManServiceAccount>, GenericStorageError> {
+        let path = self.get_service_accounts_file_path();
+
+        if let Ok(content) = fs::read_to_string(path) {
+            let data: Vec<YakManServiceAccount> = serde_json::from_str(&content)?;
+            return Ok(data);
+        }
+
+        return Ok(vec![]);
+    }
+
+    async fn save_service_accounts(
+        &self,
+        service_accounts: &[YakManServiceAccount],
+    ) -> Result<(), GenericStorageError> {
+        let data = serde_json::to_string(service_accounts)?;
+        let data_file_path = self.get_service_accounts_file_path();
+        let mut data_file = File::create(data_file_path)?;
+        Write::write_all(&mut data_file, data.as_bytes())?;
+        Ok(())
+    }
+
+    async fn get_scheduled_applies(
+        &self,
+    ) -> Result<Vec<YakManScheduledApply>, GenericStorageError> {
+        let dir = self.get_scheduled_applies_dir();
+        let mut scheduled_applies: Vec<YakManScheduledApply> = vec![];
+
+        if let Ok(config_dirs) = fs::read_dir(dir) {
+            for config_dir in config_dirs {
+                for file in fs::read_dir(config_dir?.path())? {
+                    let content = fs::read_to_string(file?.path())?;
+                    scheduled_applies.push(serde_json::from_str(&content)?);
+                }
+            }
+        }
+
+        return Ok(scheduled_applies);
+    }
+
+    async fn get_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<Option<YakManScheduledApply>, GenericStorageError> {
+        let dir = self.get_scheduled_applies_dir();
+        let path = format!("{dir}/{config_id}/{instance}.json");
+
+        if let Ok(content) = fs::read_to_string(path) {
+            let data: YakManScheduledApply = serde_json::from_str(&content)?;
+            return Ok(Some(data));
+        }
+
+        return Ok(None);
+    }
+
+    async fn save_scheduled_apply(
+        &self,
+        scheduled: &YakManScheduledApply,
+    ) -> Result<(), GenericStorageError> {
+        let dir = format!(
+            "{}/{}",
+            self.get_scheduled_applies_dir(),
+            scheduled.config_id
+        );
+        fs::create_dir_all(&dir)?;
+        let path = format!("{dir}/{}.json", scheduled.instance);
+        let data: String = serde_json::to_string(&scheduled)?;
+        let mut data_file = File::create(path)?;
+        Write::write_all(&mut data_file, data.as_bytes())?;
+        return Ok(());
+    }
+
+    async fn delete_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_scheduled_applies_dir();
+        let path = format!("{dir}/{config_id}/{instance}.json");
+        if Path::new(&path).exists() {
+            remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    async fn get_scheduled_apply_lock(&self) -> Result<YakManSnapshotLock, GenericStorageError> {
+        let path = self.get_scheduled_apply_lock_file_path();
+
+        if let Ok(content) = fs::read_to_string(path) {
+            let data: YakManSnapshotLock = serde_json::from_str(&content)?;
+            return Ok(data);
+        }
+
+        return Ok(YakManSnapshotLock::unlocked());
+    }
+
+    async fn save_scheduled_apply_lock(
+        &self,
+        lock: &YakManSnapshotLock,
+    ) -> Result<(), GenericStorageError> {
+        let data = serde_json::to_string(lock)?;
+        let data_file_path = self.get_scheduled_apply_lock_file_path();
+        let mut data_file = File::create(data_file_path)?;
+        Write::write_all(&mut data_file, data.as_bytes())?;
+        Ok(())
+    }
+
+    async fn delete_user_details(&self, user_id: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_user_dir();
+        remove_file(format!("{dir}/{user_id}.json"))?;
+        return Ok(());
+    }
+
+    async fn delete_password(&self, email_hash: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_password_dir();
+        remove_file(format!("{dir}/{email_hash}.json"))?;
+        return Ok(());
+    }
+
+    async fn get_login_attempts(
+        &self,
+        key: &str,
+    ) -> Result<Option<YakManLoginAttempts>, GenericStorageError> {
+        let dir = self.get_login_attempts_dir();
+        let path = format!("{dir}/{key}.json");
+
+        if let Ok(content) = fs::read_to_string(path) {
+            let data: YakManLoginAttempts = serde_json::from_str(&content)?;
+            return Ok(Some(data));
+        }
+
+        return Ok(None);
+    }
+
+    async fn save_login_attempts(
+        &self,
+        key: &str,
+        attempts: &YakManLoginAttempts,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_login_attempts_dir();
+        let path = format!("{dir}/{key}.json");
+        let data: String = serde_json::to_string(&attempts)?;
+        let mut data_file = File::create(path)?;
+        Write::write_all(&mut data_file, data.as_bytes())?;
+        return Ok(());
+    }
+
+    async fn delete_login_attempts(&self, key: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_login_attempts_dir();
+        let path = format!("{dir}/{key}.json");
+        if Path::new(&path).exists() {
+            remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    async fn get_api_key_usage(
+        &self,
+        api_key_id: &str,
+    ) -> Result<Option<YakManApiKeyUsage>, GenericStorageError> {
+        let dir = self.get_api_key_usage_dir();
+        let path = format!("{dir}/{api_key_id}.json");
+
+        if let Ok(content) = fs::read_to_string(path) {
+            let data: YakManApiKeyUsage = serde_json::from_str(&content)?;
+            return Ok(Some(data));
+        }
+
+        return Ok(None);
+    }
+
+    async fn save_api_key_usage(
+        &self,
+        api_key_id: &str,
+        usage: &YakManApiKeyUsage,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_api_key_usage_dir();
+        let path = format!("{dir}/{api_key_id}.json");
+        let data: String = serde_json::to_string(&usage)?;
+        let mut data_file = File::create(path)?;
+        Write::write_all(&mut data_file, data.as_bytes())?;
+        return Ok(());
+    }
+
+    async fn delete_api_key_usage(&self, api_key_id: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_api_key_usage_dir();
+        let path = format!("{dir}/{api_key_id}.json");
+        if Path::new(&path).exists() {
+            remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    async fn get_revoked_refresh_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<YakManRevokedRefreshToken>, GenericStorageError> {
+        let dir = self.get_revoked_refresh_tokens_dir();
+        let path = format!("{dir}/{token_hash}.json");
+
+        if let Ok(content) = fs::read_to_string(path) {
+            let data: YakManRevokedRefreshToken = serde_json::from_str(&content)?;
+            return Ok(Some(data));
+        }
+
+        return Ok(None);
+    }
+
+    async fn save_revoked_refresh_token(
+        &self,
+        token_hash: &str,
+        revoked_token: &YakManRevokedRefreshToken,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_revoked_refresh_tokens_dir();
+        let path = format!("{dir}/{token_hash}.json");
+        let data: String = serde_json::to_string(&revoked_token)?;
+        let mut data_file = File::create(path)?;
+        Write::write_all(&mut data_file, data.as_bytes())?;
+        return Ok(());
+    }
+
+    async fn delete_expired_revoked_refresh_tokens(
+        &self,
+        now: i64,
+    ) -> Result<(), GenericStorageError> {
+        let dir = self.get_revoked_refresh_tokens_dir();
+
+        if let Ok(files) = fs::read_dir(dir) {
+            for file in files {
+                let path = file?.path();
+                let content = fs::read_to_string(&path)?;
+                let revoked_token: YakManRevokedRefreshToken = serde_json::from_str(&content)?;
+                if revoked_token.expires_at <= now {
+                    remove_file(path)?;
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    async fn get_totp(&self, user_id: &str) -> Result<Option<YakManTotp>, GenericStorageError> {
+        let dir = self.get_totp_dir();
+        let path = format!("{dir}/{user_id}.json");
+
+        if let Ok(content) = fs::read_to_string(path) {
+            let data: YakManTotp = serde_json::from_str(&content)?;
+            return Ok(Some(data));
+        }
+
+        return Ok(None);
+    }
+
+    async fn save_totp(&self, user_id: &str, totp: &YakManTotp) -> Result<(), GenericStorageError> {
+        let dir = self.get_totp_dir();
+        let path = format!("{dir}/{user_id}.json");
+        let data: String = serde_json::to_string(&totp)?;
+        let mut data_file = File::create(path)?;
+        Write::write_all(&mut data_file, data.as_bytes())?;
+        return Ok(());
+    }
+
+    async fn delete_totp(&self, user_id: &str) -> Result<(), GenericStorageError> {
+        let dir = self.get_totp_dir();
+        let path = format!("{dir}/{user_id}.json");
+        if Path::new(&path).exists() {
+            remove_file(path)?;
+        }
+        Ok(())
+    }
+
     async fn get_snapshot_lock(&self) -> Result<YakManSnapshotLock, GenericStorageError> {
         let path = self.get_snapshot_lock_file_path();
         let data = fs::read_to_string(path)?;
@@ -634,6 +1124,26 @@ impl LocalFileStorageAdapter {
         return format!("{yakman_dir}/configs.json");
     }
 
+    fn get_service_accounts_file_path(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/service-accounts.json");
+    }
+
+    fn get_scheduled_applies_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/scheduled-applies");
+    }
+
+    fn get_scheduled_apply_lock_file_path(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/scheduled-apply-lock.json");
+    }
+
+    fn get_notification_delivery_lock_file_path(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/notification-delivery-lock.json");
+    }
+
     fn get_api_key_file_path(&self) -> String {
         let yakman_dir = self.get_yakman_dir();
         return format!("{yakman_dir}/api-keys.json");
@@ -684,11 +1194,46 @@ impl LocalFileStorageAdapter {
         return format!("{yakman_dir}/passwords");
     }
 
+    fn get_api_key_usage_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/api-key-usage");
+    }
+
+    fn get_revoked_refresh_tokens_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/revoked-refresh-tokens");
+    }
+
+    fn get_login_attempts_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/login-attempts");
+    }
+
+    fn get_totp_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/totp");
+    }
+
     fn get_password_reset_link_dir(&self) -> String {
         let yakman_dir = self.get_yakman_dir();
         return format!("{yakman_dir}/password-reset-links");
     }
 
+    fn get_notification_deliveries_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/notification-deliveries");
+    }
+
+    fn get_pending_notification_deliveries_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/pending-notification-deliveries");
+    }
+
+    fn get_audit_log_dir(&self) -> String {
+        let yakman_dir = self.get_yakman_dir();
+        return format!("{yakman_dir}/audit-log");
+    }
+
     fn get_config_details_dir(&self) -> String {
         let yakman_dir = self.get_yakman_dir();
         return format!("{yakman_dir}/configs");
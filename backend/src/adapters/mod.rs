@@ -1,9 +1,11 @@
 use std::{env, sync::Arc};
 
 use crate::model::{
-    ConfigDetails, ConfigInstanceRevision, LabelType, YakManApiKey, YakManConfig, YakManPassword,
-    YakManPasswordResetLink, YakManProject, YakManProjectDetails, YakManSnapshotLock, YakManTeam,
-    YakManTeamDetails, YakManUser, YakManUserDetails,
+    ConfigDetails, ConfigInstanceRevision, LabelType, YakManApiKey, YakManApiKeyUsage,
+    YakManAuditLogEntry, YakManConfig, YakManLoginAttempts, YakManNotificationDelivery,
+    YakManPassword, YakManPasswordResetLink, YakManProject, YakManProjectDetails,
+    YakManRevokedRefreshToken, YakManScheduledApply, YakManServiceAccount, YakManSnapshotLock,
+    YakManTeam, YakManTeamDetails, YakManTotp, YakManUser, YakManUserDetails,
 };
 use anyhow::Context;
 use async_trait::async_trait;
@@ -23,6 +25,9 @@ pub mod in_memory;
 pub mod local_file;
 pub mod redis;
 
+/// Object store adapters fetch listed objects concurrently, up to this many requests at a time
+pub const MAX_CONCURRENT_OBJECT_READS: usize = 16;
+
 pub async fn init_adapter_from_env() -> Arc<dyn KVStorageAdapter> {
     let adapter_name = env::var("YAKMAN_ADAPTER").expect("$YAKMAN_ADAPTER is not set");
 
@@ -159,10 +164,62 @@ pub trait KVStorageAdapter: Sync + Send {
 
     async fn save_users(&self, users: &[YakManUser]) -> Result<(), GenericStorageError>;
 
+    async fn delete_user_details(&self, user_id: &str) -> Result<(), GenericStorageError>;
+
     async fn get_api_keys(&self) -> Result<Vec<YakManApiKey>, GenericStorageError>;
 
     async fn save_api_keys(&self, api_keys: &[YakManApiKey]) -> Result<(), GenericStorageError>;
 
+    async fn get_api_key_usage(
+        &self,
+        api_key_id: &str,
+    ) -> Result<Option<YakManApiKeyUsage>, GenericStorageError>;
+
+    async fn save_api_key_usage(
+        &self,
+        api_key_id: &str,
+        usage: &YakManApiKeyUsage,
+    ) -> Result<(), GenericStorageError>;
+
+    async fn delete_api_key_usage(&self, api_key_id: &str) -> Result<(), GenericStorageError>;
+
+    async fn get_service_accounts(&self) -> Result<Vec<YakManServiceAccount>, GenericStorageError>;
+
+    async fn save_service_accounts(
+        &self,
+        service_accounts: &[YakManServiceAccount],
+    ) -> Result<(), GenericStorageError>;
+
+    /// Approved revisions waiting to be applied by the scheduled apply worker, in no particular order
+    async fn get_scheduled_applies(&self)
+        -> Result<Vec<YakManScheduledApply>, GenericStorageError>;
+
+    async fn get_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<Option<YakManScheduledApply>, GenericStorageError>;
+
+    /// Each instance's schedule is stored under its own key, so scheduling or cancelling never rewrites other schedules
+    async fn save_scheduled_apply(
+        &self,
+        scheduled: &YakManScheduledApply,
+    ) -> Result<(), GenericStorageError>;
+
+    async fn delete_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<(), GenericStorageError>;
+
+    /// Leader lock of the scheduled apply worker, uses the same format as the snapshot lock
+    async fn get_scheduled_apply_lock(&self) -> Result<YakManSnapshotLock, GenericStorageError>;
+
+    async fn save_scheduled_apply_lock(
+        &self,
+        lock: &YakManSnapshotLock,
+    ) -> Result<(), GenericStorageError>;
+
     async fn get_password(
         &self,
         email_hash: &str,
@@ -174,6 +231,8 @@ pub trait KVStorageAdapter: Sync + Send {
         password: &YakManPassword,
     ) -> Result<(), GenericStorageError>;
 
+    async fn delete_password(&self, email_hash: &str) -> Result<(), GenericStorageError>;
+
     async fn get_password_reset_link(
         &self,
         id: &str,
@@ -204,6 +263,110 @@ pub trait KVStorageAdapter: Sync + Send {
 
     async fn delete_team_details(&self, team_id: &str) -> Result<(), GenericStorageError>;
 
+    /// Delivered and failed deliveries of the project delivery log, in no particular order
+    async fn get_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError>;
+
+    /// Each delivery is stored under its own key, so completing a delivery never rewrites other deliveries
+    async fn save_notification_delivery(
+        &self,
+        project_id: &str,
+        delivery: &YakManNotificationDelivery,
+    ) -> Result<(), GenericStorageError>;
+
+    async fn delete_notification_delivery(
+        &self,
+        project_id: &str,
+        delivery_id: &str,
+    ) -> Result<(), GenericStorageError>;
+
+    /// Removes the whole delivery log of a project
+    async fn delete_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<(), GenericStorageError>;
+
+    /// Deliveries waiting to be sent for every project, in no particular order.
+    /// They are kept apart from the delivery log, so polling the outbox never reads completed deliveries.
+    async fn get_pending_notification_deliveries(
+        &self,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError>;
+
+    async fn save_pending_notification_delivery(
+        &self,
+        delivery: &YakManNotificationDelivery,
+    ) -> Result<(), GenericStorageError>;
+
+    async fn delete_pending_notification_delivery(
+        &self,
+        delivery_id: &str,
+    ) -> Result<(), GenericStorageError>;
+
+    /// Leader lock of the notification delivery worker, uses the same format as the snapshot lock
+    async fn get_notification_delivery_lock(
+        &self,
+    ) -> Result<YakManSnapshotLock, GenericStorageError>;
+
+    async fn save_notification_delivery_lock(
+        &self,
+        lock: &YakManSnapshotLock,
+    ) -> Result<(), GenericStorageError>;
+
+    /// Audit log entries, bucketed by UTC day (YYYY-MM-DD), in no particular order.
+    /// Each entry is its own object, so the object store adapters list the day and then fetch every entry,
+    /// which costs one read per entry (fetched concurrently) on top of the list requests.
+    async fn get_audit_log(
+        &self,
+        day: &str,
+    ) -> Result<Vec<YakManAuditLogEntry>, GenericStorageError>;
+
+    /// Each entry is stored under its own key, so appending never rewrites existing entries
+    async fn save_audit_log_entry(
+        &self,
+        day: &str,
+        entry: &YakManAuditLogEntry,
+    ) -> Result<(), GenericStorageError>;
+
+    /// Failed login counters, keyed by a hash of the account or client IP
+    async fn get_login_attempts(
+        &self,
+        key: &str,
+    ) -> Result<Option<YakManLoginAttempts>, GenericStorageError>;
+
+    async fn save_login_attempts(
+        &self,
+        key: &str,
+        attempts: &YakManLoginAttempts,
+    ) -> Result<(), GenericStorageError>;
+
+    async fn delete_login_attempts(&self, key: &str) -> Result<(), GenericStorageError>;
+
+    /// Refresh tokens revoked by logging out, keyed by a hash of the token
+    async fn get_revoked_refresh_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<YakManRevokedRefreshToken>, GenericStorageError>;
+
+    async fn save_revoked_refresh_token(
+        &self,
+        token_hash: &str,
+        revoked_token: &YakManRevokedRefreshToken,
+    ) -> Result<(), GenericStorageError>;
+
+    /// Deletes the revoked refresh tokens that expired before `now`. Redis expires them on its own.
+    async fn delete_expired_revoked_refresh_tokens(
+        &self,
+        now: i64,
+    ) -> Result<(), GenericStorageError>;
+
+    async fn get_totp(&self, user_id: &str) -> Result<Option<YakManTotp>, GenericStorageError>;
+
+    async fn save_totp(&self, user_id: &str, totp: &YakManTotp) -> Result<(), GenericStorageError>;
+
+    async fn delete_totp(&self, user_id: &str) -> Result<(), GenericStorageError>;
+
     async fn get_snapshot_lock(&self) -> Result<YakManSnapshotLock, GenericStorageError>;
 
     async fn save_snapshot_lock(
@@ -4,9 +4,11 @@ use std::env;
 use super::KVStorageAdapter;
 use crate::adapters::errors::GenericStorageError;
 use crate::model::{
-    ConfigDetails, ConfigInstanceRevision, LabelType, YakManApiKey, YakManConfig, YakManPassword,
-    YakManPasswordResetLink, YakManProject, YakManProjectDetails, YakManSnapshotLock, YakManTeam,
-    YakManTeamDetails, YakManUser, YakManUserDetails,
+    ConfigDetails, ConfigInstanceRevision, LabelType, YakManApiKey, YakManApiKeyUsage,
+    YakManAuditLogEntry, YakManConfig, YakManLoginAttempts, YakManNotificationDelivery,
+    YakManPassword, YakManPasswordResetLink, YakManProject, YakManProjectDetails,
+    YakManRevokedRefreshToken, YakManScheduledApply, YakManServiceAccount, YakManSnapshotLock,
+    YakManTeam, YakManTeamDetails, YakManTotp, YakManUser, YakManUserDetails,
 };
 use anyhow::Result;
 use async_trait::async_trait;
@@ -349,6 +351,344 @@ impl KVStorageAdapter for RedisStorageAdapter {
         Ok(())
     }
 
+    async fn get_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let values: Vec<String> =
+            connection.hvals(self.get_notification_deliveries_key(project_id))?;
+        let mut deliveries = vec![];
+        for value in values {
+            deliveries.push(serde_json::from_str(&value)?);
+        }
+        return Ok(deliveries);
+    }
+
+    async fn save_notification_delivery(
+        &self,
+        project_id: &str,
+        delivery: &YakManNotificationDelivery,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.hset(
+            self.get_notification_deliveries_key(project_id),
+            &delivery.id,
+            serde_json::to_string(&delivery)?,
+        )?;
+        Ok(())
+    }
+
+    async fn delete_notification_delivery(
+        &self,
+        project_id: &str,
+        delivery_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.hdel(
+            self.get_notification_deliveries_key(project_id),
+            delivery_id,
+        )?;
+        Ok(())
+    }
+
+    async fn delete_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.del(self.get_notification_deliveries_key(project_id))?;
+        Ok(())
+    }
+
+    async fn get_pending_notification_deliveries(
+        &self,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let values: Vec<String> =
+            connection.hvals(self.get_pending_notification_deliveries_key())?;
+        let mut deliveries = vec![];
+        for value in values {
+            deliveries.push(serde_json::from_str(&value)?);
+        }
+        return Ok(deliveries);
+    }
+
+    async fn save_pending_notification_delivery(
+        &self,
+        delivery: &YakManNotificationDelivery,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.hset(
+            self.get_pending_notification_deliveries_key(),
+            &delivery.id,
+            serde_json::to_string(&delivery)?,
+        )?;
+        Ok(())
+    }
+
+    async fn delete_pending_notification_delivery(
+        &self,
+        delivery_id: &str,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.hdel(self.get_pending_notification_deliveries_key(), delivery_id)?;
+        Ok(())
+    }
+
+    async fn get_notification_delivery_lock(
+        &self,
+    ) -> Result<YakManSnapshotLock, GenericStorageError> {
+        return Ok(self
+            .get_optional_data(&self.get_notification_delivery_lock_key())
+            .await?
+            .unwrap_or(YakManSnapshotLock::unlocked()));
+    }
+
+    async fn save_notification_delivery_lock(
+        &self,
+        lock: &YakManSnapshotLock,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.set(
+            self.get_notification_delivery_lock_key(),
+            serde_json::to_string(&lock)?,
+        )?;
+        Ok(())
+    }
+
+    async fn get_audit_log(
+        &self,
+        day: &str,
+    ) -> Result<Vec<YakManAuditLogEntry>, GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let values: Vec<String> = connection.hvals(self.get_audit_log_entries_key(day))?;
+        let mut entries = vec![];
+        for value in values {
+            entries.push(serde_json::from_str(&value)?);
+        }
+        return Ok(entries);
+    }
+
+    async fn save_audit_log_entry(
+        &self,
+        day: &str,
+        entry: &YakManAuditLogEntry,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.hset_nx(
+            self.get_audit_log_entries_key(day),
+            &entry.id,
+            serde_json::to_string(&entry)?,
+        )?;
+        Ok(())
+    }
+
+    async fn get_service_accounts(&self) -> Result<Vec<YakManServiceAccount>, GenericStorageError> {
+        return Ok(self
+            .get_optional_data(&self.get_service_accounts_key())
+            .await?
+            .unwrap_or_default());
+    }
+
+    async fn save_service_accounts(
+        &self,
+        service_accounts: &[YakManServiceAccount],
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.set(
+            self.get_service_accounts_key(),
+            serde_json::to_string(&service_accounts)?,
+        )?;
+        Ok(())
+    }
+
+    async fn get_scheduled_applies(
+        &self,
+    ) -> Result<Vec<YakManScheduledApply>, GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let values: Vec<String> = connection.hvals(self.get_scheduled_applies_key())?;
+        let mut scheduled_applies = vec![];
+        for value in values {
+            scheduled_applies.push(serde_json::from_str(&value)?);
+        }
+        return Ok(scheduled_applies);
+    }
+
+    async fn get_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<Option<YakManScheduledApply>, GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let value: Option<String> = connection.hget(
+            self.get_scheduled_applies_key(),
+            self.get_scheduled_apply_field(config_id, instance),
+        )?;
+        if let Some(value) = value {
+            return Ok(Some(serde_json::from_str(&value)?));
+        }
+        return Ok(None);
+    }
+
+    async fn save_scheduled_apply(
+        &self,
+        scheduled: &YakManScheduledApply,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.hset(
+            self.get_scheduled_applies_key(),
+            self.get_scheduled_apply_field(&scheduled.config_id, &scheduled.instance),
+            serde_json::to_string(&scheduled)?,
+        )?;
+        Ok(())
+    }
+
+    async fn delete_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.hdel(
+            self.get_scheduled_applies_key(),
+            self.get_scheduled_apply_field(config_id, instance),
+        )?;
+        Ok(())
+    }
+
+    async fn get_scheduled_apply_lock(&self) -> Result<YakManSnapshotLock, GenericStorageError> {
+        return Ok(self
+            .get_optional_data(&self.get_scheduled_apply_lock_key())
+            .await?
+            .unwrap_or(YakManSnapshotLock::unlocked()));
+    }
+
+    async fn save_scheduled_apply_lock(
+        &self,
+        lock: &YakManSnapshotLock,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.set(
+            self.get_scheduled_apply_lock_key(),
+            serde_json::to_string(&lock)?,
+        )?;
+        Ok(())
+    }
+
+    async fn delete_user_details(&self, user_id: &str) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.del(self.get_user_key(user_id))?;
+        return Ok(());
+    }
+
+    async fn delete_password(&self, email_hash: &str) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.del(self.get_password_key(email_hash))?;
+        return Ok(());
+    }
+
+    async fn get_login_attempts(
+        &self,
+        key: &str,
+    ) -> Result<Option<YakManLoginAttempts>, GenericStorageError> {
+        return self
+            .get_optional_data(&self.get_login_attempts_key(key))
+            .await;
+    }
+
+    async fn save_login_attempts(
+        &self,
+        key: &str,
+        attempts: &YakManLoginAttempts,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.set(
+            self.get_login_attempts_key(key),
+            serde_json::to_string(&attempts)?,
+        )?;
+        Ok(())
+    }
+
+    async fn delete_login_attempts(&self, key: &str) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.del(self.get_login_attempts_key(key))?;
+        Ok(())
+    }
+
+    async fn get_api_key_usage(
+        &self,
+        api_key_id: &str,
+    ) -> Result<Option<YakManApiKeyUsage>, GenericStorageError> {
+        return self
+            .get_optional_data(&self.get_api_key_usage_key(api_key_id))
+            .await;
+    }
+
+    async fn save_api_key_usage(
+        &self,
+        api_key_id: &str,
+        usage: &YakManApiKeyUsage,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.set(
+            self.get_api_key_usage_key(api_key_id),
+            serde_json::to_string(&usage)?,
+        )?;
+        Ok(())
+    }
+
+    async fn delete_api_key_usage(&self, api_key_id: &str) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.del(self.get_api_key_usage_key(api_key_id))?;
+        Ok(())
+    }
+
+    async fn get_revoked_refresh_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<YakManRevokedRefreshToken>, GenericStorageError> {
+        return self
+            .get_optional_data(&self.get_revoked_refresh_token_key(token_hash))
+            .await;
+    }
+
+    async fn save_revoked_refresh_token(
+        &self,
+        token_hash: &str,
+        revoked_token: &YakManRevokedRefreshToken,
+    ) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let key = self.get_revoked_refresh_token_key(token_hash);
+        let _: () = connection.set(&key, serde_json::to_string(&revoked_token)?)?;
+        let _: () = connection.pexpire_at(&key, revoked_token.expires_at)?;
+        Ok(())
+    }
+
+    async fn delete_expired_revoked_refresh_tokens(
+        &self,
+        _now: i64,
+    ) -> Result<(), GenericStorageError> {
+        // The keys are saved with an expiry, so redis removes them itself
+        Ok(())
+    }
+
+    async fn get_totp(&self, user_id: &str) -> Result<Option<YakManTotp>, GenericStorageError> {
+        return self.get_optional_data(&self.get_totp_key(user_id)).await;
+    }
+
+    async fn save_totp(&self, user_id: &str, totp: &YakManTotp) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.set(self.get_totp_key(user_id), serde_json::to_string(&totp)?)?;
+        Ok(())
+    }
+
+    async fn delete_totp(&self, user_id: &str) -> Result<(), GenericStorageError> {
+        let mut connection = self.get_connection()?;
+        let _: () = connection.del(self.get_totp_key(user_id))?;
+        Ok(())
+    }
+
     async fn get_snapshot_lock(&self) -> Result<YakManSnapshotLock, GenericStorageError> {
         return self
             .get_optional_data(&self.get_snapshot_lock_key())
@@ -521,6 +861,26 @@ impl RedisStorageAdapter {
         format!("{REDIS_PREFIX}_USERS")
     }
 
+    fn get_service_accounts_key(&self) -> String {
+        return format!("{REDIS_PREFIX}_SERVICE_ACCOUNTS");
+    }
+
+    fn get_scheduled_applies_key(&self) -> String {
+        return format!("{REDIS_PREFIX}_SCHEDULED_APPLY_ENTRIES");
+    }
+
+    fn get_scheduled_apply_field(&self, config_id: &str, instance: &str) -> String {
+        return format!("{config_id}/{instance}");
+    }
+
+    fn get_scheduled_apply_lock_key(&self) -> String {
+        return format!("{REDIS_PREFIX}_SCHEDULED_APPLY_LOCK");
+    }
+
+    fn get_notification_delivery_lock_key(&self) -> String {
+        return format!("{REDIS_PREFIX}_NOTIFICATION_DELIVERY_LOCK");
+    }
+
     fn get_api_keys_key(&self) -> String {
         return format!("{REDIS_PREFIX}_API_KEYS");
     }
@@ -557,6 +917,34 @@ impl RedisStorageAdapter {
         return format!("{REDIS_PREFIX}_PASSWORDS_{email_hash}");
     }
 
+    fn get_notification_deliveries_key(&self, project_id: &str) -> String {
+        return format!("{REDIS_PREFIX}_NOTIFICATION_DELIVERY_ENTRIES_{project_id}");
+    }
+
+    fn get_pending_notification_deliveries_key(&self) -> String {
+        return format!("{REDIS_PREFIX}_PENDING_NOTIFICATION_DELIVERIES");
+    }
+
+    fn get_audit_log_entries_key(&self, day: &str) -> String {
+        return format!("{REDIS_PREFIX}_AUDIT_LOG_ENTRIES_{day}");
+    }
+
+    fn get_api_key_usage_key(&self, api_key_id: &str) -> String {
+        return format!("{REDIS_PREFIX}_API_KEY_USAGE_{api_key_id}");
+    }
+
+    fn get_revoked_refresh_token_key(&self, token_hash: &str) -> String {
+        return format!("{REDIS_PREFIX}_REVOKED_REFRESH_TOKENS_{token_hash}");
+    }
+
+    fn get_login_attempts_key(&self, key: &str) -> String {
+        return format!("{REDIS_PREFIX}_LOGIN_ATTEMPTS_{key}");
+    }
+
+    fn get_totp_key(&self, user_id: &str) -> String {
+        return format!("{REDIS_PREFIX}_TOTP_{user_id}");
+    }
+
     fn get_password_reset_link_key(&self, id: &str) -> String {
         return format!("{REDIS_PREFIX}_PASSWORD_RESET_LINK_{id}");
     }
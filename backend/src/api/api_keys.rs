@@ -5,12 +5,12 @@ use crate::error::YakManApiError;
 use crate::middleware::roles::YakManRoleBinding;
 use crate::middleware::YakManPrinciple;
 use crate::model::YakManApiKey;
+use crate::model::YakManApiKeyScope;
 use crate::model::YakManRole;
-use crate::model::YakManUser;
 use crate::services::StorageService;
 use actix_web::web::Json;
 use actix_web::{delete, HttpResponse, Responder};
-use actix_web::{get, put, web};
+use actix_web::{get, post, put, web};
 use actix_web_grants::authorities::AuthDetails;
 use actix_web_validation::Validated;
 use chrono::Utc;
@@ -19,8 +19,17 @@ use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
+/// An api key with its last recorded usage
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: YakManApiKey,
+    /// Usage is recorded at most every few minutes, so this may lag behind
+    pub last_used_at: Option<i64>,
+}
+
 /// Get Api Keys
-#[utoipa::path(responses((status = 200, body = Vec<YakManUser>)))]
+#[utoipa::path(responses((status = 200, body = Vec<ApiKeyResponse>)))]
 #[get("/v1/api-keys")]
 pub async fn get_api_keys(
     auth_details: AuthDetails<YakManRoleBinding>,
@@ -32,14 +41,22 @@ pub async fn get_api_keys(
         return Err(YakManApiError::forbidden());
     }
 
-    let mut api_keys = storage_service.get_api_keys().await?;
+    let api_keys = storage_service.get_api_keys().await?;
+    let api_key_ids: Vec<String> = api_keys.iter().map(|key| key.id.clone()).collect();
+    let usages = storage_service.get_api_key_usages(&api_key_ids).await?;
 
-    // Avoid exposing the hash outside of the API
-    api_keys = api_keys
+    let api_keys: Vec<ApiKeyResponse> = api_keys
         .into_iter()
         .map(|mut key| {
+            // Avoid exposing the hash outside of the API
             key.hash = String::default();
-            key
+            key.previous_hash = None;
+
+            let last_used_at = usages.get(&key.id).map(|usage| usage.last_used_at);
+            ApiKeyResponse {
+                api_key: key,
+                last_used_at: last_used_at,
+            }
         })
         .collect();
 
@@ -51,6 +68,11 @@ pub struct CreateApiKeyRequest {
     #[validate(length(min = 1))]
     pub project_id: String,
     pub role: YakManRole,
+    /// Unix timestamp (in milliseconds) after which the key is rejected
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    #[serde(default)]
+    pub scope: Option<YakManApiKeyScope>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
@@ -84,16 +106,46 @@ pub async fn create_api_key(
         return Err(YakManApiError::bad_request("Invalid project"));
     };
 
+    if let Some(scope) = &request.scope {
+        if scope.operations.is_empty() {
+            return Err(YakManApiError::bad_request(
+                "Scope must allow at least one operation",
+            ));
+        }
+
+        let project_configs = storage_service
+            .get_visible_configs(Some(request.project_id.to_string()))
+            .await?;
+        for config_id in &scope.config_ids {
+            if !project_configs.iter().any(|c| &c.id == config_id) {
+                return Err(YakManApiError::bad_request("Invalid config in scope"));
+            }
+        }
+    }
+
     let now = Utc::now().timestamp_millis();
-    let new_api_key = format!("{API_KEY_PREFIX}{}", Uuid::new_v4());
+
+    if matches!(request.expires_at, Some(expires_at) if expires_at <= now) {
+        return Err(YakManApiError::bad_request(
+            "Expiration must be in the future",
+        ));
+    }
+
+    let new_api_key = generate_api_key_secret();
 
     let ak = YakManApiKey {
         id: format!("apikey-{}", Uuid::new_v4()),
         hash: sha256::digest(&new_api_key),
-        project_id: request.project_id.to_string(),
-        role: request.role.clone(),
+        project_id: Some(request.project_id.to_string()),
+        role: Some(request.role.clone()),
         created_at: now,
         created_by_user_id: user_id.to_string(),
+        expires_at: request.expires_at,
+        rotated_at: None,
+        previous_hash: None,
+        previous_hash_expires_at: None,
+        scope: request.scope,
+        service_account_id: None,
     };
 
     storage_service.save_api_key(ak).await?;
@@ -103,6 +155,63 @@ pub async fn create_api_key(
     }));
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema, Validate)]
+pub struct RotateApiKeyRequest {
+    /// How long the previous secret remains valid after rotating. Defaults to 0 (revoked immediately).
+    #[serde(default)]
+    #[validate(range(max = 604800))]
+    pub grace_period_seconds: u32,
+}
+
+/// Rotate an API key, issuing a new secret for the same key
+#[utoipa::path(request_body = RotateApiKeyRequest, responses((status = 200, body = CreateApiKeyResponse)))]
+#[post("/v1/api-keys/{id}/rotate")]
+pub async fn rotate_api_key(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+    path: web::Path<String>,
+    Validated(Json(request)): Validated<Json<RotateApiKeyRequest>>,
+) -> Result<impl Responder, YakManApiError> {
+    let is_admin = YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities);
+
+    if !is_admin {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let id = path.into_inner();
+    let Some(mut api_key) = storage_service.get_api_key_by_id(&id).await? else {
+        return Err(YakManApiError::not_found("API key not found"));
+    };
+
+    let now = Utc::now().timestamp_millis();
+    if api_key.is_expired(now) {
+        return Err(YakManApiError::bad_request("API key is expired"));
+    }
+
+    let new_api_key = generate_api_key_secret();
+
+    if request.grace_period_seconds > 0 {
+        api_key.previous_hash = Some(api_key.hash);
+        api_key.previous_hash_expires_at =
+            Some(now + i64::from(request.grace_period_seconds) * 1000);
+    } else {
+        api_key.previous_hash = None;
+        api_key.previous_hash_expires_at = None;
+    }
+    api_key.hash = sha256::digest(&new_api_key);
+    api_key.rotated_at = Some(now);
+
+    storage_service.save_api_key(api_key).await?;
+
+    return Ok(web::Json(CreateApiKeyResponse {
+        api_key: new_api_key,
+    }));
+}
+
+pub(crate) fn generate_api_key_secret() -> String {
+    return format!("{API_KEY_PREFIX}{}", Uuid::new_v4());
+}
+
 /// Revoke an API key
 #[utoipa::path(responses((status = 200, body = ())))]
 #[delete("/v1/api-keys/{id}")]
@@ -137,10 +246,16 @@ mod tests {
         YakManApiKey {
             id: "apikey-d66a57c5-a425-4157-b790-13756084d0cf".to_string(),
             hash: "5fd924625f6ab16a19cc9807c7c506ae1813490e4ba675f843d5a10e0baacdb8".to_string(),
-            project_id: "91d16380-9df0-41dc-8542-c2dcf3633e7b".to_string(),
-            role: YakManRole::Viewer,
+            project_id: Some("91d16380-9df0-41dc-8542-c2dcf3633e7b".to_string()),
+            role: Some(YakManRole::Viewer),
             created_at: 1704330312738,
             created_by_user_id: "c34e15d0-0697-47c1-b36a-7f3456c68f1d".to_string(),
+            expires_at: None,
+            rotated_at: None,
+            previous_hash: None,
+            previous_hash_expires_at: None,
+            scope: None,
+            service_account_id: None,
         }
     }
 
@@ -184,13 +299,60 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn get_api_keys_should_return_last_used_at_without_rewriting_api_keys() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let fake_api_key = fake_api_key();
+        storage_service.save_api_key(fake_api_key.clone()).await?;
+        storage_service
+            .record_api_key_usage(&fake_api_key.id)
+            .await?;
+
+        // Recording usage after the key was deleted must not bring it back or leave its usage behind
+        storage_service.delete_api_key(&fake_api_key.id).await?;
+        storage_service
+            .record_api_key_usage(&fake_api_key.id)
+            .await?;
+        assert!(storage_service.get_api_keys().await?.is_empty());
+        assert!(storage_service
+            .get_api_key_usages(std::slice::from_ref(&fake_api_key.id))
+            .await?
+            .is_empty());
+
+        storage_service.save_api_key(fake_api_key.clone()).await?;
+        storage_service
+            .record_api_key_usage(&fake_api_key.id)
+            .await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(get_api_keys),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/v1/api-keys").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let value: Value = body_to_json_value(resp.map_into_boxed_body()).await?;
+
+        let first = &value.as_array().unwrap()[0];
+        assert!(first["last_used_at"].as_i64().is_some());
+
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn create_api_keys_should_create_the_api_key_properly() -> Result<()> {
         prepare_for_actix_test()?;
 
         let storage_service = test_storage_service().await?;
 
-        let project_id = storage_service.create_project("foo", None).await?;
+        let project_id = storage_service.create_project("foo", vec![]).await?;
 
         let api_keys = storage_service.get_api_keys().await?;
         assert_eq!(0, api_keys.len());
@@ -214,6 +376,8 @@ mod tests {
             .set_json(&CreateApiKeyRequest {
                 project_id: project_id.clone(),
                 role: YakManRole::Viewer,
+                expires_at: None,
+                scope: None,
             })
             .to_request();
         let resp = test::call_service(&app, req).await;
@@ -229,8 +393,8 @@ mod tests {
         assert_eq!(1, api_keys.len());
 
         let api_key = &api_keys[0];
-        assert_eq!(project_id, api_key.project_id);
-        assert_eq!(YakManRole::Viewer, api_key.role);
+        assert_eq!(Some(project_id), api_key.project_id);
+        assert_eq!(Some(YakManRole::Viewer), api_key.role);
         assert_eq!(
             "c34e15d0-0697-47c1-b36a-7f3456c68f1d",
             api_key.created_by_user_id
@@ -270,4 +434,125 @@ mod tests {
 
         Ok(())
     }
+
+    #[actix_web::test]
+    async fn create_api_keys_should_reject_expiration_in_the_past() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let project_id = storage_service.create_project("foo", vec![]).await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some("c34e15d0-0697-47c1-b36a-7f3456c68f1d".to_string()),
+                    });
+
+                    srv.call(req)
+                })
+                .service(create_api_key),
+        )
+        .await;
+        let req = test::TestRequest::put()
+            .uri("/v1/api-keys")
+            .set_json(&CreateApiKeyRequest {
+                project_id: project_id,
+                role: YakManRole::Viewer,
+                expires_at: Some(Utc::now().timestamp_millis() - 1000),
+                scope: None,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+
+        let api_keys = storage_service.get_api_keys().await?;
+        assert_eq!(0, api_keys.len());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn rotate_api_key_should_keep_previous_secret_valid_during_grace_period() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let fake_api_key = fake_api_key();
+        storage_service.save_api_key(fake_api_key.clone()).await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(rotate_api_key),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/api-keys/{}/rotate", fake_api_key.id))
+            .set_json(&RotateApiKeyRequest {
+                grace_period_seconds: 3600,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let value: Value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        let new_secret = value["api_key"].as_str().unwrap();
+        let new_hash = sha256::digest(new_secret);
+        let now = Utc::now().timestamp_millis();
+
+        let api_key = storage_service
+            .get_api_key_by_hash(&new_hash)
+            .await?
+            .unwrap();
+        assert_eq!(fake_api_key.id, api_key.id);
+        assert!(api_key.is_valid_for_hash(&new_hash, now));
+        assert!(api_key.rotated_at.is_some());
+
+        let old_api_key = storage_service
+            .get_api_key_by_hash(&fake_api_key.hash)
+            .await?
+            .unwrap();
+        assert_eq!(fake_api_key.id, old_api_key.id);
+        assert!(old_api_key.is_valid_for_hash(&fake_api_key.hash, now));
+        assert!(!old_api_key.is_valid_for_hash(&fake_api_key.hash, now + 3600 * 1000));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn rotate_api_key_without_grace_period_should_revoke_previous_secret() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let fake_api_key = fake_api_key();
+        storage_service.save_api_key(fake_api_key.clone()).await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(rotate_api_key),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/api-keys/{}/rotate", fake_api_key.id))
+            .set_json(&RotateApiKeyRequest {
+                grace_period_seconds: 0,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let old_api_key = storage_service
+            .get_api_key_by_hash(&fake_api_key.hash)
+            .await?;
+        assert!(old_api_key.is_none());
+
+        Ok(())
+    }
 }
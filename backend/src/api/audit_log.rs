@@ -0,0 +1,173 @@
+use crate::{
+    error::YakManApiError,
+    middleware::roles::YakManRoleBinding,
+    model::{YakManAuditAction, YakManAuditLogEntry, YakManAuditTargetType, YakManRole},
+    services::{audit::AuditLogFilter, StorageService},
+};
+use actix_web::{get, web, Responder};
+use actix_web_grants::authorities::AuthDetails;
+use chrono::Utc;
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+const DEFAULT_RANGE_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+/// Each day in the range is a separate read from storage
+const MAX_RANGE_MS: i64 = 90 * 24 * 60 * 60 * 1000;
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+#[derive(Deserialize, IntoParams)]
+pub struct GetAuditLogQuery {
+    /// Start of the range as a unix timestamp in milliseconds, defaults to 7 days before `to`
+    pub from: Option<i64>,
+    /// End of the range as a unix timestamp in milliseconds, defaults to now
+    pub to: Option<i64>,
+    /// The user or api key id that made the change
+    pub actor: Option<String>,
+    pub action: Option<YakManAuditAction>,
+    pub target_type: Option<YakManAuditTargetType>,
+    pub target_id: Option<String>,
+    /// Maximum number of entries to return (default 100, max 1000)
+    pub limit: Option<usize>,
+}
+
+/// Get audit log entries, newest first
+#[utoipa::path(params(GetAuditLogQuery), responses((status = 200, body = Vec<YakManAuditLogEntry>)))]
+#[get("/v1/audit-log")]
+pub async fn get_audit_log(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    query: web::Query<GetAuditLogQuery>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let query = query.into_inner();
+    let to_ms = query.to.unwrap_or_else(|| Utc::now().timestamp_millis());
+    let from_ms = query.from.unwrap_or(to_ms - DEFAULT_RANGE_MS);
+    if from_ms > to_ms {
+        return Err(YakManApiError::bad_request("from must be before to"));
+    }
+    if to_ms - from_ms > MAX_RANGE_MS {
+        return Err(YakManApiError::bad_request(
+            "The range cannot be longer than 90 days",
+        ));
+    }
+
+    let filter = AuditLogFilter {
+        from_ms: from_ms,
+        to_ms: to_ms,
+        actor: query.actor,
+        action: query.action,
+        target_type: query.target_type,
+        target_id: query.target_id,
+        limit: query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT),
+    };
+
+    let entries = storage_service.get_audit_log(&filter).await?;
+    return Ok(web::Json(entries));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::audit;
+    use crate::test_utils::*;
+    use crate::{api::projects::ProjectNotificationSettings, model::LabelType};
+    use actix_web::{test, web::Data, App};
+    use actix_web_grants::GrantsMiddleware;
+    use anyhow::Result;
+    use serde_json::Value;
+
+    #[actix_web::test]
+    async fn get_audit_log_should_return_filtered_mutations() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let project_id = audit::with_actor(Some(String::from("u1")), async {
+            let notification_settings: Vec<ProjectNotificationSettings> = vec![];
+            storage_service
+                .create_project("foo", notification_settings.clone())
+                .await?;
+            let project_id = storage_service.get_projects().await?[0].id.clone();
+            storage_service
+                .update_project(
+                    &project_id,
+                    "bar",
+                    notification_settings,
+                    Default::default(),
+                    vec![],
+                )
+                .await?;
+            storage_service
+                .create_label(LabelType {
+                    id: String::from("l1"),
+                    name: String::from("env"),
+                    description: String::from(""),
+                    options: vec![String::from("dev")],
+                })
+                .await?;
+            return anyhow::Ok(project_id);
+        })
+        .await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(get_audit_log),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/v1/audit-log?target_type=Project&actor=u1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let value: Value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        let entries = value.as_array().unwrap();
+        assert_eq!(2, entries.len());
+        // Newest first
+        assert_eq!("Update", entries[0]["action"]);
+        assert_eq!(project_id, entries[0]["target_id"]);
+        assert_eq!("foo", entries[0]["before"]["name"]);
+        assert_eq!("bar", entries[0]["after"]["name"]);
+        assert_eq!("Create", entries[1]["action"]);
+        assert_eq!("u1", entries[1]["actor"]);
+
+        let req = test::TestRequest::get()
+            .uri("/v1/audit-log?limit=1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let value: Value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        assert_eq!(1, value.as_array().unwrap().len());
+        assert_eq!("Label", value[0]["target_type"]);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn get_audit_log_should_require_admin() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::viewer_role))
+                .service(get_audit_log),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/v1/audit-log").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(403, resp.status().as_u16());
+
+        Ok(())
+    }
+}
@@ -1,19 +1,25 @@
-use crate::auth::token::TokenService;
+use crate::auth::signing_key::JsonWebKeySet;
+use crate::auth::token::{TokenService, YakManRefreshToken};
 use crate::auth::{oauth_service::OAuthService, LoginError};
+use crate::services::login_protection::{LoginProtection, LoginThrottle};
 use crate::services::StorageService;
+use crate::settings;
 use crate::{
     auth::token::YakManTokenService,
-    error::{CreatePasswordResetLinkError, ResetPasswordError, YakManApiError},
+    error::{
+        ChangePasswordError, CreatePasswordResetLinkError, ResetPasswordError, YakManApiError,
+    },
     middleware::{roles::YakManRoleBinding, YakManPrinciple},
-    model::{YakManPublicPasswordResetLink, YakManRole},
+    model::{YakManPublicPasswordResetLink, YakManRole, YakManUser},
     services::password::verify_password,
 };
 use actix_web::{
-    post,
+    get, post,
     web::{self, Json},
-    HttpResponse, Responder,
+    HttpRequest, HttpResponse, Responder,
 };
 use actix_web_grants::authorities::AuthDetails;
+use chrono::Utc;
 use log::error;
 use oauth2::PkceCodeChallenge;
 use oauth2::PkceCodeVerifier;
@@ -37,34 +43,94 @@ pub struct LoginResponse {
     pub refresh_token: Option<String>,
 }
 
-/// Login for non-oauth users
+/// Returned by the login instead of tokens when the user has to complete a second factor
+/// with `/auth/login/totp`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TwoFactorChallengeResponse {
+    pub two_factor_required: bool,
+    pub two_factor_token: String,
+    /// TOTP is required but the user has not set it up yet, see `/auth/login/totp/enroll`
+    pub enrollment_required: bool,
+}
+
+/// Login for non-oauth users.
+/// Repeated failures for an account or client IP are delayed and then temporarily locked (429 with `Retry-After`).
+/// Users with TOTP enabled (or when `YAKMAN_TOTP_REQUIRED` is set) get a `TwoFactorChallengeResponse` instead of tokens.
 #[utoipa::path(responses((status = 200, body = String)))]
 #[post("/auth/login")]
 pub async fn login(
+    req: HttpRequest,
     payload: web::Form<LoginRequest>,
     storage_service: web::Data<Arc<dyn StorageService>>,
     token_service: web::Data<Arc<YakManTokenService>>,
 ) -> Result<impl Responder, YakManApiError> {
-    let password = match storage_service
-        .get_password_by_email(&payload.username)
+    let login_protection = LoginProtection::from_settings();
+    let client_ip = get_client_ip(&req);
+    let now = Utc::now().timestamp_millis();
+
+    let throttle = login_protection
+        .check(&storage_service, &payload.username, &client_ip, now)
         .await
-    {
-        Ok(Some(password)) => password,
-        _ => return Err(YakManApiError::unauthorized()),
+        .map_err(|e| {
+            log::error!("Failed to load login attempts {e}");
+            YakManApiError::server_error("Failed to login")
+        })?;
+
+    if let Some(response) = throttled_response(throttle) {
+        return Ok(response);
+    }
+
+    let user = match verify_login(&storage_service, &payload).await {
+        Some(user) => user,
+        None => {
+            if let Err(e) = login_protection
+                .record_failure(&storage_service, &payload.username, &client_ip, now)
+                .await
+            {
+                log::error!("Failed to record failed login {e}");
+            }
+            return Err(YakManApiError::unauthorized());
+        }
     };
-    match verify_password(&payload.password, password) {
-        Ok(true) => {}
-        _ => return Err(YakManApiError::unauthorized()),
+
+    if user.is_deactivated() {
+        return Err(YakManApiError::unauthorized());
+    }
+
+    let totp_enabled = match storage_service.get_totp(&user.id).await {
+        Ok(totp) => totp.is_some_and(|totp| totp.confirmed),
+        Err(e) => {
+            log::error!("Failed to load TOTP {e}");
+            return Err(YakManApiError::server_error("Failed to login"));
+        }
     };
+    if totp_enabled || settings::is_totp_required() {
+        // Failed attempts are only reset once the second factor is verified,
+        // otherwise the code could be brute forced by logging in again between guesses
+        let two_factor_token = token_service
+            .create_two_factor_token(&user.id)
+            .map_err(|e| {
+                log::error!("Failed to create two factor token {e}");
+                YakManApiError::server_error("Failed to create token")
+            })?;
+        return Ok(HttpResponse::Ok().json(TwoFactorChallengeResponse {
+            two_factor_required: true,
+            two_factor_token: two_factor_token,
+            enrollment_required: !totp_enabled,
+        }));
+    }
 
-    let user: crate::model::YakManUser =
-        match storage_service.get_user_by_email(&payload.username).await {
-            Ok(Some(user)) => user,
-            _ => return Err(YakManApiError::unauthorized()),
-        };
+    if let Err(e) = login_protection
+        .record_success(&storage_service, &payload.username)
+        .await
+    {
+        log::error!("Failed to reset login attempts {e}");
+    }
+
+    let token_version = get_token_version(&storage_service, &user.id).await?;
 
     let (access_token_jwt, expire_timestamp) =
-        match token_service.create_acess_token_jwt(&user.email, &user) {
+        match token_service.create_acess_token_jwt(&user.email, &user, token_version) {
             Ok(data) => data,
             Err(e) => {
                 log::error!("Failed to create token {e}");
@@ -72,13 +138,65 @@ pub async fn login(
             }
         };
 
-    return Ok(web::Json(LoginResponse {
+    return Ok(HttpResponse::Ok().json(LoginResponse {
         access_token: access_token_jwt,
         access_token_expire_timestamp: expire_timestamp,
         refresh_token: None, // TODO: Support refresh token
     }));
 }
 
+/// The 429 response for login attempts that have to wait before trying again
+pub(super) fn throttled_response(throttle: LoginThrottle) -> Option<HttpResponse> {
+    let (message, retry_after_ms) = match throttle {
+        LoginThrottle::Allowed => return None,
+        LoginThrottle::Delayed { retry_after_ms } => {
+            ("too many failed login attempts", retry_after_ms)
+        }
+        LoginThrottle::Locked { retry_after_ms } => ("account temporarily locked", retry_after_ms),
+    };
+    let retry_after_seconds = (retry_after_ms + 999) / 1000;
+    return Some(
+        HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", retry_after_seconds.to_string()))
+            .json(YakManApiError::too_many_requests(message)),
+    );
+}
+
+/// Returns the user if the password is correct
+async fn verify_login(
+    storage_service: &Arc<dyn StorageService>,
+    payload: &LoginRequest,
+) -> Option<YakManUser> {
+    let password = match storage_service
+        .get_password_by_email(&payload.username)
+        .await
+    {
+        Ok(Some(password)) => password,
+        _ => return None,
+    };
+    match verify_password(&payload.password, password) {
+        Ok(true) => {}
+        _ => return None,
+    };
+
+    return match storage_service.get_user_by_email(&payload.username).await {
+        Ok(Some(user)) => Some(user),
+        _ => None,
+    };
+}
+
+pub(super) fn get_client_ip(req: &HttpRequest) -> String {
+    if settings::is_forwarded_for_trusted() {
+        if let Some(ip) = req.connection_info().realip_remote_addr() {
+            return ip.to_string();
+        }
+    }
+    return req
+        .peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or("unknown".to_string());
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreatePasswordResetLink {
     pub user_id: String,
@@ -170,6 +288,53 @@ pub async fn reset_password(
     return Ok(HttpResponse::Ok().finish());
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChangePasswordPayload {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// Change the logged in user's password (non-oauth).
+/// The new password must follow the password policy and not be one of the user's recent passwords.
+#[utoipa::path(request_body = ChangePasswordPayload, responses((status = 200)))]
+#[post("/auth/change-password")]
+pub async fn change_password(
+    payload: Json<ChangePasswordPayload>,
+    principle: YakManPrinciple,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    let Some(user_id) = principle.user_id else {
+        return Err(YakManApiError::unauthorized());
+    };
+
+    storage_service
+        .change_password(&user_id, &payload.current_password, &payload.new_password)
+        .await?;
+    return Ok(HttpResponse::Ok().finish());
+}
+
+impl From<ChangePasswordError> for YakManApiError {
+    fn from(value: ChangePasswordError) -> Self {
+        match value {
+            ChangePasswordError::InvalidUser => return YakManApiError::bad_request("Invalid user"),
+            ChangePasswordError::IncorrectPassword => {
+                return YakManApiError::bad_request("Current password is incorrect")
+            }
+            ChangePasswordError::PasswordValidationError { error } => {
+                return YakManApiError::bad_request(&error.to_string())
+            }
+            ChangePasswordError::PasswordHashError { error } => {
+                log::error!("Failed to hash password {error:?}");
+                return YakManApiError::server_error("Failed to change password");
+            }
+            ChangePasswordError::StorageError { message } => {
+                log::error!("Failed to change password {message}");
+                return YakManApiError::server_error("Failed to change password");
+            }
+        }
+    }
+}
+
 impl From<ResetPasswordError> for YakManApiError {
     fn from(value: ResetPasswordError) -> Self {
         match value {
@@ -287,6 +452,7 @@ pub struct OAuthExchangeResponse {
 #[post("/oauth2/exchange")]
 pub async fn oauth_exchange(
     payload: Json<OAuthExchangePayload>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
     token_service: web::Data<Arc<YakManTokenService>>,
     oauth_service: web::Data<Arc<dyn OAuthService>>,
 ) -> Result<impl Responder, YakManApiError> {
@@ -306,6 +472,9 @@ pub async fn oauth_exchange(
                 LoginError::UserNotRegistered => {
                     Err(YakManApiError::forbidden().set_message("User not registered"))
                 }
+                LoginError::UserDeactivated => {
+                    Err(YakManApiError::forbidden().set_message("User is deactivated"))
+                }
                 e => {
                     error!("Login error {e:?}");
                     Err(YakManApiError::server_error("Failed to validate user"))
@@ -314,8 +483,10 @@ pub async fn oauth_exchange(
         }
     };
 
+    let token_version = get_token_version(&storage_service, &user.id).await?;
+
     let (access_token_jwt, expire_timestamp) =
-        match token_service.create_acess_token_jwt(&username, &user) {
+        match token_service.create_acess_token_jwt(&username, &user, token_version) {
             Ok(data) => data,
             Err(e) => {
                 log::error!("Failed to create token {e}");
@@ -323,10 +494,18 @@ pub async fn oauth_exchange(
             }
         };
 
+    let refresh_token = refresh_token.map(|t| {
+        let refresh_token = YakManRefreshToken {
+            refresh_token: t.secret().to_string(),
+            token_version: token_version,
+        };
+        token_service.encrypt_refresh_token(&refresh_token.encode())
+    });
+
     Ok(web::Json(OAuthExchangeResponse {
         access_token: access_token_jwt,
         access_token_expire_timestamp: expire_timestamp,
-        refresh_token: refresh_token.map(|t| token_service.encrypt_refresh_token(t.secret())),
+        refresh_token: refresh_token,
     }))
 }
 
@@ -353,10 +532,20 @@ pub async fn oauth_refresh(
     let encrypted_refresh_token = &payload.refresh_token;
     log::info!("{encrypted_refresh_token}");
     let refresh_token = match token_service.decrypt_refresh_token(encrypted_refresh_token) {
-        Ok(refresh_token) => refresh_token,
+        Ok(refresh_token) => YakManRefreshToken::decode(&refresh_token),
         Err(_) => return Err(YakManApiError::unauthorized().set_message("refresh_token not valid")),
     };
-    let (_access_token, username) = match oauth_service.refresh_token(&refresh_token).await {
+    if storage_service
+        .is_refresh_token_revoked(&refresh_token.refresh_token)
+        .await?
+    {
+        return Err(YakManApiError::unauthorized().set_message("refresh_token revoked"));
+    }
+
+    let (_access_token, username) = match oauth_service
+        .refresh_token(&refresh_token.refresh_token)
+        .await
+    {
         Ok(token) => token,
         Err(e) => {
             log::error!("Could not refresh token {e}");
@@ -373,8 +562,17 @@ pub async fn oauth_refresh(
         }
     };
 
+    if user.is_deactivated() {
+        return Err(YakManApiError::forbidden().set_message("User is deactivated"));
+    }
+
+    let token_version = get_token_version(&storage_service, &user.id).await?;
+    if refresh_token.token_version < token_version {
+        return Err(YakManApiError::unauthorized().set_message("refresh_token revoked"));
+    }
+
     let (access_token_jwt, expire_timestamp) =
-        match token_service.create_acess_token_jwt(&username, &user) {
+        match token_service.create_acess_token_jwt(&username, &user, token_version) {
             Ok(data) => data,
             Err(e) => {
                 log::error!("Failed to create token {e}");
@@ -387,3 +585,53 @@ pub async fn oauth_refresh(
         access_token_expire_timestamp: expire_timestamp,
     }))
 }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LogoutPayload {
+    pub refresh_token: String,
+}
+
+/// Log out of the current session by revoking its refresh token.
+/// The user's other sessions stay active, use `/v1/users/{id}/revoke-sessions` to log out everywhere.
+#[utoipa::path(request_body = LogoutPayload, responses((status = 200)))]
+#[post("/auth/logout")]
+pub async fn logout(
+    payload: Json<LogoutPayload>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+    token_service: web::Data<Arc<YakManTokenService>>,
+) -> Result<impl Responder, YakManApiError> {
+    let refresh_token = match token_service.decrypt_refresh_token(&payload.refresh_token) {
+        Ok(refresh_token) => YakManRefreshToken::decode(&refresh_token),
+        Err(_) => return Err(YakManApiError::unauthorized().set_message("refresh_token not valid")),
+    };
+
+    storage_service
+        .revoke_refresh_token(&refresh_token.refresh_token)
+        .await?;
+
+    return Ok(HttpResponse::Ok().finish());
+}
+
+/// The public keys access tokens are signed with, so other services can validate them.
+/// Empty when tokens are signed with a shared HS256 secret.
+#[utoipa::path(responses((status = 200, body = JsonWebKeySet)))]
+#[get("/.well-known/jwks.json")]
+pub async fn get_json_web_key_set(
+    token_service: web::Data<Arc<YakManTokenService>>,
+) -> HttpResponse {
+    return HttpResponse::Ok().json(token_service.get_json_web_key_set());
+}
+
+pub(super) async fn get_token_version(
+    storage_service: &Arc<dyn StorageService>,
+    user_id: &str,
+) -> Result<u32, YakManApiError> {
+    return match storage_service.get_user_details(user_id).await {
+        Ok(Some(details)) => Ok(details.token_version),
+        Ok(None) => Err(YakManApiError::unauthorized()),
+        Err(e) => {
+            log::error!("Could not fetch user details {e}");
+            Err(YakManApiError::server_error("Could not load user"))
+        }
+    };
+}
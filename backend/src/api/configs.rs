@@ -31,7 +31,8 @@ pub async fn get_configs(
     storage_service: web::Data<Arc<dyn StorageService>>,
 ) -> Result<impl Responder, YakManApiError> {
     let project_id = query.project.to_owned();
-    let has_global_role = YakManRoleBinding::has_any_global_role(
+    // Scoped api keys are allowed to list configs, the configs are filtered by the scope below
+    let has_global_role = YakManRoleBinding::has_any_global_role_ignoring_scope(
         vec![
             YakManRole::Admin,
             YakManRole::Approver,
@@ -41,9 +42,12 @@ pub async fn get_configs(
         &auth_details.authorities,
     );
 
+    let scope = YakManRoleBinding::get_api_key_scope(&auth_details.authorities);
+
     if let Some(project_id) = &project_id {
+        // Scoped api keys are allowed to list configs, the configs are filtered by the scope below
         if !has_global_role
-            && !YakManRoleBinding::has_any_role(
+            && !YakManRoleBinding::has_any_role_ignoring_scope(
                 vec![
                     YakManRole::Admin,
                     YakManRole::Approver,
@@ -69,13 +73,14 @@ pub async fn get_configs(
 
     let data = storage_service.get_visible_configs(project_id).await?;
 
-    if has_global_role {
+    if has_global_role && scope.is_none() {
         return Ok(web::Json(data));
     }
 
     let filtered_data = data
         .into_iter()
-        .filter(|c| allowed_projects.contains(&c.project_id))
+        .filter(|c| has_global_role || allowed_projects.contains(&c.project_id))
+        .filter(|c| scope.is_none_or(|scope| scope.allows_config(&c.id)))
         .collect();
 
     return Ok(web::Json(filtered_data));
@@ -186,7 +191,7 @@ mod tests {
 
     use super::*;
     use crate::{
-        model::YakManProjectRole,
+        model::{YakManApiKeyOperation, YakManApiKeyScope, YakManProjectRole},
         test_utils::{fake_roles::FakeRoleExtractor, *},
     };
     use actix_web::{test, web::Data, App};
@@ -201,7 +206,7 @@ mod tests {
         let storage_service = test_storage_service().await?;
 
         // Setup test project with 2 configs
-        let project_id = storage_service.create_project("test", None).await?;
+        let project_id = storage_service.create_project("test", vec![]).await?;
         storage_service
             .create_config("config1", &project_id)
             .await?;
@@ -248,11 +253,11 @@ mod tests {
         let storage_service = test_storage_service().await?;
 
         // Setup test 2 project with 1 config each
-        let project1_id = storage_service.create_project("proj1", None).await?;
+        let project1_id = storage_service.create_project("proj1", vec![]).await?;
         storage_service
             .create_config("config1", &project1_id)
             .await?;
-        let project2_id = storage_service.create_project("proj2", None).await?;
+        let project2_id = storage_service.create_project("proj2", vec![]).await?;
         storage_service
             .create_config("config2", &project2_id)
             .await?;
@@ -292,7 +297,7 @@ mod tests {
         let storage_service = test_storage_service().await?;
 
         // Setup test project with config
-        let project1_id = storage_service.create_project("proj1", None).await?;
+        let project1_id = storage_service.create_project("proj1", vec![]).await?;
         storage_service
             .create_config("config1", &project1_id)
             .await?;
@@ -328,7 +333,7 @@ mod tests {
         let storage_service = test_storage_service().await?;
 
         // Setup test project with 2 configs
-        let project_id = storage_service.create_project("test", None).await?;
+        let project_id = storage_service.create_project("test", vec![]).await?;
         storage_service
             .create_config("config1", &project_id)
             .await?;
@@ -373,7 +378,7 @@ mod tests {
         let storage_service = test_storage_service().await?;
 
         // Setup test project
-        let project_id = storage_service.create_project("test", None).await?;
+        let project_id = storage_service.create_project("test", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -411,7 +416,7 @@ mod tests {
         let storage_service = test_storage_service().await?;
 
         // Setup test project
-        let project_id = storage_service.create_project("test", None).await?;
+        let project_id = storage_service.create_project("test", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -440,7 +445,7 @@ mod tests {
         let storage_service = test_storage_service().await?;
 
         // Setup test project
-        let project_id = storage_service.create_project("test", None).await?;
+        let project_id = storage_service.create_project("test", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -469,7 +474,7 @@ mod tests {
         let storage_service = test_storage_service().await?;
 
         // Setup test project
-        let project_id = storage_service.create_project("test", None).await?;
+        let project_id = storage_service.create_project("test", vec![]).await?;
         storage_service
             .create_config("foo-bar", &project_id)
             .await?;
@@ -493,4 +498,94 @@ mod tests {
 
         Ok(())
     }
+
+    #[actix_web::test]
+    async fn get_configs_should_only_return_configs_in_api_key_scope() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let project_id = storage_service.create_project("test", vec![]).await?;
+        let config1_id = storage_service
+            .create_config("config1", &project_id)
+            .await?;
+        storage_service
+            .create_config("config2", &project_id)
+            .await?;
+
+        let fake_role_extractor = FakeRoleExtractor::new(vec![
+            YakManRoleBinding::ProjectRoleBinding(YakManProjectRole {
+                project_id: project_id.to_string(),
+                role: YakManRole::Viewer,
+            }),
+            YakManRoleBinding::ApiKeyScope(YakManApiKeyScope {
+                operations: vec![YakManApiKeyOperation::ReadData],
+                config_ids: vec![config1_id.to_string()],
+                label_selectors: vec![],
+            }),
+        ]);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service))
+                .wrap(GrantsMiddleware::with_extractor(fake_role_extractor))
+                .service(get_configs),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/v1/configs?project={project_id}"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let value: Value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        let configs = value.as_array().unwrap();
+        assert_eq!(1, configs.len());
+        assert_eq!(config1_id, configs[0]["id"]);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn create_config_should_return_forbidden_for_scoped_api_keys() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let project_id = storage_service.create_project("test", vec![]).await?;
+
+        let fake_role_extractor = FakeRoleExtractor::new(vec![
+            YakManRoleBinding::ProjectRoleBinding(YakManProjectRole {
+                project_id: project_id.to_string(),
+                role: YakManRole::Admin,
+            }),
+            YakManRoleBinding::ApiKeyScope(YakManApiKeyScope {
+                operations: vec![
+                    YakManApiKeyOperation::ReadData,
+                    YakManApiKeyOperation::SubmitRevisions,
+                    YakManApiKeyOperation::ApproveRevisions,
+                ],
+                config_ids: vec![],
+                label_selectors: vec![],
+            }),
+        ]);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service))
+                .wrap(GrantsMiddleware::with_extractor(fake_role_extractor))
+                .service(create_config),
+        )
+        .await;
+        let req = test::TestRequest::put()
+            .uri("/v1/configs")
+            .set_json(&CreateConfigPayload {
+                config_name: "config1".to_string(),
+                project_id: project_id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(403, resp.status().as_u16());
+
+        Ok(())
+    }
 }
@@ -1,8 +1,9 @@
 use std::sync::Arc;
 
+use crate::api::instances::get_instance_labels_for_scope;
 use crate::error::YakManApiError;
 use crate::middleware::roles::YakManRoleBinding;
-use crate::model::YakManRole;
+use crate::model::{YakManApiKeyOperation, YakManRole};
 use crate::services::StorageService;
 use actix_web::{get, web, HttpResponse, Responder};
 use actix_web_grants::authorities::AuthDetails;
@@ -25,7 +26,15 @@ async fn get_instance_data(
         Err(_) => return Err(YakManApiError::server_error("Failed to load config")),
     };
 
-    let has_role = YakManRoleBinding::has_any_role(
+    let instance_labels = get_instance_labels_for_scope(
+        &auth_details,
+        storage_service.get_ref(),
+        &config_id,
+        &instance,
+    )
+    .await?;
+
+    let has_role = YakManRoleBinding::has_any_role_in_scope(
         vec![
             YakManRole::Admin,
             YakManRole::Approver,
@@ -33,6 +42,9 @@ async fn get_instance_data(
             YakManRole::Viewer,
         ],
         &config.project_id,
+        YakManApiKeyOperation::ReadData,
+        &config_id,
+        &instance_labels,
         &auth_details.authorities,
     );
 
@@ -58,7 +70,7 @@ async fn get_revision_data(
     path: web::Path<(String, String, String)>,
     storage_service: web::Data<Arc<dyn StorageService>>,
 ) -> Result<impl Responder, YakManApiError> {
-    let (config_id, _, revision) = path.into_inner();
+    let (config_id, instance, revision) = path.into_inner();
 
     let config = match storage_service.get_config(&config_id).await {
         Ok(config) => match config {
@@ -68,7 +80,15 @@ async fn get_revision_data(
         Err(_) => return Err(YakManApiError::server_error("Failed to load config")),
     };
 
-    let has_role = YakManRoleBinding::has_any_role(
+    let instance_labels = get_instance_labels_for_scope(
+        &auth_details,
+        storage_service.get_ref(),
+        &config_id,
+        &instance,
+    )
+    .await?;
+
+    let has_role = YakManRoleBinding::has_any_role_in_scope(
         vec![
             YakManRole::Admin,
             YakManRole::Approver,
@@ -76,6 +96,9 @@ async fn get_revision_data(
             YakManRole::Viewer,
         ],
         &config.project_id,
+        YakManApiKeyOperation::ReadData,
+        &config_id,
+        &instance_labels,
         &auth_details.authorities,
     );
 
@@ -4,11 +4,22 @@ use std::sync::Arc;
 use crate::error::{DeleteConfigInstanceError, SaveConfigInstanceError, YakManApiError};
 use crate::middleware::YakManPrinciple;
 use crate::model::response::{InstancePayload, RevisionPayload};
-use crate::model::{ConfigInstance, YakManLabel, YakManRole};
+use crate::model::{
+    ConfigInstance, RevisionChangeDetails, YakManApiKeyOperation, YakManLabel, YakManRole,
+};
 use crate::services::StorageService;
 use crate::{error::CreateConfigInstanceError, middleware::roles::YakManRoleBinding};
 use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
 use actix_web_grants::authorities::AuthDetails;
+use percent_encoding::percent_decode_str;
+
+/// Query parameter that overrides a freeze window, every other parameter is a label
+const BREAK_GLASS_PARAM: &str = "break_glass";
+/// Headers describing why a revision was submitted. Values are percent-encoded UTF-8.
+const CHANGE_DESCRIPTION_HEADER: &str = "YakMan-Change-Description";
+const CHANGE_TICKET_HEADER: &str = "YakMan-Change-Ticket";
+const MAX_DESCRIPTION_LENGTH: usize = 2000;
+const MAX_TICKET_LENGTH: usize = 200;
 
 /// Get config instances by config id
 #[utoipa::path(responses((status = 200, body = Vec<ConfigInstance>)))]
@@ -28,7 +39,17 @@ async fn get_instances_by_config_id(
         Err(_) => return Err(YakManApiError::server_error("Failed to load config")),
     };
 
-    let has_role = YakManRoleBinding::has_any_role(
+    let scope = YakManRoleBinding::get_api_key_scope(&auth_details.authorities);
+    if let Some(scope) = scope {
+        if !scope.operations.contains(&YakManApiKeyOperation::ReadData)
+            || !scope.allows_config(&config_id)
+        {
+            return Err(YakManApiError::forbidden());
+        }
+    }
+
+    // The instances are filtered by the api key scope below
+    let has_role = YakManRoleBinding::has_any_role_ignoring_scope(
         vec![
             YakManRole::Admin,
             YakManRole::Approver,
@@ -48,7 +69,11 @@ async fn get_instances_by_config_id(
         .await?;
 
     return match data {
-        Some(data) => Ok(web::Json(data)),
+        Some(data) => Ok(web::Json(
+            data.into_iter()
+                .filter(|i| scope.is_none_or(|scope| scope.allows_labels(&i.labels)))
+                .collect::<Vec<_>>(),
+        )),
         None => Err(YakManApiError::not_found("Instance not found")),
     };
 }
@@ -68,7 +93,15 @@ async fn get_instance(
         None => return Err(YakManApiError::not_found("Config not found")),
     };
 
-    let has_role = YakManRoleBinding::has_any_role(
+    let instance_labels = get_instance_labels_for_scope(
+        &auth_details,
+        storage_service.get_ref(),
+        &config_id,
+        &instance,
+    )
+    .await?;
+
+    let has_role = YakManRoleBinding::has_any_role_in_scope(
         vec![
             YakManRole::Admin,
             YakManRole::Approver,
@@ -76,6 +109,9 @@ async fn get_instance(
             YakManRole::Viewer,
         ],
         &config.project_id,
+        YakManApiKeyOperation::ReadData,
+        &config_id,
+        &instance_labels,
         &auth_details.authorities,
     );
 
@@ -92,7 +128,8 @@ async fn get_instance(
     };
 }
 
-/// Create a new config instance
+/// Create a new config instance.
+/// Labels are passed as query parameters, `break_glass=true` creates the instance during a freeze window (Admin only)
 #[utoipa::path(responses((status = 200, body = InstancePayload)))]
 #[put("/v1/configs/{config_id}/instances")]
 async fn create_new_instance(
@@ -106,7 +143,11 @@ async fn create_new_instance(
 ) -> Result<impl Responder, YakManApiError> {
     let config_id: String = path.into_inner();
 
-    let labels: Vec<YakManLabel> = extract_labels(query);
+    let mut query = query.into_inner();
+    let break_glass = query
+        .remove(BREAK_GLASS_PARAM)
+        .is_some_and(|value| value == "true");
+    let labels: Vec<YakManLabel> = extract_labels(web::Query(query));
     let content_type: Option<String> = get_content_type(&req);
 
     let config = match storage_service.get_config(&config_id).await {
@@ -117,9 +158,12 @@ async fn create_new_instance(
         Err(_) => return Err(YakManApiError::server_error("Failed to load config")),
     };
 
-    let has_role = YakManRoleBinding::has_any_role(
+    let has_role = YakManRoleBinding::has_any_role_in_scope(
         vec![YakManRole::Admin, YakManRole::Approver],
         &config.project_id,
+        YakManApiKeyOperation::SubmitRevisions,
+        &config_id,
+        &labels,
         &auth_details.authorities,
     );
 
@@ -127,10 +171,27 @@ async fn create_new_instance(
         return Err(YakManApiError::forbidden());
     }
 
+    if break_glass
+        && !YakManRoleBinding::has_any_role(
+            vec![YakManRole::Admin],
+            &config.project_id,
+            &auth_details.authorities,
+        )
+    {
+        return Err(YakManApiError::forbidden());
+    }
+
     let creator_user_id = principle.user_id.ok_or(YakManApiError::forbidden())?;
 
     match storage_service
-        .create_config_instance(&config_id, labels, &data, content_type, &creator_user_id)
+        .create_config_instance(
+            &config_id,
+            labels,
+            &data,
+            content_type,
+            &creator_user_id,
+            break_glass,
+        )
         .await
     {
         Ok(instance) => Ok(web::Json(InstancePayload { instance: instance })),
@@ -140,6 +201,9 @@ async fn create_new_instance(
         Err(CreateConfigInstanceError::InvalidLabel) => {
             Err(YakManApiError::bad_request("Invalid label"))
         }
+        Err(CreateConfigInstanceError::ChangeFreeze { reason }) => Err(
+            YakManApiError::bad_request(&format!("Changes are frozen: {reason}")),
+        ),
         Err(CreateConfigInstanceError::StorageError { message: _ }) => {
             Err(YakManApiError::server_error("Failed to create config"))
         }
@@ -160,6 +224,7 @@ async fn update_new_instance(
 ) -> Result<impl Responder, YakManApiError> {
     let (config_id, instance) = path.into_inner();
 
+    let change_details = get_change_details(&req)?;
     let labels: Vec<YakManLabel> = extract_labels(query);
     let content_type: Option<String> = get_content_type(&req);
 
@@ -171,11 +236,25 @@ async fn update_new_instance(
         Err(_) => return Err(YakManApiError::server_error("Failed to load config")),
     };
 
-    let has_role = YakManRoleBinding::has_any_role(
-        vec![YakManRole::Admin, YakManRole::Approver],
-        &config.project_id,
-        &auth_details.authorities,
-    );
+    let instance_labels = get_instance_labels_for_scope(
+        &auth_details,
+        storage_service.get_ref(),
+        &config_id,
+        &instance,
+    )
+    .await?;
+
+    // Both the current and new labels must be in scope, so a scoped api key cannot move an instance out of its scope
+    let has_role = [&instance_labels, &labels].iter().all(|labels| {
+        YakManRoleBinding::has_any_role_in_scope(
+            vec![YakManRole::Admin, YakManRole::Approver],
+            &config.project_id,
+            YakManApiKeyOperation::SubmitRevisions,
+            &config_id,
+            labels,
+            &auth_details.authorities,
+        )
+    });
 
     if !has_role {
         return Err(YakManApiError::forbidden());
@@ -190,6 +269,7 @@ async fn update_new_instance(
             labels,
             &data,
             content_type,
+            change_details,
             &creator_user_id,
         )
         .await
@@ -199,6 +279,9 @@ async fn update_new_instance(
                 YakManApiError::bad_request("invalid instance")
             }
             SaveConfigInstanceError::InvalidLabel => YakManApiError::bad_request("invalid label"),
+            SaveConfigInstanceError::MissingChangeDescription => {
+                YakManApiError::bad_request("A change description is required")
+            }
             SaveConfigInstanceError::StorageError { message: _ } => {
                 YakManApiError::server_error("failed to create instance")
             }
@@ -254,6 +337,25 @@ async fn delete_instance(
     Ok(HttpResponse::Ok().finish())
 }
 
+/// Gets the labels of an instance so they can be checked against the scope of an api key.
+/// Requests without an api key scope do not need the labels, so they are not loaded.
+pub(crate) async fn get_instance_labels_for_scope(
+    auth_details: &AuthDetails<YakManRoleBinding>,
+    storage_service: &Arc<dyn StorageService>,
+    config_id: &str,
+    instance: &str,
+) -> Result<Vec<YakManLabel>, YakManApiError> {
+    if YakManRoleBinding::get_api_key_scope(&auth_details.authorities).is_none() {
+        return Ok(vec![]);
+    }
+
+    return Ok(storage_service
+        .get_config_instance(config_id, instance)
+        .await?
+        .map(|i| i.labels)
+        .unwrap_or_default());
+}
+
 fn extract_labels(query: web::Query<HashMap<String, String>>) -> Vec<YakManLabel> {
     return query
         .iter()
@@ -265,6 +367,51 @@ fn extract_labels(query: web::Query<HashMap<String, String>>) -> Vec<YakManLabel
         .collect();
 }
 
+fn get_change_details(req: &HttpRequest) -> Result<RevisionChangeDetails, YakManApiError> {
+    let description = get_change_detail_header(req, CHANGE_DESCRIPTION_HEADER)?;
+    let ticket = get_change_detail_header(req, CHANGE_TICKET_HEADER)?;
+
+    if description
+        .as_ref()
+        .is_some_and(|d| d.chars().count() > MAX_DESCRIPTION_LENGTH)
+    {
+        return Err(YakManApiError::bad_request("Description is too long"));
+    }
+    if ticket
+        .as_ref()
+        .is_some_and(|t| t.chars().count() > MAX_TICKET_LENGTH)
+    {
+        return Err(YakManApiError::bad_request("Ticket is too long"));
+    }
+
+    return Ok(RevisionChangeDetails {
+        description: description,
+        ticket: ticket,
+    });
+}
+
+fn get_change_detail_header(
+    req: &HttpRequest,
+    header: &str,
+) -> Result<Option<String>, YakManApiError> {
+    let Some(value) = req.headers().get(header) else {
+        return Ok(None);
+    };
+    let value = value
+        .to_str()
+        .ok()
+        .and_then(|v| percent_decode_str(v).decode_utf8().ok())
+        .ok_or(YakManApiError::bad_request(&format!(
+            "Invalid {header} header"
+        )))?;
+
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    return Ok(Some(value.to_string()));
+}
+
 fn get_content_type(req: &HttpRequest) -> Option<String> {
     req.headers()
         .get("content-type")?
@@ -272,3 +419,35 @@ fn get_content_type(req: &HttpRequest) -> Option<String> {
         .map(String::from)
         .ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn get_change_details_should_decode_headers() {
+        let req = TestRequest::default()
+            .insert_header((CHANGE_DESCRIPTION_HEADER, "Raise%20the%20limit%20%E2%9C%93"))
+            .insert_header((CHANGE_TICKET_HEADER, "  OPS-123 "))
+            .to_http_request();
+
+        let change_details = get_change_details(&req).unwrap();
+
+        assert_eq!(
+            Some("Raise the limit ✓".to_string()),
+            change_details.description
+        );
+        assert_eq!(Some("OPS-123".to_string()), change_details.ticket);
+    }
+
+    #[test]
+    fn get_change_details_should_allow_missing_headers() {
+        let req = TestRequest::default().to_http_request();
+
+        let change_details = get_change_details(&req).unwrap();
+
+        assert_eq!(None, change_details.description);
+        assert_eq!(None, change_details.ticket);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod api_keys;
+pub mod audit_log;
 pub mod auth;
 pub mod configs;
 pub mod data;
@@ -7,7 +8,10 @@ pub mod labels;
 pub mod lifecycle;
 pub mod projects;
 pub mod revisions;
+pub mod scim;
+pub mod service_accounts;
 pub mod teams;
+pub mod totp;
 pub mod users;
 pub mod validation;
 
@@ -21,16 +25,25 @@ use utoipa::OpenApi;
         lifecycle::yakman_settings,
         auth::login,
         auth::reset_password,
+        auth::change_password,
         auth::create_password_reset_link,
         auth::validate_password_reset_link,
         auth::oauth_init,
         auth::oauth_exchange,
         auth::oauth_refresh,
+        auth::logout,
+        auth::get_json_web_key_set,
+        totp::login_with_totp,
+        totp::enroll_totp_during_login,
+        totp::enroll_totp,
+        totp::confirm_totp,
+        totp::disable_totp,
         projects::get_projects,
         projects::get_project,
         projects::create_project,
         projects::update_project,
         projects::delete_project,
+        projects::get_project_notification_deliveries,
         configs::get_configs,
         configs::create_config,
         configs::delete_config,
@@ -49,8 +62,21 @@ use utoipa::OpenApi;
         revisions::review_pending_instance_revision,
         revisions::apply_instance_revision,
         revisions::rollback_instance_revision,
+        revisions::schedule_instance_revision_apply,
+        revisions::cancel_scheduled_apply,
+        revisions::get_scheduled_apply,
+        revisions::add_revision_comment,
         users::get_yakman_users,
         users::create_yakman_user,
+        users::invite_yakman_user,
+        users::get_yakman_user_details,
+        users::update_yakman_user_roles,
+        users::deactivate_yakman_user,
+        users::reactivate_yakman_user,
+        users::revoke_yakman_user_sessions,
+        users::unlock_yakman_user,
+        users::reset_yakman_user_totp,
+        users::delete_yakman_user,
         users::get_user_info,
         teams::get_teams,
         teams::get_team,
@@ -59,7 +85,27 @@ use utoipa::OpenApi;
         teams::delete_team,
         api_keys::get_api_keys,
         api_keys::create_api_key,
+        api_keys::rotate_api_key,
         api_keys::delete_api_key,
+        service_accounts::get_service_accounts,
+        service_accounts::get_service_account,
+        service_accounts::create_service_account,
+        service_accounts::update_service_account,
+        service_accounts::delete_service_account,
+        service_accounts::create_service_account_api_key,
+        audit_log::get_audit_log,
+        scim::get_scim_users,
+        scim::get_scim_user,
+        scim::create_scim_user,
+        scim::replace_scim_user,
+        scim::patch_scim_user,
+        scim::delete_scim_user,
+        scim::get_scim_groups,
+        scim::get_scim_group,
+        scim::create_scim_group,
+        scim::replace_scim_group,
+        scim::patch_scim_group,
+        scim::delete_scim_group,
     ),
     tags(
         (name = "auth", description = "Authentication endpoints"),
@@ -73,6 +119,8 @@ use utoipa::OpenApi;
         (name = "teams", description = "YakMan team management endpoints"),
         (name = "lifecycle", description = "Application lifecycle endpoints"),
         (name = "api_keys", description = "API Key management endpoints"),
+        (name = "service_accounts", description = "Service account management endpoints"),
+        (name = "audit_log", description = "Audit log endpoints"),
     )
 )]
 pub struct YakManApiDoc;
@@ -85,20 +133,38 @@ pub fn register_routes(config: &mut web::ServiceConfig) {
         // Auth
         .service(auth::login)
         .service(auth::reset_password)
+        .service(auth::change_password)
         .service(auth::create_password_reset_link)
         .service(auth::validate_password_reset_link)
         .service(auth::oauth_init)
         .service(auth::oauth_exchange)
         .service(auth::oauth_refresh)
+        .service(auth::logout)
+        .service(auth::get_json_web_key_set)
+        .service(totp::login_with_totp)
+        .service(totp::enroll_totp_during_login)
+        .service(totp::enroll_totp)
+        .service(totp::confirm_totp)
+        .service(totp::disable_totp)
         // Projects
         .service(projects::get_projects)
         .service(projects::get_project)
         .service(projects::create_project)
         .service(projects::update_project)
         .service(projects::delete_project)
+        .service(projects::get_project_notification_deliveries)
         // Users
         .service(users::get_yakman_users)
         .service(users::create_yakman_user)
+        .service(users::invite_yakman_user)
+        .service(users::get_yakman_user_details)
+        .service(users::update_yakman_user_roles)
+        .service(users::deactivate_yakman_user)
+        .service(users::reactivate_yakman_user)
+        .service(users::revoke_yakman_user_sessions)
+        .service(users::unlock_yakman_user)
+        .service(users::reset_yakman_user_totp)
+        .service(users::delete_yakman_user)
         .service(users::get_user_info)
         // Teams
         .service(teams::get_teams)
@@ -106,10 +172,31 @@ pub fn register_routes(config: &mut web::ServiceConfig) {
         .service(teams::update_team)
         .service(teams::create_team)
         .service(teams::delete_team)
+        .service(service_accounts::get_service_accounts)
+        .service(service_accounts::get_service_account)
+        .service(service_accounts::update_service_account)
+        .service(service_accounts::create_service_account)
+        .service(service_accounts::delete_service_account)
+        .service(service_accounts::create_service_account_api_key)
+        .service(scim::get_scim_users)
+        .service(scim::get_scim_user)
+        .service(scim::create_scim_user)
+        .service(scim::replace_scim_user)
+        .service(scim::patch_scim_user)
+        .service(scim::delete_scim_user)
+        .service(scim::get_scim_groups)
+        .service(scim::get_scim_group)
+        .service(scim::create_scim_group)
+        .service(scim::replace_scim_group)
+        .service(scim::patch_scim_group)
+        .service(scim::delete_scim_group)
         // Api Keys
         .service(api_keys::get_api_keys)
         .service(api_keys::create_api_key)
+        .service(api_keys::rotate_api_key)
         .service(api_keys::delete_api_key)
+        // Audit Log
+        .service(audit_log::get_audit_log)
         // Configs
         .service(configs::get_configs)
         .service(configs::create_config)
@@ -132,5 +219,9 @@ pub fn register_routes(config: &mut web::ServiceConfig) {
         .service(revisions::get_instance_revisions)
         .service(revisions::review_pending_instance_revision)
         .service(revisions::apply_instance_revision)
-        .service(revisions::rollback_instance_revision);
+        .service(revisions::rollback_instance_revision)
+        .service(revisions::schedule_instance_revision_apply)
+        .service(revisions::cancel_scheduled_apply)
+        .service(revisions::get_scheduled_apply)
+        .service(revisions::add_revision_comment);
 }
@@ -1,12 +1,13 @@
-use std::{collections::HashSet, sync::Arc};
+use std::{collections::HashSet, str::FromStr, sync::Arc};
 
 use crate::{
     api::validation::validate_kebab_case,
     error::{CreateProjectError, DeleteProjectError, UpdateProjectError, YakManApiError},
     middleware::roles::YakManRoleBinding,
     model::{
-        NotificationSetting, NotificationSettingEvents, YakManProject, YakManProjectDetails,
-        YakManRole,
+        deserialize_notification_settings, NotificationSetting, NotificationSettingEvents,
+        YakManApprovalPolicy, YakManFreezeSchedule, YakManFreezeWindow, YakManNotificationDelivery,
+        YakManProject, YakManProjectDetails, YakManRole,
     },
     services::StorageService,
     settings,
@@ -23,7 +24,12 @@ use log::error;
 use serde::{Deserialize, Serialize};
 use url::Url;
 use utoipa::ToSchema;
-use validator::{Validate, ValidationError};
+use validator::{Validate, ValidateEmail, ValidationError};
+
+const MAX_REQUIRED_APPROVALS: u32 = 10;
+const MAX_FREEZE_WINDOWS: usize = 50;
+/// Recurring freezes longer than a week would overlap themselves
+const MAX_FREEZE_DURATION_MINUTES: u32 = 7 * 24 * 60;
 
 /// Get all of the projects (user has access to)
 #[utoipa::path(responses((status = 200, body = Vec<YakManProject>)))]
@@ -46,8 +52,8 @@ pub async fn get_projects(
         .authorities
         .iter()
         .filter_map(|p| match p {
-            YakManRoleBinding::GlobalRoleBinding(_) => None,
             YakManRoleBinding::ProjectRoleBinding(r) => Some(r.project_id.clone()),
+            _ => None,
         })
         .collect();
 
@@ -96,18 +102,62 @@ pub async fn get_project(
     return Ok(web::Json(details));
 }
 
+/// Get the notification delivery log of a project, newest first
+#[utoipa::path(responses((status = 200, body = Vec<YakManNotificationDelivery>)))]
+#[get("/v1/projects/{id}/notification-deliveries")]
+pub async fn get_project_notification_deliveries(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    let project_id: String = path.into_inner();
+
+    if !YakManRoleBinding::has_role(YakManRole::Admin, &project_id, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    if storage_service
+        .get_project_details(&project_id)
+        .await?
+        .is_none()
+    {
+        return Err(YakManApiError::not_found("Project not found"));
+    }
+
+    let mut deliveries = storage_service
+        .get_notification_deliveries(&project_id)
+        .await?;
+    deliveries.sort_by_key(|d| std::cmp::Reverse(d.created_at_ms));
+
+    return Ok(web::Json(deliveries));
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema, Validate)]
 pub struct CreateProjectPayload {
     #[validate(length(min = 1), custom(function = "validate_kebab_case"))]
     pub project_name: String,
+    #[serde(default, deserialize_with = "deserialize_notification_settings")]
     #[validate(custom(function = "validate_project_notification_settings"))]
-    pub notification_settings: Option<ProjectNotificationSettings>,
+    pub notification_settings: Vec<ProjectNotificationSettings>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
 pub enum ProjectNotificationType {
-    Slack { webhook_url: String },
-    Discord { webhook_url: String },
+    Slack {
+        webhook_url: String,
+    },
+    Discord {
+        webhook_url: String,
+    },
+    MicrosoftTeams {
+        webhook_url: String,
+    },
+    Email {
+        #[serde(default)]
+        recipients: Vec<String>,
+        #[serde(default)]
+        send_to_project_approvers: bool,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
@@ -123,6 +173,16 @@ pub struct ProjectNotificationSettings {
     pub is_revision_approved_enabled: bool,
     #[serde(default)]
     pub is_revision_reject_enabled: bool,
+    #[serde(default)]
+    pub is_revision_rollback_enabled: bool,
+    #[serde(default)]
+    pub is_instance_deleted_enabled: bool,
+    #[serde(default)]
+    pub is_config_deleted_enabled: bool,
+    #[serde(default)]
+    pub is_project_settings_updated_enabled: bool,
+    #[serde(default)]
+    pub is_api_key_created_enabled: bool,
 }
 
 impl From<ProjectNotificationSettings> for crate::model::ProjectNotificationSettings {
@@ -133,6 +193,11 @@ impl From<ProjectNotificationSettings> for crate::model::ProjectNotificationSett
             is_revision_submitted_enabled: val.is_revision_submitted_enabled,
             is_revision_approved_enabled: val.is_revision_approved_enabled,
             is_revision_reject_enabled: val.is_revision_reject_enabled,
+            is_revision_rollback_enabled: val.is_revision_rollback_enabled,
+            is_instance_deleted_enabled: val.is_instance_deleted_enabled,
+            is_config_deleted_enabled: val.is_config_deleted_enabled,
+            is_project_settings_updated_enabled: val.is_project_settings_updated_enabled,
+            is_api_key_created_enabled: val.is_api_key_created_enabled,
         };
 
         let settings = match val.notification_type {
@@ -142,6 +207,18 @@ impl From<ProjectNotificationSettings> for crate::model::ProjectNotificationSett
             ProjectNotificationType::Discord { webhook_url } => NotificationSetting::Discord {
                 webhook_url: webhook_url,
             },
+            ProjectNotificationType::MicrosoftTeams { webhook_url } => {
+                NotificationSetting::MicrosoftTeams {
+                    webhook_url: webhook_url,
+                }
+            }
+            ProjectNotificationType::Email {
+                recipients,
+                send_to_project_approvers,
+            } => NotificationSetting::Email {
+                recipients: recipients,
+                send_to_project_approvers: send_to_project_approvers,
+            },
         };
         crate::model::ProjectNotificationSettings { settings, events }
     }
@@ -162,7 +239,7 @@ async fn create_project(
         .iter()
         .filter_map(|p| match p {
             YakManRoleBinding::GlobalRoleBinding(role) => Some(role.clone()),
-            YakManRoleBinding::ProjectRoleBinding(_) => None,
+            _ => None,
         })
         .filter(|role| [YakManRole::Admin, YakManRole::Approver].contains(role))
         .collect::<Vec<_>>()
@@ -193,8 +270,15 @@ async fn create_project(
 pub struct UpdateProjectPayload {
     #[validate(length(min = 1), custom(function = "validate_kebab_case"))]
     pub project_name: String,
+    #[serde(default, deserialize_with = "deserialize_notification_settings")]
     #[validate(custom(function = "validate_project_notification_settings"))]
-    pub notification_settings: Option<ProjectNotificationSettings>,
+    pub notification_settings: Vec<ProjectNotificationSettings>,
+    #[serde(default)]
+    #[validate(custom(function = "validate_approval_policy"))]
+    pub approval_policy: YakManApprovalPolicy,
+    #[serde(default)]
+    #[validate(custom(function = "validate_freeze_windows"))]
+    pub freeze_windows: Vec<YakManFreezeWindow>,
 }
 
 /// Update a project
@@ -220,7 +304,13 @@ async fn update_project(
     }
 
     return match storage_service
-        .update_project(&project_id, &project_name, payload.notification_settings)
+        .update_project(
+            &project_id,
+            &project_name,
+            payload.notification_settings,
+            payload.approval_policy,
+            payload.freeze_windows,
+        )
         .await
     {
         Ok(project_id) => Ok(HttpResponse::Ok().body(project_id)),
@@ -275,13 +365,106 @@ pub async fn delete_project(
 }
 
 fn validate_project_notification_settings(
+    notification_settings: &[ProjectNotificationSettings],
+) -> Result<(), ValidationError> {
+    for settings in notification_settings {
+        validate_project_notification_channel(settings)?;
+    }
+
+    return Ok(());
+}
+
+fn validate_project_notification_channel(
     notification_settings: &ProjectNotificationSettings,
 ) -> Result<(), ValidationError> {
     match &notification_settings.notification_type {
         ProjectNotificationType::Slack { webhook_url } => validate_webhook_url(webhook_url)?,
         ProjectNotificationType::Discord { webhook_url } => validate_webhook_url(webhook_url)?,
+        ProjectNotificationType::MicrosoftTeams { webhook_url } => {
+            validate_webhook_url(webhook_url)?
+        }
+        ProjectNotificationType::Email {
+            recipients,
+            send_to_project_approvers,
+        } => validate_email_recipients(recipients, *send_to_project_approvers)?,
+    }
+
+    return Ok(());
+}
+
+fn validate_email_recipients(
+    recipients: &[String],
+    send_to_project_approvers: bool,
+) -> Result<(), ValidationError> {
+    if !settings::is_smtp_configured() {
+        return Err(ValidationError::new("SMTP is not configured"));
+    }
+
+    if recipients.is_empty() && !send_to_project_approvers {
+        return Err(ValidationError::new("No email recipients"));
+    }
+
+    if !recipients
+        .iter()
+        .all(|recipient| recipient.validate_email())
+    {
+        return Err(ValidationError::new("Invalid email recipient"));
     }
+    return Ok(());
+}
+
+fn validate_approval_policy(approval_policy: &YakManApprovalPolicy) -> Result<(), ValidationError> {
+    let is_valid_count = |count: u32| (1..=MAX_REQUIRED_APPROVALS).contains(&count);
 
+    if !is_valid_count(approval_policy.required_approvals) {
+        return Err(ValidationError::new("Invalid number of required approvals"));
+    }
+
+    for rule in &approval_policy.label_rules {
+        if rule.label_id.is_empty() || rule.value.is_empty() {
+            return Err(ValidationError::new("Invalid approval rule label"));
+        }
+        if !is_valid_count(rule.required_approvals) {
+            return Err(ValidationError::new("Invalid number of required approvals"));
+        }
+    }
+    return Ok(());
+}
+
+fn validate_freeze_windows(
+    freeze_windows: &Vec<YakManFreezeWindow>,
+) -> Result<(), ValidationError> {
+    if freeze_windows.len() > MAX_FREEZE_WINDOWS {
+        return Err(ValidationError::new("Too many freeze windows"));
+    }
+
+    for window in freeze_windows {
+        if window.reason.trim().is_empty() {
+            return Err(ValidationError::new("Freeze window reason is required"));
+        }
+        match &window.schedule {
+            YakManFreezeSchedule::Range { start_ms, end_ms } => {
+                if start_ms >= end_ms {
+                    return Err(ValidationError::new(
+                        "Freeze window must end after it starts",
+                    ));
+                }
+            }
+            YakManFreezeSchedule::Cron {
+                expression,
+                duration_minutes,
+            } => {
+                if cron::Schedule::from_str(expression).is_err() {
+                    return Err(ValidationError::new(
+                        "Invalid freeze window cron expression",
+                    ));
+                }
+                if !(1..=MAX_FREEZE_DURATION_MINUTES).contains(duration_minutes) {
+                    return Err(ValidationError::new("Invalid freeze window duration"));
+                }
+            }
+        }
+    }
     return Ok(());
 }
 
@@ -310,13 +493,16 @@ mod tests {
     use core::panic;
 
     use super::*;
-    use crate::model::YakManProjectRole;
+    use crate::adapters::{in_memory::InMemoryStorageAdapter, KVStorageAdapter};
+    use crate::model::{NotificationDeliveryStatus, NotificationSetting, YakManProjectRole};
+    use crate::notifications::YakManNotificationType;
+    use crate::services::kv_storage_service::KVStorageService;
     use crate::test_utils::fake_roles::FakeRoleExtractor;
     use crate::test_utils::*;
     use actix_web::{test, App};
     use actix_web_grants::GrantsMiddleware;
     use anyhow::Result;
-    use serde_json::Value;
+    use serde_json::{json, Value};
 
     #[actix_web::test]
     async fn get_projects_should_return_projects() -> Result<()> {
@@ -324,8 +510,8 @@ mod tests {
 
         let storage_service = test_storage_service().await?;
 
-        let project_foo_id = storage_service.create_project("foo", None).await?;
-        let project_bar_id = storage_service.create_project("bar", None).await?;
+        let project_foo_id = storage_service.create_project("foo", vec![]).await?;
+        let project_bar_id = storage_service.create_project("bar", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -357,8 +543,8 @@ mod tests {
 
         let storage_service = test_storage_service().await?;
 
-        let _project_foo_id = storage_service.create_project("foo", None).await?;
-        let project_bar_id = storage_service.create_project("bar", None).await?;
+        let _project_foo_id = storage_service.create_project("foo", vec![]).await?;
+        let project_bar_id = storage_service.create_project("bar", vec![]).await?;
 
         let fake_extractor = FakeRoleExtractor::new(vec![YakManRoleBinding::ProjectRoleBinding(
             YakManProjectRole {
@@ -395,8 +581,8 @@ mod tests {
 
         let storage_service = test_storage_service().await?;
 
-        let project_foo_id = storage_service.create_project("foo", None).await?;
-        let _project_bar_id = storage_service.create_project("bar", None).await?;
+        let project_foo_id = storage_service.create_project("foo", vec![]).await?;
+        let _project_bar_id = storage_service.create_project("bar", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -419,14 +605,90 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn get_project_should_return_all_notification_channels() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let project_foo_id = storage_service
+            .create_project(
+                "foo",
+                vec![
+                    ProjectNotificationSettings {
+                        notification_type: ProjectNotificationType::Slack {
+                            webhook_url: "https://hooks.slack.com/services/review".to_string(),
+                        },
+                        is_instance_updated_enabled: false,
+                        is_instance_created_enabled: false,
+                        is_revision_submitted_enabled: true,
+                        is_revision_approved_enabled: true,
+                        is_revision_reject_enabled: false,
+                        is_revision_rollback_enabled: false,
+                        is_instance_deleted_enabled: false,
+                        is_config_deleted_enabled: false,
+                        is_project_settings_updated_enabled: false,
+                        is_api_key_created_enabled: false,
+                    },
+                    ProjectNotificationSettings {
+                        notification_type: ProjectNotificationType::Discord {
+                            webhook_url: "https://discord.com/api/webhooks/ops".to_string(),
+                        },
+                        is_instance_updated_enabled: true,
+                        is_instance_created_enabled: false,
+                        is_revision_submitted_enabled: false,
+                        is_revision_approved_enabled: false,
+                        is_revision_reject_enabled: false,
+                        is_revision_rollback_enabled: false,
+                        is_instance_deleted_enabled: false,
+                        is_config_deleted_enabled: false,
+                        is_project_settings_updated_enabled: false,
+                        is_api_key_created_enabled: false,
+                    },
+                ],
+            )
+            .await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(storage_service))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(get_project),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/v1/projects/{project_foo_id}"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let value: Value = body_to_json_value(resp.map_into_boxed_body()).await?;
+
+        let channels = value["notification_settings"].as_array().unwrap();
+        assert_eq!(2, channels.len());
+        assert_eq!(
+            "https://hooks.slack.com/services/review",
+            channels[0]["settings"]["Slack"]["webhook_url"]
+        );
+        assert_eq!(true, channels[0]["events"]["is_revision_approved_enabled"]);
+        assert_eq!(false, channels[0]["events"]["is_instance_updated_enabled"]);
+        assert_eq!(
+            "https://discord.com/api/webhooks/ops",
+            channels[1]["settings"]["Discord"]["webhook_url"]
+        );
+        assert_eq!(true, channels[1]["events"]["is_instance_updated_enabled"]);
+
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn get_project_should_not_return_projects_that_user_does_not_have() -> Result<()> {
         prepare_for_actix_test()?;
 
         let storage_service = test_storage_service().await?;
 
-        let project_foo_id = storage_service.create_project("foo", None).await?;
-        let project_bar_id = storage_service.create_project("bar", None).await?;
+        let project_foo_id = storage_service.create_project("foo", vec![]).await?;
+        let project_bar_id = storage_service.create_project("bar", vec![]).await?;
 
         let fake_extractor = FakeRoleExtractor::new(vec![YakManRoleBinding::ProjectRoleBinding(
             YakManProjectRole {
@@ -450,6 +712,59 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn get_project_notification_deliveries_should_return_delivery_log() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let project_foo_id = storage_service.create_project("foo", vec![]).await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(storage_service))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(get_project_notification_deliveries),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v1/projects/{project_foo_id}/notification-deliveries"
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let value: Value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        assert_eq!(0, value.as_array().unwrap().len());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn get_project_notification_deliveries_should_require_project_admin() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let project_foo_id = storage_service.create_project("foo", vec![]).await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(storage_service))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::viewer_role))
+                .service(get_project_notification_deliveries),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v1/projects/{project_foo_id}/notification-deliveries"
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(403, resp.status().as_u16());
+
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn create_project_should_create_project_if_request_is_valid() -> Result<()> {
         prepare_for_actix_test()?;
@@ -465,7 +780,7 @@ mod tests {
             .uri("/v1/projects")
             .set_json(CreateProjectPayload {
                 project_name: "valid-project-name".to_string(),
-                notification_settings: None,
+                notification_settings: vec![],
             })
             .to_request();
         let resp = test::call_service(&app, req).await;
@@ -473,6 +788,29 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn create_project_should_accept_null_notification_settings() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_storage_service().await?))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(create_project),
+        )
+        .await;
+        let req = test::TestRequest::put()
+            .uri("/v1/projects")
+            .set_json(json!({
+                "project_name": "valid-project-name",
+                "notification_settings": null,
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn create_project_should_return_bad_request_if_project_name_is_invalid() -> Result<()> {
         prepare_for_actix_test()?;
@@ -488,7 +826,7 @@ mod tests {
             .uri("/v1/projects")
             .set_json(CreateProjectPayload {
                 project_name: "this is not a valid name".to_string(),
-                notification_settings: None,
+                notification_settings: vec![],
             })
             .to_request();
         let resp = test::call_service(&app, req).await;
@@ -512,7 +850,47 @@ mod tests {
             .uri("/v1/projects")
             .set_json(CreateProjectPayload {
                 project_name: "".to_string(),
-                notification_settings: None,
+                notification_settings: vec![],
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status().as_u16();
+        assert_eq!(400, status);
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn create_project_should_return_bad_request_if_email_notifications_have_no_smtp_server(
+    ) -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_storage_service().await?))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(create_project),
+        )
+        .await;
+        let req = test::TestRequest::put()
+            .uri("/v1/projects")
+            .set_json(CreateProjectPayload {
+                project_name: "foo".to_string(),
+                notification_settings: vec![ProjectNotificationSettings {
+                    notification_type: ProjectNotificationType::Email {
+                        recipients: vec!["approver@yakman.dev".to_string()],
+                        send_to_project_approvers: false,
+                    },
+                    is_instance_updated_enabled: false,
+                    is_instance_created_enabled: false,
+                    is_revision_submitted_enabled: true,
+                    is_revision_approved_enabled: false,
+                    is_revision_reject_enabled: false,
+                    is_revision_rollback_enabled: false,
+                    is_instance_deleted_enabled: false,
+                    is_config_deleted_enabled: false,
+                    is_project_settings_updated_enabled: false,
+                    is_api_key_created_enabled: false,
+                }],
             })
             .to_request();
         let resp = test::call_service(&app, req).await;
@@ -527,7 +905,7 @@ mod tests {
 
         let storage_service = test_storage_service().await?;
 
-        let project_foo_id = storage_service.create_project("foo", None).await?;
+        let project_foo_id = storage_service.create_project("foo", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -540,7 +918,9 @@ mod tests {
             .uri(&format!("/v1/projects/{project_foo_id}"))
             .set_json(UpdateProjectPayload {
                 project_name: "foo".to_string(),
-                notification_settings: None,
+                notification_settings: vec![],
+                approval_policy: YakManApprovalPolicy::default(),
+                freeze_windows: vec![],
             })
             .to_request();
         let resp = test::call_service(&app, req).await;
@@ -554,8 +934,8 @@ mod tests {
 
         let storage_service = test_storage_service().await?;
 
-        let project_foo_id = storage_service.create_project("foo", None).await?;
-        let _project_bar_id = storage_service.create_project("bar", None).await?;
+        let project_foo_id = storage_service.create_project("foo", vec![]).await?;
+        let _project_bar_id = storage_service.create_project("bar", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -568,7 +948,9 @@ mod tests {
             .uri(&format!("/v1/projects/{project_foo_id}"))
             .set_json(UpdateProjectPayload {
                 project_name: "bar".to_string(),
-                notification_settings: None,
+                notification_settings: vec![],
+                approval_policy: YakManApprovalPolicy::default(),
+                freeze_windows: vec![],
             })
             .to_request();
         let resp = test::call_service(&app, req).await;
@@ -582,7 +964,7 @@ mod tests {
 
         let storage_service = test_storage_service().await?;
 
-        let _project_foo_id = storage_service.create_project("foo", None).await?;
+        let _project_foo_id = storage_service.create_project("foo", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -595,7 +977,9 @@ mod tests {
             .uri(&"/v1/projects/p48ad84e623f0".to_string()) // random id
             .set_json(UpdateProjectPayload {
                 project_name: "foo".to_string(),
-                notification_settings: None,
+                notification_settings: vec![],
+                approval_policy: YakManApprovalPolicy::default(),
+                freeze_windows: vec![],
             })
             .to_request();
         let resp = test::call_service(&app, req).await;
@@ -609,7 +993,7 @@ mod tests {
 
         let storage_service = test_storage_service().await?;
 
-        let project_foo_id = storage_service.create_project("foo", None).await?;
+        let project_foo_id = storage_service.create_project("foo", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -622,7 +1006,9 @@ mod tests {
             .uri(&format!("/v1/projects/{project_foo_id}"))
             .set_json(UpdateProjectPayload {
                 project_name: "invalid project".to_string(),
-                notification_settings: None,
+                notification_settings: vec![],
+                approval_policy: YakManApprovalPolicy::default(),
+                freeze_windows: vec![],
             })
             .to_request();
         let resp = test::call_service(&app, req).await;
@@ -636,7 +1022,7 @@ mod tests {
 
         let storage_service = test_storage_service().await?;
 
-        let project_foo_id = storage_service.create_project("foo", None).await?;
+        let project_foo_id = storage_service.create_project("foo", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -649,7 +1035,9 @@ mod tests {
             .uri(&format!("/v1/projects/{project_foo_id}"))
             .set_json(UpdateProjectPayload {
                 project_name: "foo".to_string(),
-                notification_settings: None,
+                notification_settings: vec![],
+                approval_policy: YakManApprovalPolicy::default(),
+                freeze_windows: vec![],
             })
             .to_request();
         let resp = test::call_service(&app, req).await;
@@ -663,8 +1051,8 @@ mod tests {
 
         let storage_service = test_storage_service().await?;
 
-        let project_foo_id = storage_service.create_project("foo", None).await?;
-        let _project_bar_id = storage_service.create_project("bar", None).await?;
+        let project_foo_id = storage_service.create_project("foo", vec![]).await?;
+        let _project_bar_id = storage_service.create_project("bar", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -693,13 +1081,78 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn delete_project_should_delete_notification_deliveries() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let adapter = Arc::new(InMemoryStorageAdapter::new());
+        adapter.initialize_yakman_storage().await?;
+        let storage_service: Arc<dyn StorageService> =
+            Arc::new(KVStorageService::new(adapter.clone()));
+
+        let project_id = storage_service.create_project("foo", vec![]).await?;
+        let delivery = YakManNotificationDelivery {
+            id: "n1".to_string(),
+            project_id: project_id.clone(),
+            settings: NotificationSetting::Slack {
+                webhook_url: "https://hooks.slack.com/services/review".to_string(),
+            },
+            event: YakManNotificationType::InstanceCreated {
+                project_name: "foo".to_string(),
+                config_name: "bar".to_string(),
+                instance: "i123".to_string(),
+            },
+            status: NotificationDeliveryStatus::Pending,
+            attempts: 0,
+            created_at_ms: 0,
+            next_attempt_at_ms: 0,
+            last_attempt_at_ms: None,
+            last_error: None,
+        };
+        adapter
+            .save_pending_notification_delivery(&delivery)
+            .await?;
+        let delivered = YakManNotificationDelivery {
+            id: "n2".to_string(),
+            status: NotificationDeliveryStatus::Delivered,
+            ..delivery
+        };
+        adapter
+            .save_notification_delivery(&project_id, &delivered)
+            .await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(delete_project),
+        )
+        .await;
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v1/projects/{project_id}"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        assert!(adapter
+            .get_pending_notification_deliveries()
+            .await?
+            .is_empty());
+        assert!(adapter
+            .get_notification_deliveries(&project_id)
+            .await?
+            .is_empty());
+
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn delete_project_should_return_not_found_for_none_existent_project() -> Result<()> {
         prepare_for_actix_test()?;
 
         let storage_service = test_storage_service().await?;
 
-        let _project_foo_id = storage_service.create_project("foo", None).await?;
+        let _project_foo_id = storage_service.create_project("foo", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -723,7 +1176,7 @@ mod tests {
 
         let storage_service = test_storage_service().await?;
 
-        let project_foo_id = storage_service.create_project("foo", None).await?;
+        let project_foo_id = storage_service.create_project("foo", vec![]).await?;
 
         let app = test::init_service(
             App::new()
@@ -1,15 +1,28 @@
 use std::sync::Arc;
 
-use crate::error::{RollbackRevisionError, YakManApiError};
+use crate::api::instances::get_instance_labels_for_scope;
+use crate::error::{
+    AddRevisionCommentError, ApplyRevisionError, ApproveRevisionError, RollbackRevisionError,
+    ScheduleApplyError, YakManApiError,
+};
 use crate::middleware::roles::YakManRoleBinding;
 use crate::middleware::YakManPrinciple;
-use crate::model::response::RevisionPayload;
-use crate::model::{ConfigInstanceRevision, YakManRole};
+use crate::model::response::{ReviewRevisionResponse, RevisionPayload};
+use crate::model::{
+    ConfigInstanceRevision, RevisionComment, YakManApiKeyOperation, YakManRole,
+    YakManScheduledApply,
+};
 use crate::services::StorageService;
-use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::{
+    delete, get, post,
+    web::{self, Json},
+    HttpResponse, Responder,
+};
 use actix_web_grants::authorities::AuthDetails;
-use serde::Deserialize;
-use utoipa::ToSchema;
+use actix_web_validation::Validated;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
 
 /// Get all of the revisions for a config
 #[utoipa::path(responses((status = 200, body = Vec<ConfigInstanceRevision>)))]
@@ -27,7 +40,15 @@ async fn get_instance_revisions(
         .unwrap()
         .unwrap(); // TODO: handle these unwraps
 
-    if !YakManRoleBinding::has_any_role(
+    let instance_labels = get_instance_labels_for_scope(
+        &auth_details,
+        storage_service.get_ref(),
+        &config_id,
+        &instance,
+    )
+    .await?;
+
+    if !YakManRoleBinding::has_any_role_in_scope(
         vec![
             YakManRole::Admin,
             YakManRole::Approver,
@@ -35,6 +56,9 @@ async fn get_instance_revisions(
             YakManRole::Viewer,
         ],
         &config.project_id,
+        YakManApiKeyOperation::ReadData,
+        &config_id,
+        &instance_labels,
         &auth_details.authorities,
     ) {
         return Err(YakManApiError::forbidden());
@@ -56,16 +80,35 @@ pub enum ReviewResult {
     Reject,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct ReviewPayload {
+    /// Optional when approving, the reason is required when rejecting
+    #[validate(length(max = 2000))]
+    pub comment: Option<String>,
+}
+
 /// Updates a revsion based on a review result.
-#[utoipa::path(responses((status = 200, body = ())))]
+#[utoipa::path(request_body = Option<ReviewPayload>, responses((status = 200, body = ReviewRevisionResponse)))]
 #[post("/v1/configs/{config_id}/instances/{instance}/revisions/{revision}/review/{result}")]
 async fn review_pending_instance_revision(
     auth_details: AuthDetails<YakManRoleBinding>,
     path: web::Path<(String, String, String, ReviewResult)>,
+    // The body is optional since approving does not need a comment
+    payload: Option<Json<ReviewPayload>>,
     storage_service: web::Data<Arc<dyn StorageService>>,
     principle: YakManPrinciple,
 ) -> Result<impl Responder, YakManApiError> {
     let (config_id, instance, revision, result) = path.into_inner();
+    let payload = payload.map(Json::into_inner);
+    if let Some(payload) = &payload {
+        if payload.validate().is_err() {
+            return Err(YakManApiError::bad_request("Comment is too long"));
+        }
+    }
+    let comment = payload
+        .and_then(|payload| payload.comment)
+        .map(|comment| comment.trim().to_string())
+        .filter(|comment| !comment.is_empty());
 
     let config = storage_service
         .get_config(&config_id)
@@ -73,9 +116,20 @@ async fn review_pending_instance_revision(
         .unwrap()
         .unwrap();
 
-    if !YakManRoleBinding::has_any_role(
+    let instance_labels = get_instance_labels_for_scope(
+        &auth_details,
+        storage_service.get_ref(),
+        &config_id,
+        &instance,
+    )
+    .await?;
+
+    if !YakManRoleBinding::has_any_role_in_scope(
         vec![YakManRole::Admin, YakManRole::Approver],
         &config.project_id,
+        YakManApiKeyOperation::ApproveRevisions,
+        &config_id,
+        &instance_labels,
         &auth_details.authorities,
     ) {
         return Err(YakManApiError::forbidden());
@@ -87,51 +141,74 @@ async fn review_pending_instance_revision(
 
     match result {
         ReviewResult::ApproveAndApply | ReviewResult::Approve => {
-            return match storage_service
-                .approve_instance_revision(&config_id, &instance, &revision, &reviewer_user_id)
-                .await
-            {
-                Ok(_) => {
-                    if result == ReviewResult::ApproveAndApply {
-                        return match storage_service
-                            .apply_instance_revision(
-                                &config_id,
-                                &instance,
-                                &revision,
-                                &reviewer_user_id,
-                            )
-                            .await
-                        {
-                            Ok(_) => Ok(HttpResponse::Ok().finish()),
-                            Err(_) => {
-                                Err(YakManApiError::server_error("failed to update instance"))
-                            }
-                        };
-                    }
-
-                    return Ok(HttpResponse::Ok().finish());
-                }
-                Err(_) => Err(YakManApiError::server_error("failed to update instance")),
-            };
+            let fully_approved = storage_service
+                .approve_instance_revision(
+                    &config_id,
+                    &instance,
+                    &revision,
+                    &reviewer_user_id,
+                    comment,
+                )
+                .await?;
+
+            // Still waiting on other approvers, the approval is kept and the apply is skipped
+            let apply = result == ReviewResult::ApproveAndApply && fully_approved;
+            if apply {
+                storage_service
+                    .apply_instance_revision(
+                        &config_id,
+                        &instance,
+                        &revision,
+                        &reviewer_user_id,
+                        false,
+                    )
+                    .await?;
+            }
+            return Ok(HttpResponse::Ok().json(ReviewRevisionResponse {
+                fully_approved: fully_approved,
+                applied: apply,
+            }));
         }
         ReviewResult::Reject => {
+            let Some(reason) = comment else {
+                return Err(YakManApiError::bad_request(
+                    "A reason is required to reject a revision",
+                ));
+            };
             return match storage_service
-                .reject_instance_revision(&config_id, &instance, &revision, &reviewer_user_id)
+                .reject_instance_revision(
+                    &config_id,
+                    &instance,
+                    &revision,
+                    &reviewer_user_id,
+                    &reason,
+                )
                 .await
             {
-                Ok(_) => Ok(HttpResponse::Ok().finish()),
+                Ok(_) => Ok(HttpResponse::Ok().json(ReviewRevisionResponse {
+                    fully_approved: false,
+                    applied: false,
+                })),
                 Err(_) => Err(YakManApiError::server_error("failed to update instance")),
             };
         }
     }
 }
 
+#[derive(Deserialize, IntoParams)]
+pub struct ApplyRevisionQuery {
+    /// Apply even if the project has an active freeze window, requires the Admin role
+    #[serde(default)]
+    pub break_glass: bool,
+}
+
 /// Applies an approved revision
-#[utoipa::path(responses((status = 200, body = ())))]
+#[utoipa::path(params(ApplyRevisionQuery), responses((status = 200, body = ())))]
 #[post("/v1/configs/{config_id}/instances/{instance}/revisions/{revision}/apply")]
 async fn apply_instance_revision(
     auth_details: AuthDetails<YakManRoleBinding>,
     path: web::Path<(String, String, String)>,
+    query: web::Query<ApplyRevisionQuery>,
     storage_service: web::Data<Arc<dyn StorageService>>,
     principle: YakManPrinciple,
 ) -> Result<impl Responder, YakManApiError> {
@@ -143,29 +220,197 @@ async fn apply_instance_revision(
         .unwrap()
         .unwrap(); // todo: handle these unwraps
 
-    if !YakManRoleBinding::has_any_role(
+    let instance_labels = get_instance_labels_for_scope(
+        &auth_details,
+        storage_service.get_ref(),
+        &config_id,
+        &instance,
+    )
+    .await?;
+
+    if !YakManRoleBinding::has_any_role_in_scope(
         vec![
             YakManRole::Admin,
             YakManRole::Approver,
             YakManRole::Operator,
         ],
         &config.project_id,
+        YakManApiKeyOperation::ApproveRevisions,
+        &config_id,
+        &instance_labels,
         &auth_details.authorities,
     ) {
         return Err(YakManApiError::forbidden());
     }
 
+    if query.break_glass
+        && !YakManRoleBinding::has_any_role(
+            vec![YakManRole::Admin],
+            &config.project_id,
+            &auth_details.authorities,
+        )
+    {
+        return Err(YakManApiError::forbidden());
+    }
+
     let Some(reviewer_user_id) = principle.user_id else {
         return Err(YakManApiError::forbidden());
     };
 
-    return match storage_service
-        .apply_instance_revision(&config_id, &instance, &revision, &reviewer_user_id)
-        .await
-    {
-        Ok(_) => Ok(HttpResponse::Ok().finish()),
-        Err(_) => Err(YakManApiError::server_error("failed to update instance")),
-    };
+    storage_service
+        .apply_instance_revision(
+            &config_id,
+            &instance,
+            &revision,
+            &reviewer_user_id,
+            query.break_glass,
+        )
+        .await?;
+    return Ok(HttpResponse::Ok().finish());
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ScheduleApplyPayload {
+    /// Unix timestamp in milliseconds
+    pub apply_at_ms: i64,
+}
+
+/// Schedules an approved revision to be applied at a future time
+#[utoipa::path(request_body = ScheduleApplyPayload, responses((status = 200, body = YakManScheduledApply)))]
+#[post("/v1/configs/{config_id}/instances/{instance}/revisions/{revision}/schedule")]
+async fn schedule_instance_revision_apply(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<(String, String, String)>,
+    payload: web::Json<ScheduleApplyPayload>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+    principle: YakManPrinciple,
+) -> Result<impl Responder, YakManApiError> {
+    let (config_id, instance, revision) = path.into_inner();
+
+    verify_can_apply(
+        &auth_details,
+        storage_service.get_ref(),
+        &config_id,
+        &instance,
+    )
+    .await?;
+
+    let scheduled_by_user_id = principle.user_id.ok_or(YakManApiError::forbidden())?;
+
+    let scheduled = storage_service
+        .schedule_instance_revision_apply(
+            &config_id,
+            &instance,
+            &revision,
+            payload.apply_at_ms,
+            &scheduled_by_user_id,
+        )
+        .await?;
+    return Ok(web::Json(scheduled));
+}
+
+/// Cancels the scheduled apply of a revision
+#[utoipa::path(responses((status = 200, body = ())))]
+#[delete("/v1/configs/{config_id}/instances/{instance}/revisions/{revision}/schedule")]
+async fn cancel_scheduled_apply(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<(String, String, String)>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+    principle: YakManPrinciple,
+) -> Result<impl Responder, YakManApiError> {
+    let (config_id, instance, revision) = path.into_inner();
+
+    verify_can_apply(
+        &auth_details,
+        storage_service.get_ref(),
+        &config_id,
+        &instance,
+    )
+    .await?;
+
+    let cancelled_by_user_id = principle.user_id.ok_or(YakManApiError::forbidden())?;
+
+    storage_service
+        .cancel_scheduled_apply(&config_id, &instance, &revision, &cancelled_by_user_id)
+        .await?;
+    return Ok(HttpResponse::Ok().finish());
+}
+
+/// Get the scheduled apply of an instance, null if nothing is scheduled
+#[utoipa::path(responses((status = 200, body = Option<YakManScheduledApply>)))]
+#[get("/v1/configs/{config_id}/instances/{instance}/scheduled-apply")]
+async fn get_scheduled_apply(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<(String, String)>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    let (config_id, instance) = path.into_inner();
+
+    let config = storage_service
+        .get_config(&config_id)
+        .await?
+        .ok_or(YakManApiError::not_found("Config not found"))?;
+
+    let instance_labels = get_instance_labels_for_scope(
+        &auth_details,
+        storage_service.get_ref(),
+        &config_id,
+        &instance,
+    )
+    .await?;
+
+    if !YakManRoleBinding::has_any_role_in_scope(
+        vec![
+            YakManRole::Admin,
+            YakManRole::Approver,
+            YakManRole::Operator,
+            YakManRole::Viewer,
+        ],
+        &config.project_id,
+        YakManApiKeyOperation::ReadData,
+        &config_id,
+        &instance_labels,
+        &auth_details.authorities,
+    ) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let scheduled = storage_service
+        .get_scheduled_apply(&config_id, &instance)
+        .await?;
+    return Ok(web::Json(scheduled));
+}
+
+/// Scheduling an apply requires the same roles as applying
+async fn verify_can_apply(
+    auth_details: &AuthDetails<YakManRoleBinding>,
+    storage_service: &Arc<dyn StorageService>,
+    config_id: &str,
+    instance: &str,
+) -> Result<(), YakManApiError> {
+    let config = storage_service
+        .get_config(config_id)
+        .await?
+        .ok_or(ScheduleApplyError::InvalidConfig)?;
+
+    let instance_labels =
+        get_instance_labels_for_scope(auth_details, storage_service, config_id, instance).await?;
+
+    if !YakManRoleBinding::has_any_role_in_scope(
+        vec![
+            YakManRole::Admin,
+            YakManRole::Approver,
+            YakManRole::Operator,
+        ],
+        &config.project_id,
+        YakManApiKeyOperation::ApproveRevisions,
+        config_id,
+        &instance_labels,
+        &auth_details.authorities,
+    ) {
+        return Err(YakManApiError::forbidden());
+    }
+    return Ok(());
 }
 
 /// Rollback an instance a previous revision (by cloning the revision)
@@ -184,13 +429,24 @@ async fn rollback_instance_revision(
         .await?
         .ok_or(RollbackRevisionError::InvalidConfig)?;
 
-    if !YakManRoleBinding::has_any_role(
+    let instance_labels = get_instance_labels_for_scope(
+        &auth_details,
+        storage_service.get_ref(),
+        &config_id,
+        &instance,
+    )
+    .await?;
+
+    if !YakManRoleBinding::has_any_role_in_scope(
         vec![
             YakManRole::Admin,
             YakManRole::Approver,
             YakManRole::Operator,
         ],
         &config.project_id,
+        YakManApiKeyOperation::SubmitRevisions,
+        &config_id,
+        &instance_labels,
         &auth_details.authorities,
     ) {
         return Err(YakManApiError::forbidden());
@@ -207,6 +463,183 @@ async fn rollback_instance_revision(
     }))
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct RevisionCommentPayload {
+    #[validate(length(min = 1, max = 2000))]
+    pub message: String,
+}
+
+/// Adds a comment to a revision
+#[utoipa::path(request_body = RevisionCommentPayload, responses((status = 200, body = RevisionComment)))]
+#[post("/v1/configs/{config_id}/instances/{instance}/revisions/{revision}/comments")]
+async fn add_revision_comment(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<(String, String, String)>,
+    Validated(Json(payload)): Validated<Json<RevisionCommentPayload>>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+    principle: YakManPrinciple,
+) -> Result<impl Responder, YakManApiError> {
+    let (config_id, instance, revision) = path.into_inner();
+
+    let Some(config) = storage_service.get_config(&config_id).await? else {
+        return Err(YakManApiError::not_found("Config not found"));
+    };
+
+    let instance_labels = get_instance_labels_for_scope(
+        &auth_details,
+        storage_service.get_ref(),
+        &config_id,
+        &instance,
+    )
+    .await?;
+
+    // Submitters and reviewers both take part in the discussion
+    let can_submit = YakManRoleBinding::has_any_role_in_scope(
+        vec![YakManRole::Admin, YakManRole::Approver],
+        &config.project_id,
+        YakManApiKeyOperation::SubmitRevisions,
+        &config_id,
+        &instance_labels,
+        &auth_details.authorities,
+    );
+    let can_review = YakManRoleBinding::has_any_role_in_scope(
+        vec![
+            YakManRole::Admin,
+            YakManRole::Approver,
+            YakManRole::Operator,
+        ],
+        &config.project_id,
+        YakManApiKeyOperation::ApproveRevisions,
+        &config_id,
+        &instance_labels,
+        &auth_details.authorities,
+    );
+    if !can_submit && !can_review {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let Some(user_id) = principle.user_id else {
+        return Err(YakManApiError::forbidden());
+    };
+
+    let message = payload.message.trim();
+    if message.is_empty() {
+        return Err(YakManApiError::bad_request("Comment cannot be empty"));
+    }
+
+    let comment = storage_service
+        .add_revision_comment(&config_id, &instance, &revision, &user_id, message)
+        .await?;
+    return Ok(web::Json(comment));
+}
+
+impl From<AddRevisionCommentError> for YakManApiError {
+    fn from(value: AddRevisionCommentError) -> Self {
+        return match value {
+            AddRevisionCommentError::InvalidConfig => YakManApiError::bad_request("Invalid Config"),
+            AddRevisionCommentError::InvalidInstance => {
+                YakManApiError::bad_request("Invalid Config Instance")
+            }
+            AddRevisionCommentError::InvalidRevision => {
+                YakManApiError::bad_request("Invalid Revision")
+            }
+            AddRevisionCommentError::StorageError { message } => {
+                log::error!("Error while adding revision comment {message}");
+                YakManApiError::server_error("failed to add comment")
+            }
+        };
+    }
+}
+
+impl From<ApproveRevisionError> for YakManApiError {
+    fn from(value: ApproveRevisionError) -> Self {
+        return match value {
+            ApproveRevisionError::InvalidConfig => YakManApiError::bad_request("Invalid Config"),
+            ApproveRevisionError::InvalidInstance => {
+                YakManApiError::bad_request("Invalid Config Instance")
+            }
+            ApproveRevisionError::InvalidRevision => {
+                YakManApiError::bad_request("Invalid Revision")
+            }
+            ApproveRevisionError::AlreadyApproved => {
+                YakManApiError::bad_request("You have already approved this revision")
+            }
+            ApproveRevisionError::SelfApproval => {
+                YakManApiError::bad_request("You cannot approve a revision you submitted")
+            }
+            ApproveRevisionError::StorageError { message } => {
+                log::error!("Error while approving revision {message}");
+                YakManApiError::server_error("failed to update instance")
+            }
+        };
+    }
+}
+
+impl From<ApplyRevisionError> for YakManApiError {
+    fn from(value: ApplyRevisionError) -> Self {
+        return match value {
+            ApplyRevisionError::InvalidConfig => YakManApiError::bad_request("Invalid Config"),
+            ApplyRevisionError::InvalidInstance => {
+                YakManApiError::bad_request("Invalid Config Instance")
+            }
+            ApplyRevisionError::InvalidRevision => YakManApiError::bad_request("Invalid Revision"),
+            ApplyRevisionError::NotApproved => {
+                YakManApiError::bad_request("Revision is not approved")
+            }
+            ApplyRevisionError::NotEnoughApprovals {
+                required,
+                approvals,
+            } => YakManApiError::bad_request(&format!(
+                "Revision has {approvals} of {required} required approvals"
+            )),
+            ApplyRevisionError::ChangeFreeze { reason } => {
+                YakManApiError::bad_request(&format!("Changes are frozen: {reason}"))
+            }
+            ApplyRevisionError::StorageError { message } => {
+                log::error!("Error while applying revision {message}");
+                YakManApiError::server_error("failed to update instance")
+            }
+        };
+    }
+}
+
+impl From<ScheduleApplyError> for YakManApiError {
+    fn from(value: ScheduleApplyError) -> Self {
+        return match value {
+            ScheduleApplyError::InvalidConfig => YakManApiError::bad_request("Invalid Config"),
+            ScheduleApplyError::InvalidInstance => {
+                YakManApiError::bad_request("Invalid Config Instance")
+            }
+            ScheduleApplyError::InvalidRevision => YakManApiError::bad_request("Invalid Revision"),
+            ScheduleApplyError::NotApproved => {
+                YakManApiError::bad_request("Revision is not approved")
+            }
+            ScheduleApplyError::NotEnoughApprovals {
+                required,
+                approvals,
+            } => YakManApiError::bad_request(&format!(
+                "Revision has {approvals} of {required} required approvals"
+            )),
+            ScheduleApplyError::InvalidApplyTime => {
+                YakManApiError::bad_request("Apply time must be in the future")
+            }
+            ScheduleApplyError::ChangeFreeze { reason } => YakManApiError::bad_request(&format!(
+                "Changes are frozen at the apply time: {reason}"
+            )),
+            ScheduleApplyError::AlreadyScheduled => {
+                YakManApiError::bad_request("Instance already has a scheduled apply")
+            }
+            ScheduleApplyError::NotScheduled => {
+                YakManApiError::bad_request("Revision is not scheduled")
+            }
+            ScheduleApplyError::StorageError { message } => {
+                log::error!("Error while scheduling apply {message}");
+                YakManApiError::server_error("Storage error")
+            }
+        };
+    }
+}
+
 impl From<RollbackRevisionError> for YakManApiError {
     fn from(value: RollbackRevisionError) -> Self {
         return match value {
@@ -224,3 +657,716 @@ impl From<RollbackRevisionError> for YakManApiError {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{ApproveRevisionError, CreateConfigInstanceError, SaveConfigInstanceError};
+    use crate::model::ConfigInstanceEventData;
+    use crate::model::{
+        LabelType, RevisionChangeDetails, RevisionReviewState, YakManApiKey, YakManApprovalPolicy,
+        YakManAuditAction, YakManFreezeSchedule, YakManFreezeWindow, YakManLabel,
+        YakManLabelApprovalRule,
+    };
+    use crate::services::audit::AuditLogFilter;
+    use crate::test_utils::*;
+    use actix_web::dev::Service;
+    use actix_web::{test, web::Data, App, HttpMessage};
+    use actix_web_grants::GrantsMiddleware;
+    use anyhow::Result;
+    use chrono::Utc;
+
+    #[actix_web::test]
+    async fn apply_should_require_approvals_from_policy() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let project_id = storage_service.create_project("foo", vec![]).await?;
+        storage_service
+            .update_project(
+                &project_id,
+                "foo",
+                vec![],
+                YakManApprovalPolicy {
+                    required_approvals: 1,
+                    label_rules: vec![YakManLabelApprovalRule {
+                        label_id: String::from("env"),
+                        value: String::from("prod"),
+                        required_approvals: 2,
+                    }],
+                    forbid_self_approval: false,
+                    require_change_description: false,
+                },
+                vec![],
+            )
+            .await?;
+        storage_service
+            .create_label(LabelType {
+                id: String::from("env"),
+                name: String::from("env"),
+                description: String::from(""),
+                options: vec![String::from("dev"), String::from("prod")],
+            })
+            .await?;
+        let config_id = storage_service.create_config("bar", &project_id).await?;
+        let labels = vec![YakManLabel {
+            label_id: String::from("env"),
+            name: None,
+            value: String::from("prod"),
+        }];
+        let instance = storage_service
+            .create_config_instance(&config_id, labels.clone(), "v1", None, "u0", false)
+            .await?;
+        let revision = storage_service
+            .submit_new_instance_revision(
+                &config_id,
+                &instance,
+                labels,
+                "v2",
+                None,
+                RevisionChangeDetails::default(),
+                "u0",
+            )
+            .await?;
+
+        storage_service
+            .approve_instance_revision(&config_id, &instance, &revision, "u1", None)
+            .await?;
+        let result = storage_service
+            .approve_instance_revision(&config_id, &instance, &revision, "u1", None)
+            .await;
+        assert!(matches!(result, Err(ApproveRevisionError::AlreadyApproved)));
+        // An api key created by the same user is not a second approver
+        storage_service
+            .save_api_key(YakManApiKey {
+                id: String::from("apikey-u1"),
+                hash: String::from("hash"),
+                project_id: Some(project_id.clone()),
+                role: Some(YakManRole::Approver),
+                service_account_id: None,
+                created_at: 0,
+                created_by_user_id: String::from("u1"),
+                expires_at: None,
+                rotated_at: None,
+                previous_hash: None,
+                previous_hash_expires_at: None,
+                scope: None,
+            })
+            .await?;
+        let result = storage_service
+            .approve_instance_revision(&config_id, &instance, &revision, "apikey-u1", None)
+            .await;
+        assert!(matches!(result, Err(ApproveRevisionError::AlreadyApproved)));
+
+        let revisions = storage_service
+            .get_instance_revisions(&config_id, &instance)
+            .await?
+            .unwrap();
+        let pending = revisions.iter().find(|r| r.revision == revision).unwrap();
+        assert_eq!(RevisionReviewState::Pending, pending.review_state);
+        assert_eq!(1, pending.approvals.len());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some(String::from("u2")),
+                    });
+                    srv.call(req)
+                })
+                .service(review_pending_instance_revision)
+                .service(apply_instance_revision),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri(&format!(
+                "/v1/configs/{config_id}/instances/{instance}/revisions/{revision}/apply"
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+        let value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        assert_eq!("Revision has 1 of 2 required approvals", value["message"]);
+
+        let req = test::TestRequest::post()
+            .uri(&format!(
+                "/v1/configs/{config_id}/instances/{instance}/revisions/{revision}/review/ApproveAndApply"
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let instance = storage_service
+            .get_config_instance(&config_id, &instance)
+            .await?
+            .unwrap();
+        assert_eq!(revision, instance.current_revision);
+        assert_eq!(None, instance.pending_revision);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn approve_and_apply_should_skip_apply_until_fully_approved() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let project_id = storage_service.create_project("foo", vec![]).await?;
+        storage_service
+            .update_project(
+                &project_id,
+                "foo",
+                vec![],
+                YakManApprovalPolicy {
+                    required_approvals: 2,
+                    ..YakManApprovalPolicy::default()
+                },
+                vec![],
+            )
+            .await?;
+        let config_id = storage_service.create_config("bar", &project_id).await?;
+        let instance = storage_service
+            .create_config_instance(&config_id, vec![], "v1", None, "u0", false)
+            .await?;
+        let revision = storage_service
+            .submit_new_instance_revision(
+                &config_id,
+                &instance,
+                vec![],
+                "v2",
+                None,
+                RevisionChangeDetails::default(),
+                "u0",
+            )
+            .await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some(String::from("u1")),
+                    });
+                    srv.call(req)
+                })
+                .service(review_pending_instance_revision),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri(&format!(
+                "/v1/configs/{config_id}/instances/{instance}/revisions/{revision}/review/ApproveAndApply"
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+        let value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        assert_eq!(false, value["fully_approved"]);
+        assert_eq!(false, value["applied"]);
+
+        let instance = storage_service
+            .get_config_instance(&config_id, &instance)
+            .await?
+            .unwrap();
+        assert_eq!(Some(revision.clone()), instance.pending_revision);
+        let revisions = storage_service
+            .get_instance_revisions(&config_id, &instance.instance)
+            .await?
+            .unwrap();
+        let pending = revisions.iter().find(|r| r.revision == revision).unwrap();
+        assert_eq!(1, pending.approvals.len());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn approve_should_reject_self_approval_when_four_eyes_is_enabled() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let project_id = storage_service.create_project("foo", vec![]).await?;
+        storage_service
+            .update_project(
+                &project_id,
+                "foo",
+                vec![],
+                YakManApprovalPolicy {
+                    required_approvals: 1,
+                    label_rules: vec![],
+                    forbid_self_approval: true,
+                    require_change_description: false,
+                },
+                vec![],
+            )
+            .await?;
+        storage_service
+            .save_api_key(YakManApiKey {
+                id: String::from("apikey-u1"),
+                hash: String::from("hash"),
+                project_id: Some(project_id.clone()),
+                role: Some(YakManRole::Operator),
+                service_account_id: None,
+                created_at: 0,
+                created_by_user_id: String::from("u1"),
+                expires_at: None,
+                rotated_at: None,
+                previous_hash: None,
+                previous_hash_expires_at: None,
+                scope: None,
+            })
+            .await?;
+        let config_id = storage_service.create_config("bar", &project_id).await?;
+        let instance = storage_service
+            .create_config_instance(&config_id, vec![], "v1", None, "u0", false)
+            .await?;
+        // Submitted with an api key created by u1
+        let revision = storage_service
+            .submit_new_instance_revision(
+                &config_id,
+                &instance,
+                vec![],
+                "v2",
+                None,
+                RevisionChangeDetails::default(),
+                "apikey-u1",
+            )
+            .await?;
+
+        let result = storage_service
+            .approve_instance_revision(&config_id, &instance, &revision, "apikey-u1", None)
+            .await;
+        assert!(matches!(result, Err(ApproveRevisionError::SelfApproval)));
+        let result = storage_service
+            .approve_instance_revision(&config_id, &instance, &revision, "u1", None)
+            .await;
+        assert!(matches!(result, Err(ApproveRevisionError::SelfApproval)));
+
+        storage_service
+            .approve_instance_revision(&config_id, &instance, &revision, "u2", None)
+            .await?;
+        storage_service
+            .apply_instance_revision(&config_id, &instance, &revision, "u1", false)
+            .await?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn scheduled_apply_should_be_cancellable_and_applied_by_worker() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let project_id = storage_service.create_project("foo", vec![]).await?;
+        let config_id = storage_service.create_config("bar", &project_id).await?;
+        let instance = storage_service
+            .create_config_instance(&config_id, vec![], "v1", None, "u0", false)
+            .await?;
+        let revision = storage_service
+            .submit_new_instance_revision(
+                &config_id,
+                &instance,
+                vec![],
+                "v2",
+                None,
+                RevisionChangeDetails::default(),
+                "u0",
+            )
+            .await?;
+        storage_service
+            .approve_instance_revision(&config_id, &instance, &revision, "u1", None)
+            .await?;
+
+        let result = storage_service
+            .schedule_instance_revision_apply(&config_id, &instance, &revision, 0, "u1")
+            .await;
+        assert!(matches!(result, Err(ScheduleApplyError::InvalidApplyTime)));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some(String::from("u2")),
+                    });
+                    srv.call(req)
+                })
+                .service(schedule_instance_revision_apply)
+                .service(cancel_scheduled_apply)
+                .service(get_scheduled_apply),
+        )
+        .await;
+
+        let apply_at_ms = Utc::now().timestamp_millis() + 60 * 60 * 1000;
+        let schedule_uri =
+            format!("/v1/configs/{config_id}/instances/{instance}/revisions/{revision}/schedule");
+        let req = test::TestRequest::post()
+            .uri(&schedule_uri)
+            .set_json(ScheduleApplyPayload {
+                apply_at_ms: apply_at_ms,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let req = test::TestRequest::post()
+            .uri(&schedule_uri)
+            .set_json(ScheduleApplyPayload {
+                apply_at_ms: apply_at_ms,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+
+        let get_uri = format!("/v1/configs/{config_id}/instances/{instance}/scheduled-apply");
+        let req = test::TestRequest::get().uri(&get_uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        let value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        assert_eq!(revision, value["revision"]);
+        assert_eq!("u2", value["scheduled_by_user_id"]);
+
+        let req = test::TestRequest::delete().uri(&schedule_uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let req = test::TestRequest::get().uri(&get_uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        let value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        assert!(value.is_null());
+
+        let scheduled = storage_service
+            .schedule_instance_revision_apply(&config_id, &instance, &revision, apply_at_ms, "u2")
+            .await?;
+        storage_service.run_scheduled_apply(&scheduled).await?;
+
+        assert!(storage_service.get_scheduled_applies().await?.is_empty());
+        let instance = storage_service
+            .get_config_instance(&config_id, &instance)
+            .await?
+            .unwrap();
+        assert_eq!(revision, instance.current_revision);
+        let events: Vec<_> = instance.changelog.iter().map(|e| &e.event).collect();
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, ConfigInstanceEventData::ScheduledApplyCancelled { .. })));
+        assert!(matches!(
+            events.last(),
+            Some(ConfigInstanceEventData::Updated {
+                applied_by_user_id,
+                ..
+            }) if applied_by_user_id == "u2"
+        ));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn apply_should_be_blocked_by_freeze_window_unless_admin_breaks_glass() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let project_id = storage_service.create_project("foo", vec![]).await?;
+        let config_id = storage_service.create_config("bar", &project_id).await?;
+        let instance = storage_service
+            .create_config_instance(&config_id, vec![], "v1", None, "u0", false)
+            .await?;
+        let revision = storage_service
+            .submit_new_instance_revision(
+                &config_id,
+                &instance,
+                vec![],
+                "v2",
+                None,
+                RevisionChangeDetails::default(),
+                "u0",
+            )
+            .await?;
+        storage_service
+            .approve_instance_revision(&config_id, &instance, &revision, "u1", None)
+            .await?;
+
+        let now = Utc::now().timestamp_millis();
+        storage_service
+            .update_project(
+                &project_id,
+                "foo",
+                vec![],
+                YakManApprovalPolicy::default(),
+                vec![YakManFreezeWindow {
+                    reason: String::from("Incident 42"),
+                    schedule: YakManFreezeSchedule::Range {
+                        start_ms: now - 60_000,
+                        end_ms: now + 60 * 60 * 1000,
+                    },
+                }],
+            )
+            .await?;
+
+        let result = storage_service
+            .create_config_instance(&config_id, vec![], "v1", None, "u0", false)
+            .await;
+        assert!(matches!(
+            result,
+            Err(CreateConfigInstanceError::ChangeFreeze { .. })
+        ));
+
+        let apply_uri =
+            format!("/v1/configs/{config_id}/instances/{instance}/revisions/{revision}/apply");
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::approver_role))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some(String::from("u2")),
+                    });
+                    srv.call(req)
+                })
+                .service(apply_instance_revision),
+        )
+        .await;
+
+        let req = test::TestRequest::post().uri(&apply_uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+        let value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        assert_eq!("Changes are frozen: Incident 42", value["message"]);
+
+        let req = test::TestRequest::post()
+            .uri(&format!("{apply_uri}?break_glass=true"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(403, resp.status().as_u16());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some(String::from("u2")),
+                    });
+                    srv.call(req)
+                })
+                .service(apply_instance_revision),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri(&format!("{apply_uri}?break_glass=true"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let entries = storage_service
+            .get_audit_log(&AuditLogFilter {
+                from_ms: now - 60_000,
+                to_ms: Utc::now().timestamp_millis(),
+                action: Some(YakManAuditAction::BreakGlass),
+                limit: 10,
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(1, entries.len());
+        assert_eq!(instance, entries[0].target_id);
+        assert_eq!("Apply", entries[0].after.as_ref().unwrap()["action"]);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn reject_should_require_a_reason_and_comments_should_be_returned() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let project_id = storage_service.create_project("foo", vec![]).await?;
+        let config_id = storage_service.create_config("bar", &project_id).await?;
+        let instance = storage_service
+            .create_config_instance(&config_id, vec![], "v1", None, "u0", false)
+            .await?;
+        let revision = storage_service
+            .submit_new_instance_revision(
+                &config_id,
+                &instance,
+                vec![],
+                "v2",
+                None,
+                RevisionChangeDetails::default(),
+                "u0",
+            )
+            .await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some(String::from("u1")),
+                    });
+                    srv.call(req)
+                })
+                .service(review_pending_instance_revision)
+                .service(add_revision_comment)
+                .service(get_instance_revisions),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri(&format!(
+                "/v1/configs/{config_id}/instances/{instance}/revisions/{revision}/comments"
+            ))
+            .set_json(RevisionCommentPayload {
+                message: String::from("Why is the port changing?"),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v1/configs/{config_id}/instances/{instance}/revisions"
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        let pending = value
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["revision"] == revision.as_str())
+            .unwrap();
+        let comments = pending["comments"].as_array().unwrap();
+        assert_eq!(1, comments.len());
+        assert_eq!("Comment", comments[0]["kind"]);
+        assert_eq!("Why is the port changing?", comments[0]["message"]);
+        assert_eq!("u1", comments[0]["user_id"]);
+
+        let reject_uri = format!(
+            "/v1/configs/{config_id}/instances/{instance}/revisions/{revision}/review/Reject"
+        );
+        let req = test::TestRequest::post().uri(&reject_uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+
+        let req = test::TestRequest::post()
+            .uri(&reject_uri)
+            .set_json(ReviewPayload {
+                comment: Some(String::from("  ")),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+
+        let req = test::TestRequest::post()
+            .uri(&reject_uri)
+            .set_json(ReviewPayload {
+                comment: Some(String::from("Port is still in use")),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let instance = storage_service
+            .get_config_instance(&config_id, &instance)
+            .await?
+            .unwrap();
+        assert!(matches!(
+            &instance.changelog.last().unwrap().event,
+            ConfigInstanceEventData::NewRevisionRejected {
+                reason: Some(reason),
+                ..
+            } if reason == "Port is still in use"
+        ));
+
+        Ok(())
+    }
+    #[actix_web::test]
+    async fn submit_should_require_change_description_when_policy_requires_it() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let project_id = storage_service.create_project("foo", vec![]).await?;
+        storage_service
+            .update_project(
+                &project_id,
+                "foo",
+                vec![],
+                YakManApprovalPolicy {
+                    require_change_description: true,
+                    ..YakManApprovalPolicy::default()
+                },
+                vec![],
+            )
+            .await?;
+        let config_id = storage_service.create_config("bar", &project_id).await?;
+        let instance = storage_service
+            .create_config_instance(&config_id, vec![], "v1", None, "u0", false)
+            .await?;
+
+        let result = storage_service
+            .submit_new_instance_revision(
+                &config_id,
+                &instance,
+                vec![],
+                "v2",
+                None,
+                RevisionChangeDetails::default(),
+                "u0",
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(SaveConfigInstanceError::MissingChangeDescription)
+        ));
+
+        let revision = storage_service
+            .submit_new_instance_revision(
+                &config_id,
+                &instance,
+                vec![],
+                "v2",
+                None,
+                RevisionChangeDetails {
+                    description: Some(String::from("Increase the pool size")),
+                    ticket: Some(String::from("OPS-42")),
+                },
+                "u0",
+            )
+            .await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(get_instance_revisions),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v1/configs/{config_id}/instances/{instance}/revisions"
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+        let value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        let submitted = value
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["revision"] == revision.as_str())
+            .unwrap();
+        assert_eq!(
+            "Increase the pool size",
+            submitted["change_details"]["description"]
+        );
+        assert_eq!("OPS-42", submitted["change_details"]["ticket"]);
+
+        Ok(())
+    }
+}
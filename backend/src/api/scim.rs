@@ -0,0 +1,1029 @@
+//! SCIM 2.0 (RFC 7643/7644) provisioning endpoints so an IdP can manage users and teams.
+//! YakMan users are identified by email, so the SCIM `userName` is the user's email.
+//! Requests are authenticated with the `YAKMAN_SCIM_TOKEN` bearer token, not a user token or api key.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::adapters::errors::GenericStorageError;
+use crate::api::teams::{CreateTeamPayload, UpdateTeamPayload};
+use crate::error::{
+    CreateTeamError, DeleteTeamError, DeleteUserError, UpdateTeamError, UpdateUserError,
+};
+use crate::model::request::CreateYakManUserPayload;
+use crate::model::{YakManTeam, YakManTeamDetails, YakManUser};
+use crate::services::StorageService;
+use crate::settings;
+use actix_web::http::StatusCode;
+use actix_web::{delete, get, patch, post, put, web, HttpRequest, HttpResponse, ResponseError};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+const GROUP_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+const LIST_RESPONSE_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+const ERROR_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+const SCIM_CONTENT_TYPE: &str = "application/scim+json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ScimMeta {
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ScimEmail {
+    pub value: String,
+    #[serde(default)]
+    pub primary: bool,
+}
+
+/// A reference to a group member, or to a group the user belongs to
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ScimReference {
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUser {
+    pub schemas: Vec<String>,
+    pub id: String,
+    pub user_name: String,
+    pub active: bool,
+    pub emails: Vec<ScimEmail>,
+    pub groups: Vec<ScimReference>,
+    pub meta: ScimMeta,
+}
+
+#[derive(Debug, Deserialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserPayload {
+    pub user_name: String,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimGroup {
+    pub schemas: Vec<String>,
+    pub id: String,
+    pub display_name: String,
+    pub members: Vec<ScimReference>,
+    pub meta: ScimMeta,
+}
+
+#[derive(Debug, Deserialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimGroupPayload {
+    pub display_name: String,
+    #[serde(default)]
+    pub members: Vec<ScimReference>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListResponse<T> {
+    pub schemas: Vec<String>,
+    pub total_results: usize,
+    pub start_index: usize,
+    pub items_per_page: usize,
+    #[serde(rename = "Resources")]
+    pub resources: Vec<T>,
+}
+
+#[derive(Debug, Deserialize, Clone, ToSchema)]
+pub struct ScimPatchPayload {
+    #[serde(rename = "Operations")]
+    pub operations: Vec<ScimPatchOperation>,
+}
+
+#[derive(Debug, Deserialize, Clone, ToSchema)]
+pub struct ScimPatchOperation {
+    pub op: String,
+    pub path: Option<String>,
+    pub value: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListQuery {
+    pub filter: Option<String>,
+    pub start_index: Option<usize>,
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimError {
+    schemas: Vec<String>,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scim_type: Option<String>,
+    detail: String,
+    #[serde(skip_serializing)]
+    status_code: StatusCode,
+}
+
+impl ScimError {
+    fn new(status_code: StatusCode, scim_type: Option<&str>, detail: &str) -> ScimError {
+        return ScimError {
+            schemas: vec![ERROR_SCHEMA.to_string()],
+            status: status_code.as_u16().to_string(),
+            scim_type: scim_type.map(String::from),
+            detail: detail.to_string(),
+            status_code: status_code,
+        };
+    }
+
+    fn unauthorized() -> ScimError {
+        return ScimError::new(StatusCode::UNAUTHORIZED, None, "unauthorized");
+    }
+
+    fn not_found(detail: &str) -> ScimError {
+        return ScimError::new(StatusCode::NOT_FOUND, None, detail);
+    }
+
+    fn bad_request(scim_type: &str, detail: &str) -> ScimError {
+        return ScimError::new(StatusCode::BAD_REQUEST, Some(scim_type), detail);
+    }
+
+    fn conflict(detail: &str) -> ScimError {
+        return ScimError::new(StatusCode::CONFLICT, Some("uniqueness"), detail);
+    }
+
+    fn server_error(e: impl fmt::Display) -> ScimError {
+        log::error!("SCIM request failed: {e}");
+        return ScimError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            None,
+            "internal server error",
+        );
+    }
+}
+
+impl fmt::Display for ScimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.status, self.detail)
+    }
+}
+
+impl ResponseError for ScimError {
+    fn status_code(&self) -> StatusCode {
+        return self.status_code;
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        return HttpResponse::build(self.status_code)
+            .content_type(SCIM_CONTENT_TYPE)
+            .json(self);
+    }
+}
+
+impl From<GenericStorageError> for ScimError {
+    fn from(e: GenericStorageError) -> Self {
+        return ScimError::server_error(e);
+    }
+}
+
+/// The SCIM bearer token, registered as app data so tests can inject it without touching the env
+#[derive(Debug, Clone)]
+pub struct ScimConfig {
+    pub token: Option<String>,
+}
+
+impl ScimConfig {
+    pub fn from_env() -> ScimConfig {
+        return ScimConfig {
+            token: settings::scim_token(),
+        };
+    }
+}
+
+/// SCIM is disabled unless `YAKMAN_SCIM_TOKEN` is set
+fn authorize(req: &HttpRequest) -> Result<(), ScimError> {
+    let Some(scim_token) = req
+        .app_data::<web::Data<ScimConfig>>()
+        .and_then(|config| config.token.clone())
+    else {
+        return Err(ScimError::not_found("SCIM provisioning is not enabled"));
+    };
+
+    let token = req
+        .headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, token)| token.trim());
+
+    // Compare hashes so the comparison time does not depend on the token
+    return match token {
+        Some(token) if sha256::digest(token) == sha256::digest(scim_token.as_str()) => Ok(()),
+        _ => Err(ScimError::unauthorized()),
+    };
+}
+
+/// SCIM clients send `application/scim+json`, which the `Json` extractor rejects, so bodies are parsed manually
+fn parse_body<T: DeserializeOwned>(body: &web::Bytes) -> Result<T, ScimError> {
+    return serde_json::from_slice(body)
+        .map_err(|e| ScimError::bad_request("invalidSyntax", &e.to_string()));
+}
+
+fn scim_response(status: StatusCode, body: impl Serialize) -> HttpResponse {
+    return HttpResponse::build(status)
+        .content_type(SCIM_CONTENT_TYPE)
+        .json(body);
+}
+
+/// Only `<attribute> eq "<value>"` filters are supported, which is what IdPs use to look up existing resources
+fn parse_eq_filter(filter: &str, attribute: &str) -> Result<String, ScimError> {
+    let invalid_filter = || ScimError::bad_request("invalidFilter", "unsupported filter");
+
+    let mut parts = filter.trim().splitn(3, ' ');
+    let (Some(filter_attribute), Some(operator), Some(value)) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid_filter());
+    };
+
+    if !filter_attribute.eq_ignore_ascii_case(attribute) || !operator.eq_ignore_ascii_case("eq") {
+        return Err(invalid_filter());
+    }
+
+    return value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .map(String::from)
+        .ok_or_else(invalid_filter);
+}
+
+fn list_response<T>(resources: Vec<T>, query: &ScimListQuery) -> ScimListResponse<T> {
+    let total_results = resources.len();
+    let start_index = query.start_index.unwrap_or(1).max(1);
+    let resources: Vec<T> = resources
+        .into_iter()
+        .skip(start_index - 1)
+        .take(query.count.unwrap_or(usize::MAX))
+        .collect();
+
+    return ScimListResponse {
+        schemas: vec![LIST_RESPONSE_SCHEMA.to_string()],
+        total_results: total_results,
+        start_index: start_index,
+        items_per_page: resources.len(),
+        resources: resources,
+    };
+}
+
+/// Accepts booleans and the `"True"`/`"False"` strings some IdPs send
+fn parse_bool(value: &Value) -> Result<bool, ScimError> {
+    return match value {
+        Value::Bool(b) => Ok(*b),
+        Value::String(s) => s
+            .parse::<bool>()
+            .or_else(|_| s.to_lowercase().parse::<bool>())
+            .map_err(|_| ScimError::bad_request("invalidValue", "expected a boolean")),
+        _ => Err(ScimError::bad_request("invalidValue", "expected a boolean")),
+    };
+}
+
+fn parse_references(value: &Value) -> Result<Vec<String>, ScimError> {
+    let references: Vec<ScimReference> = serde_json::from_value(match value {
+        Value::Array(_) => value.clone(),
+        _ => Value::Array(vec![value.clone()]),
+    })
+    .map_err(|_| ScimError::bad_request("invalidValue", "expected members"))?;
+    return Ok(references.into_iter().map(|r| r.value).collect());
+}
+
+async fn to_scim_user(
+    storage_service: &Arc<dyn StorageService>,
+    user: &YakManUser,
+    teams: &[YakManTeam],
+) -> Result<ScimUser, ScimError> {
+    let team_ids = storage_service
+        .get_user_details(&user.id)
+        .await?
+        .map(|d| d.team_ids)
+        .unwrap_or_default();
+
+    return Ok(ScimUser {
+        schemas: vec![USER_SCHEMA.to_string()],
+        id: user.id.clone(),
+        user_name: user.email.clone(),
+        active: !user.is_deactivated(),
+        emails: vec![ScimEmail {
+            value: user.email.clone(),
+            primary: true,
+        }],
+        groups: team_ids
+            .into_iter()
+            .map(|team_id| ScimReference {
+                display: teams
+                    .iter()
+                    .find(|t| t.id == team_id)
+                    .map(|t| t.name.clone()),
+                value: team_id,
+            })
+            .collect(),
+        meta: ScimMeta {
+            resource_type: "User".to_string(),
+        },
+    });
+}
+
+fn to_scim_group(team: &YakManTeamDetails, users: &[YakManUser]) -> ScimGroup {
+    return ScimGroup {
+        schemas: vec![GROUP_SCHEMA.to_string()],
+        id: team.id.clone(),
+        display_name: team.name.clone(),
+        members: team
+            .member_user_ids
+            .iter()
+            .map(|user_id| ScimReference {
+                value: user_id.clone(),
+                display: users
+                    .iter()
+                    .find(|u| u.id == *user_id)
+                    .map(|u| u.email.clone()),
+            })
+            .collect(),
+        meta: ScimMeta {
+            resource_type: "Group".to_string(),
+        },
+    };
+}
+
+async fn get_user(
+    storage_service: &Arc<dyn StorageService>,
+    user_id: &str,
+) -> Result<YakManUser, ScimError> {
+    return storage_service
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| ScimError::not_found("user not found"));
+}
+
+async fn set_user_active(
+    storage_service: &Arc<dyn StorageService>,
+    user: &YakManUser,
+    active: bool,
+) -> Result<(), ScimError> {
+    let is_active = !user.is_deactivated();
+    if active == is_active {
+        return Ok(());
+    }
+
+    let result = match active {
+        true => storage_service.reactivate_user(&user.id).await,
+        false => storage_service.deactivate_user(&user.id).await,
+    };
+    return result.map_err(|e| match e {
+        UpdateUserError::LastActiveAdmin => {
+            ScimError::bad_request("mutability", "Cannot deactivate the last active admin")
+        }
+        e => ScimError::server_error(e),
+    });
+}
+
+async fn user_response(
+    storage_service: &Arc<dyn StorageService>,
+    user_id: &str,
+    status: StatusCode,
+) -> Result<HttpResponse, ScimError> {
+    let user = get_user(storage_service, user_id).await?;
+    let teams = storage_service.get_teams().await?;
+    return Ok(scim_response(
+        status,
+        to_scim_user(storage_service, &user, &teams).await?,
+    ));
+}
+
+/// List users, optionally filtered by `userName eq "<email>"`
+#[utoipa::path(responses((status = 200, body = ScimListResponse<ScimUser>)))]
+#[get("/scim/v2/Users")]
+pub async fn get_scim_users(
+    req: HttpRequest,
+    query: web::Query<ScimListQuery>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<HttpResponse, ScimError> {
+    authorize(&req)?;
+
+    let mut users = storage_service.get_users().await?;
+    if let Some(filter) = &query.filter {
+        let user_name = parse_eq_filter(filter, "userName")?;
+        users.retain(|u| u.email.eq_ignore_ascii_case(&user_name));
+    }
+
+    let teams = storage_service.get_teams().await?;
+    let mut scim_users = vec![];
+    for user in &users {
+        scim_users.push(to_scim_user(&storage_service, user, &teams).await?);
+    }
+
+    return Ok(scim_response(
+        StatusCode::OK,
+        list_response(scim_users, &query),
+    ));
+}
+
+/// Get a user
+#[utoipa::path(responses((status = 200, body = ScimUser)))]
+#[get("/scim/v2/Users/{id}")]
+pub async fn get_scim_user(
+    req: HttpRequest,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<HttpResponse, ScimError> {
+    authorize(&req)?;
+    return user_response(&storage_service, &path.into_inner(), StatusCode::OK).await;
+}
+
+/// Provision a user. New users have no roles until they are added to a team or granted roles by an admin.
+#[utoipa::path(request_body = ScimUserPayload, responses((status = 201, body = ScimUser)))]
+#[post("/scim/v2/Users")]
+pub async fn create_scim_user(
+    req: HttpRequest,
+    body: web::Bytes,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<HttpResponse, ScimError> {
+    authorize(&req)?;
+    let payload: ScimUserPayload = parse_body(&body)?;
+
+    if payload.user_name.trim().is_empty() {
+        return Err(ScimError::bad_request(
+            "invalidValue",
+            "userName is required",
+        ));
+    }
+
+    if storage_service
+        .get_user_by_email(&payload.user_name)
+        .await?
+        .is_some()
+    {
+        return Err(ScimError::conflict("user already exists"));
+    }
+
+    let user_id = storage_service
+        .create_user(CreateYakManUserPayload {
+            email: payload.user_name.clone(),
+            role: None,
+        })
+        .await?;
+
+    if payload.active == Some(false) {
+        let user = get_user(&storage_service, &user_id).await?;
+        set_user_active(&storage_service, &user, false).await?;
+    }
+
+    return user_response(&storage_service, &user_id, StatusCode::CREATED).await;
+}
+
+/// Replace a user. The `userName` cannot be changed as it is the user's login email.
+#[utoipa::path(request_body = ScimUserPayload, responses((status = 200, body = ScimUser)))]
+#[put("/scim/v2/Users/{id}")]
+pub async fn replace_scim_user(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Bytes,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<HttpResponse, ScimError> {
+    authorize(&req)?;
+    let payload: ScimUserPayload = parse_body(&body)?;
+    let user = get_user(&storage_service, &path.into_inner()).await?;
+
+    if !payload.user_name.eq_ignore_ascii_case(&user.email) {
+        return Err(ScimError::bad_request(
+            "mutability",
+            "userName cannot be changed",
+        ));
+    }
+
+    set_user_active(&storage_service, &user, payload.active.unwrap_or(true)).await?;
+
+    return user_response(&storage_service, &user.id, StatusCode::OK).await;
+}
+
+/// Update a user. Only `active` is stored by YakMan, other attributes are ignored.
+#[utoipa::path(request_body = ScimPatchPayload, responses((status = 200, body = ScimUser)))]
+#[patch("/scim/v2/Users/{id}")]
+pub async fn patch_scim_user(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Bytes,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<HttpResponse, ScimError> {
+    authorize(&req)?;
+    let payload: ScimPatchPayload = parse_body(&body)?;
+    let user = get_user(&storage_service, &path.into_inner()).await?;
+
+    let mut active: Option<bool> = None;
+    for operation in &payload.operations {
+        let op = operation.op.to_lowercase();
+        if op != "add" && op != "replace" {
+            continue;
+        }
+
+        let value = operation.value.as_ref();
+        match operation.path.as_deref() {
+            Some(path) if path.eq_ignore_ascii_case("active") => {
+                if let Some(value) = value {
+                    active = Some(parse_bool(value)?);
+                }
+            }
+            Some(_) => {}
+            None => {
+                if let Some(value) = value.and_then(|v| v.get("active")) {
+                    active = Some(parse_bool(value)?);
+                }
+            }
+        }
+    }
+
+    if let Some(active) = active {
+        set_user_active(&storage_service, &user, active).await?;
+    }
+
+    return user_response(&storage_service, &user.id, StatusCode::OK).await;
+}
+
+/// Delete a user
+#[utoipa::path(responses((status = 204)))]
+#[delete("/scim/v2/Users/{id}")]
+pub async fn delete_scim_user(
+    req: HttpRequest,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<HttpResponse, ScimError> {
+    authorize(&req)?;
+
+    return match storage_service.delete_user(&path.into_inner()).await {
+        Ok(_) => Ok(HttpResponse::NoContent().finish()),
+        Err(DeleteUserError::UserNotFound) => Err(ScimError::not_found("user not found")),
+        Err(DeleteUserError::LastActiveAdmin) => Err(ScimError::bad_request(
+            "mutability",
+            "Cannot delete the last active admin",
+        )),
+        Err(e) => Err(ScimError::server_error(e)),
+    };
+}
+
+async fn get_team(
+    storage_service: &Arc<dyn StorageService>,
+    team_id: &str,
+) -> Result<YakManTeamDetails, ScimError> {
+    return storage_service
+        .get_team_details(team_id)
+        .await?
+        .ok_or_else(|| ScimError::not_found("group not found"));
+}
+
+/// Drops members that are not YakMan users so the team does not reference missing users
+async fn existing_user_ids(
+    storage_service: &Arc<dyn StorageService>,
+    user_ids: Vec<String>,
+) -> Result<Vec<String>, ScimError> {
+    let users = storage_service.get_users().await?;
+    let mut existing: Vec<String> = vec![];
+    for user_id in user_ids {
+        if users.iter().any(|u| u.id == user_id) && !existing.contains(&user_id) {
+            existing.push(user_id);
+        } else {
+            log::warn!("Ignoring SCIM group member {user_id}");
+        }
+    }
+    return Ok(existing);
+}
+
+async fn save_team(
+    storage_service: &Arc<dyn StorageService>,
+    team: YakManTeamDetails,
+    name: String,
+    member_user_ids: Vec<String>,
+) -> Result<(), ScimError> {
+    let member_user_ids = existing_user_ids(storage_service, member_user_ids).await?;
+    let payload = UpdateTeamPayload {
+        name: name,
+        // Roles are managed in YakMan, SCIM only manages the name and members
+        global_roles: team.global_roles,
+        roles: team.roles,
+        team_member_user_ids: member_user_ids,
+    };
+
+    return match storage_service.update_team(&team.id, payload).await {
+        Ok(_) => Ok(()),
+        Err(UpdateTeamError::TeamNotFound) => Err(ScimError::not_found("group not found")),
+        Err(UpdateTeamError::DuplicateTeam) => Err(ScimError::conflict("group already exists")),
+        Err(e) => Err(ScimError::server_error(e)),
+    };
+}
+
+async fn group_response(
+    storage_service: &Arc<dyn StorageService>,
+    team_id: &str,
+    status: StatusCode,
+) -> Result<HttpResponse, ScimError> {
+    let team = get_team(storage_service, team_id).await?;
+    let users = storage_service.get_users().await?;
+    return Ok(scim_response(status, to_scim_group(&team, &users)));
+}
+
+/// List teams, optionally filtered by `displayName eq "<name>"`
+#[utoipa::path(responses((status = 200, body = ScimListResponse<ScimGroup>)))]
+#[get("/scim/v2/Groups")]
+pub async fn get_scim_groups(
+    req: HttpRequest,
+    query: web::Query<ScimListQuery>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<HttpResponse, ScimError> {
+    authorize(&req)?;
+
+    let mut teams = storage_service.get_teams().await?;
+    if let Some(filter) = &query.filter {
+        let display_name = parse_eq_filter(filter, "displayName")?;
+        teams.retain(|t| t.name == display_name);
+    }
+
+    let users = storage_service.get_users().await?;
+    let mut groups = vec![];
+    for team in &teams {
+        if let Some(details) = storage_service.get_team_details(&team.id).await? {
+            groups.push(to_scim_group(&details, &users));
+        }
+    }
+
+    return Ok(scim_response(StatusCode::OK, list_response(groups, &query)));
+}
+
+/// Get a team
+#[utoipa::path(responses((status = 200, body = ScimGroup)))]
+#[get("/scim/v2/Groups/{id}")]
+pub async fn get_scim_group(
+    req: HttpRequest,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<HttpResponse, ScimError> {
+    authorize(&req)?;
+    return group_response(&storage_service, &path.into_inner(), StatusCode::OK).await;
+}
+
+/// Provision a team. New teams have no roles until they are granted by an admin.
+#[utoipa::path(request_body = ScimGroupPayload, responses((status = 201, body = ScimGroup)))]
+#[post("/scim/v2/Groups")]
+pub async fn create_scim_group(
+    req: HttpRequest,
+    body: web::Bytes,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<HttpResponse, ScimError> {
+    authorize(&req)?;
+    let payload: ScimGroupPayload = parse_body(&body)?;
+
+    if payload.display_name.trim().is_empty() {
+        return Err(ScimError::bad_request(
+            "invalidValue",
+            "displayName is required",
+        ));
+    }
+
+    let member_user_ids = existing_user_ids(
+        &storage_service,
+        payload.members.into_iter().map(|m| m.value).collect(),
+    )
+    .await?;
+
+    let team_id = match storage_service
+        .create_team(CreateTeamPayload {
+            name: payload.display_name,
+            global_roles: vec![],
+            roles: vec![],
+            team_member_user_ids: member_user_ids,
+        })
+        .await
+    {
+        Ok(team_id) => team_id,
+        Err(CreateTeamError::DuplicateTeam) => {
+            return Err(ScimError::conflict("group already exists"))
+        }
+        Err(e) => return Err(ScimError::server_error(e)),
+    };
+
+    return group_response(&storage_service, &team_id, StatusCode::CREATED).await;
+}
+
+/// Replace a team's name and members
+#[utoipa::path(request_body = ScimGroupPayload, responses((status = 200, body = ScimGroup)))]
+#[put("/scim/v2/Groups/{id}")]
+pub async fn replace_scim_group(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Bytes,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<HttpResponse, ScimError> {
+    authorize(&req)?;
+    let payload: ScimGroupPayload = parse_body(&body)?;
+    let team = get_team(&storage_service, &path.into_inner()).await?;
+    let team_id = team.id.clone();
+
+    let member_user_ids = payload.members.into_iter().map(|m| m.value).collect();
+    save_team(
+        &storage_service,
+        team,
+        payload.display_name,
+        member_user_ids,
+    )
+    .await?;
+
+    return group_response(&storage_service, &team_id, StatusCode::OK).await;
+}
+
+/// Update a team's name or members.
+/// Supports adding and removing members by value or with a `members[value eq "<id>"]` path.
+#[utoipa::path(request_body = ScimPatchPayload, responses((status = 200, body = ScimGroup)))]
+#[patch("/scim/v2/Groups/{id}")]
+pub async fn patch_scim_group(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Bytes,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<HttpResponse, ScimError> {
+    authorize(&req)?;
+    let payload: ScimPatchPayload = parse_body(&body)?;
+    let team = get_team(&storage_service, &path.into_inner()).await?;
+    let team_id = team.id.clone();
+
+    let mut name = team.name.clone();
+    let mut member_user_ids = team.member_user_ids.clone();
+
+    for operation in &payload.operations {
+        let op = operation.op.to_lowercase();
+        let path = operation.path.as_deref().unwrap_or_default();
+        let value = operation.value.as_ref();
+
+        if path.eq_ignore_ascii_case("displayName") {
+            if let Some(Value::String(display_name)) = value {
+                name = display_name.clone();
+            }
+        } else if path.eq_ignore_ascii_case("members") {
+            let values = match value {
+                Some(value) => parse_references(value)?,
+                None => vec![],
+            };
+            match op.as_str() {
+                "add" => member_user_ids.extend(values),
+                "replace" => member_user_ids = values,
+                // Removing without a value removes every member
+                "remove" if value.is_none() => member_user_ids.clear(),
+                "remove" => member_user_ids.retain(|id| !values.contains(id)),
+                _ => return Err(ScimError::bad_request("invalidSyntax", "unsupported op")),
+            }
+        } else if let Some(filter) = path
+            .strip_prefix("members[")
+            .and_then(|p| p.strip_suffix(']'))
+        {
+            if op != "remove" {
+                return Err(ScimError::bad_request("invalidPath", "unsupported path"));
+            }
+            let user_id = parse_eq_filter(filter, "value")?;
+            member_user_ids.retain(|id| *id != user_id);
+        } else if path.is_empty() && op == "replace" {
+            if let Some(Value::String(display_name)) = value.and_then(|v| v.get("displayName")) {
+                name = display_name.clone();
+            }
+            if let Some(members) = value.and_then(|v| v.get("members")) {
+                member_user_ids = parse_references(members)?;
+            }
+        } else {
+            return Err(ScimError::bad_request("invalidPath", "unsupported path"));
+        }
+    }
+
+    save_team(&storage_service, team, name, member_user_ids).await?;
+
+    return group_response(&storage_service, &team_id, StatusCode::OK).await;
+}
+
+/// Delete a team
+#[utoipa::path(responses((status = 204)))]
+#[delete("/scim/v2/Groups/{id}")]
+pub async fn delete_scim_group(
+    req: HttpRequest,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<HttpResponse, ScimError> {
+    authorize(&req)?;
+
+    return match storage_service.delete_team(&path.into_inner()).await {
+        Ok(_) => Ok(HttpResponse::NoContent().finish()),
+        Err(DeleteTeamError::TeamNotFound) => Err(ScimError::not_found("group not found")),
+        Err(e) => Err(ScimError::server_error(e)),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use actix_web::{test, web::Data, App};
+    use anyhow::Result;
+    use serde_json::json;
+
+    const TOKEN: &str = "test-scim-token";
+
+    fn scim_config() -> Data<ScimConfig> {
+        return Data::new(ScimConfig {
+            token: Some(TOKEN.to_string()),
+        });
+    }
+
+    fn scim_request(req: test::TestRequest) -> test::TestRequest {
+        return req
+            .insert_header(("Authorization", format!("Bearer {TOKEN}")))
+            .insert_header(("Content-Type", SCIM_CONTENT_TYPE));
+    }
+
+    #[actix_web::test]
+    async fn should_reject_requests_without_scim_token() -> Result<()> {
+        prepare_for_actix_test()?;
+        let storage_service = test_storage_service().await?;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .app_data(scim_config())
+                .service(get_scim_users),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/scim/v2/Users")
+            .insert_header(("Authorization", "Bearer wrong-token"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(401, resp.status().as_u16());
+
+        let value = body_to_json_value(resp).await?;
+        assert_eq!(json!([ERROR_SCHEMA]), value["schemas"]);
+        assert_eq!("401", value["status"]);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn should_provision_and_deprovision_user() -> Result<()> {
+        prepare_for_actix_test()?;
+        let storage_service = test_storage_service().await?;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .app_data(scim_config())
+                .service(get_scim_users)
+                .service(create_scim_user)
+                .service(patch_scim_user)
+                .service(delete_scim_user),
+        )
+        .await;
+
+        let req = scim_request(test::TestRequest::post().uri("/scim/v2/Users"))
+            .set_payload(
+                json!({
+                    "schemas": [USER_SCHEMA],
+                    "userName": "scim@yakman.com",
+                    "active": true
+                })
+                .to_string(),
+            )
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(201, resp.status().as_u16());
+        let value = body_to_json_value(resp).await?;
+        let user_id = value["id"].as_str().unwrap().to_string();
+        assert_eq!("scim@yakman.com", value["userName"]);
+        assert_eq!(true, value["active"]);
+
+        // Duplicate users are rejected
+        let req = scim_request(test::TestRequest::post().uri("/scim/v2/Users"))
+            .set_payload(json!({ "userName": "scim@yakman.com" }).to_string())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(409, resp.status().as_u16());
+
+        let req = scim_request(
+            test::TestRequest::get()
+                .uri("/scim/v2/Users?filter=userName%20eq%20%22scim@yakman.com%22"),
+        )
+        .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+        let value = body_to_json_value(resp).await?;
+        assert_eq!(1, value["totalResults"]);
+        assert_eq!(user_id, value["Resources"][0]["id"]);
+
+        let req =
+            scim_request(test::TestRequest::patch().uri(&format!("/scim/v2/Users/{user_id}")))
+                .set_payload(
+                    json!({
+                        "Operations": [{ "op": "Replace", "path": "active", "value": "False" }]
+                    })
+                    .to_string(),
+                )
+                .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+        let value = body_to_json_value(resp).await?;
+        assert_eq!(false, value["active"]);
+        let details = storage_service.get_user_details(&user_id).await?.unwrap();
+        assert!(details.is_deactivated());
+
+        let req =
+            scim_request(test::TestRequest::delete().uri(&format!("/scim/v2/Users/{user_id}")))
+                .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(204, resp.status().as_u16());
+        assert!(storage_service.get_user_by_id(&user_id).await?.is_none());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn should_provision_group_and_patch_members() -> Result<()> {
+        prepare_for_actix_test()?;
+        let storage_service = test_storage_service().await?;
+        let first_user_id = storage_service
+            .create_user(CreateYakManUserPayload {
+                email: "first@yakman.com".to_string(),
+                role: None,
+            })
+            .await?;
+        let second_user_id = storage_service
+            .create_user(CreateYakManUserPayload {
+                email: "second@yakman.com".to_string(),
+                role: None,
+            })
+            .await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .app_data(scim_config())
+                .service(create_scim_group)
+                .service(patch_scim_group),
+        )
+        .await;
+
+        let req = scim_request(test::TestRequest::post().uri("/scim/v2/Groups"))
+            .set_payload(
+                json!({
+                    "schemas": [GROUP_SCHEMA],
+                    "displayName": "engineering",
+                    "members": [{ "value": first_user_id }, { "value": "missing-user" }]
+                })
+                .to_string(),
+            )
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(201, resp.status().as_u16());
+        let value = body_to_json_value(resp).await?;
+        let team_id = value["id"].as_str().unwrap().to_string();
+        assert_eq!(
+            json!([{ "value": first_user_id, "display": "first@yakman.com" }]),
+            value["members"]
+        );
+
+        let req = scim_request(
+            test::TestRequest::patch().uri(&format!("/scim/v2/Groups/{team_id}")),
+        )
+        .set_payload(
+            json!({
+                "Operations": [
+                    { "op": "add", "path": "members", "value": [{ "value": second_user_id }] },
+                    { "op": "remove", "path": format!("members[value eq \"{first_user_id}\"]") }
+                ]
+            })
+            .to_string(),
+        )
+        .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let team = storage_service.get_team_details(&team_id).await?.unwrap();
+        assert_eq!(vec![second_user_id.clone()], team.member_user_ids);
+        let first = storage_service
+            .get_user_details(&first_user_id)
+            .await?
+            .unwrap();
+        assert!(!first.team_ids.contains(&team_id));
+        let second = storage_service
+            .get_user_details(&second_user_id)
+            .await?
+            .unwrap();
+        assert!(second.team_ids.contains(&team_id));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,461 @@
+use crate::{
+    adapters::errors::GenericStorageError,
+    api::{
+        api_keys::{generate_api_key_secret, CreateApiKeyResponse},
+        validation::validate_kebab_case,
+    },
+    error::{
+        CreateServiceAccountError, DeleteServiceAccountError, UpdateServiceAccountError,
+        YakManApiError,
+    },
+    middleware::{roles::YakManRoleBinding, YakManPrinciple},
+    model::{YakManApiKey, YakManApiKeyScope, YakManProjectRole, YakManServiceAccount},
+};
+use crate::{model::YakManRole, services::StorageService};
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Json},
+    HttpResponse, Responder,
+};
+use actix_web_grants::authorities::AuthDetails;
+use actix_web_validation::Validated;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Get service accounts
+#[utoipa::path(responses((status = 200, body = Vec<YakManServiceAccount>)))]
+#[get("/v1/service-accounts")]
+async fn get_service_accounts(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    return match storage_service.get_service_accounts().await {
+        Ok(service_accounts) => Ok(web::Json(service_accounts)),
+        Err(GenericStorageError {
+            message,
+            raw_message,
+        }) => {
+            log::error!("Failed to get service accounts, error: {message}, raw: {raw_message}");
+            Err(YakManApiError::server_error(
+                "Failed to get service accounts",
+            ))
+        }
+    };
+}
+
+/// Get service account by id
+#[utoipa::path(responses((status = 200, body = YakManServiceAccount)))]
+#[get("/v1/service-accounts/{id}")]
+async fn get_service_account(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let id = path.into_inner();
+    return match storage_service.get_service_account(&id).await {
+        Ok(Some(service_account)) => Ok(web::Json(service_account)),
+        Ok(None) => Err(YakManApiError::not_found("service account not found")),
+        Err(GenericStorageError {
+            message,
+            raw_message,
+        }) => {
+            log::error!("Failed to get service account, error: {message}, raw: {raw_message}");
+            Err(YakManApiError::server_error(
+                "Failed to get service account",
+            ))
+        }
+    };
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema, Validate)]
+pub struct ServiceAccountPayload {
+    #[validate(length(min = 1), custom(function = "validate_kebab_case"))]
+    pub name: String,
+    pub global_roles: Vec<YakManRole>,
+    pub roles: Vec<YakManProjectRole>,
+    #[serde(default)]
+    pub team_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateServiceAccountResponse {
+    service_account_id: String,
+}
+
+/// Create a new service account
+#[utoipa::path(request_body = ServiceAccountPayload, responses((status = 200, body = CreateServiceAccountResponse)))]
+#[put("/v1/service-accounts")]
+async fn create_service_account(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    principle: YakManPrinciple,
+    Validated(Json(payload)): Validated<Json<ServiceAccountPayload>>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let Some(user_id) = &principle.user_id else {
+        return Err(YakManApiError::forbidden());
+    };
+
+    return match storage_service
+        .create_service_account(payload, user_id)
+        .await
+    {
+        Ok(service_account_id) => Ok(web::Json(CreateServiceAccountResponse {
+            service_account_id,
+        })),
+        Err(e) => match e {
+            CreateServiceAccountError::DuplicateServiceAccount => Err(YakManApiError::bad_request(
+                "duplicate service account name",
+            )),
+            CreateServiceAccountError::TeamNotFound => {
+                Err(YakManApiError::bad_request("team not found"))
+            }
+            CreateServiceAccountError::StorageError { message } => {
+                log::error!("Failed to create service account, error: {message}");
+                Err(YakManApiError::server_error(
+                    "Failed to create service account",
+                ))
+            }
+        },
+    };
+}
+
+/// Update a service account
+#[utoipa::path(request_body = ServiceAccountPayload, responses((status = 200, body = ())))]
+#[post("/v1/service-accounts/{id}")]
+async fn update_service_account(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<String>,
+    Validated(Json(payload)): Validated<Json<ServiceAccountPayload>>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let id = path.into_inner();
+    return match storage_service.update_service_account(&id, payload).await {
+        Ok(_) => Ok(HttpResponse::Ok().finish()),
+        Err(e) => match e {
+            UpdateServiceAccountError::ServiceAccountNotFound => {
+                Err(YakManApiError::not_found("service account not found"))
+            }
+            UpdateServiceAccountError::DuplicateServiceAccount => Err(YakManApiError::bad_request(
+                "duplicate service account name",
+            )),
+            UpdateServiceAccountError::TeamNotFound => {
+                Err(YakManApiError::bad_request("team not found"))
+            }
+            UpdateServiceAccountError::StorageError { message } => {
+                log::error!("Failed to update service account, error: {message}");
+                Err(YakManApiError::server_error(
+                    "Failed to update service account",
+                ))
+            }
+        },
+    };
+}
+
+/// Delete a service account and revoke all of its api keys
+#[utoipa::path(responses((status = 200, body = ())))]
+#[delete("/v1/service-accounts/{id}")]
+async fn delete_service_account(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let id = path.into_inner();
+    return match storage_service.delete_service_account(&id).await {
+        Ok(_) => Ok(HttpResponse::Ok().finish()),
+        Err(e) => match e {
+            DeleteServiceAccountError::ServiceAccountNotFound => {
+                Err(YakManApiError::not_found("service account not found"))
+            }
+            DeleteServiceAccountError::StorageError { message } => {
+                log::error!("Failed to delete service account, error: {message}");
+                Err(YakManApiError::server_error(
+                    "Failed to delete service account",
+                ))
+            }
+        },
+    };
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema, Validate)]
+pub struct CreateServiceAccountApiKeyRequest {
+    /// Unix timestamp (in milliseconds) after which the key is rejected
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    #[serde(default)]
+    pub scope: Option<YakManApiKeyScope>,
+}
+
+/// Create an api key that acts as the service account
+#[utoipa::path(request_body = CreateServiceAccountApiKeyRequest, responses((status = 200, body = CreateApiKeyResponse)))]
+#[put("/v1/service-accounts/{id}/api-keys")]
+async fn create_service_account_api_key(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    principle: YakManPrinciple,
+    path: web::Path<String>,
+    Validated(Json(request)): Validated<Json<CreateServiceAccountApiKeyRequest>>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let Some(user_id) = &principle.user_id else {
+        return Err(YakManApiError::forbidden());
+    };
+
+    let id = path.into_inner();
+    let Some(service_account) = storage_service.get_service_account(&id).await? else {
+        return Err(YakManApiError::not_found("service account not found"));
+    };
+
+    if let Some(scope) = &request.scope {
+        if scope.operations.is_empty() {
+            return Err(YakManApiError::bad_request(
+                "Scope must allow at least one operation",
+            ));
+        }
+    }
+
+    let now = Utc::now().timestamp_millis();
+
+    if matches!(request.expires_at, Some(expires_at) if expires_at <= now) {
+        return Err(YakManApiError::bad_request(
+            "Expiration must be in the future",
+        ));
+    }
+
+    let new_api_key = generate_api_key_secret();
+
+    let ak = YakManApiKey {
+        id: format!("apikey-{}", Uuid::new_v4()),
+        hash: sha256::digest(&new_api_key),
+        project_id: None,
+        role: None,
+        created_at: now,
+        created_by_user_id: user_id.to_string(),
+        expires_at: request.expires_at,
+        rotated_at: None,
+        previous_hash: None,
+        previous_hash_expires_at: None,
+        scope: request.scope,
+        service_account_id: Some(service_account.id),
+    };
+
+    storage_service.save_api_key(ak).await?;
+
+    return Ok(web::Json(CreateApiKeyResponse {
+        api_key: new_api_key,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{YakManApiKeyOperation, YakManApiKeyScope};
+    use crate::test_utils::fake_roles::FakeRoleExtractor;
+    use crate::test_utils::*;
+    use actix_web::dev::Service;
+    use actix_web::{test, web::Data, App, HttpMessage};
+    use actix_web_grants::GrantsMiddleware;
+    use anyhow::Result;
+    use serde_json::Value;
+
+    fn fake_payload(name: &str) -> ServiceAccountPayload {
+        ServiceAccountPayload {
+            name: name.to_string(),
+            global_roles: vec![],
+            roles: vec![],
+            team_ids: vec![],
+        }
+    }
+
+    #[actix_web::test]
+    async fn create_service_account_should_create_service_account() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some("u1".to_string()),
+                    });
+                    srv.call(req)
+                })
+                .service(create_service_account),
+        )
+        .await;
+        let req = test::TestRequest::put()
+            .uri("/v1/service-accounts")
+            .set_json(fake_payload("ci-pipeline"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let value: Value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        let id = value["service_account_id"].as_str().unwrap();
+
+        let service_account = storage_service.get_service_account(id).await?.unwrap();
+        assert_eq!("ci-pipeline", service_account.name);
+        assert_eq!("u1", service_account.created_by_user_id);
+
+        // Names must be unique
+        let req = test::TestRequest::put()
+            .uri("/v1/service-accounts")
+            .set_json(fake_payload("ci-pipeline"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn create_service_account_should_reject_unknown_team() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let result = storage_service
+            .create_service_account(
+                ServiceAccountPayload {
+                    team_ids: vec!["t-missing".to_string()],
+                    ..fake_payload("ci-pipeline")
+                },
+                "u1",
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(CreateServiceAccountError::TeamNotFound)
+        ));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn delete_service_account_should_remove_its_api_keys() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let id = storage_service
+            .create_service_account(fake_payload("ci-pipeline"), "u1")
+            .await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some("u1".to_string()),
+                    });
+                    srv.call(req)
+                })
+                .service(create_service_account_api_key)
+                .service(delete_service_account),
+        )
+        .await;
+        let req = test::TestRequest::put()
+            .uri(&format!("/v1/service-accounts/{id}/api-keys"))
+            .set_json(&CreateServiceAccountApiKeyRequest {
+                expires_at: None,
+                scope: None,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let api_keys = storage_service.get_api_keys().await?;
+        assert_eq!(1, api_keys.len());
+        assert_eq!(Some(id.as_str()), api_keys[0].service_account_id.as_deref());
+        assert_eq!(None, api_keys[0].project_id);
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v1/service-accounts/{id}"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        assert!(storage_service.get_service_account(&id).await?.is_none());
+        assert!(storage_service.get_api_keys().await?.is_empty());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn scoped_api_key_should_not_be_granted_global_roles() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let id = storage_service
+            .create_service_account(fake_payload("ci-pipeline"), "u1")
+            .await?;
+
+        // A key of an Admin service account that is scoped to reading a single config
+        let fake_extractor = FakeRoleExtractor::new(vec![
+            YakManRoleBinding::GlobalRoleBinding(YakManRole::Admin),
+            YakManRoleBinding::ApiKeyScope(YakManApiKeyScope {
+                operations: vec![YakManApiKeyOperation::ReadData],
+                config_ids: vec!["c1".to_string()],
+                label_selectors: vec![],
+            }),
+        ]);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_extractor))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some("apikey-1".to_string()),
+                    });
+                    srv.call(req)
+                })
+                .service(create_service_account_api_key),
+        )
+        .await;
+        let req = test::TestRequest::put()
+            .uri(&format!("/v1/service-accounts/{id}/api-keys"))
+            .set_json(&CreateServiceAccountApiKeyRequest {
+                expires_at: None,
+                scope: None,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(403, resp.status().as_u16());
+
+        assert!(storage_service.get_api_keys().await?.is_empty());
+
+        Ok(())
+    }
+}
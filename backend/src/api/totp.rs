@@ -0,0 +1,430 @@
+use std::sync::Arc;
+
+use crate::api::auth::{get_client_ip, get_token_version, throttled_response, LoginResponse};
+use crate::auth::token::{TokenService, YakManTokenService};
+use crate::error::YakManApiError;
+use crate::middleware::YakManPrinciple;
+use crate::model::{YakManTotp, YakManUser};
+use crate::services::login_protection::LoginProtection;
+use crate::services::totp::{
+    generate_recovery_codes, generate_totp, get_otpauth_url, use_recovery_code, verify_code,
+};
+use crate::services::StorageService;
+use crate::settings;
+use actix_web::{
+    post,
+    web::{self, Json},
+    HttpRequest, HttpResponse, Responder,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TotpLoginRequest {
+    pub two_factor_token: String,
+    /// A code from the authenticator app or an unused recovery code
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TotpLoginResponse {
+    #[serde(flatten)]
+    pub tokens: LoginResponse,
+    /// Only set when the login completed the user's TOTP enrollment
+    pub recovery_codes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TotpEnrollmentRequest {
+    pub two_factor_token: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TotpEnrollmentResponse {
+    /// Base32 encoded secret for authenticator apps that do not support QR codes
+    pub secret: String,
+    pub otpauth_url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TotpCodePayload {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TotpRecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Second login step for users with TOTP enabled. Exchanges the `two_factor_token` from `/auth/login`
+/// and a TOTP (or recovery) code for an access token.
+/// If the user was enrolling, this confirms the enrollment and returns their recovery codes.
+#[utoipa::path(request_body = TotpLoginRequest, responses((status = 200, body = TotpLoginResponse)))]
+#[post("/auth/login/totp")]
+pub async fn login_with_totp(
+    req: HttpRequest,
+    payload: Json<TotpLoginRequest>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+    token_service: web::Data<Arc<YakManTokenService>>,
+) -> Result<impl Responder, YakManApiError> {
+    let user =
+        get_two_factor_token_user(&storage_service, &token_service, &payload.two_factor_token)
+            .await?;
+
+    let login_protection = LoginProtection::from_settings();
+    let client_ip = get_client_ip(&req);
+    let now = Utc::now().timestamp_millis();
+
+    let throttle = login_protection
+        .check(&storage_service, &user.email, &client_ip, now)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to load login attempts {e}");
+            YakManApiError::server_error("Failed to login")
+        })?;
+    if let Some(response) = throttled_response(throttle) {
+        return Ok(response);
+    }
+
+    let Some(mut totp) = load_totp(&storage_service, &user.id).await? else {
+        return Err(YakManApiError::bad_request("TOTP enrollment required"));
+    };
+    let was_confirmed = totp.confirmed;
+
+    if !verify_second_factor(&mut totp, &payload.code, now)? {
+        if let Err(e) = login_protection
+            .record_failure(&storage_service, &user.email, &client_ip, now)
+            .await
+        {
+            log::error!("Failed to record failed login {e}");
+        }
+        return Err(YakManApiError::unauthorized());
+    }
+
+    let recovery_codes = (!was_confirmed).then(|| {
+        totp.confirmed = true;
+        generate_recovery_codes(&mut totp)
+    });
+    save_totp(&storage_service, &user.id, &totp).await?;
+
+    if let Err(e) = login_protection
+        .record_success(&storage_service, &user.email)
+        .await
+    {
+        log::error!("Failed to reset login attempts {e}");
+    }
+
+    let token_version = get_token_version(&storage_service, &user.id).await?;
+    let (access_token_jwt, expire_timestamp) = token_service
+        .create_acess_token_jwt(&user.email, &user, token_version)
+        .map_err(|e| {
+            log::error!("Failed to create token {e}");
+            YakManApiError::server_error("Failed to create token")
+        })?;
+
+    return Ok(HttpResponse::Ok().json(TotpLoginResponse {
+        tokens: LoginResponse {
+            access_token: access_token_jwt,
+            access_token_expire_timestamp: expire_timestamp,
+            refresh_token: None,
+        },
+        recovery_codes: recovery_codes,
+    }));
+}
+
+/// Starts TOTP enrollment during login, for users that are required to use TOTP but have not set it up yet.
+/// The enrollment is confirmed by completing the login with `/auth/login/totp`.
+#[utoipa::path(request_body = TotpEnrollmentRequest, responses((status = 200, body = TotpEnrollmentResponse)))]
+#[post("/auth/login/totp/enroll")]
+pub async fn enroll_totp_during_login(
+    payload: Json<TotpEnrollmentRequest>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+    token_service: web::Data<Arc<YakManTokenService>>,
+) -> Result<impl Responder, YakManApiError> {
+    let user =
+        get_two_factor_token_user(&storage_service, &token_service, &payload.two_factor_token)
+            .await?;
+    return Ok(web::Json(start_enrollment(&storage_service, &user).await?));
+}
+
+/// Starts TOTP enrollment for the logged in user.
+/// Replaces any unconfirmed enrollment, confirm it with `/v1/user-info/totp/confirm`.
+#[utoipa::path(responses((status = 200, body = TotpEnrollmentResponse)))]
+#[post("/v1/user-info/totp/enroll")]
+pub async fn enroll_totp(
+    principle: YakManPrinciple,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    let user = get_principle_user(&storage_service, &principle).await?;
+    return Ok(web::Json(start_enrollment(&storage_service, &user).await?));
+}
+
+/// Enables TOTP for the logged in user once they have entered a valid code.
+/// Returns the recovery codes, they are not shown again.
+#[utoipa::path(request_body = TotpCodePayload, responses((status = 200, body = TotpRecoveryCodesResponse)))]
+#[post("/v1/user-info/totp/confirm")]
+pub async fn confirm_totp(
+    payload: Json<TotpCodePayload>,
+    principle: YakManPrinciple,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    let user = get_principle_user(&storage_service, &principle).await?;
+
+    let Some(mut totp) = load_totp(&storage_service, &user.id).await? else {
+        return Err(YakManApiError::bad_request("TOTP enrollment not started"));
+    };
+    if totp.confirmed {
+        return Err(YakManApiError::bad_request("TOTP is already enabled"));
+    }
+
+    let now = Utc::now().timestamp_millis();
+    if !verify_second_factor(&mut totp, &payload.code, now)? {
+        return Err(YakManApiError::bad_request("Invalid code"));
+    }
+
+    totp.confirmed = true;
+    let recovery_codes = generate_recovery_codes(&mut totp);
+    save_totp(&storage_service, &user.id, &totp).await?;
+
+    return Ok(web::Json(TotpRecoveryCodesResponse {
+        recovery_codes: recovery_codes,
+    }));
+}
+
+/// Disables TOTP for the logged in user. Not allowed when TOTP is required.
+#[utoipa::path(request_body = TotpCodePayload, responses((status = 200, body = ())))]
+#[post("/v1/user-info/totp/disable")]
+pub async fn disable_totp(
+    payload: Json<TotpCodePayload>,
+    principle: YakManPrinciple,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if settings::is_totp_required() {
+        return Err(YakManApiError::bad_request("TOTP is required"));
+    }
+
+    let user = get_principle_user(&storage_service, &principle).await?;
+
+    let Some(mut totp) = load_totp(&storage_service, &user.id).await? else {
+        return Err(YakManApiError::bad_request("TOTP is not enabled"));
+    };
+    if !totp.confirmed {
+        return Err(YakManApiError::bad_request("TOTP is not enabled"));
+    }
+
+    let now = Utc::now().timestamp_millis();
+    if !verify_second_factor(&mut totp, &payload.code, now)? {
+        return Err(YakManApiError::bad_request("Invalid code"));
+    }
+
+    storage_service.delete_totp(&user.id).await.map_err(|e| {
+        log::error!("Failed to delete TOTP {e}");
+        YakManApiError::server_error("Failed to disable TOTP")
+    })?;
+
+    return Ok(HttpResponse::Ok().finish());
+}
+
+async fn start_enrollment(
+    storage_service: &Arc<dyn StorageService>,
+    user: &YakManUser,
+) -> Result<TotpEnrollmentResponse, YakManApiError> {
+    if settings::is_oauth_enabled() {
+        return Err(YakManApiError::bad_request(
+            "TOTP is not supported with OAuth, use the identity provider's MFA instead",
+        ));
+    }
+
+    if let Some(totp) = load_totp(storage_service, &user.id).await? {
+        if totp.confirmed {
+            return Err(YakManApiError::bad_request("TOTP is already enabled"));
+        }
+    }
+
+    let totp = generate_totp(Utc::now().timestamp_millis());
+    let otpauth_url = get_otpauth_url(&totp, &user.email).map_err(|e| {
+        log::error!("Failed to create otpauth url {e}");
+        YakManApiError::server_error("Failed to enroll TOTP")
+    })?;
+    save_totp(storage_service, &user.id, &totp).await?;
+
+    return Ok(TotpEnrollmentResponse {
+        secret: totp.secret,
+        otpauth_url: otpauth_url,
+    });
+}
+
+/// Checks the code against the TOTP or, if that fails, its recovery codes.
+/// The TOTP is updated with the used time step or recovery code, so it must be saved on success.
+fn verify_second_factor(
+    totp: &mut YakManTotp,
+    code: &str,
+    now: i64,
+) -> Result<bool, YakManApiError> {
+    let step = verify_code(totp, code, (now / 1000) as u64).map_err(|e| {
+        log::error!("Failed to verify TOTP code {e}");
+        YakManApiError::server_error("Failed to verify code")
+    })?;
+    if let Some(step) = step {
+        totp.last_used_step = Some(step);
+        return Ok(true);
+    }
+    return Ok(use_recovery_code(totp, code));
+}
+
+async fn get_two_factor_token_user(
+    storage_service: &Arc<dyn StorageService>,
+    token_service: &Arc<YakManTokenService>,
+    two_factor_token: &str,
+) -> Result<YakManUser, YakManApiError> {
+    let user_id = token_service
+        .validate_two_factor_token(two_factor_token)
+        .map_err(|_| YakManApiError::unauthorized())?;
+
+    return match storage_service.get_user_by_id(&user_id).await {
+        Ok(Some(user)) if !user.is_deactivated() => Ok(user),
+        Ok(_) => Err(YakManApiError::unauthorized()),
+        Err(e) => {
+            log::error!("Failed to load user {e}");
+            Err(YakManApiError::server_error("Failed to load user"))
+        }
+    };
+}
+
+/// TOTP is only available to users, not API keys
+async fn get_principle_user(
+    storage_service: &Arc<dyn StorageService>,
+    principle: &YakManPrinciple,
+) -> Result<YakManUser, YakManApiError> {
+    let Some(user_id) = &principle.user_id else {
+        return Err(YakManApiError::unauthorized());
+    };
+
+    return match storage_service.get_user_by_id(user_id).await {
+        Ok(Some(user)) => Ok(user),
+        Ok(None) => Err(YakManApiError::forbidden()),
+        Err(e) => {
+            log::error!("Failed to load user {e}");
+            Err(YakManApiError::server_error("Failed to load user"))
+        }
+    };
+}
+
+async fn load_totp(
+    storage_service: &Arc<dyn StorageService>,
+    user_id: &str,
+) -> Result<Option<YakManTotp>, YakManApiError> {
+    return storage_service.get_totp(user_id).await.map_err(|e| {
+        log::error!("Failed to load TOTP {e}");
+        YakManApiError::server_error("Failed to load TOTP")
+    });
+}
+
+async fn save_totp(
+    storage_service: &Arc<dyn StorageService>,
+    user_id: &str,
+    totp: &YakManTotp,
+) -> Result<(), YakManApiError> {
+    return storage_service.save_totp(user_id, totp).await.map_err(|e| {
+        log::error!("Failed to save TOTP {e}");
+        YakManApiError::server_error("Failed to save TOTP")
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::request::CreateYakManUserPayload;
+    use crate::test_utils::*;
+    use actix_web::dev::Service;
+    use actix_web::{test, web::Data, App, HttpMessage};
+    use anyhow::Result;
+    use serde_json::json;
+    use totp_rs::{Algorithm, Secret, TOTP};
+
+    fn current_code(secret: &str) -> String {
+        let secret = Secret::Encoded(secret.to_string()).to_bytes().unwrap();
+        let totp = TOTP::new(Algorithm::SHA1, 6, 1, 30, secret, None, "".to_string()).unwrap();
+        return totp.generate((Utc::now().timestamp_millis() / 1000) as u64);
+    }
+
+    #[actix_web::test]
+    async fn should_enroll_confirm_and_disable_totp() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let user_id = storage_service
+            .create_user(CreateYakManUserPayload {
+                email: "test@example.com".to_string(),
+                role: None,
+            })
+            .await?;
+
+        let principle_user_id = user_id.clone();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap_fn(move |req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some(principle_user_id.clone()),
+                    });
+                    srv.call(req)
+                })
+                .service(enroll_totp)
+                .service(confirm_totp)
+                .service(disable_totp),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/v1/user-info/totp/enroll")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+        let value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        let secret = value["secret"].as_str().unwrap().to_string();
+        assert!(value["otpauth_url"]
+            .as_str()
+            .unwrap()
+            .starts_with("otpauth://totp/"));
+
+        let req = test::TestRequest::post()
+            .uri("/v1/user-info/totp/confirm")
+            .set_json(json!({ "code": "000000x" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+        assert!(!storage_service.get_totp(&user_id).await?.unwrap().confirmed);
+
+        let req = test::TestRequest::post()
+            .uri("/v1/user-info/totp/confirm")
+            .set_json(json!({ "code": current_code(&secret) }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+        let value = body_to_json_value(resp.map_into_boxed_body()).await?;
+        let recovery_codes = value["recovery_codes"].as_array().unwrap();
+        assert_eq!(10, recovery_codes.len());
+
+        let totp = storage_service.get_totp(&user_id).await?.unwrap();
+        assert!(totp.confirmed);
+
+        // Enrolling again is not allowed once confirmed
+        let req = test::TestRequest::post()
+            .uri("/v1/user-info/totp/enroll")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+
+        let req = test::TestRequest::post()
+            .uri("/v1/user-info/totp/disable")
+            .set_json(json!({ "code": recovery_codes[0] }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+        assert!(storage_service.get_totp(&user_id).await?.is_none());
+
+        Ok(())
+    }
+}
@@ -1,20 +1,26 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::error::YakManApiError;
+use crate::error::{DeleteUserError, UpdateUserError, YakManApiError};
 use crate::middleware::roles::YakManRoleBinding;
 use crate::middleware::YakManPrinciple;
-use crate::model::YakManUser;
 use crate::model::{request::CreateYakManUserPayload, YakManRole};
+use crate::model::{
+    YakManProjectRole, YakManPublicPasswordResetLink, YakManUser, YakManUserDetails,
+};
+use crate::services::email::send_email;
+use crate::services::login_protection::account_key;
 use crate::services::StorageService;
+use crate::settings;
 use actix_web::{
-    get, put,
+    delete, get, post, put,
     web::{self, Json},
 };
 use actix_web::{HttpResponse, Responder};
 use actix_web_grants::authorities::AuthDetails;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use validator::ValidateEmail;
 
 /// Gets users
 #[utoipa::path(responses((status = 200, body = Vec<YakManUser>)))]
@@ -55,11 +61,306 @@ pub async fn create_yakman_user(
     Ok(HttpResponse::Ok().finish())
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InviteUserResponse {
+    pub user_id: String,
+    pub invitation: YakManPublicPasswordResetLink,
+    /// The link to set a password, only available when `YAKMAN_APPLICATION_HOST` is configured
+    pub invitation_url: Option<String>,
+    pub email_sent: bool,
+}
+
+/// Invite a new (non-oauth) user. Creates the user with the role and emails them a link to set their password.
+/// The invitation is also returned so it can be shared manually if email is not configured.
+#[utoipa::path(request_body = CreateYakManUserPayload, responses((status = 200, body = InviteUserResponse)))]
+#[post("/v1/users/invite")]
+pub async fn invite_yakman_user(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    payload: Json<CreateYakManUserPayload>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    if settings::is_oauth_enabled() {
+        return Err(YakManApiError::bad_request(
+            "Invitations are not supported with OAuth",
+        ));
+    }
+
+    let payload = payload.into_inner();
+    let email = payload.email.trim().to_string();
+    if !email.validate_email() {
+        return Err(YakManApiError::bad_request("Invalid email"));
+    }
+    if storage_service.get_user_by_email(&email).await?.is_some() {
+        return Err(YakManApiError::bad_request("User already exists"));
+    }
+
+    let user_id = storage_service
+        .create_user(CreateYakManUserPayload {
+            email: email.clone(),
+            role: payload.role,
+        })
+        .await?;
+
+    let invitation = match storage_service.create_user_invitation(&user_id).await {
+        Ok(invitation) => invitation,
+        Err(e) => {
+            log::error!("Failed to create invitation {e}");
+            return Err(YakManApiError::server_error("Failed to create invitation"));
+        }
+    };
+
+    let invitation_url = settings::yakman_application_host().map(|host| {
+        format!(
+            "{host}/session/reset-password?id={}&user_id={}&invite=true",
+            invitation.id, invitation.user_id
+        )
+    });
+
+    let mut email_sent = false;
+    if let (Some(url), true) = (&invitation_url, settings::is_smtp_configured()) {
+        let expiration_hours = settings::invitation_expiration_hours();
+        let body = format!(
+            "You have been invited to YakMan.\n\nSet your password to get started: {url}\n\nThis link expires in {expiration_hours} hours.\n"
+        );
+        match send_email(&[email], "[YakMan] You have been invited to YakMan", &body).await {
+            Ok(_) => email_sent = true,
+            Err(e) => log::error!("Failed to send invitation email {e}"),
+        }
+    }
+
+    return Ok(web::Json(InviteUserResponse {
+        user_id: user_id,
+        invitation: invitation,
+        invitation_url: invitation_url,
+        email_sent: email_sent,
+    }));
+}
+
+/// Get a user's details, including their roles
+#[utoipa::path(responses((status = 200, body = YakManUserDetails)))]
+#[get("/v1/users/{id}")]
+pub async fn get_yakman_user_details(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let user_id = path.into_inner();
+    return match storage_service.get_user_details(&user_id).await? {
+        Some(details) => Ok(web::Json(details)),
+        None => Err(YakManApiError::not_found("user not found")),
+    };
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub struct UpdateUserRolesPayload {
+    pub global_roles: Vec<YakManRole>,
+    pub roles: Vec<YakManProjectRole>,
+}
+
+/// Update a user's global and project roles
+#[utoipa::path(request_body = UpdateUserRolesPayload, responses((status = 200, body = ())))]
+#[post("/v1/users/{id}/roles")]
+pub async fn update_yakman_user_roles(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<String>,
+    payload: Json<UpdateUserRolesPayload>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let user_id = path.into_inner();
+    storage_service
+        .update_user_roles(&user_id, payload.into_inner())
+        .await?;
+
+    return Ok(HttpResponse::Ok().finish());
+}
+
+/// Deactivate a user, blocking login and revoking access for existing tokens
+#[utoipa::path(responses((status = 200, body = ())))]
+#[post("/v1/users/{id}/deactivate")]
+pub async fn deactivate_yakman_user(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    principle: YakManPrinciple,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let user_id = path.into_inner();
+    if principle.user_id.as_deref() == Some(user_id.as_str()) {
+        return Err(YakManApiError::bad_request("Cannot deactivate yourself"));
+    }
+
+    storage_service.deactivate_user(&user_id).await?;
+
+    return Ok(HttpResponse::Ok().finish());
+}
+
+/// Reactivate a previously deactivated user
+#[utoipa::path(responses((status = 200, body = ())))]
+#[post("/v1/users/{id}/reactivate")]
+pub async fn reactivate_yakman_user(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let user_id = path.into_inner();
+    storage_service.reactivate_user(&user_id).await?;
+
+    return Ok(HttpResponse::Ok().finish());
+}
+
+/// Revoke all of a user's sessions, invalidating their access and refresh tokens
+#[utoipa::path(responses((status = 200, body = ())))]
+#[post("/v1/users/{id}/revoke-sessions")]
+pub async fn revoke_yakman_user_sessions(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let user_id = path.into_inner();
+    storage_service.revoke_user_sessions(&user_id).await?;
+
+    return Ok(HttpResponse::Ok().finish());
+}
+
+/// Unlock a user's account after too many failed login attempts
+#[utoipa::path(responses((status = 200, body = ())))]
+#[post("/v1/users/{id}/unlock")]
+pub async fn unlock_yakman_user(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let user_id = path.into_inner();
+    let user = match storage_service.get_user_by_id(&user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return Err(YakManApiError::not_found("user not found")),
+        Err(e) => {
+            log::error!("Failed to load user {e}");
+            return Err(YakManApiError::server_error("Failed to unlock user"));
+        }
+    };
+
+    if let Err(e) = storage_service
+        .reset_login_attempts(&account_key(&user.email))
+        .await
+    {
+        log::error!("Failed to reset login attempts {e}");
+        return Err(YakManApiError::server_error("Failed to unlock user"));
+    }
+
+    return Ok(HttpResponse::Ok().finish());
+}
+
+/// Remove a user's TOTP second factor, for example after they lost their device and recovery codes.
+/// If TOTP is required, they will be asked to enroll again at their next login.
+#[utoipa::path(responses((status = 200, body = ())))]
+#[post("/v1/users/{id}/reset-totp")]
+pub async fn reset_yakman_user_totp(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let user_id = path.into_inner();
+    match storage_service.get_user_by_id(&user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(YakManApiError::not_found("user not found")),
+        Err(e) => {
+            log::error!("Failed to load user {e}");
+            return Err(YakManApiError::server_error("Failed to reset TOTP"));
+        }
+    };
+
+    if let Err(e) = storage_service.delete_totp(&user_id).await {
+        log::error!("Failed to delete TOTP {e}");
+        return Err(YakManApiError::server_error("Failed to reset TOTP"));
+    }
+
+    return Ok(HttpResponse::Ok().finish());
+}
+
+/// Delete a user. Their id is kept in revision history.
+#[utoipa::path(responses((status = 200, body = ())))]
+#[delete("/v1/users/{id}")]
+pub async fn delete_yakman_user(
+    auth_details: AuthDetails<YakManRoleBinding>,
+    principle: YakManPrinciple,
+    path: web::Path<String>,
+    storage_service: web::Data<Arc<dyn StorageService>>,
+) -> Result<impl Responder, YakManApiError> {
+    if !YakManRoleBinding::has_global_role(YakManRole::Admin, &auth_details.authorities) {
+        return Err(YakManApiError::forbidden());
+    }
+
+    let user_id = path.into_inner();
+    if principle.user_id.as_deref() == Some(user_id.as_str()) {
+        return Err(YakManApiError::bad_request("Cannot delete yourself"));
+    }
+
+    return match storage_service.delete_user(&user_id).await {
+        Ok(_) => Ok(HttpResponse::Ok().finish()),
+        Err(DeleteUserError::UserNotFound) => Err(YakManApiError::not_found("user not found")),
+        Err(DeleteUserError::LastActiveAdmin) => Err(YakManApiError::bad_request(
+            "Cannot delete the last active admin",
+        )),
+        Err(DeleteUserError::StorageError { message }) => {
+            log::error!("Failed to delete user, error: {message}");
+            Err(YakManApiError::server_error("Failed to delete user"))
+        }
+    };
+}
+
+impl From<UpdateUserError> for YakManApiError {
+    fn from(value: UpdateUserError) -> Self {
+        match value {
+            UpdateUserError::UserNotFound => return YakManApiError::not_found("user not found"),
+            UpdateUserError::LastActiveAdmin => {
+                return YakManApiError::bad_request("Cannot remove the last active admin")
+            }
+            UpdateUserError::StorageError { message } => {
+                log::error!("Failed to update user, error: {message}");
+                return YakManApiError::server_error("Failed to update user");
+            }
+        }
+    }
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct GetUserInfoResponse {
     pub profile_picture: Option<String>,
     pub global_roles: Vec<YakManRole>,
     pub roles: HashMap<String, YakManRole>,
+    pub totp_enabled: bool,
 }
 
 /// Endpoint to get the currently logged in user's metadata and roles
@@ -91,16 +392,474 @@ pub async fn get_user_info(
         .collect();
 
     let mut profile_picture = None;
+    let mut totp_enabled = false;
 
     if let Some(user_id) = principle.user_id {
         if let Some(user) = storage_service.get_user_details(&user_id).await? {
             profile_picture = user.profile_picture;
         }
+        totp_enabled = storage_service
+            .get_totp(&user_id)
+            .await?
+            .is_some_and(|totp| totp.confirmed);
     }
 
     return Ok(web::Json(GetUserInfoResponse {
         profile_picture: profile_picture,
         global_roles: global_roles,
         roles: roles,
+        totp_enabled: totp_enabled,
     }));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        adapters::{in_memory::InMemoryStorageAdapter, KVStorageAdapter},
+        api::teams::CreateTeamPayload,
+        model::{YakManLoginAttempts, YakManRevokedRefreshToken},
+        services::totp::generate_totp,
+        test_utils::*,
+    };
+    use actix_web::dev::Service;
+    use actix_web::{test, web::Data, App, HttpMessage};
+    use actix_web_grants::GrantsMiddleware;
+    use anyhow::Result;
+
+    async fn create_test_user(storage_service: &Arc<dyn StorageService>) -> Result<String> {
+        let user_id = storage_service
+            .create_user(CreateYakManUserPayload {
+                email: "test@example.com".to_string(),
+                role: None,
+            })
+            .await?;
+        return Ok(user_id);
+    }
+
+    #[actix_web::test]
+    async fn deactivate_user_should_deactivate_and_reactivate_user() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let user_id = create_test_user(&storage_service).await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some("admin".to_string()),
+                    });
+                    srv.call(req)
+                })
+                .service(deactivate_yakman_user)
+                .service(reactivate_yakman_user),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/users/{user_id}/deactivate"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let details = storage_service.get_user_details(&user_id).await?.unwrap();
+        assert!(details.is_deactivated());
+        let user = storage_service.get_user_by_id(&user_id).await?.unwrap();
+        assert!(user.is_deactivated());
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/users/{user_id}/reactivate"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let details = storage_service.get_user_details(&user_id).await?.unwrap();
+        assert!(!details.is_deactivated());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn update_user_roles_should_update_user_details() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let user_id = create_test_user(&storage_service).await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(update_yakman_user_roles),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/users/{user_id}/roles"))
+            .set_json(UpdateUserRolesPayload {
+                global_roles: vec![YakManRole::Viewer],
+                roles: vec![YakManProjectRole {
+                    project_id: "p1".to_string(),
+                    role: YakManRole::Operator,
+                }],
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let details = storage_service.get_user_details(&user_id).await?.unwrap();
+        assert_eq!(vec![YakManRole::Viewer], details.global_roles);
+        assert_eq!(1, details.roles.len());
+
+        let users = storage_service.get_users().await?;
+        let user = users.iter().find(|u| u.id == user_id).unwrap();
+        assert_eq!(Some(YakManRole::Viewer), user.role);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn unlock_user_should_reset_failed_login_attempts() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let user_id = create_test_user(&storage_service).await?;
+        let key = account_key("test@example.com");
+        storage_service
+            .save_login_attempts(
+                &key,
+                &YakManLoginAttempts {
+                    failed_attempts: 10,
+                    last_failed_at: 0,
+                    locked_until: Some(i64::MAX),
+                },
+            )
+            .await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(unlock_yakman_user),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/users/{user_id}/unlock"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        assert!(storage_service.get_login_attempts(&key).await?.is_none());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn invite_user_should_create_user_with_role_and_invitation() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(invite_yakman_user),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/v1/users/invite")
+            .set_json(CreateYakManUserPayload {
+                email: "invited@example.com".to_string(),
+                role: Some(YakManRole::Operator),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+        let value = body_to_json_value(resp.map_into_boxed_body()).await?;
+
+        let user_id = value["user_id"].as_str().unwrap();
+        let invitation_id = value["invitation"]["id"].as_str().unwrap();
+        assert!(
+            storage_service
+                .validate_password_reset_link(invitation_id, user_id)
+                .await?
+        );
+        let details = storage_service.get_user_details(user_id).await?.unwrap();
+        assert_eq!(vec![YakManRole::Operator], details.global_roles);
+
+        // The same email cannot be invited twice
+        let req = test::TestRequest::post()
+            .uri("/v1/users/invite")
+            .set_json(CreateYakManUserPayload {
+                email: "invited@example.com".to_string(),
+                role: None,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn invite_user_should_reject_invalid_email() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(invite_yakman_user),
+        )
+        .await;
+
+        for email in ["@", "@example.com", "invited@", "invited@@example.com"] {
+            let req = test::TestRequest::post()
+                .uri("/v1/users/invite")
+                .set_json(CreateYakManUserPayload {
+                    email: email.to_string(),
+                    role: None,
+                })
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(400, resp.status().as_u16(), "{email} should be rejected");
+        }
+
+        assert!(storage_service.get_users().await?.is_empty());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn reset_totp_should_remove_users_second_factor() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let user_id = create_test_user(&storage_service).await?;
+        let mut totp = generate_totp(0);
+        totp.confirmed = true;
+        storage_service.save_totp(&user_id, &totp).await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(reset_yakman_user_totp),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/users/{user_id}/reset-totp"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        assert!(storage_service.get_totp(&user_id).await?.is_none());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn revoke_user_sessions_should_increment_token_version() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let user_id = create_test_user(&storage_service).await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .service(revoke_yakman_user_sessions),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/users/{user_id}/revoke-sessions"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        let details = storage_service.get_user_details(&user_id).await?.unwrap();
+        assert_eq!(1, details.token_version);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn revoke_refresh_token_should_only_revoke_that_token() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let user_id = create_test_user(&storage_service).await?;
+
+        storage_service.revoke_refresh_token("token-1").await?;
+
+        assert!(storage_service.is_refresh_token_revoked("token-1").await?);
+        assert!(!storage_service.is_refresh_token_revoked("token-2").await?);
+
+        // Logging out a single session does not revoke the user's other sessions
+        let details = storage_service.get_user_details(&user_id).await?.unwrap();
+        assert_eq!(0, details.token_version);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn delete_expired_revoked_refresh_tokens_should_keep_unexpired_tokens() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let adapter = InMemoryStorageAdapter::new();
+        adapter.initialize_yakman_storage().await?;
+
+        let expired = YakManRevokedRefreshToken {
+            revoked_at: 100,
+            expires_at: 200,
+        };
+        let unexpired = YakManRevokedRefreshToken {
+            revoked_at: 100,
+            expires_at: 400,
+        };
+        adapter
+            .save_revoked_refresh_token("expired", &expired)
+            .await?;
+        adapter
+            .save_revoked_refresh_token("unexpired", &unexpired)
+            .await?;
+
+        adapter.delete_expired_revoked_refresh_tokens(300).await?;
+
+        assert_eq!(None, adapter.get_revoked_refresh_token("expired").await?);
+        assert_eq!(
+            Some(unexpired),
+            adapter.get_revoked_refresh_token("unexpired").await?
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn delete_user_should_remove_user_and_team_membership() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let user_id = create_test_user(&storage_service).await?;
+        let team_id = storage_service
+            .create_team(CreateTeamPayload {
+                name: "foo".to_string(),
+                global_roles: vec![],
+                roles: vec![],
+                team_member_user_ids: vec![user_id.clone()],
+            })
+            .await
+            .unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some("admin".to_string()),
+                    });
+                    srv.call(req)
+                })
+                .service(delete_yakman_user),
+        )
+        .await;
+
+        // Admins cannot delete themselves
+        let req = test::TestRequest::delete()
+            .uri("/v1/users/admin")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v1/users/{user_id}"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        assert!(storage_service.get_user_by_id(&user_id).await?.is_none());
+        assert!(storage_service.get_user_details(&user_id).await?.is_none());
+
+        let team_details = storage_service.get_team_details(&team_id).await?.unwrap();
+        assert!(!team_details.member_user_ids.contains(&user_id));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn last_active_admin_should_not_be_removed() -> Result<()> {
+        prepare_for_actix_test()?;
+
+        let storage_service = test_storage_service().await?;
+        let admin_id = storage_service
+            .create_user(CreateYakManUserPayload {
+                email: "admin@example.com".to_string(),
+                role: Some(YakManRole::Admin),
+            })
+            .await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(storage_service.clone()))
+                .wrap(GrantsMiddleware::with_extractor(fake_roles::admin_role))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(YakManPrinciple {
+                        user_id: Some("other-admin".to_string()),
+                    });
+                    srv.call(req)
+                })
+                .service(update_yakman_user_roles)
+                .service(deactivate_yakman_user)
+                .service(delete_yakman_user),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/users/{admin_id}/roles"))
+            .set_json(&UpdateUserRolesPayload {
+                global_roles: vec![YakManRole::Viewer],
+                roles: vec![],
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/users/{admin_id}/deactivate"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v1/users/{admin_id}"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status().as_u16());
+
+        let details = storage_service.get_user_details(&admin_id).await?.unwrap();
+        assert_eq!(vec![YakManRole::Admin], details.global_roles);
+        assert!(!details.is_deactivated());
+
+        // Once another admin exists the first one can be removed
+        storage_service
+            .create_user(CreateYakManUserPayload {
+                email: "admin2@example.com".to_string(),
+                role: Some(YakManRole::Admin),
+            })
+            .await?;
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v1/users/{admin_id}"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status().as_u16());
+
+        Ok(())
+    }
+}
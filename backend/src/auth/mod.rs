@@ -1,4 +1,5 @@
 pub mod oauth_service;
+pub mod signing_key;
 pub mod token;
 
 use thiserror::Error;
@@ -9,12 +10,18 @@ pub enum LoginError {
     FailedToExchangeCode,
     #[error("User not registered")]
     UserNotRegistered,
+    #[error("User is deactivated")]
+    UserDeactivated,
     #[error("Failed to check registered users")]
     FailedToCheckRegisteredUsers,
     #[error("Failed to parse claims from openid connect response")]
     FailedToParseClaims,
     #[error("Failed to parse username from openid connect response")]
     FailedToParseUsername,
+    #[error("Failed to create user on first login")]
+    FailedToProvisionUser,
+    #[error("Failed to sync teams from groups claim")]
+    FailedToSyncTeams,
 }
 
 #[derive(Error, Debug)]
@@ -25,4 +32,6 @@ pub enum RefreshTokenError {
     FailedToParseClaims,
     #[error("Failed to parse username from openid connect response")]
     FailedToParseUsername,
+    #[error("Failed to sync teams from groups claim")]
+    FailedToSyncTeams,
 }
@@ -1,6 +1,9 @@
 use super::{LoginError, RefreshTokenError};
+use crate::error::UpdateUserError;
+use crate::model::request::CreateYakManUserPayload;
 use crate::model::YakManUser;
 use crate::services::StorageService;
+use crate::settings;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use log::debug;
@@ -8,17 +11,76 @@ use log::debug;
 use mockall::automock;
 use oauth2::{
     AuthorizationCode, ClientId, ClientSecret, CsrfToken, PkceCodeChallenge, PkceCodeVerifier,
-    RedirectUrl, RefreshToken, Scope, TokenResponse, TokenUrl,
+    RedirectUrl, RefreshToken, Scope, StandardTokenResponse, TokenResponse, TokenUrl,
 };
 use openidconnect::core::{
-    CoreClient, CoreIdTokenClaims, CoreIdTokenVerifier, CoreProviderMetadata, CoreResponseType,
+    CoreAuthDisplay, CoreAuthPrompt, CoreErrorResponseType, CoreGenderClaim, CoreIdTokenVerifier,
+    CoreJsonWebKey, CoreJsonWebKeyType, CoreJsonWebKeyUse, CoreJweContentEncryptionAlgorithm,
+    CoreJwsSigningAlgorithm, CoreProviderMetadata, CoreResponseType, CoreRevocableToken,
+    CoreRevocationErrorResponse, CoreTokenIntrospectionResponse, CoreTokenType,
 };
 use openidconnect::reqwest::async_http_client;
-use openidconnect::{AuthenticationFlow, IssuerUrl, Nonce};
+use openidconnect::{
+    AdditionalClaims, AuthenticationFlow, Client, EmptyExtraTokenFields, IdTokenClaims,
+    IdTokenFields, IssuerUrl, Nonce, StandardErrorResponse,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::env;
 use std::sync::Arc;
 
+/// Claims outside of the OIDC standard claims, used to read the groups claim
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct YakManAdditionalClaims {
+    #[serde(flatten)]
+    claims: HashMap<String, Value>,
+}
+
+impl AdditionalClaims for YakManAdditionalClaims {}
+
+type YakManIdTokenClaims = IdTokenClaims<YakManAdditionalClaims, CoreGenderClaim>;
+
+type YakManIdTokenFields = IdTokenFields<
+    YakManAdditionalClaims,
+    EmptyExtraTokenFields,
+    CoreGenderClaim,
+    CoreJweContentEncryptionAlgorithm,
+    CoreJwsSigningAlgorithm,
+    CoreJsonWebKeyType,
+>;
+
+/// Same as `CoreClient` but with `YakManAdditionalClaims` instead of `EmptyAdditionalClaims`
+type YakManOidcClient = Client<
+    YakManAdditionalClaims,
+    CoreAuthDisplay,
+    CoreGenderClaim,
+    CoreJweContentEncryptionAlgorithm,
+    CoreJwsSigningAlgorithm,
+    CoreJsonWebKeyType,
+    CoreJsonWebKeyUse,
+    CoreJsonWebKey,
+    CoreAuthPrompt,
+    StandardErrorResponse<CoreErrorResponseType>,
+    StandardTokenResponse<YakManIdTokenFields, CoreTokenType>,
+    CoreTokenType,
+    CoreTokenIntrospectionResponse,
+    CoreRevocableToken,
+    CoreRevocationErrorResponse,
+>;
+
+/// Maps the groups in an OIDC claim to YakMan teams.
+/// Membership of every team in the mapping is managed by the IdP and re-evaluated on each login and refresh,
+/// teams not in the mapping can still be managed manually.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OAuthGroupMapping {
+    /// The ID token claim with the user's groups. Nested claims can be accessed with dots (ex. `realm_access.roles`)
+    pub claim: String,
+    /// OIDC group to the names of the YakMan teams its members are added to
+    pub group_teams: HashMap<String, Vec<String>>,
+}
+
 #[cfg_attr(test, automock)]
 #[async_trait]
 pub trait OAuthService: Send + Sync {
@@ -41,9 +103,12 @@ pub trait OAuthService: Send + Sync {
 
 pub struct YakManOAuthService {
     pub storage: Arc<dyn StorageService>,
-    client: CoreClient,
+    client: YakManOidcClient,
     scopes: Vec<Scope>,
     redirect_url: RedirectUrl,
+    group_mapping: Option<OAuthGroupMapping>,
+    /// Create users on their first login instead of requiring them to be registered
+    auto_provision_users: bool,
 }
 
 impl YakManOAuthService {
@@ -53,7 +118,7 @@ impl YakManOAuthService {
 
         // TODO: Support creating CoreClient without fetching metadata
 
-        let client = CoreClient::from_provider_metadata(
+        let client = YakManOidcClient::from_provider_metadata(
             provider_metadata,
             get_client_id()?,
             Some(get_client_secret()?),
@@ -67,8 +132,29 @@ impl YakManOAuthService {
             client: client,
             scopes: scopes,
             redirect_url: get_redirect_url()?,
+            group_mapping: get_group_mapping(),
+            auto_provision_users: settings::is_oauth_auto_provisioning_enabled(),
         });
     }
+
+    /// Re-evaluates the user's teams from the groups in the ID token.
+    /// Does nothing if group mapping is disabled. A missing groups claim is treated as no groups,
+    /// so the user does not keep IdP managed teams they may no longer belong to.
+    async fn sync_teams(
+        &self,
+        user_id: &str,
+        claims: &YakManIdTokenClaims,
+    ) -> Result<(), UpdateUserError> {
+        let Some(group_mapping) = &self.group_mapping else {
+            return Ok(());
+        };
+        let groups = extract_groups(claims.additional_claims(), &group_mapping.claim)
+            .unwrap_or_else(|| {
+                debug!("ID token did not contain '{}' claim", group_mapping.claim);
+                vec![]
+            });
+        return sync_user_teams(&self.storage, group_mapping, user_id, &groups).await;
+    }
 }
 
 #[async_trait]
@@ -110,7 +196,7 @@ impl OAuthService for YakManOAuthService {
             .map_err(|_| LoginError::FailedToExchangeCode)?;
 
         let id_token_verifier: CoreIdTokenVerifier = self.client.id_token_verifier();
-        let id_token_claims: &CoreIdTokenClaims = data
+        let id_token_claims: &YakManIdTokenClaims = data
             .extra_fields()
             .id_token()
             .expect("Server did not return an ID token")
@@ -127,31 +213,58 @@ impl OAuthService for YakManOAuthService {
             .as_str()
             .to_string();
 
-        if let Some(yakman_user) = self
+        let yakman_user = match self
             .storage
             .get_user_by_email(&username)
             .await
             .map_err(|_| LoginError::FailedToCheckRegisteredUsers)?
         {
-            // Update the user's profile picture
-            if let Some(profile_picture) = &picture {
-                if let Ok(Some(mut user)) = self.storage.get_user_details(&yakman_user.id).await {
-                    user.profile_picture = Some(profile_picture.to_owned());
-                    // Ignore the error, if the profile picture does not get update,
-                    // its fine just ignore and move on
-                    let _ = self.storage.save_user_details(&yakman_user.id, user).await;
-                }
+            Some(yakman_user) => yakman_user,
+            None if self.auto_provision_users => {
+                log::info!("Provisioning user {username} on first login");
+                let user_id = self
+                    .storage
+                    .create_user(CreateYakManUserPayload {
+                        email: username.clone(),
+                        role: None,
+                    })
+                    .await
+                    .map_err(|_| LoginError::FailedToProvisionUser)?;
+                self.storage
+                    .get_user_by_id(&user_id)
+                    .await
+                    .ok()
+                    .flatten()
+                    .ok_or(LoginError::FailedToProvisionUser)?
             }
+            None => return Err(LoginError::UserNotRegistered),
+        };
 
-            return Ok((
-                username,
-                yakman_user,
-                data.refresh_token().cloned(),
-                picture,
-            ));
+        if yakman_user.is_deactivated() {
+            return Err(LoginError::UserDeactivated);
         }
 
-        return Err(LoginError::UserNotRegistered);
+        if let Err(e) = self.sync_teams(&yakman_user.id, id_token_claims).await {
+            log::error!("Failed to sync teams for user {}: {e}", yakman_user.id);
+            return Err(LoginError::FailedToSyncTeams);
+        }
+
+        // Update the user's profile picture
+        if let Some(profile_picture) = &picture {
+            if let Ok(Some(mut user)) = self.storage.get_user_details(&yakman_user.id).await {
+                user.profile_picture = Some(profile_picture.to_owned());
+                // Ignore the error, if the profile picture does not get update,
+                // its fine just ignore and move on
+                let _ = self.storage.save_user_details(&yakman_user.id, user).await;
+            }
+        }
+
+        return Ok((
+            username,
+            yakman_user,
+            data.refresh_token().cloned(),
+            picture,
+        ));
     }
 
     async fn refresh_token(
@@ -170,7 +283,7 @@ impl OAuthService for YakManOAuthService {
         let access_token = response.access_token().secret();
 
         let id_token_verifier: CoreIdTokenVerifier = self.client.id_token_verifier();
-        let id_token_claims: &CoreIdTokenClaims = &response
+        let id_token_claims: &YakManIdTokenClaims = &response
             .extra_fields()
             .id_token()
             .expect("Server did not return an ID token")
@@ -185,6 +298,15 @@ impl OAuthService for YakManOAuthService {
             .as_str()
             .to_string();
 
+        if let Ok(Some(user)) = self.storage.get_user_by_email(&username).await {
+            if !user.is_deactivated() {
+                if let Err(e) = self.sync_teams(&user.id, id_token_claims).await {
+                    log::error!("Failed to sync teams for user {}: {e}", user.id);
+                    return Err(RefreshTokenError::FailedToSyncTeams);
+                }
+            }
+        }
+
         return Ok((String::from(access_token), username));
     }
 }
@@ -225,6 +347,78 @@ impl OAuthService for OAuthDisabledService {
     }
 }
 
+/// Adds the user to the teams mapped from their groups and removes them from the other mapped teams
+pub async fn sync_user_teams(
+    storage: &Arc<dyn StorageService>,
+    group_mapping: &OAuthGroupMapping,
+    user_id: &str,
+    groups: &[String],
+) -> Result<(), UpdateUserError> {
+    let teams = storage.get_teams().await?;
+    let team_id = |name: &String| {
+        let team = teams.iter().find(|t| t.name == *name);
+        if team.is_none() {
+            log::warn!("Team '{name}' in the OIDC group mapping does not exist");
+        }
+        team.map(|t| t.id.clone())
+    };
+
+    let managed_team_ids: Vec<String> = group_mapping
+        .group_teams
+        .values()
+        .flatten()
+        .filter_map(team_id)
+        .collect();
+
+    let member_team_ids: Vec<String> = groups
+        .iter()
+        .filter_map(|group| group_mapping.group_teams.get(group))
+        .flatten()
+        .filter_map(team_id)
+        .collect();
+
+    return storage
+        .sync_user_team_memberships(user_id, &managed_team_ids, &member_team_ids)
+        .await;
+}
+
+/// Reads the groups claim, which may be an array of strings or a single string.
+/// Returns `None` if the claim is not present.
+fn extract_groups(claims: &YakManAdditionalClaims, claim: &str) -> Option<Vec<String>> {
+    let mut path = claim.split('.');
+    let mut value = claims.claims.get(path.next()?)?;
+    for key in path {
+        value = value.get(key)?;
+    }
+
+    return match value {
+        Value::Array(values) => Some(
+            values
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect(),
+        ),
+        Value::String(group) => Some(vec![group.clone()]),
+        _ => None,
+    };
+}
+
+/// Parses `group=team` pairs. A group may be mapped to multiple teams.
+fn parse_group_team_mapping(entries: &[String]) -> HashMap<String, Vec<String>> {
+    let mut group_teams: HashMap<String, Vec<String>> = HashMap::new();
+    for entry in entries {
+        let Some((group, team)) = entry.split_once('=') else {
+            log::warn!("Ignoring invalid OIDC group mapping '{entry}', expected <group>=<team>");
+            continue;
+        };
+        group_teams
+            .entry(group.trim().to_string())
+            .or_default()
+            .push(team.trim().to_string());
+    }
+    return group_teams;
+}
+
 fn no_op_nonce_verifier(_: Option<&Nonce>) -> Result<(), String> {
     Ok(())
 }
@@ -268,7 +462,120 @@ fn get_client_secret() -> Result<ClientSecret> {
     ))
 }
 
+fn get_group_mapping() -> Option<OAuthGroupMapping> {
+    return Some(OAuthGroupMapping {
+        claim: settings::oauth_groups_claim()?,
+        group_teams: parse_group_team_mapping(&settings::oauth_group_team_mapping()),
+    });
+}
+
 fn get_oauth_scopes() -> Vec<String> {
     let scopes = env::var("YAKMAN_OAUTH_SCOPES").expect("$YAKMAN_OAUTH_SCOPES is not set");
     return scopes.split(',').map(|s| s.to_string()).collect();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api::teams::CreateTeamPayload, test_utils::*};
+    use serde_json::json;
+
+    fn mapping(entries: &[&str]) -> HashMap<String, Vec<String>> {
+        let entries: Vec<String> = entries.iter().map(|e| e.to_string()).collect();
+        return parse_group_team_mapping(&entries);
+    }
+
+    fn claims(value: Value) -> YakManAdditionalClaims {
+        return serde_json::from_value(value).unwrap();
+    }
+
+    async fn create_team(
+        storage: &Arc<dyn StorageService>,
+        name: &str,
+        members: Vec<String>,
+    ) -> String {
+        return storage
+            .create_team(CreateTeamPayload {
+                name: name.to_string(),
+                global_roles: vec![],
+                roles: vec![],
+                team_member_user_ids: members,
+            })
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn should_parse_group_team_mapping() {
+        let mapping = mapping(&[
+            "admins=platform",
+            "devs=backend",
+            "devs=frontend",
+            "invalid",
+        ]);
+
+        assert_eq!(2, mapping.len());
+        assert_eq!(vec!["platform"], mapping["admins"]);
+        assert_eq!(vec!["backend", "frontend"], mapping["devs"]);
+    }
+
+    #[test]
+    fn should_extract_groups_from_claims() {
+        let array = claims(json!({ "groups": ["admins", "devs"] }));
+        assert_eq!(
+            Some(vec!["admins".to_string(), "devs".to_string()]),
+            extract_groups(&array, "groups")
+        );
+
+        let single = claims(json!({ "groups": "admins" }));
+        assert_eq!(
+            Some(vec!["admins".to_string()]),
+            extract_groups(&single, "groups")
+        );
+
+        let nested = claims(json!({ "realm_access": { "roles": ["devs"] } }));
+        assert_eq!(
+            Some(vec!["devs".to_string()]),
+            extract_groups(&nested, "realm_access.roles")
+        );
+
+        assert_eq!(None, extract_groups(&array, "roles"));
+    }
+
+    #[actix_web::test]
+    async fn sync_user_teams_should_only_change_mapped_teams() -> Result<()> {
+        prepare_for_actix_test()?;
+        let storage = test_storage_service().await?;
+
+        let user_id = storage
+            .create_user(CreateYakManUserPayload {
+                email: "test@yakman.com".to_string(),
+                role: None,
+            })
+            .await?;
+        let platform_id = create_team(&storage, "platform", vec![user_id.clone()]).await;
+        let backend_id = create_team(&storage, "backend", vec![]).await;
+        let manual_id = create_team(&storage, "manual", vec![user_id.clone()]).await;
+
+        let group_mapping = OAuthGroupMapping {
+            claim: "groups".to_string(),
+            group_teams: mapping(&["admins=platform", "devs=backend", "devs=missing"]),
+        };
+
+        sync_user_teams(&storage, &group_mapping, &user_id, &["devs".to_string()]).await?;
+
+        let details = storage.get_user_details(&user_id).await?.unwrap();
+        assert!(!details.team_ids.contains(&platform_id));
+        assert!(details.team_ids.contains(&backend_id));
+        assert!(details.team_ids.contains(&manual_id));
+
+        let platform = storage.get_team_details(&platform_id).await?.unwrap();
+        assert!(platform.member_user_ids.is_empty());
+        let backend = storage.get_team_details(&backend_id).await?.unwrap();
+        assert_eq!(vec![user_id.clone()], backend.member_user_ids);
+        let manual = storage.get_team_details(&manual_id).await?.unwrap();
+        assert_eq!(vec![user_id.clone()], manual.member_user_ids);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use rsa::{
+    pkcs1::DecodeRsaPrivateKey,
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+    traits::PublicKeyParts,
+    RsaPrivateKey, RsaPublicKey,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// A public key in the JWK format (RFC 7517)
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct JsonWebKey {
+    pub kty: String,
+    #[serde(rename = "use")]
+    pub key_use: String,
+    pub alg: String,
+    pub kid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct JsonWebKeySet {
+    pub keys: Vec<JsonWebKey>,
+}
+
+/// The key used to sign new access tokens
+pub struct AccessTokenSigningKey {
+    pub kid: Option<String>,
+    pub algorithm: Algorithm,
+    pub encoding_key: EncodingKey,
+}
+
+/// A key that access tokens are verified against.
+/// Asymmetric keys are published in the JWKS so other services can validate tokens.
+pub struct AccessTokenVerificationKey {
+    pub kid: Option<String>,
+    pub algorithm: Algorithm,
+    pub decoding_key: DecodingKey,
+    pub jwk: Option<JsonWebKey>,
+}
+
+pub fn parse_signing_algorithm(value: &str) -> Result<Algorithm, SigningKeyError> {
+    return match value.to_uppercase().as_str() {
+        "HS256" => Ok(Algorithm::HS256),
+        "RS256" => Ok(Algorithm::RS256),
+        "ES256" => Ok(Algorithm::ES256),
+        _ => Err(SigningKeyError::UnsupportedAlgorithm(value.to_string())),
+    };
+}
+
+/// Creates the signing and verification key for an HMAC secret.
+/// The secret is never published, so the verification key has no JWK.
+pub fn hmac_signing_key(
+    secret: &str,
+    kid: Option<String>,
+) -> (AccessTokenSigningKey, AccessTokenVerificationKey) {
+    let signing_key = AccessTokenSigningKey {
+        kid: kid.clone(),
+        algorithm: Algorithm::HS256,
+        encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+    };
+    let verification_key = AccessTokenVerificationKey {
+        kid: kid,
+        algorithm: Algorithm::HS256,
+        decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+        jwk: None,
+    };
+    return (signing_key, verification_key);
+}
+
+/// Creates the signing and verification key from a PEM encoded private key.
+/// RSA keys may be PKCS#1 or PKCS#8, EC keys must be PKCS#8 on the P-256 curve.
+/// If no key id is given, the RFC 7638 thumbprint of the public key is used.
+pub fn private_key_signing_key(
+    algorithm: Algorithm,
+    private_key_pem: &str,
+    kid: Option<String>,
+) -> Result<(AccessTokenSigningKey, AccessTokenVerificationKey), SigningKeyError> {
+    let (encoding_key, public_jwk) = match algorithm {
+        Algorithm::RS256 => {
+            let private_key = RsaPrivateKey::from_pkcs8_pem(private_key_pem)
+                .or_else(|_| RsaPrivateKey::from_pkcs1_pem(private_key_pem))
+                .map_err(|e| SigningKeyError::InvalidKey(e.to_string()))?;
+            let encoding_key = EncodingKey::from_rsa_pem(private_key_pem.as_bytes())
+                .map_err(|e| SigningKeyError::InvalidKey(e.to_string()))?;
+            (encoding_key, rsa_jwk(&private_key.to_public_key()))
+        }
+        Algorithm::ES256 => {
+            let private_key = p256::SecretKey::from_pkcs8_pem(private_key_pem)
+                .map_err(|e| SigningKeyError::InvalidKey(e.to_string()))?;
+            let encoding_key = EncodingKey::from_ec_pem(private_key_pem.as_bytes())
+                .map_err(|e| SigningKeyError::InvalidKey(e.to_string()))?;
+            (encoding_key, ec_jwk(&private_key.public_key()))
+        }
+        algorithm => {
+            return Err(SigningKeyError::UnsupportedAlgorithm(format!(
+                "{algorithm:?}"
+            )))
+        }
+    };
+
+    let kid = kid.unwrap_or_else(|| public_jwk.thumbprint());
+    let verification_key = public_jwk.into_verification_key(kid.clone())?;
+    let signing_key = AccessTokenSigningKey {
+        kid: Some(kid),
+        algorithm: algorithm,
+        encoding_key: encoding_key,
+    };
+    return Ok((signing_key, verification_key));
+}
+
+/// Creates a verification key from a PEM encoded RSA or P-256 public key.
+/// Used to keep accepting tokens signed by a previous key after rotating.
+pub fn public_key_verification_key(
+    public_key_pem: &str,
+    kid: String,
+) -> Result<AccessTokenVerificationKey, SigningKeyError> {
+    if let Ok(public_key) = RsaPublicKey::from_public_key_pem(public_key_pem) {
+        return rsa_jwk(&public_key).into_verification_key(kid);
+    }
+
+    if let Ok(public_key) = p256::PublicKey::from_public_key_pem(public_key_pem) {
+        return ec_jwk(&public_key).into_verification_key(kid);
+    }
+
+    return Err(SigningKeyError::InvalidKey(format!(
+        "public key {kid} is not a valid RSA or P-256 public key"
+    )));
+}
+
+/// The public components of a key, before a key id has been assigned
+struct PublicJwk {
+    algorithm: Algorithm,
+    kty: &'static str,
+    n: Option<String>,
+    e: Option<String>,
+    crv: Option<&'static str>,
+    x: Option<String>,
+    y: Option<String>,
+}
+
+fn rsa_jwk(public_key: &RsaPublicKey) -> PublicJwk {
+    return PublicJwk {
+        algorithm: Algorithm::RS256,
+        kty: "RSA",
+        n: Some(URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be())),
+        e: Some(URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be())),
+        crv: None,
+        x: None,
+        y: None,
+    };
+}
+
+fn ec_jwk(public_key: &p256::PublicKey) -> PublicJwk {
+    let point = public_key.to_encoded_point(false);
+    return PublicJwk {
+        algorithm: Algorithm::ES256,
+        kty: "EC",
+        n: None,
+        e: None,
+        crv: Some("P-256"),
+        x: point.x().map(|x| URL_SAFE_NO_PAD.encode(x)),
+        y: point.y().map(|y| URL_SAFE_NO_PAD.encode(y)),
+    };
+}
+
+impl PublicJwk {
+    /// RFC 7638 thumbprint, the hash of the required members in lexicographic order
+    fn thumbprint(&self) -> String {
+        let canonical = match self.kty {
+            "RSA" => format!(
+                r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#,
+                self.e.as_deref().unwrap_or_default(),
+                self.n.as_deref().unwrap_or_default()
+            ),
+            _ => format!(
+                r#"{{"crv":"{}","kty":"EC","x":"{}","y":"{}"}}"#,
+                self.crv.unwrap_or_default(),
+                self.x.as_deref().unwrap_or_default(),
+                self.y.as_deref().unwrap_or_default()
+            ),
+        };
+        return URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()));
+    }
+
+    fn into_verification_key(
+        self,
+        kid: String,
+    ) -> Result<AccessTokenVerificationKey, SigningKeyError> {
+        let decoding_key = match (&self.n, &self.e, &self.x, &self.y) {
+            (Some(n), Some(e), _, _) => DecodingKey::from_rsa_components(n, e),
+            (_, _, Some(x), Some(y)) => DecodingKey::from_ec_components(x, y),
+            _ => {
+                return Err(SigningKeyError::InvalidKey(
+                    "missing public key components".to_string(),
+                ))
+            }
+        }
+        .map_err(|e| SigningKeyError::InvalidKey(e.to_string()))?;
+
+        return Ok(AccessTokenVerificationKey {
+            kid: Some(kid.clone()),
+            algorithm: self.algorithm,
+            decoding_key: decoding_key,
+            jwk: Some(JsonWebKey {
+                kty: self.kty.to_string(),
+                key_use: "sig".to_string(),
+                alg: format!("{:?}", self.algorithm),
+                kid: kid,
+                n: self.n,
+                e: self.e,
+                crv: self.crv.map(String::from),
+                x: self.x,
+                y: self.y,
+            }),
+        });
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SigningKeyError {
+    #[error("Unsupported signing algorithm {0}, expected HS256, RS256 or ES256")]
+    UnsupportedAlgorithm(String),
+    #[error("Invalid signing key: {0}")]
+    InvalidKey(String),
+}
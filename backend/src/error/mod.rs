@@ -6,7 +6,7 @@ use serde::Serialize;
 
 use crate::{
     adapters::errors::GenericStorageError,
-    services::password::{PasswordHashError, PasswordStrengthError},
+    services::password::{NewPasswordError, PasswordHashError, PasswordStrengthError},
 };
 use std::fmt;
 use thiserror::Error;
@@ -55,6 +55,13 @@ impl YakManApiError {
             message: String::from(message.into().unwrap_or("not found")),
         }
     }
+    pub fn too_many_requests(message: &str) -> YakManApiError {
+        YakManApiError {
+            status: StatusCode::TOO_MANY_REQUESTS,
+            timestamp: Utc::now().timestamp_millis(),
+            message: String::from(message),
+        }
+    }
     pub fn server_error(message: &str) -> YakManApiError {
         YakManApiError {
             status: StatusCode::INTERNAL_SERVER_ERROR,
@@ -244,6 +251,8 @@ pub enum CreateConfigInstanceError {
     NoConfigFound,
     #[error("Invalid label")]
     InvalidLabel,
+    #[error("Changes are frozen: {reason}")]
+    ChangeFreeze { reason: String },
     #[error("Error storing label: {message}")]
     StorageError { message: String },
 }
@@ -262,6 +271,8 @@ pub enum SaveConfigInstanceError {
     InvalidInstance,
     #[error("Invalid label")]
     InvalidLabel,
+    #[error("A change description is required")]
+    MissingChangeDescription,
     #[error("Error storing label: {message}")]
     StorageError { message: String },
 }
@@ -280,6 +291,10 @@ pub enum ApproveRevisionError {
     InvalidInstance,
     #[error("Invalid revision")]
     InvalidRevision,
+    #[error("Revision already approved by this user")]
+    AlreadyApproved,
+    #[error("Revision cannot be approved by its submitter")]
+    SelfApproval,
     #[error("Error storing approval: {message}")]
     StorageError { message: String },
 }
@@ -290,6 +305,24 @@ impl From<GenericStorageError> for ApproveRevisionError {
     }
 }
 
+#[derive(Error, Debug)]
+pub enum AddRevisionCommentError {
+    #[error("Invalid config")]
+    InvalidConfig,
+    #[error("Invalid instance")]
+    InvalidInstance,
+    #[error("Invalid revision")]
+    InvalidRevision,
+    #[error("Error storing comment: {message}")]
+    StorageError { message: String },
+}
+
+impl From<GenericStorageError> for AddRevisionCommentError {
+    fn from(e: GenericStorageError) -> Self {
+        AddRevisionCommentError::StorageError { message: e.message }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum ApplyRevisionError {
     #[error("Invalid config")]
@@ -300,6 +333,10 @@ pub enum ApplyRevisionError {
     InvalidRevision,
     #[error("Revision not Approved")]
     NotApproved,
+    #[error("Revision has {approvals} of {required} required approvals")]
+    NotEnoughApprovals { required: u32, approvals: u32 },
+    #[error("Changes are frozen: {reason}")]
+    ChangeFreeze { reason: String },
     #[error("Error storing approval: {message}")]
     StorageError { message: String },
 }
@@ -310,6 +347,60 @@ impl From<GenericStorageError> for ApplyRevisionError {
     }
 }
 
+#[derive(Error, Debug)]
+pub enum ScheduleApplyError {
+    #[error("Invalid config")]
+    InvalidConfig,
+    #[error("Invalid instance")]
+    InvalidInstance,
+    #[error("Invalid revision")]
+    InvalidRevision,
+    #[error("Revision not Approved")]
+    NotApproved,
+    #[error("Revision has {approvals} of {required} required approvals")]
+    NotEnoughApprovals { required: u32, approvals: u32 },
+    #[error("Apply time must be in the future")]
+    InvalidApplyTime,
+    #[error("Changes are frozen at the apply time: {reason}")]
+    ChangeFreeze { reason: String },
+    #[error("Instance already has a scheduled apply")]
+    AlreadyScheduled,
+    #[error("Revision is not scheduled")]
+    NotScheduled,
+    #[error("Error storing scheduled apply: {message}")]
+    StorageError { message: String },
+}
+
+impl From<GenericStorageError> for ScheduleApplyError {
+    fn from(e: GenericStorageError) -> Self {
+        ScheduleApplyError::StorageError { message: e.message }
+    }
+}
+
+impl From<ApplyRevisionError> for ScheduleApplyError {
+    fn from(e: ApplyRevisionError) -> Self {
+        return match e {
+            ApplyRevisionError::InvalidConfig => ScheduleApplyError::InvalidConfig,
+            ApplyRevisionError::InvalidInstance => ScheduleApplyError::InvalidInstance,
+            ApplyRevisionError::InvalidRevision => ScheduleApplyError::InvalidRevision,
+            ApplyRevisionError::NotApproved => ScheduleApplyError::NotApproved,
+            ApplyRevisionError::NotEnoughApprovals {
+                required,
+                approvals,
+            } => ScheduleApplyError::NotEnoughApprovals {
+                required: required,
+                approvals: approvals,
+            },
+            ApplyRevisionError::ChangeFreeze { reason } => {
+                ScheduleApplyError::ChangeFreeze { reason: reason }
+            }
+            ApplyRevisionError::StorageError { message } => {
+                ScheduleApplyError::StorageError { message: message }
+            }
+        };
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum RollbackRevisionError {
     #[error("Invalid config")]
@@ -385,6 +476,44 @@ impl From<GenericStorageError> for ResetPasswordError {
     }
 }
 
+impl From<NewPasswordError> for ResetPasswordError {
+    fn from(e: NewPasswordError) -> Self {
+        match e {
+            NewPasswordError::Invalid(error) => Self::PasswordValidationError { error },
+            NewPasswordError::Hash(error) => Self::PasswordHashError { error },
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ChangePasswordError {
+    #[error("Invalid user")]
+    InvalidUser,
+    #[error("Current password is incorrect")]
+    IncorrectPassword,
+    #[error("Invalid password: {error}")]
+    PasswordValidationError { error: PasswordStrengthError },
+    #[error("Password could not be hashed")]
+    PasswordHashError { error: PasswordHashError },
+    #[error("Storage Error: {message}")]
+    StorageError { message: String },
+}
+
+impl From<GenericStorageError> for ChangePasswordError {
+    fn from(e: GenericStorageError) -> Self {
+        Self::StorageError { message: e.message }
+    }
+}
+
+impl From<NewPasswordError> for ChangePasswordError {
+    fn from(e: NewPasswordError) -> Self {
+        match e {
+            NewPasswordError::Invalid(error) => Self::PasswordValidationError { error },
+            NewPasswordError::Hash(error) => Self::PasswordHashError { error },
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum CreatePasswordResetLinkError {
     #[error("Invalid user")]
@@ -442,3 +571,83 @@ impl From<GenericStorageError> for DeleteTeamError {
         Self::StorageError { message: e.message }
     }
 }
+
+#[derive(Error, Debug)]
+pub enum CreateServiceAccountError {
+    #[error("Duplicate service account")]
+    DuplicateServiceAccount,
+    #[error("Team not found")]
+    TeamNotFound,
+    #[error("Storage Error: {message}")]
+    StorageError { message: String },
+}
+
+impl From<GenericStorageError> for CreateServiceAccountError {
+    fn from(e: GenericStorageError) -> Self {
+        Self::StorageError { message: e.message }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum UpdateServiceAccountError {
+    #[error("Service account not found")]
+    ServiceAccountNotFound,
+    #[error("Duplicate service account")]
+    DuplicateServiceAccount,
+    #[error("Team not found")]
+    TeamNotFound,
+    #[error("Storage Error: {message}")]
+    StorageError { message: String },
+}
+
+impl From<GenericStorageError> for UpdateServiceAccountError {
+    fn from(e: GenericStorageError) -> Self {
+        Self::StorageError { message: e.message }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DeleteServiceAccountError {
+    #[error("Service account not found")]
+    ServiceAccountNotFound,
+    #[error("Storage Error: {message}")]
+    StorageError { message: String },
+}
+
+impl From<GenericStorageError> for DeleteServiceAccountError {
+    fn from(e: GenericStorageError) -> Self {
+        Self::StorageError { message: e.message }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum UpdateUserError {
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Cannot remove the last active admin")]
+    LastActiveAdmin,
+    #[error("Storage Error: {message}")]
+    StorageError { message: String },
+}
+
+impl From<GenericStorageError> for UpdateUserError {
+    fn from(e: GenericStorageError) -> Self {
+        Self::StorageError { message: e.message }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DeleteUserError {
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Cannot remove the last active admin")]
+    LastActiveAdmin,
+    #[error("Storage Error: {message}")]
+    StorageError { message: String },
+}
+
+impl From<GenericStorageError> for DeleteUserError {
+    fn from(e: GenericStorageError) -> Self {
+        Self::StorageError { message: e.message }
+    }
+}
@@ -34,6 +34,17 @@ async fn main() -> std::io::Result<()> {
     let adapter = init_adapter_from_env().await;
     let storage_service: Arc<dyn StorageService> = Arc::new(KVStorageService::new(adapter.clone()));
 
+    if settings::is_notifications_enabled() {
+        services::notification_delivery::register_notification_delivery_worker(adapter.clone());
+    }
+
+    services::scheduled_apply::register_scheduled_apply_worker(
+        adapter.clone(),
+        storage_service.clone(),
+    );
+
+    services::refresh_token_sweep::register_refresh_token_sweep_worker(adapter.clone());
+
     if settings::is_snapshot_backups_enabled() {
         services::snapshot::register_snapshot_worker(adapter);
     } else {
@@ -52,6 +63,8 @@ async fn main() -> std::io::Result<()> {
             .expect("Failed to create jwt service"),
     );
 
+    let scim_config = api::scim::ScimConfig::from_env();
+
     let openapi = YakManApiDoc::openapi();
 
     let (host, port) = yakman_host_port_from_env();
@@ -62,6 +75,7 @@ async fn main() -> std::io::Result<()> {
             .app_data(web::Data::new(storage_service.clone()))
             .app_data(web::Data::new(jwt_service.clone()))
             .app_data(web::Data::new(oauth_service.clone()))
+            .app_data(web::Data::new(scim_config.clone()))
             .wrap(Etag)
             .wrap(Compress::default())
             .wrap(Logger::new("%s %r"))
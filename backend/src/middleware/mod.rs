@@ -5,6 +5,7 @@ use actix_web::{
     web::{self},
     Error, FromRequest, HttpMessage, HttpRequest,
 };
+use chrono::Utc;
 use futures_util::future::{self, err, ok, LocalBoxFuture};
 use std::{
     future::{ready, Ready},
@@ -14,10 +15,10 @@ use std::{
 
 use crate::{
     auth::token::{TokenService, YakManTokenService},
-    services::StorageService,
+    services::{audit, StorageService},
 };
 
-use self::token::extract_access_token;
+use self::token::{extract_access_token, validate_user_access_token};
 
 #[derive(Debug, Clone)]
 pub struct YakManPrinciple {
@@ -93,16 +94,35 @@ where
                     // todo: handle unwrap??
                     if let Some(api_key) = storage_service.get_api_key_by_hash(&hash).await.unwrap()
                     {
-                        user_id = Some(api_key.id.to_string());
+                        let now = Utc::now().timestamp_millis();
+                        if api_key.is_valid_for_hash(&hash, now) {
+                            if let Err(err) =
+                                storage_service.record_api_key_usage(&api_key.id).await
+                            {
+                                log::error!("Failed to record api key usage, {err:?}");
+                            }
+                            user_id = Some(api_key.id.to_string());
+                        }
+                    }
+                } else {
+                    let storage_service = req
+                        .app_data::<web::Data<Arc<dyn StorageService>>>()
+                        .unwrap();
+
+                    if let Some((claims, _)) =
+                        validate_user_access_token(token_service, storage_service, &token).await
+                    {
+                        user_id = Some(claims.user_id);
                     }
-                } else if let Ok(claims) = token_service.validate_access_token(&token) {
-                    user_id = Some(claims.user_id);
                 }
             }
 
-            req.extensions_mut().insert(YakManPrinciple { user_id });
+            req.extensions_mut().insert(YakManPrinciple {
+                user_id: user_id.clone(),
+            });
 
-            let res = svc.call(req).await?;
+            // Mutations made while handling the request are attributed to the principal in the audit log
+            let res = audit::with_actor(user_id, svc.call(req)).await?;
 
             Ok(res)
         })
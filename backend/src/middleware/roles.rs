@@ -1,13 +1,16 @@
 use std::collections::HashSet;
 use std::sync::Arc;
 
-use super::token::extract_access_token;
+use super::token::{extract_access_token, validate_user_access_token};
 use super::YakManPrinciple;
 use crate::auth::token::{TokenService, YakManTokenService};
-use crate::model::{YakManProjectRole, YakManRole};
+use crate::model::{
+    YakManApiKeyOperation, YakManApiKeyScope, YakManLabel, YakManProjectRole, YakManRole,
+};
 use crate::services::StorageService;
 use actix_web::HttpMessage;
 use actix_web::{dev::ServiceRequest, web, Error};
+use chrono::Utc;
 use futures_util::future::join_all;
 use futures_util::TryFutureExt;
 
@@ -15,13 +18,47 @@ use futures_util::TryFutureExt;
 pub enum YakManRoleBinding {
     GlobalRoleBinding(YakManRole),
     ProjectRoleBinding(YakManProjectRole),
+    /// Restricts the other role bindings of a scoped api key
+    ApiKeyScope(YakManApiKeyScope),
 }
 
 impl YakManRoleBinding {
+    /// Scoped api keys are always rejected, endpoints that support scopes should use `has_any_role_in_scope`
     pub fn has_any_role(
         roles_to_match: Vec<YakManRole>,
         project_id: &str,
         roles: &HashSet<YakManRoleBinding>,
+    ) -> bool {
+        if YakManRoleBinding::get_api_key_scope(roles).is_some() {
+            return false;
+        }
+
+        return YakManRoleBinding::has_any_role_ignoring_scope(roles_to_match, project_id, roles);
+    }
+
+    /// Checks the roles and, for scoped api keys, that the scope allows the operation on the config instance
+    pub fn has_any_role_in_scope(
+        roles_to_match: Vec<YakManRole>,
+        project_id: &str,
+        operation: YakManApiKeyOperation,
+        config_id: &str,
+        instance_labels: &[YakManLabel],
+        roles: &HashSet<YakManRoleBinding>,
+    ) -> bool {
+        if let Some(scope) = YakManRoleBinding::get_api_key_scope(roles) {
+            if !scope.allows(&operation, config_id, instance_labels) {
+                return false;
+            }
+        }
+
+        return YakManRoleBinding::has_any_role_ignoring_scope(roles_to_match, project_id, roles);
+    }
+
+    /// Only use this when the response is filtered using the api key scope
+    pub fn has_any_role_ignoring_scope(
+        roles_to_match: Vec<YakManRole>,
+        project_id: &str,
+        roles: &HashSet<YakManRoleBinding>,
     ) -> bool {
         for role in roles {
             match role {
@@ -45,12 +82,20 @@ impl YakManRoleBinding {
                         }
                     }
                 }
+                YakManRoleBinding::ApiKeyScope(_) => {}
             }
         }
 
         return false;
     }
 
+    pub fn get_api_key_scope(roles: &HashSet<YakManRoleBinding>) -> Option<&YakManApiKeyScope> {
+        return roles.iter().find_map(|role| match role {
+            YakManRoleBinding::ApiKeyScope(scope) => Some(scope),
+            _ => None,
+        });
+    }
+
     #[allow(dead_code)]
     pub fn has_role(
         role_to_match: YakManRole,
@@ -64,9 +109,22 @@ impl YakManRoleBinding {
         return YakManRoleBinding::has_any_global_role(vec![role_to_match], roles);
     }
 
+    /// Scoped api keys are always rejected, a scope never grants access to global endpoints
     pub fn has_any_global_role(
         roles_to_match: Vec<YakManRole>,
         roles: &HashSet<YakManRoleBinding>,
+    ) -> bool {
+        if YakManRoleBinding::get_api_key_scope(roles).is_some() {
+            return false;
+        }
+
+        return YakManRoleBinding::has_any_global_role_ignoring_scope(roles_to_match, roles);
+    }
+
+    /// Only use this when the response is filtered using the api key scope
+    pub fn has_any_global_role_ignoring_scope(
+        roles_to_match: Vec<YakManRole>,
+        roles: &HashSet<YakManRoleBinding>,
     ) -> bool {
         for role in roles {
             if let YakManRoleBinding::GlobalRoleBinding(r) = role {
@@ -111,13 +169,49 @@ pub async fn extract_roles(req: &ServiceRequest) -> Result<HashSet<YakManRoleBin
                     .unwrap();
 
                 if let Some(api_key) = storage_service.get_api_key_by_id(key_id).await.unwrap() {
+                    if api_key.is_expired(Utc::now().timestamp_millis()) {
+                        return Ok(HashSet::new());
+                    }
+
                     let mut api_key_roles = HashSet::new();
-                    api_key_roles.insert(YakManRoleBinding::ProjectRoleBinding(
-                        YakManProjectRole {
-                            project_id: api_key.project_id,
-                            role: api_key.role,
-                        },
-                    ));
+                    if let (Some(project_id), Some(role)) = (api_key.project_id, api_key.role) {
+                        api_key_roles.insert(YakManRoleBinding::ProjectRoleBinding(
+                            YakManProjectRole { project_id, role },
+                        ));
+                    }
+
+                    // Service account keys act with the roles of the service account
+                    if let Some(service_account_id) = &api_key.service_account_id {
+                        let Some(service_account) = storage_service
+                            .get_service_account(service_account_id)
+                            .await?
+                        else {
+                            return Ok(HashSet::new());
+                        };
+
+                        api_key_roles.extend(
+                            service_account
+                                .global_roles
+                                .into_iter()
+                                .map(YakManRoleBinding::GlobalRoleBinding),
+                        );
+                        api_key_roles.extend(
+                            service_account
+                                .roles
+                                .into_iter()
+                                .map(YakManRoleBinding::ProjectRoleBinding),
+                        );
+                        load_team_role_bindings(
+                            storage_service,
+                            &service_account.team_ids,
+                            &mut api_key_roles,
+                        )
+                        .await;
+                    }
+
+                    if let Some(scope) = api_key.scope {
+                        api_key_roles.insert(YakManRoleBinding::ApiKeyScope(scope));
+                    }
 
                     Ok(api_key_roles)
                 } else {
@@ -128,83 +222,80 @@ pub async fn extract_roles(req: &ServiceRequest) -> Result<HashSet<YakManRoleBin
         };
     }
 
-    match token_service.validate_access_token(&token) {
-        Ok(claims) => {
-            let user_id = claims.user_id;
-
-            let storage_service = req
-                .app_data::<web::Data<Arc<dyn StorageService>>>()
-                .unwrap();
-
-            if let Some(details) = storage_service.get_user_details(&user_id).await? {
-                let global_roles: Vec<YakManRoleBinding> = details
-                    .global_roles
-                    .iter()
-                    .map(|p| YakManRoleBinding::GlobalRoleBinding(p.clone()))
-                    .collect();
-
-                role_bindings.extend(global_roles);
-
-                let project_role_bindings: Vec<YakManRoleBinding> = details
-                    .roles
-                    .into_iter()
-                    .map(YakManRoleBinding::ProjectRoleBinding)
-                    .collect();
-
-                role_bindings.extend(project_role_bindings);
-
-                // If the user already has global admin role, we can skip loading team roles
-                if !role_bindings.contains(&YakManRoleBinding::GlobalRoleBinding(YakManRole::Admin))
-                {
-                    // Load team roles
-                    let futures: Vec<_> = details
-                        .team_ids
-                        .iter()
-                        .map(|team_id| {
-                            storage_service
-                                .get_team_details(team_id)
-                                .map_ok(move |inner| {
-                                    inner.ok_or(format!("Team with ID not found {team_id}"))
-                                })
-                        })
-                        .collect();
-
-                    for result in join_all(futures).await {
-                        let team_details = match result {
-                            Ok(Ok(team_details)) => team_details,
-                            Ok(Err(err)) => {
-                                log::warn!("Could not load team to get roles {err:?}");
-                                continue;
-                            }
-                            Err(err) => {
-                                log::warn!("Could not load team to get roles {err:?}");
-                                continue;
-                            }
-                        };
-                        let global_roles: Vec<YakManRoleBinding> = team_details
-                            .global_roles
-                            .iter()
-                            .map(|p| YakManRoleBinding::GlobalRoleBinding(p.clone()))
-                            .collect();
-                        role_bindings.extend(global_roles);
-
-                        let project_role_bindings: Vec<YakManRoleBinding> = team_details
-                            .roles
-                            .into_iter()
-                            .map(YakManRoleBinding::ProjectRoleBinding)
-                            .collect();
-
-                        role_bindings.extend(project_role_bindings);
-                    }
-                }
-            } else {
-                log::info!("user details not found");
-            }
-        }
-        Err(e) => {
-            log::info!("token invalid {e:?}");
-        }
+    let storage_service = req
+        .app_data::<web::Data<Arc<dyn StorageService>>>()
+        .unwrap();
+
+    if let Some((_, details)) =
+        validate_user_access_token(token_service, storage_service, &token).await
+    {
+        let global_roles: Vec<YakManRoleBinding> = details
+            .global_roles
+            .iter()
+            .map(|p| YakManRoleBinding::GlobalRoleBinding(p.clone()))
+            .collect();
+
+        role_bindings.extend(global_roles);
+
+        let project_role_bindings: Vec<YakManRoleBinding> = details
+            .roles
+            .into_iter()
+            .map(YakManRoleBinding::ProjectRoleBinding)
+            .collect();
+
+        role_bindings.extend(project_role_bindings);
+
+        load_team_role_bindings(storage_service, &details.team_ids, &mut role_bindings).await;
     }
 
     return Ok(role_bindings);
 }
+
+/// Adds the role bindings granted by the given teams
+async fn load_team_role_bindings(
+    storage_service: &Arc<dyn StorageService>,
+    team_ids: &[String],
+    role_bindings: &mut HashSet<YakManRoleBinding>,
+) {
+    // If there is already a global admin role, we can skip loading team roles
+    if role_bindings.contains(&YakManRoleBinding::GlobalRoleBinding(YakManRole::Admin)) {
+        return;
+    }
+
+    let futures: Vec<_> = team_ids
+        .iter()
+        .map(|team_id| {
+            storage_service
+                .get_team_details(team_id)
+                .map_ok(move |inner| inner.ok_or(format!("Team with ID not found {team_id}")))
+        })
+        .collect();
+
+    for result in join_all(futures).await {
+        let team_details = match result {
+            Ok(Ok(team_details)) => team_details,
+            Ok(Err(err)) => {
+                log::warn!("Could not load team to get roles {err:?}");
+                continue;
+            }
+            Err(err) => {
+                log::warn!("Could not load team to get roles {err:?}");
+                continue;
+            }
+        };
+        let global_roles: Vec<YakManRoleBinding> = team_details
+            .global_roles
+            .iter()
+            .map(|p| YakManRoleBinding::GlobalRoleBinding(p.clone()))
+            .collect();
+        role_bindings.extend(global_roles);
+
+        let project_role_bindings: Vec<YakManRoleBinding> = team_details
+            .roles
+            .into_iter()
+            .map(YakManRoleBinding::ProjectRoleBinding)
+            .collect();
+
+        role_bindings.extend(project_role_bindings);
+    }
+}
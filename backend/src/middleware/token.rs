@@ -1,5 +1,13 @@
+use std::sync::Arc;
+
 use actix_web::dev::ServiceRequest;
 
+use crate::{
+    auth::token::{JwtValidationError, TokenService, YakManJwtClaims, YakManTokenService},
+    model::YakManUserDetails,
+    services::StorageService,
+};
+
 pub fn extract_access_token(req: &ServiceRequest) -> Option<String> {
     if let Some(token_header) = req.headers().get("Authorization") {
         if let Ok(token) = token_header.to_str() {
@@ -19,6 +27,46 @@ pub fn extract_access_token(req: &ServiceRequest) -> Option<String> {
     None
 }
 
+/// Validates a user's access token and loads their details.
+/// Tokens of deactivated users and tokens issued before the user's sessions were revoked are rejected.
+pub async fn validate_user_access_token(
+    token_service: &YakManTokenService,
+    storage_service: &Arc<dyn StorageService>,
+    token: &str,
+) -> Option<(YakManJwtClaims, YakManUserDetails)> {
+    let claims = match token_service.validate_access_token(token) {
+        Ok(claims) => claims,
+        Err(e) => {
+            log::info!("token invalid {e:?}");
+            return None;
+        }
+    };
+
+    let details = match storage_service.get_user_details(&claims.user_id).await {
+        Ok(Some(details)) => details,
+        Ok(None) => {
+            log::info!("user details not found");
+            return None;
+        }
+        Err(e) => {
+            log::error!("Failed to load user details, {e:?}");
+            return None;
+        }
+    };
+
+    if details.is_deactivated() {
+        log::info!("user {} is deactivated", claims.user_id);
+        return None;
+    }
+
+    if claims.token_version < details.token_version {
+        log::info!("token invalid {:?}", JwtValidationError::TokenRevoked);
+        return None;
+    }
+
+    return Some((claims, details));
+}
+
 #[cfg(test)]
 mod test {
     use actix_web::test::TestRequest;
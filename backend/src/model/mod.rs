@@ -3,7 +3,12 @@ pub mod response;
 
 pub use serde::Deserialize;
 pub use serde::Serialize;
+
+use crate::notifications::YakManNotificationType;
+use chrono::{DateTime, Duration, Utc};
+use cron::Schedule;
 use std::fmt;
+use std::str::FromStr;
 use utoipa::ToSchema;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
@@ -16,7 +21,134 @@ pub struct YakManProject {
 pub struct YakManProjectDetails {
     pub id: String,
     pub name: String,
-    pub notification_settings: Option<ProjectNotificationSettings>,
+    /// Each entry is a separate notification channel with its own event filter
+    #[serde(default, deserialize_with = "deserialize_notification_settings")]
+    pub notification_settings: Vec<ProjectNotificationSettings>,
+    #[serde(default)]
+    pub approval_policy: YakManApprovalPolicy,
+    /// Applies and new instances are blocked while any of these windows are active
+    #[serde(default)]
+    pub freeze_windows: Vec<YakManFreezeWindow>,
+}
+
+/// How many distinct approvals a revision needs before it can be applied
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub struct YakManApprovalPolicy {
+    /// Applies to every revision in the project
+    pub required_approvals: u32,
+    /// Revisions with a matching label need at least the approvals of the rule
+    #[serde(default)]
+    pub label_rules: Vec<YakManLabelApprovalRule>,
+    /// Four-eyes principle, the submitter of a revision cannot approve it
+    #[serde(default)]
+    pub forbid_self_approval: bool,
+    /// New revisions must be submitted with a change description
+    #[serde(default)]
+    pub require_change_description: bool,
+}
+
+impl Default for YakManApprovalPolicy {
+    fn default() -> Self {
+        return YakManApprovalPolicy {
+            required_approvals: 1,
+            label_rules: vec![],
+            forbid_self_approval: false,
+            require_change_description: false,
+        };
+    }
+}
+
+impl YakManApprovalPolicy {
+    /// The highest requirement of the project and every rule matching the labels
+    pub fn required_approvals_for(&self, labels: &[YakManLabel]) -> u32 {
+        let label_requirement = self
+            .label_rules
+            .iter()
+            .filter(|rule| {
+                labels
+                    .iter()
+                    .any(|l| l.label_id == rule.label_id && l.value == rule.value)
+            })
+            .map(|rule| rule.required_approvals)
+            .max()
+            .unwrap_or(0);
+        return self.required_approvals.max(label_requirement).max(1);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub struct YakManLabelApprovalRule {
+    pub label_id: String,
+    pub value: String,
+    pub required_approvals: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub struct YakManFreezeWindow {
+    /// Shown to users when a change is blocked by the freeze
+    pub reason: String,
+    pub schedule: YakManFreezeSchedule,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub enum YakManFreezeSchedule {
+    /// A one off freeze between two unix timestamps in milliseconds
+    Range { start_ms: i64, end_ms: i64 },
+    /// A recurring freeze starting at each time the cron expression matches (UTC)
+    Cron {
+        expression: String,
+        duration_minutes: u32,
+    },
+}
+
+impl YakManFreezeWindow {
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        return match &self.schedule {
+            YakManFreezeSchedule::Range { start_ms, end_ms } => {
+                let now = now.timestamp_millis();
+                *start_ms <= now && now < *end_ms
+            }
+            YakManFreezeSchedule::Cron {
+                expression,
+                duration_minutes,
+            } => {
+                let Ok(schedule) = Schedule::from_str(expression) else {
+                    return false;
+                };
+                // Active if the window started within the last `duration_minutes`
+                let window_start = now - Duration::minutes(*duration_minutes as i64);
+                schedule
+                    .after(&window_start)
+                    .next()
+                    .is_some_and(|start| start <= now)
+            }
+        };
+    }
+}
+
+/// Projects used to support a single notification channel, so older project details
+/// and API clients may send a single object (or null) rather than a list.
+pub(crate) fn deserialize_notification_settings<'de, D, T>(
+    deserializer: D,
+) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NotificationSettingsFormat<T> {
+        List(Vec<T>),
+        Single(T),
+    }
+
+    return Ok(
+        match Option::<NotificationSettingsFormat<T>>::deserialize(deserializer)? {
+            Some(NotificationSettingsFormat::List(settings)) => settings,
+            Some(NotificationSettingsFormat::Single(settings)) => vec![settings],
+            None => vec![],
+        },
+    );
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
@@ -27,8 +159,21 @@ pub struct ProjectNotificationSettings {
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
 pub enum NotificationSetting {
-    Slack { webhook_url: String },
-    Discord { webhook_url: String },
+    Slack {
+        webhook_url: String,
+    },
+    Discord {
+        webhook_url: String,
+    },
+    MicrosoftTeams {
+        webhook_url: String,
+    },
+    Email {
+        recipients: Vec<String>,
+        /// Also send to every user with the approver (or admin) role on the project
+        #[serde(default)]
+        send_to_project_approvers: bool,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
@@ -43,6 +188,40 @@ pub struct NotificationSettingEvents {
     pub is_revision_approved_enabled: bool,
     #[serde(default)]
     pub is_revision_reject_enabled: bool,
+    #[serde(default)]
+    pub is_revision_rollback_enabled: bool,
+    #[serde(default)]
+    pub is_instance_deleted_enabled: bool,
+    #[serde(default)]
+    pub is_config_deleted_enabled: bool,
+    #[serde(default)]
+    pub is_project_settings_updated_enabled: bool,
+    #[serde(default)]
+    pub is_api_key_created_enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub enum NotificationDeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+/// A notification for a single channel. Pending deliveries make up the outbox,
+/// delivered and failed deliveries make up the delivery log.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub struct YakManNotificationDelivery {
+    pub id: String,
+    pub project_id: String,
+    /// The channel with any project approvers already resolved
+    pub settings: NotificationSetting,
+    pub event: YakManNotificationType,
+    pub status: NotificationDeliveryStatus,
+    pub attempts: u32,
+    pub created_at_ms: i64,
+    pub next_attempt_at_ms: i64,
+    pub last_attempt_at_ms: Option<i64>,
+    pub last_error: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
@@ -120,6 +299,22 @@ pub enum ConfigInstanceEventData {
     NewRevisionRejected {
         new_revision: String,
         rejected_by_user_id: String,
+        /// Rejected revisions are removed from the instance, so the reason is kept here too
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    ApplyScheduled {
+        new_revision: String,
+        apply_at_ms: i64,
+        scheduled_by_user_id: String,
+    },
+    ScheduledApplyCancelled {
+        new_revision: String,
+        cancelled_by_user_id: String,
+    },
+    ScheduledApplyFailed {
+        new_revision: String,
+        reason: String,
     },
 }
 
@@ -142,16 +337,168 @@ pub struct ConfigInstanceRevision {
     pub submitted_by_user_id: String,
     pub submit_timestamp_ms: i64,
     pub content_type: String,
+    /// Every approval the revision has received, oldest first
+    #[serde(default)]
+    pub approvals: Vec<RevisionApproval>,
+    /// Review discussion, oldest first
+    #[serde(default)]
+    pub comments: Vec<RevisionComment>,
+    /// Why the change was made, provided by the submitter
+    #[serde(default)]
+    pub change_details: RevisionChangeDetails,
+}
+
+impl ConfigInstanceRevision {
+    /// The number of distinct approvers. Revisions approved before approvals were tracked
+    /// count their reviewer as the only approval.
+    pub fn approval_count(&self) -> u32 {
+        if self.approvals.is_empty() {
+            let is_legacy_approval = self.review_state == RevisionReviewState::Approved
+                && self.reviewed_by_user_id.is_some();
+            return if is_legacy_approval { 1 } else { 0 };
+        }
+        return self.approvals.len() as u32;
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default, ToSchema)]
+pub struct RevisionChangeDetails {
+    /// Commit style message describing the change
+    pub description: Option<String>,
+    /// Reference to an external ticket, ie. `PROJ-123` or a link
+    pub ticket: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub struct RevisionApproval {
+    pub user_id: String,
+    pub timestamp_ms: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub struct RevisionComment {
+    pub id: String,
+    pub user_id: String,
+    pub timestamp_ms: i64,
+    pub message: String,
+    pub kind: RevisionCommentKind,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, ToSchema)]
+pub enum RevisionCommentKind {
+    Comment,
+    /// Left by a reviewer when approving
+    Approval,
+    /// The reason a revision was rejected
+    Rejection,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
 pub struct YakManApiKey {
     pub id: String,
     pub hash: String,
-    pub project_id: String,
-    pub role: YakManRole,
+    /// The project the key has access to. Not set for service account keys.
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// The role of the key in `project_id`. Not set for service account keys.
+    #[serde(default)]
+    pub role: Option<YakManRole>,
+    /// Service account keys have the role bindings of the service account
+    #[serde(default)]
+    pub service_account_id: Option<String>,
     pub created_at: i64,
     pub created_by_user_id: String,
+    /// The key is rejected after this timestamp. Keys without an expiration never expire.
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    #[serde(default)]
+    pub rotated_at: Option<i64>,
+    /// The hash of the secret before the key was last rotated.
+    /// It remains valid until `previous_hash_expires_at` so clients can switch to the new secret.
+    #[serde(default)]
+    pub previous_hash: Option<String>,
+    #[serde(default)]
+    pub previous_hash_expires_at: Option<i64>,
+    /// Restricts the key to a subset of the project. Keys without a scope have full access for their role.
+    #[serde(default)]
+    pub scope: Option<YakManApiKeyScope>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq, Clone, Hash)]
+pub enum YakManApiKeyOperation {
+    /// Read instances, revisions and config data
+    ReadData,
+    /// Create instances, submit new revisions and rollbacks
+    SubmitRevisions,
+    /// Approve, reject and apply revisions
+    ApproveRevisions,
+}
+
+/// Matches instances that have a label with the given value
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq, Clone, Hash)]
+pub struct YakManLabelSelector {
+    pub label_id: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq, Clone, Hash)]
+pub struct YakManApiKeyScope {
+    /// The operations the key may perform, still limited by the role of the key
+    pub operations: Vec<YakManApiKeyOperation>,
+    /// The configs the key may access. Empty allows every config in the project.
+    #[serde(default)]
+    pub config_ids: Vec<String>,
+    /// Instances must match every selector. Empty allows every instance.
+    #[serde(default)]
+    pub label_selectors: Vec<YakManLabelSelector>,
+}
+
+impl YakManApiKeyScope {
+    pub fn allows_config(&self, config_id: &str) -> bool {
+        return self.config_ids.is_empty() || self.config_ids.iter().any(|id| id == config_id);
+    }
+
+    pub fn allows_labels(&self, labels: &[YakManLabel]) -> bool {
+        return self.label_selectors.iter().all(|selector| {
+            labels
+                .iter()
+                .any(|l| l.label_id == selector.label_id && l.value == selector.value)
+        });
+    }
+
+    pub fn allows(
+        &self,
+        operation: &YakManApiKeyOperation,
+        config_id: &str,
+        labels: &[YakManLabel],
+    ) -> bool {
+        return self.operations.contains(operation)
+            && self.allows_config(config_id)
+            && self.allows_labels(labels);
+    }
+}
+
+impl YakManApiKey {
+    pub fn is_expired(&self, now_ms: i64) -> bool {
+        return matches!(self.expires_at, Some(expires_at) if expires_at <= now_ms);
+    }
+
+    /// Checks if the hash of a secret matches this key, either the current secret
+    /// or the previous secret during its grace period.
+    pub fn is_valid_for_hash(&self, hash: &str, now_ms: i64) -> bool {
+        if self.is_expired(now_ms) {
+            return false;
+        }
+
+        if self.hash == hash {
+            return true;
+        }
+
+        return match (&self.previous_hash, self.previous_hash_expires_at) {
+            (Some(previous_hash), Some(expires_at)) => previous_hash == hash && now_ms < expires_at,
+            _ => false,
+        };
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq, Clone, Hash)]
@@ -203,6 +550,15 @@ pub struct YakManUser {
     pub email: String,
     pub id: String,
     pub role: Option<YakManRole>,
+    /// Mirrors `YakManUserDetails::deactivated_at` so the user list can be checked without loading details
+    #[serde(default)]
+    pub deactivated_at: Option<i64>,
+}
+
+impl YakManUser {
+    pub fn is_deactivated(&self) -> bool {
+        return self.deactivated_at.is_some();
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq, Clone, Hash)]
@@ -218,12 +574,28 @@ pub struct YakManUserDetails {
     pub global_roles: Vec<YakManRole>,
     pub roles: Vec<YakManProjectRole>,
     pub team_ids: Vec<String>,
+    /// Unix timestamp (in milliseconds) of when the user was deactivated.
+    /// Deactivated users cannot login, refresh tokens or use existing tokens.
+    #[serde(default)]
+    pub deactivated_at: Option<i64>,
+    /// Incremented to revoke all of the user's existing access and refresh tokens
+    #[serde(default)]
+    pub token_version: u32,
+}
+
+impl YakManUserDetails {
+    pub fn is_deactivated(&self) -> bool {
+        return self.deactivated_at.is_some();
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct YakManPassword {
     pub hash: String,
     pub timestamp: i64,
+    /// Hashes of the most recent previous passwords, newest first, to prevent reuse
+    #[serde(default)]
+    pub previous_hashes: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -232,6 +604,44 @@ pub struct YakManPasswordResetLink {
     pub expiration_timestamp_ms: i64,
 }
 
+/// When an api key was last used. Stored separately from the api keys so recording usage
+/// never rewrites the key list.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct YakManApiKeyUsage {
+    pub last_used_at: i64,
+}
+
+/// A refresh token that was revoked by logging out, keyed by the hash of the token
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct YakManRevokedRefreshToken {
+    pub revoked_at: i64,
+    /// After this the record is pruned, the refresh token will have expired by then
+    pub expires_at: i64,
+}
+
+/// Failed login attempts for an account or client IP
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct YakManLoginAttempts {
+    pub failed_attempts: u32,
+    pub last_failed_at: i64,
+    pub locked_until: Option<i64>,
+}
+
+/// A user's TOTP second factor. Stored separately from the user details so the secret is never
+/// returned by the user APIs.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct YakManTotp {
+    /// Base32 encoded shared secret
+    pub secret: String,
+    /// Set once the user has proven they can generate codes, unconfirmed secrets are not required at login
+    pub confirmed: bool,
+    /// SHA256 hashes of the unused recovery codes
+    pub recovery_code_hashes: Vec<String>,
+    /// The last time step a code was accepted for, codes cannot be used twice
+    pub last_used_step: Option<u64>,
+    pub created_at: i64,
+}
+
 /// Public response when creating a password reset link
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct YakManPublicPasswordResetLink {
@@ -239,6 +649,18 @@ pub struct YakManPublicPasswordResetLink {
     pub user_id: String,
 }
 
+/// A non-human principal, such as a deploy pipeline, that authenticates with its api keys
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct YakManServiceAccount {
+    pub id: String,
+    pub name: String,
+    pub global_roles: Vec<YakManRole>,
+    pub roles: Vec<YakManProjectRole>,
+    pub team_ids: Vec<String>,
+    pub created_at: i64,
+    pub created_by_user_id: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct YakManTeam {
     pub id: String,
@@ -254,6 +676,18 @@ pub struct YakManTeamDetails {
     pub member_user_ids: Vec<String>,
 }
 
+/// An approved revision that will be applied by the scheduled apply worker
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub struct YakManScheduledApply {
+    pub config_id: String,
+    pub instance: String,
+    pub revision: String,
+    pub apply_at_ms: i64,
+    /// The revision is applied on behalf of this user
+    pub scheduled_by_user_id: String,
+    pub scheduled_at_ms: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct YakManSnapshotLock {
     pub lock: Option<YakManSnapshotLockInner>,
@@ -276,3 +710,238 @@ impl YakManSnapshotLock {
         Self { lock: None }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, ToSchema)]
+pub enum YakManAuditAction {
+    Create,
+    Update,
+    Delete,
+    Submit,
+    Approve,
+    Apply,
+    Reject,
+    Rollback,
+    Deactivate,
+    Reactivate,
+    RevokeSessions,
+    EnableTotp,
+    DisableTotp,
+    CreatePasswordResetLink,
+    Invite,
+    ResetPassword,
+    ChangePassword,
+    Unlock,
+    ScheduleApply,
+    CancelScheduledApply,
+    /// An admin overrode an active freeze window
+    BreakGlass,
+    Comment,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, ToSchema)]
+pub enum YakManAuditTargetType {
+    Project,
+    Label,
+    Config,
+    ConfigInstance,
+    Revision,
+    User,
+    Team,
+    ServiceAccount,
+    ApiKey,
+    LoginAttempts,
+}
+
+/// A single mutation in the audit log. Entries are only ever appended, never updated or deleted.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub struct YakManAuditLogEntry {
+    pub id: String,
+    pub timestamp_ms: i64,
+    /// The user or api key id of the request. Not set for unauthenticated requests, such as password resets.
+    pub actor: Option<String>,
+    pub action: YakManAuditAction,
+    pub target_type: YakManAuditTargetType,
+    pub target_id: String,
+    /// Summary of the target before the change, secrets are never included
+    #[schema(value_type = Option<Object>)]
+    pub before: Option<serde_json::Value>,
+    /// Summary of the target after the change, secrets are never included
+    #[schema(value_type = Option<Object>)]
+    pub after: Option<serde_json::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn project_details_should_deserialize_single_notification_setting() {
+        let details: YakManProjectDetails = serde_json::from_value(json!({
+            "id": "p123",
+            "name": "foo",
+            "notification_settings": {
+                "settings": { "Slack": { "webhook_url": "https://hooks.slack.com/services/foo" } },
+                "events": { "is_revision_submitted_enabled": true }
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(1, details.notification_settings.len());
+        assert!(
+            details.notification_settings[0]
+                .events
+                .is_revision_submitted_enabled
+        );
+    }
+
+    #[test]
+    fn project_details_should_deserialize_missing_notification_settings() {
+        let details: YakManProjectDetails = serde_json::from_value(json!({
+            "id": "p123",
+            "name": "foo",
+            "notification_settings": null
+        }))
+        .unwrap();
+
+        assert!(details.notification_settings.is_empty());
+    }
+
+    fn api_key() -> YakManApiKey {
+        return YakManApiKey {
+            id: "apikey-123".to_string(),
+            hash: "new-hash".to_string(),
+            project_id: Some("p123".to_string()),
+            role: Some(YakManRole::Viewer),
+            service_account_id: None,
+            created_at: 0,
+            created_by_user_id: "u123".to_string(),
+            expires_at: None,
+            rotated_at: Some(1000),
+            previous_hash: Some("old-hash".to_string()),
+            previous_hash_expires_at: Some(2000),
+            scope: None,
+        };
+    }
+
+    #[test]
+    fn api_key_previous_hash_should_only_be_valid_during_grace_period() {
+        let key = api_key();
+
+        assert!(key.is_valid_for_hash("new-hash", 5000));
+        assert!(key.is_valid_for_hash("old-hash", 1500));
+        assert!(!key.is_valid_for_hash("old-hash", 2000));
+        assert!(!key.is_valid_for_hash("other-hash", 1500));
+    }
+
+    #[test]
+    fn expired_api_key_should_not_be_valid() {
+        let mut key = api_key();
+        key.expires_at = Some(1200);
+
+        assert!(key.is_valid_for_hash("new-hash", 1100));
+        assert!(!key.is_valid_for_hash("new-hash", 1200));
+        assert!(!key.is_valid_for_hash("old-hash", 1500));
+    }
+
+    #[test]
+    fn api_key_scope_should_require_operation_config_and_labels() {
+        let scope = YakManApiKeyScope {
+            operations: vec![YakManApiKeyOperation::ReadData],
+            config_ids: vec!["c123".to_string()],
+            label_selectors: vec![YakManLabelSelector {
+                label_id: "env".to_string(),
+                value: "prod".to_string(),
+            }],
+        };
+        let prod = vec![YakManLabel {
+            label_id: "env".to_string(),
+            name: None,
+            value: "prod".to_string(),
+        }];
+        let dev = vec![YakManLabel {
+            label_id: "env".to_string(),
+            name: None,
+            value: "dev".to_string(),
+        }];
+
+        assert!(scope.allows(&YakManApiKeyOperation::ReadData, "c123", &prod));
+        assert!(!scope.allows(&YakManApiKeyOperation::SubmitRevisions, "c123", &prod));
+        assert!(!scope.allows(&YakManApiKeyOperation::ReadData, "c456", &prod));
+        assert!(!scope.allows(&YakManApiKeyOperation::ReadData, "c123", &dev));
+        assert!(!scope.allows(&YakManApiKeyOperation::ReadData, "c123", &[]));
+    }
+
+    #[test]
+    fn approval_policy_should_use_highest_matching_requirement() {
+        let policy = YakManApprovalPolicy {
+            required_approvals: 1,
+            label_rules: vec![
+                YakManLabelApprovalRule {
+                    label_id: "env".to_string(),
+                    value: "prod".to_string(),
+                    required_approvals: 2,
+                },
+                YakManLabelApprovalRule {
+                    label_id: "tier".to_string(),
+                    value: "critical".to_string(),
+                    required_approvals: 3,
+                },
+            ],
+            forbid_self_approval: false,
+            require_change_description: false,
+        };
+        let label = |label_id: &str, value: &str| YakManLabel {
+            label_id: label_id.to_string(),
+            name: None,
+            value: value.to_string(),
+        };
+
+        assert_eq!(1, policy.required_approvals_for(&[]));
+        assert_eq!(1, policy.required_approvals_for(&[label("env", "dev")]));
+        assert_eq!(2, policy.required_approvals_for(&[label("env", "prod")]));
+        assert_eq!(
+            3,
+            policy.required_approvals_for(&[label("env", "prod"), label("tier", "critical")])
+        );
+    }
+
+    #[test]
+    fn project_details_should_default_to_single_approval() {
+        let details: YakManProjectDetails = serde_json::from_value(json!({
+            "id": "p123",
+            "name": "foo"
+        }))
+        .unwrap();
+
+        assert_eq!(YakManApprovalPolicy::default(), details.approval_policy);
+        assert_eq!(1, details.approval_policy.required_approvals_for(&[]));
+    }
+
+    #[test]
+    fn freeze_window_should_only_be_active_during_its_schedule() {
+        let at = |timestamp: &str| timestamp.parse::<DateTime<Utc>>().unwrap();
+        let range = YakManFreezeWindow {
+            reason: String::from("release"),
+            schedule: YakManFreezeSchedule::Range {
+                start_ms: at("2024-02-29T09:00:00Z").timestamp_millis(),
+                end_ms: at("2024-02-29T10:00:00Z").timestamp_millis(),
+            },
+        };
+        assert!(range.is_active_at(at("2024-02-29T09:00:00Z")));
+        assert!(!range.is_active_at(at("2024-02-29T10:00:00Z")));
+
+        // Every Friday from 16:00 for the rest of the day
+        let weekly = YakManFreezeWindow {
+            reason: String::from("weekend"),
+            schedule: YakManFreezeSchedule::Cron {
+                expression: String::from("0 0 16 * * Fri"),
+                duration_minutes: 8 * 60,
+            },
+        };
+        assert!(weekly.is_active_at(at("2024-03-01T16:00:00Z")));
+        assert!(weekly.is_active_at(at("2024-03-01T23:59:00Z")));
+        assert!(!weekly.is_active_at(at("2024-03-01T15:59:00Z")));
+        assert!(!weekly.is_active_at(at("2024-03-02T00:00:00Z")));
+    }
+}
@@ -15,3 +15,11 @@ pub struct InstancePayload {
 pub struct RevisionPayload {
     pub revision: String,
 }
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub struct ReviewRevisionResponse {
+    /// True once the revision has every approval it requires
+    pub fully_approved: bool,
+    /// ApproveAndApply only applies the revision when it is fully approved
+    pub applied: bool,
+}
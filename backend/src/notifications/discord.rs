@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use serde_json::{json, Value};
 
-use crate::settings;
+use crate::{model::RevisionChangeDetails, settings};
 
 use super::{YakManNotificationAdapter, YakManNotificationType};
 
@@ -32,6 +32,7 @@ impl YakManNotificationType {
                 config_name,
                 instance,
                 revision,
+                change_details,
             } => {
                 let review_request_message = if let Some(host) = settings::yakman_application_host()
                 {
@@ -43,7 +44,7 @@ impl YakManNotificationType {
                     format!("Revision: {revision}")
                 };
 
-                json!({
+                let mut payload = json!({
                     "embeds": [
                         {
                             "title": format!(":loudspeaker: New config change request submitted for `{project_name}`"),
@@ -64,15 +65,18 @@ impl YakManNotificationType {
                             }
                         }
                     ]
-                })
+                });
+                add_change_details(&mut payload, change_details);
+                payload
             }
             YakManNotificationType::RevisionReviewApproved {
                 project_name,
                 config_name,
                 instance,
                 revision: _,
+                comment,
             } => {
-                json!({
+                let mut payload = json!({
                     "embeds": [
                         {
                             "title": format!(":white_check_mark: Config change request approved for `{project_name}`"),
@@ -90,15 +94,20 @@ impl YakManNotificationType {
                             ]
                         }
                     ]
-                })
+                });
+                if let Some(comment) = comment {
+                    payload["embeds"][0]["description"] = json!(format!("**Comment:** {comment}"));
+                }
+                payload
             }
             YakManNotificationType::RevisionReviewApplied {
                 project_name,
                 config_name,
                 instance,
                 revision: _,
+                change_details,
             } => {
-                json!({
+                let mut payload = json!({
                     "embeds": [
                         {
                             "title": format!(":rocket: Config change applied for `{project_name}`"),
@@ -116,18 +125,22 @@ impl YakManNotificationType {
                             ]
                         }
                     ]
-                })
+                });
+                add_change_details(&mut payload, change_details);
+                payload
             }
             YakManNotificationType::RevisionReviewRejected {
                 project_name,
                 config_name,
                 instance,
                 revision: _,
+                reason,
             } => {
                 json!({
                     "embeds": [
                         {
                             "title": format!(":boom: Config change rejected for `{project_name}`"),
+                            "description": format!("**Reason:** {reason}"),
                             "fields": [
                                 {
                                     "name": "Config",
@@ -169,6 +182,136 @@ impl YakManNotificationType {
                     ]
                 })
             }
+
+            YakManNotificationType::RevisionRollbackSubmitted {
+                project_name,
+                config_name,
+                instance,
+                revision,
+                rollback_to_revision,
+            } => {
+                json!({
+                    "embeds": [
+                        {
+                            "title": format!(":rewind: Config rollback submitted for `{project_name}`"),
+                            "fields": [
+                                {
+                                    "name": "Config",
+                                    "value": config_name,
+                                    "inline": true
+                                },
+                                {
+                                    "name": "Instance",
+                                    "value": format!("`{instance}`"),
+                                    "inline": true
+                                }
+                            ],
+                            "footer": {
+                                "text": format!("Revision: {revision} (rollback to {rollback_to_revision})")
+                            }
+                        }
+                    ]
+                })
+            }
+            YakManNotificationType::InstanceDeleted {
+                project_name,
+                config_name,
+                instance,
+            } => {
+                json!({
+                    "embeds": [
+                        {
+                            "title": format!(":wastebasket: Config instance deleted for `{project_name}`"),
+                            "fields": [
+                                {
+                                    "name": "Config",
+                                    "value": config_name,
+                                    "inline": true
+                                },
+                                {
+                                    "name": "Instance",
+                                    "value": format!("`{instance}`"),
+                                    "inline": true
+                                }
+                            ]
+                        }
+                    ]
+                })
+            }
+            YakManNotificationType::ConfigDeleted {
+                project_name,
+                config_name,
+            } => {
+                json!({
+                    "embeds": [
+                        {
+                            "title": format!(":wastebasket: Config deleted for `{project_name}`"),
+                            "fields": [
+                                {
+                                    "name": "Config",
+                                    "value": config_name,
+                                    "inline": true
+                                }
+                            ]
+                        }
+                    ]
+                })
+            }
+            YakManNotificationType::ProjectSettingsUpdated {
+                project_name,
+                changes,
+            } => {
+                let changes: Vec<String> = changes.iter().map(|c| format!("- {c}")).collect();
+
+                json!({
+                    "embeds": [
+                        {
+                            "title": format!(":gear: Project settings updated for `{project_name}`"),
+                            "description": changes.join("\n")
+                        }
+                    ]
+                })
+            }
+            YakManNotificationType::ApiKeyCreated {
+                project_name,
+                api_key_id,
+                role,
+            } => {
+                json!({
+                    "embeds": [
+                        {
+                            "title": format!(":key: API key created for `{project_name}`"),
+                            "fields": [
+                                {
+                                    "name": "Key",
+                                    "value": format!("`{api_key_id}`"),
+                                    "inline": true
+                                },
+                                {
+                                    "name": "Role",
+                                    "value": role.to_string(),
+                                    "inline": true
+                                }
+                            ]
+                        }
+                    ]
+                })
+            }
         }
     }
 }
+
+/// The description becomes the embed body and the ticket an extra field
+fn add_change_details(payload: &mut Value, change_details: &RevisionChangeDetails) {
+    let embed = &mut payload["embeds"][0];
+    if let Some(description) = &change_details.description {
+        embed["description"] = json!(format!("**Description:** {description}"));
+    }
+    if let (Some(ticket), Some(fields)) = (&change_details.ticket, embed["fields"].as_array_mut()) {
+        fields.push(json!({
+            "name": "Ticket",
+            "value": ticket,
+            "inline": true
+        }));
+    }
+}
@@ -0,0 +1,299 @@
+use async_trait::async_trait;
+
+use crate::{model::RevisionChangeDetails, services::email::send_email, settings};
+
+use super::{YakManNotificationAdapter, YakManNotificationType};
+
+pub struct EmailNotificationAdapter {
+    pub recipients: Vec<String>,
+}
+
+#[async_trait]
+impl YakManNotificationAdapter for EmailNotificationAdapter {
+    async fn send_notification(&self, event: YakManNotificationType) -> anyhow::Result<()> {
+        if self.recipients.is_empty() {
+            return Ok(());
+        }
+
+        let (subject, body) = event.to_email_content();
+        send_email(&self.recipients, &subject, &body).await?;
+
+        return Ok(());
+    }
+}
+
+impl YakManNotificationType {
+    /// Returns the subject and plain text body of the email
+    fn to_email_content(&self) -> (String, String) {
+        match self {
+            YakManNotificationType::RevisionReviewSubmitted {
+                project_name,
+                config_name,
+                instance,
+                revision,
+                change_details,
+            } => {
+                let mut body = email_body(
+                    "A new config change request was submitted for review.",
+                    project_name,
+                    config_name,
+                    instance,
+                    Some(revision),
+                );
+                push_change_details(&mut body, change_details);
+                if let Some(host) = settings::yakman_application_host() {
+                    body.push_str(&format!(
+                        "\nReview: {host}/apply-changes/{config_name}/{instance}\n"
+                    ));
+                }
+
+                (
+                    format!("[YakMan] New config change request submitted for {project_name}"),
+                    body,
+                )
+            }
+            YakManNotificationType::RevisionReviewApproved {
+                project_name,
+                config_name,
+                instance,
+                revision,
+                comment,
+            } => {
+                let mut body = email_body(
+                    "A config change request was approved.",
+                    project_name,
+                    config_name,
+                    instance,
+                    Some(revision),
+                );
+                if let Some(comment) = comment {
+                    body.push_str(&format!("\nComment:\n{comment}\n"));
+                }
+
+                (
+                    format!("[YakMan] Config change request approved for {project_name}"),
+                    body,
+                )
+            }
+            YakManNotificationType::RevisionReviewApplied {
+                project_name,
+                config_name,
+                instance,
+                revision,
+                change_details,
+            } => {
+                let mut body = email_body(
+                    "A config change was applied.",
+                    project_name,
+                    config_name,
+                    instance,
+                    Some(revision),
+                );
+                push_change_details(&mut body, change_details);
+
+                (
+                    format!("[YakMan] Config change applied for {project_name}"),
+                    body,
+                )
+            }
+            YakManNotificationType::RevisionReviewRejected {
+                project_name,
+                config_name,
+                instance,
+                revision,
+                reason,
+            } => {
+                let mut body = email_body(
+                    "A config change request was rejected.",
+                    project_name,
+                    config_name,
+                    instance,
+                    Some(revision),
+                );
+                body.push_str(&format!("\nReason:\n{reason}\n"));
+
+                (
+                    format!("[YakMan] Config change rejected for {project_name}"),
+                    body,
+                )
+            }
+            YakManNotificationType::InstanceCreated {
+                project_name,
+                config_name,
+                instance,
+            } => (
+                format!("[YakMan] Config instance created for {project_name}"),
+                email_body(
+                    "A new config instance was created.",
+                    project_name,
+                    config_name,
+                    instance,
+                    None,
+                ),
+            ),
+
+            YakManNotificationType::RevisionRollbackSubmitted {
+                project_name,
+                config_name,
+                instance,
+                revision,
+                rollback_to_revision,
+            } => {
+                let mut body = email_body(
+                    "A config rollback was submitted for review.",
+                    project_name,
+                    config_name,
+                    instance,
+                    Some(revision),
+                );
+                body.push_str(&format!("Rollback to: {rollback_to_revision}\n"));
+
+                (
+                    format!("[YakMan] Config rollback submitted for {project_name}"),
+                    body,
+                )
+            }
+            YakManNotificationType::InstanceDeleted {
+                project_name,
+                config_name,
+                instance,
+            } => (
+                format!("[YakMan] Config instance deleted for {project_name}"),
+                email_body(
+                    "A config instance was deleted.",
+                    project_name,
+                    config_name,
+                    instance,
+                    None,
+                ),
+            ),
+            YakManNotificationType::ConfigDeleted {
+                project_name,
+                config_name,
+            } => (
+                format!("[YakMan] Config deleted for {project_name}"),
+                format!(
+                    "A config was deleted.\n\n\
+                     Project: {project_name}\n\
+                     Config: {config_name}\n"
+                ),
+            ),
+            YakManNotificationType::ProjectSettingsUpdated {
+                project_name,
+                changes,
+            } => {
+                let mut body = format!(
+                    "The project settings were updated.\n\n\
+                     Project: {project_name}\n\n"
+                );
+                for change in changes {
+                    body.push_str(&format!("- {change}\n"));
+                }
+
+                (
+                    format!("[YakMan] Project settings updated for {project_name}"),
+                    body,
+                )
+            }
+            YakManNotificationType::ApiKeyCreated {
+                project_name,
+                api_key_id,
+                role,
+            } => (
+                format!("[YakMan] API key created for {project_name}"),
+                format!(
+                    "An API key was created.\n\n\
+                     Project: {project_name}\n\
+                     Key: {api_key_id}\n\
+                     Role: {role}\n"
+                ),
+            ),
+        }
+    }
+}
+
+fn email_body(
+    summary: &str,
+    project_name: &str,
+    config_name: &str,
+    instance: &str,
+    revision: Option<&str>,
+) -> String {
+    let mut body = format!(
+        "{summary}\n\n\
+         Project: {project_name}\n\
+         Config: {config_name}\n\
+         Instance: {instance}\n"
+    );
+    if let Some(revision) = revision {
+        body.push_str(&format!("Revision: {revision}\n"));
+    }
+    return body;
+}
+
+fn push_change_details(body: &mut String, change_details: &RevisionChangeDetails) {
+    if let Some(ticket) = &change_details.ticket {
+        body.push_str(&format!("Ticket: {ticket}\n"));
+    }
+    if let Some(description) = &change_details.description {
+        body.push_str(&format!("\nDescription:\n{description}\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_content_should_include_config_details() {
+        let (subject, body) = YakManNotificationType::RevisionReviewRejected {
+            project_name: "foo".to_string(),
+            config_name: "bar".to_string(),
+            instance: "i123".to_string(),
+            revision: "r123".to_string(),
+            reason: "Wrong port".to_string(),
+        }
+        .to_email_content();
+
+        assert_eq!("[YakMan] Config change rejected for foo", subject);
+        assert!(body.starts_with("A config change request was rejected."));
+        assert!(body.contains("Config: bar\n"));
+        assert!(body.contains("Instance: i123\n"));
+        assert!(body.contains("Revision: r123\n"));
+        assert!(body.contains("Reason:\nWrong port\n"));
+    }
+
+    #[test]
+    fn email_content_should_include_change_details() {
+        let (_, body) = YakManNotificationType::RevisionReviewSubmitted {
+            project_name: "foo".to_string(),
+            config_name: "bar".to_string(),
+            instance: "i123".to_string(),
+            revision: "r123".to_string(),
+            change_details: RevisionChangeDetails {
+                description: Some("Increase the pool size".to_string()),
+                ticket: Some("OPS-42".to_string()),
+            },
+        }
+        .to_email_content();
+
+        assert!(body.contains("Revision: r123\nTicket: OPS-42\n"));
+        assert!(body.contains("Description:\nIncrease the pool size\n"));
+    }
+
+    #[test]
+    fn email_content_should_list_project_setting_changes() {
+        let (subject, body) = YakManNotificationType::ProjectSettingsUpdated {
+            project_name: "foo".to_string(),
+            changes: vec![
+                "Renamed from bar to foo".to_string(),
+                "Notification channels updated".to_string(),
+            ],
+        }
+        .to_email_content();
+
+        assert_eq!("[YakMan] Project settings updated for foo", subject);
+        assert!(body.contains("Project: foo\n"));
+        assert!(body.contains("- Renamed from bar to foo\n- Notification channels updated\n"));
+    }
+}
@@ -1,19 +1,27 @@
 pub mod discord;
+pub mod email;
 pub mod slack;
+pub mod teams;
 
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-use crate::model::NotificationSetting;
+use crate::model::{NotificationSetting, RevisionChangeDetails, YakManRole};
 
-use self::{discord::DiscordNotificationAdapter, slack::SlackNotificationAdapter};
+use self::{
+    discord::DiscordNotificationAdapter, email::EmailNotificationAdapter,
+    slack::SlackNotificationAdapter, teams::MicrosoftTeamsNotificationAdapter,
+};
 
 #[async_trait]
 pub trait YakManNotificationAdapter {
     async fn send_notification(&self, event: YakManNotificationType) -> anyhow::Result<()>;
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
 pub enum YakManNotificationType {
     InstanceCreated {
         project_name: String,
@@ -25,24 +33,61 @@ pub enum YakManNotificationType {
         config_name: String,
         instance: String,
         revision: String,
+        #[serde(default)]
+        change_details: RevisionChangeDetails,
     },
     RevisionReviewApproved {
         project_name: String,
         config_name: String,
         instance: String,
         revision: String,
+        /// Left by the reviewer that completed the approval
+        #[serde(default)]
+        comment: Option<String>,
     },
     RevisionReviewApplied {
         project_name: String,
         config_name: String,
         instance: String,
         revision: String,
+        #[serde(default)]
+        change_details: RevisionChangeDetails,
     },
     RevisionReviewRejected {
         project_name: String,
         config_name: String,
         instance: String,
         revision: String,
+        #[serde(default)]
+        reason: String,
+    },
+    RevisionRollbackSubmitted {
+        project_name: String,
+        config_name: String,
+        instance: String,
+        /// The new pending revision created by the rollback
+        revision: String,
+        /// The revision that is being rolled back to
+        rollback_to_revision: String,
+    },
+    InstanceDeleted {
+        project_name: String,
+        config_name: String,
+        instance: String,
+    },
+    ConfigDeleted {
+        project_name: String,
+        config_name: String,
+    },
+    ProjectSettingsUpdated {
+        project_name: String,
+        /// Human readable descriptions of what changed
+        changes: Vec<String>,
+    },
+    ApiKeyCreated {
+        project_name: String,
+        api_key_id: String,
+        role: YakManRole,
     },
 }
 
@@ -57,6 +102,19 @@ impl From<NotificationSetting> for Arc<dyn YakManNotificationAdapter + Send + Sy
                 http_client: reqwest::Client::new(),
                 webhook_url: webhook_url,
             }),
+            NotificationSetting::MicrosoftTeams { webhook_url } => {
+                Arc::new(MicrosoftTeamsNotificationAdapter {
+                    http_client: reqwest::Client::new(),
+                    webhook_url: webhook_url,
+                })
+            }
+            // Project approvers are resolved when the notification is added to the outbox
+            NotificationSetting::Email {
+                recipients,
+                send_to_project_approvers: _,
+            } => Arc::new(EmailNotificationAdapter {
+                recipients: recipients,
+            }),
         }
     }
 }
@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use serde_json::{json, Value};
 
-use crate::settings;
+use crate::{model::RevisionChangeDetails, settings};
 
 use super::{YakManNotificationAdapter, YakManNotificationType};
 
@@ -32,6 +32,7 @@ impl YakManNotificationType {
                 config_name,
                 instance,
                 revision,
+                change_details,
             } => {
                 let review_request_message = if let Some(host) = settings::yakman_application_host()
                 {
@@ -42,13 +43,103 @@ impl YakManNotificationType {
                     format!("Revision: {revision}")
                 };
 
+                let mut blocks = vec![
+                    json!({
+                        "type": "section",
+                        "text": {
+                            "type": "mrkdwn",
+                            "text": format!(":loudspeaker: *New config change request submitted for `{project_name}`*")
+                        }
+                    }),
+                    config_fields(config_name, instance),
+                ];
+                blocks.extend(change_details_blocks(change_details));
+                blocks.push(json!({
+                    "type": "context",
+                    "elements": [
+                        {
+                            "type": "mrkdwn",
+                            "text": review_request_message
+                        }
+                    ]
+                }));
+                json!({ "blocks": blocks })
+            }
+            YakManNotificationType::RevisionReviewApproved {
+                project_name,
+                config_name,
+                instance,
+                revision: _,
+                comment,
+            } => {
+                let mut blocks = vec![
+                    json!({
+                        "type": "section",
+                        "text": {
+                            "type": "mrkdwn",
+                            "text": format!(":white_check_mark: *Config change request approved for `{project_name}`*")
+                        }
+                    }),
+                    config_fields(config_name, instance),
+                ];
+                if let Some(comment) = comment {
+                    blocks.push(comment_block("Comment", comment));
+                }
+                json!({ "blocks": blocks })
+            }
+            YakManNotificationType::RevisionReviewApplied {
+                project_name,
+                config_name,
+                instance,
+                revision: _,
+                change_details,
+            } => {
+                let mut blocks = vec![
+                    json!({
+                        "type": "section",
+                        "text": {
+                            "type": "mrkdwn",
+                            "text": format!(":rocket: *Config change applied for `{project_name}`*")
+                        }
+                    }),
+                    config_fields(config_name, instance),
+                ];
+                blocks.extend(change_details_blocks(change_details));
+                json!({ "blocks": blocks })
+            }
+            YakManNotificationType::RevisionReviewRejected {
+                project_name,
+                config_name,
+                instance,
+                revision: _,
+                reason,
+            } => {
+                json!({
+                    "blocks": [
+                        {
+                            "type": "section",
+                            "text": {
+                                "type": "mrkdwn",
+                                "text": format!(":boom: *Config change rejected for `{project_name}`*")
+                            }
+                        },
+                        config_fields(config_name, instance),
+                        comment_block("Reason", reason)
+                    ]
+                })
+            }
+            YakManNotificationType::InstanceCreated {
+                project_name,
+                config_name,
+                instance,
+            } => {
                 json!({
                     "blocks": [
                         {
                             "type": "section",
                             "text": {
                                 "type": "mrkdwn",
-                                "text": format!(":loudspeaker: *New config change request submitted for `{project_name}`*")
+                                "text": format!(":loudspeaker: *Config instance created for `{project_name}`*")
                             }
                         },
                         {
@@ -63,24 +154,17 @@ impl YakManNotificationType {
                                     "text": format!("*Instance:* `{instance}`")
                                 }
                             ]
-                        },
-                        {
-                            "type": "context",
-                            "elements": [
-                                {
-                                    "type": "mrkdwn",
-                                    "text": review_request_message
-                                }
-                            ]
                         }
                     ]
                 })
             }
-            YakManNotificationType::RevisionReviewApproved {
+
+            YakManNotificationType::RevisionRollbackSubmitted {
                 project_name,
                 config_name,
                 instance,
-                revision: _,
+                revision,
+                rollback_to_revision,
             } => {
                 json!({
                     "blocks": [
@@ -88,7 +172,7 @@ impl YakManNotificationType {
                             "type": "section",
                             "text": {
                                 "type": "mrkdwn",
-                                "text": format!(":white_check_mark: *Config change request approved for `{project_name}`*")
+                                "text": format!(":rewind: *Config rollback submitted for `{project_name}`*")
                             }
                         },
                         {
@@ -103,15 +187,23 @@ impl YakManNotificationType {
                                     "text": format!("*Instance:* `{instance}`")
                                 }
                             ]
+                        },
+                        {
+                            "type": "context",
+                            "elements": [
+                                {
+                                    "type": "mrkdwn",
+                                    "text": format!("Revision: {revision} (rollback to {rollback_to_revision})")
+                                }
+                            ]
                         }
                     ]
                 })
             }
-            YakManNotificationType::RevisionReviewApplied {
+            YakManNotificationType::InstanceDeleted {
                 project_name,
                 config_name,
                 instance,
-                revision: _,
             } => {
                 json!({
                     "blocks": [
@@ -119,7 +211,7 @@ impl YakManNotificationType {
                             "type": "section",
                             "text": {
                                 "type": "mrkdwn",
-                                "text": format!(":rocket: *Config change applied for `{project_name}`*")
+                                "text": format!(":wastebasket: *Config instance deleted for `{project_name}`*")
                             }
                         },
                         {
@@ -138,11 +230,9 @@ impl YakManNotificationType {
                     ]
                 })
             }
-            YakManNotificationType::RevisionReviewRejected {
+            YakManNotificationType::ConfigDeleted {
                 project_name,
                 config_name,
-                instance,
-                revision: _,
             } => {
                 json!({
                     "blocks": [
@@ -150,7 +240,7 @@ impl YakManNotificationType {
                             "type": "section",
                             "text": {
                                 "type": "mrkdwn",
-                                "text": format!(":boom: *Config change rejected for `{project_name}`*")
+                                "text": format!(":wastebasket: *Config deleted for `{project_name}`*")
                             }
                         },
                         {
@@ -159,28 +249,49 @@ impl YakManNotificationType {
                                 {
                                     "type": "mrkdwn",
                                     "text": format!("*Config:* {config_name}")
-                                },
-                                {
-                                    "type": "mrkdwn",
-                                    "text": format!("*Instance:* `{instance}`")
                                 }
                             ]
                         }
                     ]
                 })
             }
-            YakManNotificationType::InstanceCreated {
+            YakManNotificationType::ProjectSettingsUpdated {
                 project_name,
-                config_name,
-                instance,
+                changes,
             } => {
+                let changes: Vec<String> = changes.iter().map(|c| format!("• {c}")).collect();
+
                 json!({
                     "blocks": [
                         {
                             "type": "section",
                             "text": {
                                 "type": "mrkdwn",
-                                "text": format!(":loudspeaker: *Config instance created for `{project_name}`*")
+                                "text": format!(":gear: *Project settings updated for `{project_name}`*")
+                            }
+                        },
+                        {
+                            "type": "section",
+                            "text": {
+                                "type": "mrkdwn",
+                                "text": changes.join("\n")
+                            }
+                        }
+                    ]
+                })
+            }
+            YakManNotificationType::ApiKeyCreated {
+                project_name,
+                api_key_id,
+                role,
+            } => {
+                json!({
+                    "blocks": [
+                        {
+                            "type": "section",
+                            "text": {
+                                "type": "mrkdwn",
+                                "text": format!(":key: *API key created for `{project_name}`*")
                             }
                         },
                         {
@@ -188,11 +299,11 @@ impl YakManNotificationType {
                             "fields": [
                                 {
                                     "type": "mrkdwn",
-                                    "text": format!("*Config:* {config_name}")
+                                    "text": format!("*Key:* `{api_key_id}`")
                                 },
                                 {
                                     "type": "mrkdwn",
-                                    "text": format!("*Instance:* `{instance}`")
+                                    "text": format!("*Role:* {role}")
                                 }
                             ]
                         }
@@ -202,3 +313,48 @@ impl YakManNotificationType {
         }
     }
 }
+
+fn config_fields(config_name: &str, instance: &str) -> Value {
+    return json!({
+        "type": "section",
+        "fields": [
+            {
+                "type": "mrkdwn",
+                "text": format!("*Config:* {config_name}")
+            },
+            {
+                "type": "mrkdwn",
+                "text": format!("*Instance:* `{instance}`")
+            }
+        ]
+    });
+}
+
+/// Quotes each line so multi-line comments stay grouped under the label
+fn comment_block(label: &str, comment: &str) -> Value {
+    let quoted: Vec<String> = comment.lines().map(|line| format!("> {line}")).collect();
+    return json!({
+        "type": "section",
+        "text": {
+            "type": "mrkdwn",
+            "text": format!("*{label}:*\n{}", quoted.join("\n"))
+        }
+    });
+}
+
+fn change_details_blocks(change_details: &RevisionChangeDetails) -> Vec<Value> {
+    let mut blocks = vec![];
+    if let Some(description) = &change_details.description {
+        blocks.push(comment_block("Description", description));
+    }
+    if let Some(ticket) = &change_details.ticket {
+        blocks.push(json!({
+            "type": "section",
+            "text": {
+                "type": "mrkdwn",
+                "text": format!("*Ticket:* {ticket}")
+            }
+        }));
+    }
+    return blocks;
+}
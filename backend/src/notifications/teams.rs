@@ -0,0 +1,314 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::{model::RevisionChangeDetails, settings};
+
+use super::{YakManNotificationAdapter, YakManNotificationType};
+
+pub struct MicrosoftTeamsNotificationAdapter {
+    pub http_client: reqwest::Client,
+    pub webhook_url: String,
+}
+
+#[async_trait]
+impl YakManNotificationAdapter for MicrosoftTeamsNotificationAdapter {
+    async fn send_notification(&self, event: YakManNotificationType) -> anyhow::Result<()> {
+        self.http_client
+            .post(&self.webhook_url)
+            .json(&event.to_teams_payload())
+            .send()
+            .await?
+            .error_for_status()?;
+
+        return Ok(());
+    }
+}
+
+impl YakManNotificationType {
+    fn to_teams_payload(&self) -> Value {
+        match self {
+            YakManNotificationType::RevisionReviewSubmitted {
+                project_name,
+                config_name,
+                instance,
+                revision,
+                change_details,
+            } => {
+                let mut actions = vec![];
+                if let Some(host) = settings::yakman_application_host() {
+                    actions.push(json!({
+                        "type": "Action.OpenUrl",
+                        "title": "Review",
+                        "url": format!("{host}/apply-changes/{config_name}/{instance}")
+                    }));
+                }
+
+                let mut extra_body = description_blocks(change_details);
+                extra_body.push(json!({
+                    "type": "TextBlock",
+                    "text": format!("Revision: {revision}"),
+                    "isSubtle": true,
+                    "size": "Small",
+                    "wrap": true
+                }));
+
+                adaptive_card(
+                    format!("📢 New config change request submitted for `{project_name}`"),
+                    revision_facts(config_name, instance, change_details),
+                    extra_body,
+                    actions,
+                )
+            }
+            YakManNotificationType::RevisionReviewApproved {
+                project_name,
+                config_name,
+                instance,
+                revision: _,
+                comment,
+            } => adaptive_card(
+                format!("✅ Config change request approved for `{project_name}`"),
+                config_facts(config_name, instance),
+                comment
+                    .iter()
+                    .map(|comment| comment_block("Comment", comment))
+                    .collect(),
+                vec![],
+            ),
+            YakManNotificationType::RevisionReviewApplied {
+                project_name,
+                config_name,
+                instance,
+                revision: _,
+                change_details,
+            } => adaptive_card(
+                format!("🚀 Config change applied for `{project_name}`"),
+                revision_facts(config_name, instance, change_details),
+                description_blocks(change_details),
+                vec![],
+            ),
+            YakManNotificationType::RevisionReviewRejected {
+                project_name,
+                config_name,
+                instance,
+                revision: _,
+                reason,
+            } => adaptive_card(
+                format!("💥 Config change rejected for `{project_name}`"),
+                config_facts(config_name, instance),
+                vec![comment_block("Reason", reason)],
+                vec![],
+            ),
+            YakManNotificationType::InstanceCreated {
+                project_name,
+                config_name,
+                instance,
+            } => adaptive_card(
+                format!("📢 Config instance created for `{project_name}`"),
+                config_facts(config_name, instance),
+                vec![],
+                vec![],
+            ),
+            YakManNotificationType::RevisionRollbackSubmitted {
+                project_name,
+                config_name,
+                instance,
+                revision,
+                rollback_to_revision,
+            } => adaptive_card(
+                format!("⏪ Config rollback submitted for `{project_name}`"),
+                config_facts(config_name, instance),
+                vec![json!({
+                    "type": "TextBlock",
+                    "text": format!("Revision: {revision} (rollback to {rollback_to_revision})"),
+                    "isSubtle": true,
+                    "size": "Small",
+                    "wrap": true
+                })],
+                vec![],
+            ),
+            YakManNotificationType::InstanceDeleted {
+                project_name,
+                config_name,
+                instance,
+            } => adaptive_card(
+                format!("🗑️ Config instance deleted for `{project_name}`"),
+                config_facts(config_name, instance),
+                vec![],
+                vec![],
+            ),
+            YakManNotificationType::ConfigDeleted {
+                project_name,
+                config_name,
+            } => adaptive_card(
+                format!("🗑️ Config deleted for `{project_name}`"),
+                vec![("Config", config_name.to_string())],
+                vec![],
+                vec![],
+            ),
+            YakManNotificationType::ProjectSettingsUpdated {
+                project_name,
+                changes,
+            } => adaptive_card(
+                format!("⚙️ Project settings updated for `{project_name}`"),
+                vec![],
+                changes
+                    .iter()
+                    .map(|change| {
+                        json!({
+                            "type": "TextBlock",
+                            "text": format!("- {change}"),
+                            "wrap": true
+                        })
+                    })
+                    .collect(),
+                vec![],
+            ),
+            YakManNotificationType::ApiKeyCreated {
+                project_name,
+                api_key_id,
+                role,
+            } => adaptive_card(
+                format!("🔑 API key created for `{project_name}`"),
+                vec![
+                    ("Key", format!("`{api_key_id}`")),
+                    ("Role", role.to_string()),
+                ],
+                vec![],
+                vec![],
+            ),
+        }
+    }
+}
+
+/// Wraps an Adaptive Card in the message envelope expected by Teams incoming webhooks.
+/// Every card has a title and a fact set describing what the event applies to.
+fn adaptive_card(
+    title: String,
+    facts: Vec<(&str, String)>,
+    extra_body: Vec<Value>,
+    actions: Vec<Value>,
+) -> Value {
+    let facts: Vec<Value> = facts
+        .into_iter()
+        .map(|(title, value)| json!({ "title": title, "value": value }))
+        .collect();
+
+    let mut body = vec![
+        json!({
+            "type": "TextBlock",
+            "text": title,
+            "weight": "Bolder",
+            "size": "Medium",
+            "wrap": true
+        }),
+        json!({
+            "type": "FactSet",
+            "facts": facts
+        }),
+    ];
+    body.extend(extra_body);
+
+    json!({
+        "type": "message",
+        "attachments": [
+            {
+                "contentType": "application/vnd.microsoft.card.adaptive",
+                "contentUrl": null,
+                "content": {
+                    "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                    "type": "AdaptiveCard",
+                    "version": "1.4",
+                    "body": body,
+                    "actions": actions
+                }
+            }
+        ]
+    })
+}
+
+fn config_facts(config_name: &str, instance: &str) -> Vec<(&'static str, String)> {
+    return vec![
+        ("Config", config_name.to_string()),
+        ("Instance", format!("`{instance}`")),
+    ];
+}
+
+fn revision_facts<'a>(
+    config_name: &str,
+    instance: &str,
+    change_details: &RevisionChangeDetails,
+) -> Vec<(&'a str, String)> {
+    let mut facts = config_facts(config_name, instance);
+    if let Some(ticket) = &change_details.ticket {
+        facts.push(("Ticket", ticket.to_string()));
+    }
+    return facts;
+}
+
+fn description_blocks(change_details: &RevisionChangeDetails) -> Vec<Value> {
+    return change_details
+        .description
+        .iter()
+        .map(|description| comment_block("Description", description))
+        .collect();
+}
+
+fn comment_block(label: &str, comment: &str) -> Value {
+    return json!({
+        "type": "TextBlock",
+        "text": format!("**{label}:** {comment}"),
+        "wrap": true
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::YakManRole;
+
+    #[test]
+    fn teams_payload_should_be_wrapped_in_an_adaptive_card_message() {
+        let payload = YakManNotificationType::RevisionReviewApproved {
+            project_name: "foo".to_string(),
+            config_name: "bar".to_string(),
+            instance: "i123".to_string(),
+            revision: "r123".to_string(),
+            comment: Some("Looks good".to_string()),
+        }
+        .to_teams_payload();
+
+        assert_eq!("message", payload["type"]);
+
+        let attachment = &payload["attachments"][0];
+        assert_eq!(
+            "application/vnd.microsoft.card.adaptive",
+            attachment["contentType"]
+        );
+
+        let card = &attachment["content"];
+        assert_eq!("AdaptiveCard", card["type"]);
+        assert_eq!(
+            "✅ Config change request approved for `foo`",
+            card["body"][0]["text"]
+        );
+        assert_eq!("bar", card["body"][1]["facts"][0]["value"]);
+        assert_eq!("`i123`", card["body"][1]["facts"][1]["value"]);
+        assert_eq!("**Comment:** Looks good", card["body"][2]["text"]);
+    }
+
+    #[test]
+    fn teams_payload_should_include_api_key_facts() {
+        let payload = YakManNotificationType::ApiKeyCreated {
+            project_name: "foo".to_string(),
+            api_key_id: "apikey-123".to_string(),
+            role: YakManRole::Operator,
+        }
+        .to_teams_payload();
+
+        let card = &payload["attachments"][0]["content"];
+        assert_eq!("🔑 API key created for `foo`", card["body"][0]["text"]);
+        assert_eq!("Key", card["body"][1]["facts"][0]["title"]);
+        assert_eq!("`apikey-123`", card["body"][1]["facts"][0]["value"]);
+        assert_eq!("Operator", card["body"][1]["facts"][1]["value"]);
+    }
+}
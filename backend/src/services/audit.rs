@@ -0,0 +1,155 @@
+use std::future::Future;
+
+use chrono::{DateTime, Duration, NaiveDate};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::model::{
+    ConfigInstanceRevision, YakManApiKey, YakManAuditAction, YakManAuditLogEntry,
+    YakManAuditTargetType,
+};
+
+tokio::task_local! {
+    /// The user or api key id of the request being handled, set by the principal middleware
+    static AUDIT_ACTOR: Option<String>;
+}
+
+/// Runs the future with `actor` recorded as the actor of any audit log entries it writes
+pub async fn with_actor<F: Future>(actor: Option<String>, f: F) -> F::Output {
+    return AUDIT_ACTOR.scope(actor, f).await;
+}
+
+/// The actor of the current request, `None` outside of a request or for unauthenticated requests
+pub fn current_actor() -> Option<String> {
+    return AUDIT_ACTOR.try_with(|actor| actor.clone()).ok().flatten();
+}
+
+/// The storage bucket an entry is written to
+pub fn audit_log_day(timestamp_ms: i64) -> String {
+    let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+    return timestamp.format("%Y-%m-%d").to_string();
+}
+
+/// The days that have to be read to find entries between `from_ms` and `to_ms` (inclusive)
+pub fn audit_log_days(from_ms: i64, to_ms: i64) -> Vec<String> {
+    let (Some(from), Some(to)) = (
+        DateTime::from_timestamp_millis(from_ms),
+        DateTime::from_timestamp_millis(to_ms),
+    ) else {
+        return vec![];
+    };
+    let to: NaiveDate = to.date_naive();
+    let mut day: NaiveDate = from.date_naive();
+    let mut days = vec![];
+    while day <= to {
+        days.push(day.format("%Y-%m-%d").to_string());
+        day += Duration::days(1);
+    }
+    return days;
+}
+
+pub fn to_summary<T: Serialize>(value: &T) -> Option<Value> {
+    return serde_json::to_value(value).ok();
+}
+
+/// Api keys are summarized without their secret hashes
+pub fn api_key_summary(api_key: &YakManApiKey) -> Option<Value> {
+    let mut summary = to_summary(api_key)?;
+    if let Some(summary) = summary.as_object_mut() {
+        summary.remove("hash");
+        summary.remove("previous_hash");
+    }
+    return Some(summary);
+}
+
+pub fn revision_summary(
+    config_id: &str,
+    instance: &str,
+    revision: &ConfigInstanceRevision,
+) -> Option<Value> {
+    return Some(json!({
+        "config_id": config_id,
+        "instance": instance,
+        "revision": revision.revision,
+        "labels": revision.labels,
+        "content_type": revision.content_type,
+        "review_state": revision.review_state,
+        "submitted_by_user_id": revision.submitted_by_user_id,
+        "reviewed_by_user_id": revision.reviewed_by_user_id,
+        "approvals": revision.approvals,
+        "change_details": revision.change_details,
+    }));
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AuditLogFilter {
+    pub from_ms: i64,
+    pub to_ms: i64,
+    pub actor: Option<String>,
+    pub action: Option<YakManAuditAction>,
+    pub target_type: Option<YakManAuditTargetType>,
+    pub target_id: Option<String>,
+    pub limit: usize,
+}
+
+impl AuditLogFilter {
+    pub fn matches(&self, entry: &YakManAuditLogEntry) -> bool {
+        return entry.timestamp_ms >= self.from_ms
+            && entry.timestamp_ms <= self.to_ms
+            && self
+                .actor
+                .as_ref()
+                .is_none_or(|actor| entry.actor.as_ref() == Some(actor))
+            && self.action.is_none_or(|action| entry.action == action)
+            && self
+                .target_type
+                .is_none_or(|target_type| entry.target_type == target_type)
+            && self
+                .target_id
+                .as_ref()
+                .is_none_or(|target_id| &entry.target_id == target_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_list_each_day_in_range() {
+        // 2024-02-28T23:00:00Z to 2024-03-01T01:00:00Z
+        let days = audit_log_days(1709161200000, 1709254800000);
+        assert_eq!(vec!["2024-02-28", "2024-02-29", "2024-03-01"], days);
+        assert_eq!("2024-02-29", audit_log_day(1709200000000));
+        assert!(audit_log_days(1709254800000, 1709161200000).is_empty());
+    }
+
+    #[test]
+    fn api_key_summary_should_not_contain_hashes() {
+        let api_key = YakManApiKey {
+            id: String::from("yak_1"),
+            hash: String::from("secret-hash"),
+            project_id: Some(String::from("p1")),
+            role: None,
+            service_account_id: None,
+            created_at: 0,
+            created_by_user_id: String::from("u1"),
+            expires_at: None,
+            rotated_at: None,
+            previous_hash: Some(String::from("old-hash")),
+            previous_hash_expires_at: None,
+            scope: None,
+        };
+        let summary = api_key_summary(&api_key).unwrap();
+        assert_eq!("yak_1", summary["id"]);
+        assert!(summary.get("hash").is_none());
+        assert!(summary.get("previous_hash").is_none());
+    }
+
+    #[actix_web::test]
+    async fn actor_should_only_be_set_within_scope() {
+        assert_eq!(None, current_actor());
+        let actor = with_actor(Some(String::from("u1")), async { current_actor() }).await;
+        assert_eq!(Some(String::from("u1")), actor);
+    }
+}
@@ -0,0 +1,465 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use tokio::{
+    fs::OpenOptions,
+    io::AsyncWriteExt,
+    net::{TcpStream, UdpSocket},
+    sync::mpsc::{self, error::TrySendError, Receiver, Sender},
+};
+
+use crate::{
+    model::YakManAuditLogEntry,
+    settings::{self, SyslogProtocol},
+};
+
+/// The maximum number of entries sent to a sink at once
+const MAX_BATCH_SIZE: usize = 100;
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+/// Facility 13 (log audit), severity 6 (informational)
+const SYSLOG_PRIORITY: u8 = 13 * 8 + 6;
+/// The largest payload of a UDP datagram over IPv4
+const MAX_SYSLOG_UDP_MESSAGE_SIZE: usize = 65_507;
+
+/// A destination outside of YakMan's storage that audit log entries are copied to, such as a SIEM
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// The maximum number of entries passed to `export` at once.
+    /// If an export fails, every entry in the batch is retried and eventually dropped together.
+    fn max_batch_size(&self) -> usize {
+        return MAX_BATCH_SIZE;
+    }
+
+    async fn export(&self, entries: &[YakManAuditLogEntry]) -> anyhow::Result<()>;
+}
+
+pub struct JsonLinesFileSink {
+    pub path: String,
+}
+
+#[async_trait]
+impl AuditSink for JsonLinesFileSink {
+    fn name(&self) -> &str {
+        return "file";
+    }
+
+    async fn export(&self, entries: &[YakManAuditLogEntry]) -> anyhow::Result<()> {
+        let mut lines = String::new();
+        for entry in entries {
+            lines.push_str(&serde_json::to_string(entry)?);
+            lines.push('\n');
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(lines.as_bytes()).await?;
+        file.flush().await?;
+        return Ok(());
+    }
+}
+
+pub struct SyslogSink {
+    pub address: String,
+    pub protocol: SyslogProtocol,
+}
+
+#[async_trait]
+impl AuditSink for SyslogSink {
+    fn name(&self) -> &str {
+        return "syslog";
+    }
+
+    /// Each UDP message is a separate datagram, so they are exported one at a time
+    /// and a message that fails to send does not take the rest of a batch with it
+    fn max_batch_size(&self) -> usize {
+        return match self.protocol {
+            SyslogProtocol::Udp => 1,
+            SyslogProtocol::Tcp => MAX_BATCH_SIZE,
+        };
+    }
+
+    async fn export(&self, entries: &[YakManAuditLogEntry]) -> anyhow::Result<()> {
+        let mut messages = vec![];
+        for entry in entries {
+            messages.push(to_syslog_message(entry)?);
+        }
+
+        match self.protocol {
+            SyslogProtocol::Udp => {
+                let socket = UdpSocket::bind("0.0.0.0:0").await?;
+                socket.connect(&self.address).await?;
+                for message in messages {
+                    socket
+                        .send(truncate_udp_syslog_message(&message).as_bytes())
+                        .await?;
+                }
+            }
+            SyslogProtocol::Tcp => {
+                // Octet counting framing (RFC 6587) so messages can contain newlines
+                let mut stream = TcpStream::connect(&self.address).await?;
+                for message in messages {
+                    let frame = format!("{} {message}", message.len());
+                    stream.write_all(frame.as_bytes()).await?;
+                }
+                stream.flush().await?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+pub struct HttpSink {
+    pub url: String,
+    pub authorization: Option<String>,
+    pub http_client: reqwest::Client,
+}
+
+#[async_trait]
+impl AuditSink for HttpSink {
+    fn name(&self) -> &str {
+        return "http";
+    }
+
+    async fn export(&self, entries: &[YakManAuditLogEntry]) -> anyhow::Result<()> {
+        let mut request = self.http_client.post(&self.url).json(entries);
+        if let Some(authorization) = &self.authorization {
+            request = request.header(reqwest::header::AUTHORIZATION, authorization);
+        }
+        request.send().await?.error_for_status()?;
+        return Ok(());
+    }
+}
+
+/// RFC 5424 message with the entry as JSON
+fn to_syslog_message(entry: &YakManAuditLogEntry) -> anyhow::Result<String> {
+    let timestamp = DateTime::from_timestamp_millis(entry.timestamp_ms)
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let message = serde_json::to_string(entry)?;
+    return Ok(format!(
+        "<{SYSLOG_PRIORITY}>1 {timestamp} - yakman - audit - {message}"
+    ));
+}
+
+/// Messages that do not fit in a UDP datagram would fail to send, so they are truncated instead
+fn truncate_udp_syslog_message(message: &str) -> &str {
+    if message.len() <= MAX_SYSLOG_UDP_MESSAGE_SIZE {
+        return message;
+    }
+
+    let mut end = MAX_SYSLOG_UDP_MESSAGE_SIZE;
+    while !message.is_char_boundary(end) {
+        end -= 1;
+    }
+    log::warn!(
+        "Syslog audit message is {} bytes, truncating it to {end} bytes",
+        message.len()
+    );
+    return &message[..end];
+}
+
+/// Sends audit log entries to the configured sinks in the background.
+/// Each sink has its own buffer, so a sink that is down does not hold up the others.
+pub struct AuditExporter {
+    senders: Vec<(String, Sender<YakManAuditLogEntry>)>,
+}
+
+impl AuditExporter {
+    pub fn from_settings() -> AuditExporter {
+        let mut sinks: Vec<Arc<dyn AuditSink>> = vec![];
+
+        if let Some(path) = settings::audit_export_file() {
+            sinks.push(Arc::new(JsonLinesFileSink { path: path }));
+        }
+        if let Some(address) = settings::audit_export_syslog_address() {
+            sinks.push(Arc::new(SyslogSink {
+                address: address,
+                protocol: settings::audit_export_syslog_protocol(),
+            }));
+        }
+        if let Some(url) = settings::audit_export_http_url() {
+            sinks.push(Arc::new(HttpSink {
+                url: url,
+                authorization: settings::audit_export_http_authorization(),
+                http_client: reqwest::Client::new(),
+            }));
+        }
+
+        let retry = RetryPolicy {
+            max_attempts: settings::audit_export_max_attempts(),
+            initial_delay: INITIAL_RETRY_DELAY,
+        };
+        return AuditExporter::new(sinks, settings::audit_export_buffer_size(), retry);
+    }
+
+    fn new(sinks: Vec<Arc<dyn AuditSink>>, buffer_size: usize, retry: RetryPolicy) -> Self {
+        let mut senders = vec![];
+        for sink in sinks {
+            let (sender, receiver) = mpsc::channel(buffer_size);
+            let name = sink.name().to_string();
+            tokio::spawn(run_sink(sink, receiver, retry.clone()));
+            log::info!("Registered {name} audit export sink");
+            senders.push((name, sender));
+        }
+        return AuditExporter { senders: senders };
+    }
+
+    /// Queues the entry for each sink without waiting for it to be sent
+    pub fn export(&self, entry: &YakManAuditLogEntry) {
+        for (name, sender) in &self.senders {
+            match sender.try_send(entry.clone()) {
+                Ok(_) => {}
+                Err(TrySendError::Full(_)) => {
+                    log::error!(
+                        "The {name} audit export buffer is full, dropping entry {}",
+                        entry.id
+                    );
+                }
+                Err(TrySendError::Closed(_)) => {
+                    log::error!("The {name} audit export sink has stopped");
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct RetryPolicy {
+    max_attempts: u32,
+    initial_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Exponential backoff, capped at 5 minutes
+    fn delay(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(20);
+        return (self.initial_delay * 2_u32.pow(exponent)).min(MAX_RETRY_DELAY);
+    }
+}
+
+/// Sends entries to the sink in batches as they arrive.
+/// A failed batch is retried, while newer entries wait in the channel.
+async fn run_sink(
+    sink: Arc<dyn AuditSink>,
+    mut receiver: Receiver<YakManAuditLogEntry>,
+    retry: RetryPolicy,
+) {
+    let max_batch_size = sink.max_batch_size();
+    let mut batch: Vec<YakManAuditLogEntry> = vec![];
+    while let Some(entry) = receiver.recv().await {
+        batch.push(entry);
+        while batch.len() < max_batch_size {
+            match receiver.try_recv() {
+                Ok(entry) => batch.push(entry),
+                Err(_) => break,
+            }
+        }
+
+        let mut attempts = 0;
+        loop {
+            let Err(err) = sink.export(&batch).await else {
+                break;
+            };
+            attempts += 1;
+            if attempts >= retry.max_attempts {
+                log::error!(
+                    "Failed to export {} audit log entries to {} after {attempts} attempts, dropping them. {err:?}",
+                    batch.len(),
+                    sink.name()
+                );
+                break;
+            }
+            log::warn!(
+                "Failed to export audit log entries to {} (attempt {attempts}), {err:?}",
+                sink.name()
+            );
+            tokio::time::sleep(retry.delay(attempts)).await;
+        }
+        batch.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{YakManAuditAction, YakManAuditTargetType};
+    use std::sync::Mutex;
+
+    fn entry(id: &str) -> YakManAuditLogEntry {
+        return YakManAuditLogEntry {
+            id: id.to_string(),
+            timestamp_ms: 1709200000000,
+            actor: Some("u1".to_string()),
+            action: YakManAuditAction::Create,
+            target_type: YakManAuditTargetType::Project,
+            target_id: "p1".to_string(),
+            before: None,
+            after: None,
+        };
+    }
+
+    /// Fails the first `failures` exports
+    struct FakeSink {
+        failures: Mutex<u32>,
+        exported: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl AuditSink for FakeSink {
+        fn name(&self) -> &str {
+            return "fake";
+        }
+
+        async fn export(&self, entries: &[YakManAuditLogEntry]) -> anyhow::Result<()> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                anyhow::bail!("sink unavailable");
+            }
+            let mut exported = self.exported.lock().unwrap();
+            exported.extend(entries.iter().map(|e| e.id.clone()));
+            return Ok(());
+        }
+    }
+
+    #[test]
+    fn syslog_message_should_follow_rfc_5424() {
+        let message = to_syslog_message(&entry("a1")).unwrap();
+        assert!(message.starts_with("<110>1 2024-02-29T09:46:40.000Z - yakman - audit - {"));
+        assert!(message.contains(r#""id":"a1""#));
+    }
+
+    #[test]
+    fn retry_delay_should_back_off_exponentially() {
+        let retry = RetryPolicy {
+            max_attempts: 8,
+            initial_delay: INITIAL_RETRY_DELAY,
+        };
+        assert_eq!(Duration::from_secs(1), retry.delay(1));
+        assert_eq!(Duration::from_secs(4), retry.delay(3));
+        assert_eq!(MAX_RETRY_DELAY, retry.delay(u32::MAX));
+    }
+
+    #[actix_web::test]
+    async fn failed_exports_should_be_retried() -> anyhow::Result<()> {
+        let sink = Arc::new(FakeSink {
+            failures: Mutex::new(2),
+            exported: Mutex::new(vec![]),
+        });
+        let retry = RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(1),
+        };
+        let exporter = AuditExporter::new(vec![sink.clone()], 10, retry);
+
+        exporter.export(&entry("a1"));
+        exporter.export(&entry("a2"));
+
+        for _ in 0..100 {
+            if sink.exported.lock().unwrap().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(vec!["a1", "a2"], *sink.exported.lock().unwrap());
+
+        Ok(())
+    }
+
+    /// Exports one entry at a time and always fails to export `rejected_id`
+    struct RejectingSink {
+        rejected_id: String,
+        exported: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl AuditSink for RejectingSink {
+        fn name(&self) -> &str {
+            return "rejecting";
+        }
+
+        fn max_batch_size(&self) -> usize {
+            return 1;
+        }
+
+        async fn export(&self, entries: &[YakManAuditLogEntry]) -> anyhow::Result<()> {
+            if entries.iter().any(|e| e.id == self.rejected_id) {
+                anyhow::bail!("entry rejected");
+            }
+            let mut exported = self.exported.lock().unwrap();
+            exported.extend(entries.iter().map(|e| e.id.clone()));
+            return Ok(());
+        }
+    }
+
+    #[actix_web::test]
+    async fn failed_entry_should_not_drop_the_rest_of_the_batch() -> anyhow::Result<()> {
+        let sink = Arc::new(RejectingSink {
+            rejected_id: "a2".to_string(),
+            exported: Mutex::new(vec![]),
+        });
+        let retry = RetryPolicy {
+            max_attempts: 2,
+            initial_delay: Duration::from_millis(1),
+        };
+        let exporter = AuditExporter::new(vec![sink.clone()], 10, retry);
+
+        exporter.export(&entry("a1"));
+        exporter.export(&entry("a2"));
+        exporter.export(&entry("a3"));
+
+        for _ in 0..100 {
+            if sink.exported.lock().unwrap().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(vec!["a1", "a3"], *sink.exported.lock().unwrap());
+
+        Ok(())
+    }
+
+    #[test]
+    fn oversized_udp_syslog_message_should_be_truncated() {
+        let message = "é".repeat(MAX_SYSLOG_UDP_MESSAGE_SIZE);
+
+        let truncated = truncate_udp_syslog_message(&message);
+
+        assert!(truncated.len() <= MAX_SYSLOG_UDP_MESSAGE_SIZE);
+        assert!(message.starts_with(truncated));
+        assert_eq!("short", truncate_udp_syslog_message("short"));
+    }
+
+    #[actix_web::test]
+    async fn file_sink_should_append_json_lines() -> anyhow::Result<()> {
+        let path =
+            std::env::temp_dir().join(format!("yakman-audit-{}.jsonl", uuid::Uuid::new_v4()));
+        let sink = JsonLinesFileSink {
+            path: path.to_string_lossy().to_string(),
+        };
+
+        sink.export(&[entry("a1"), entry("a2")]).await?;
+        sink.export(&[entry("a3")]).await?;
+
+        let content = std::fs::read_to_string(&path)?;
+        std::fs::remove_file(&path)?;
+        let ids: Vec<String> = content
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<YakManAuditLogEntry>(line)
+                    .unwrap()
+                    .id
+            })
+            .collect();
+        assert_eq!(vec!["a1", "a2", "a3"], ids);
+
+        Ok(())
+    }
+}
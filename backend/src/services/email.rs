@@ -0,0 +1,77 @@
+use anyhow::{bail, Context};
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use log::error;
+
+use crate::settings::{self, SmtpTlsMode};
+
+/// Sends a plain text email to each recipient using the globally configured SMTP server.
+/// A separate message is sent per recipient so addresses are not disclosed to each other.
+pub async fn send_email(recipients: &[String], subject: &str, body: &str) -> anyhow::Result<()> {
+    let Some(from) = settings::smtp_from_address() else {
+        bail!("SMTP from address is not configured")
+    };
+    let from: Mailbox = from.parse().context("Invalid SMTP from address")?;
+
+    let mailer = create_smtp_transport()?;
+
+    let mut failed_recipients = vec![];
+    for recipient in recipients {
+        let result = send_message(&mailer, &from, recipient, subject, body).await;
+        if let Err(err) = result {
+            error!("Failed to send email to {recipient}, {err:?}");
+            failed_recipients.push(recipient.to_string());
+        }
+    }
+
+    if !failed_recipients.is_empty() {
+        bail!("Failed to send email to {}", failed_recipients.join(", "))
+    }
+
+    return Ok(());
+}
+
+async fn send_message(
+    mailer: &AsyncSmtpTransport<Tokio1Executor>,
+    from: &Mailbox,
+    recipient: &str,
+    subject: &str,
+    body: &str,
+) -> anyhow::Result<()> {
+    let message = Message::builder()
+        .from(from.clone())
+        .to(recipient.parse()?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body.to_string())?;
+
+    mailer.send(message).await?;
+
+    return Ok(());
+}
+
+fn create_smtp_transport() -> anyhow::Result<AsyncSmtpTransport<Tokio1Executor>> {
+    let Some(host) = settings::smtp_host() else {
+        bail!("SMTP host is not configured")
+    };
+
+    let mut builder = match settings::smtp_tls_mode() {
+        SmtpTlsMode::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+        SmtpTlsMode::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)?,
+        SmtpTlsMode::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&host)?,
+    };
+
+    if let Some(port) = settings::smtp_port() {
+        builder = builder.port(port);
+    }
+
+    if let (Some(username), Some(password)) = (settings::smtp_username(), settings::smtp_password())
+    {
+        builder = builder.credentials(Credentials::new(username, password));
+    }
+
+    return Ok(builder.build());
+}
@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
 use uuid::Uuid;
 
 pub fn generate_project_id() -> String {
@@ -24,10 +26,32 @@ pub fn generate_team_id() -> String {
     return format!("t{}", short_sha(&Uuid::new_v4().to_string()));
 }
 
+pub fn generate_service_account_id() -> String {
+    return format!("s{}", short_sha(&Uuid::new_v4().to_string()));
+}
+
 pub fn generate_label_id() -> String {
     return format!("l{}", short_sha(&Uuid::new_v4().to_string()));
 }
 
+pub fn generate_notification_delivery_id() -> String {
+    return format!("n{}", short_sha(&Uuid::new_v4().to_string()));
+}
+
+/// Sorts in the order the entries were created by this replica, even within the same millisecond
+pub fn generate_audit_log_entry_id(timestamp_ms: i64) -> String {
+    static SEQUENCE: AtomicU32 = AtomicU32::new(0);
+    let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed) % 10_000;
+    return format!(
+        "a{timestamp_ms:013}{sequence:04}{}",
+        short_sha(&Uuid::new_v4().to_string())
+    );
+}
+
+pub fn generate_revision_comment_id() -> String {
+    return format!("m{}", short_sha(&Uuid::new_v4().to_string()));
+}
+
 /// Returns a 12 character string representation of a SHA256
 pub fn short_sha(input: &str) -> String {
     let sha: String = sha256::digest(input);
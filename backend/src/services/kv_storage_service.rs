@@ -1,33 +1,55 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
 use super::{
+    audit::{self, api_key_summary, revision_summary, to_summary, AuditLogFilter},
+    audit_export::AuditExporter,
     id::{generate_config_id, generate_project_id, short_sha},
-    password::{hash_password, validate_password},
+    password::{verify_password, PasswordPolicy},
     StorageService,
 };
 use crate::{
-    adapters::{errors::GenericStorageError, KVStorageAdapter},
+    adapters::{errors::GenericStorageError, KVStorageAdapter, MAX_CONCURRENT_OBJECT_READS},
     api::{
         projects::ProjectNotificationSettings,
+        service_accounts::ServiceAccountPayload,
         teams::{CreateTeamPayload, UpdateTeamPayload},
+        users::UpdateUserRolesPayload,
     },
     error::{
-        ApplyRevisionError, ApproveRevisionError, CreateConfigError, CreateConfigInstanceError,
-        CreateLabelError, CreatePasswordResetLinkError, CreateProjectError, CreateTeamError,
-        DeleteConfigError, DeleteConfigInstanceError, DeleteLabelError, DeleteProjectError,
-        DeleteTeamError, ResetPasswordError, RollbackRevisionError, SaveConfigInstanceError,
-        UpdateLabelError, UpdateProjectError, UpdateTeamError,
+        AddRevisionCommentError, ApplyRevisionError, ApproveRevisionError, ChangePasswordError,
+        CreateConfigError, CreateConfigInstanceError, CreateLabelError,
+        CreatePasswordResetLinkError, CreateProjectError, CreateServiceAccountError,
+        CreateTeamError, DeleteConfigError, DeleteConfigInstanceError, DeleteLabelError,
+        DeleteProjectError, DeleteServiceAccountError, DeleteTeamError, DeleteUserError,
+        ResetPasswordError, RollbackRevisionError, SaveConfigInstanceError, ScheduleApplyError,
+        UpdateLabelError, UpdateProjectError, UpdateServiceAccountError, UpdateTeamError,
+        UpdateUserError,
     },
+    middleware::roles::YakManRoleBinding,
     model::{
         request::CreateYakManUserPayload, ConfigDetails, ConfigInstance, ConfigInstanceEvent,
-        ConfigInstanceEventData, ConfigInstanceRevision, LabelType, RevisionReviewState,
-        YakManApiKey, YakManConfig, YakManLabel, YakManPassword, YakManPasswordResetLink,
-        YakManProject, YakManProjectDetails, YakManPublicPasswordResetLink, YakManRole, YakManTeam,
-        YakManTeamDetails, YakManUser, YakManUserDetails,
+        ConfigInstanceEventData, ConfigInstanceRevision, LabelType, NotificationDeliveryStatus,
+        NotificationSetting, NotificationSettingEvents, RevisionApproval, RevisionChangeDetails,
+        RevisionComment, RevisionCommentKind, RevisionReviewState, YakManApiKey, YakManApiKeyUsage,
+        YakManApprovalPolicy, YakManAuditAction, YakManAuditLogEntry, YakManAuditTargetType,
+        YakManConfig, YakManFreezeWindow, YakManLabel, YakManLoginAttempts,
+        YakManNotificationDelivery, YakManPassword, YakManPasswordResetLink, YakManProject,
+        YakManProjectDetails, YakManProjectRole, YakManPublicPasswordResetLink,
+        YakManRevokedRefreshToken, YakManRole, YakManScheduledApply, YakManServiceAccount,
+        YakManTeam, YakManTeamDetails, YakManTotp, YakManUser, YakManUserDetails,
     },
-    notifications::{YakManNotificationAdapter, YakManNotificationType},
-    services::id::{
-        generate_instance_id, generate_revision_id, generate_team_id, generate_user_id,
+    notifications::YakManNotificationType,
+    services::{
+        id::{
+            generate_audit_log_entry_id, generate_instance_id, generate_notification_delivery_id,
+            generate_revision_comment_id, generate_revision_id, generate_service_account_id,
+            generate_team_id, generate_user_id,
+        },
+        notification_delivery,
     },
     settings,
 };
@@ -37,17 +59,28 @@ use argon2::{
     Argon2,
 };
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt, TryStreamExt};
 use log::info;
 use moka::sync::{Cache, CacheBuilder};
+use serde_json::{json, Value};
 use uuid::Uuid;
 
+/// How often the `last_used_at` of an api key is written to storage
+const API_KEY_LAST_USED_UPDATE_INTERVAL_MS: i64 = 5 * 60 * 1000;
+/// Audit log writes are retried before the entry is given up on
+const AUDIT_LOG_WRITE_ATTEMPTS: u32 = 3;
+
 pub struct KVStorageService {
     pub adapter: Arc<dyn KVStorageAdapter>,
     /// The cache key is the ID as a string
     pub api_key_id_cache: Cache<String, YakManApiKey>,
     /// The cache key is the token hash as a string
     pub api_key_hash_cache: Cache<String, YakManApiKey>,
+    /// The last recorded usage of each api key, keyed by ID
+    api_key_last_used_cache: Cache<String, i64>,
+    pub password_policy: PasswordPolicy,
+    audit_exporter: AuditExporter,
 }
 
 #[async_trait]
@@ -63,6 +96,22 @@ impl StorageService for KVStorageService {
         return self.adapter.get_project_details(project_id).await;
     }
 
+    async fn get_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError> {
+        let mut deliveries: Vec<YakManNotificationDelivery> = self
+            .adapter
+            .get_pending_notification_deliveries()
+            .await?
+            .into_iter()
+            .filter(|d| d.project_id == project_id)
+            .collect();
+        deliveries.extend(self.adapter.get_notification_deliveries(project_id).await?);
+        notification_delivery::sort_deliveries(&mut deliveries);
+        return Ok(deliveries);
+    }
+
     async fn get_config(
         &self,
         config_id: &str,
@@ -74,7 +123,7 @@ impl StorageService for KVStorageService {
     async fn create_project(
         &self,
         project_name: &str,
-        notification_settings: Option<ProjectNotificationSettings>,
+        notification_settings: Vec<ProjectNotificationSettings>,
     ) -> Result<String, CreateProjectError> {
         let mut projects = self.adapter.get_projects().await?;
 
@@ -89,12 +138,17 @@ impl StorageService for KVStorageService {
 
         let project_id = generate_project_id();
 
-        let notification_settings = notification_settings.map(|settings| settings.into());
+        let notification_settings = notification_settings
+            .into_iter()
+            .map(|settings| settings.into())
+            .collect();
 
         let project_details: YakManProjectDetails = YakManProjectDetails {
             name: String::from(project_name),
             id: project_id.to_string(),
             notification_settings,
+            approval_policy: YakManApprovalPolicy::default(),
+            freeze_windows: vec![],
         };
 
         self.adapter
@@ -108,6 +162,15 @@ impl StorageService for KVStorageService {
 
         self.adapter.save_projects(&projects).await?;
 
+        self.record_audit(
+            YakManAuditAction::Create,
+            YakManAuditTargetType::Project,
+            &project_id,
+            None,
+            to_summary(&project_details),
+        )
+        .await;
+
         return Ok(project_id.to_string());
     }
 
@@ -115,7 +178,9 @@ impl StorageService for KVStorageService {
         &self,
         project_id: &str,
         project_name: &str,
-        notification_settings: Option<ProjectNotificationSettings>,
+        notification_settings: Vec<ProjectNotificationSettings>,
+        approval_policy: YakManApprovalPolicy,
+        freeze_windows: Vec<YakManFreezeWindow>,
     ) -> Result<(), UpdateProjectError> {
         let mut projects = self.adapter.get_projects().await?;
 
@@ -132,26 +197,69 @@ impl StorageService for KVStorageService {
         let Some(mut project_details) = self.adapter.get_project_details(project_id).await? else {
             return Err(UpdateProjectError::ProjectNotFound);
         };
+        let before = to_summary(&project_details);
         let Some(project) = projects.iter_mut().find(|p| p.id == project_id) else {
             return Err(UpdateProjectError::ProjectNotFound);
         };
 
         project.name = project_name.to_string();
 
-        let notification_settings = notification_settings.map(|settings| settings.into());
+        let notification_settings: Vec<crate::model::ProjectNotificationSettings> =
+            notification_settings
+                .into_iter()
+                .map(|settings| settings.into())
+                .collect();
+
+        let mut changes = vec![];
+        if project_details.name != project_name {
+            changes.push(format!(
+                "Renamed from {} to {project_name}",
+                project_details.name
+            ));
+        }
+        if project_details.notification_settings != notification_settings {
+            changes.push(String::from("Notification channels updated"));
+        }
+        if project_details.approval_policy != approval_policy {
+            changes.push(String::from("Approval policy updated"));
+        }
+        if project_details.freeze_windows != freeze_windows {
+            changes.push(String::from("Freeze windows updated"));
+        }
+
         project_details.name = project_name.to_string();
         project_details.notification_settings = notification_settings;
+        project_details.approval_policy = approval_policy;
+        project_details.freeze_windows = freeze_windows;
 
         self.adapter
             .save_project_details(project_id, &project_details)
             .await?;
         self.adapter.save_projects(&projects).await?;
 
+        self.record_audit(
+            YakManAuditAction::Update,
+            YakManAuditTargetType::Project,
+            project_id,
+            before,
+            to_summary(&project_details),
+        )
+        .await;
+
+        if settings::is_notifications_enabled() && !changes.is_empty() {
+            if let Err(err) = self
+                .send_project_settings_updated_notification(&project_details, changes)
+                .await
+            {
+                log::error!("Failed to queue notification, {err:?}");
+            }
+        }
+
         Ok(())
     }
 
     async fn delete_project(&self, project_id: &str) -> Result<(), DeleteProjectError> {
-        let Some(_) = self.adapter.get_project_details(project_id).await? else {
+        let Some(project_details) = self.adapter.get_project_details(project_id).await? else {
             return Err(DeleteProjectError::ProjectNotFound);
         };
         let mut projects = self.adapter.get_projects().await?;
@@ -173,7 +281,9 @@ impl StorageService for KVStorageService {
         for config in &project_configs {
             if let Ok(Some(config_details)) = self.adapter.get_config_details(&config.id).await {
                 for instance in config_details.instances {
-                    let res = self.delete_instance(&config.id, &instance.instance).await;
+                    let res = self
+                        .delete_instance_data(&config.id, &instance.instance)
+                        .await;
                     if res.is_err() {
                         log::error!("Failed to delete config {}", config.id);
                     }
@@ -200,6 +310,28 @@ impl StorageService for KVStorageService {
         self.adapter.save_projects(&projects).await?;
         self.adapter.delete_project_details(project_id).await?;
 
+        let pending_deliveries = self.adapter.get_pending_notification_deliveries().await?;
+        for delivery in pending_deliveries
+            .iter()
+            .filter(|d| d.project_id == project_id)
+        {
+            self.adapter
+                .delete_pending_notification_delivery(&delivery.id)
+                .await?;
+        }
+        self.adapter
+            .delete_notification_deliveries(project_id)
+            .await?;
+
+        self.record_audit(
+            YakManAuditAction::Delete,
+            YakManAuditTargetType::Project,
+            project_id,
+            to_summary(&project_details),
+            None,
+        )
+        .await;
+
         Ok(())
     }
 
@@ -237,10 +369,21 @@ impl StorageService for KVStorageService {
             }
         }
 
+        let label_id = label.id.clone();
+        let after = to_summary(&label);
         labels.push(label);
 
         self.adapter.save_labels(&labels).await?;
 
+        self.record_audit(
+            YakManAuditAction::Create,
+            YakManAuditTargetType::Label,
+            &label_id,
+            None,
+            after,
+        )
+        .await;
+
         return Ok(());
     }
 
@@ -270,12 +413,24 @@ impl StorageService for KVStorageService {
             }
         }
 
-        if let Some(pos) = labels.iter().position(|l| l.id == label_id) {
-            labels[pos] = label;
-        }
+        let Some(pos) = labels.iter().position(|l| l.id == label_id) else {
+            return Ok(());
+        };
+        let before = to_summary(&labels[pos]);
+        let after = to_summary(&label);
+        labels[pos] = label;
 
         self.adapter.save_labels(&labels).await?;
 
+        self.record_audit(
+            YakManAuditAction::Update,
+            YakManAuditTargetType::Label,
+            label_id,
+            before,
+            after,
+        )
+        .await;
+
         return Ok(());
     }
 
@@ -286,12 +441,21 @@ impl StorageService for KVStorageService {
             return Err(DeleteLabelError::LabelNotFound);
         };
 
-        labels.remove(index);
+        let label = labels.remove(index);
 
         // TODO: Also remove the label type from storage
 
         self.adapter.save_labels(&labels).await?;
 
+        self.record_audit(
+            YakManAuditAction::Delete,
+            YakManAuditTargetType::Label,
+            label_id,
+            to_summary(&label),
+            None,
+        )
+        .await;
+
         Ok(())
     }
 
@@ -302,6 +466,7 @@ impl StorageService for KVStorageService {
         data: &str,
         content_type: Option<String>,
         creator_user_id: &str,
+        break_glass: bool,
     ) -> Result<String, CreateConfigInstanceError> {
         if let Some(mut config_details) = self.adapter.get_config_details(config_id).await? {
             let instances = &mut config_details.instances;
@@ -314,6 +479,13 @@ impl StorageService for KVStorageService {
                 return Err(CreateConfigInstanceError::InvalidLabel);
             }
 
+            if let Some(reason) = self
+                .check_change_freeze(config_id, &instance, YakManAuditAction::Create, break_glass)
+                .await?
+            {
+                return Err(CreateConfigInstanceError::ChangeFreeze { reason: reason });
+            }
+
             // Create new file with data
             self.adapter
                 .save_instance_data(config_id, &data_key, data)
@@ -331,8 +503,15 @@ impl StorageService for KVStorageService {
                 submitted_by_user_id: creator_user_id.to_string(),
                 submit_timestamp_ms: now,
                 content_type: content_type.unwrap_or(String::from("text/plain")),
+                approvals: vec![RevisionApproval {
+                    user_id: creator_user_id.to_string(),
+                    timestamp_ms: now,
+                }],
+                comments: vec![],
+                change_details: RevisionChangeDetails::default(),
             };
             self.adapter.save_revision(config_id, &revision).await?;
+            let after = revision_summary(config_id, &instance, &revision);
 
             // Add new instance to instances and update the config details
             instances.push(ConfigInstance {
@@ -355,12 +534,21 @@ impl StorageService for KVStorageService {
                 .await?;
             log::info!("Update config details for config: {config_id}");
 
+            self.record_audit(
+                YakManAuditAction::Create,
+                YakManAuditTargetType::ConfigInstance,
+                &instance,
+                None,
+                after,
+            )
+            .await;
+
             if settings::is_notifications_enabled() {
                 if let Err(err) = self
                     .send_instance_created_notification(config_id, &instance)
                     .await
                 {
-                    log::error!("Failed to send notification, {err:?}");
+                    log::error!("Failed to queue notification, {err:?}");
                 }
             }
 
@@ -393,6 +581,7 @@ impl StorageService for KVStorageService {
             log::info!("Config '{config_name}' already exists, unhiding it");
 
             let config_id = config.id.clone();
+            let project_id = config.project_id.clone();
 
             // Config already exists, just unhide it
             config.hidden = false;
@@ -400,6 +589,16 @@ impl StorageService for KVStorageService {
                 .save_configs(&configs)
                 .await
                 .map_err(|_| CreateConfigError::storage_error("Failed to update configs file"))?;
+
+            self.record_audit(
+                YakManAuditAction::Create,
+                YakManAuditTargetType::Config,
+                &config_id,
+                None,
+                Some(json!({ "name": config_name, "project_id": project_id })),
+            )
+            .await;
+
             return Ok(config_id);
         }
 
@@ -446,6 +645,15 @@ impl StorageService for KVStorageService {
             .await
             .map_err(|_| CreateConfigError::storage_error("Failed to update configs file"))?;
 
+        self.record_audit(
+            YakManAuditAction::Create,
+            YakManAuditTargetType::Config,
+            &config_id,
+            None,
+            Some(json!({ "name": config_name, "project_id": project_id })),
+        )
+        .await;
+
         Ok(config_id)
     }
 
@@ -456,8 +664,25 @@ impl StorageService for KVStorageService {
             .iter_mut()
             .find(|config| config.id == config_id && !config.hidden)
         {
+            let before = to_summary(config);
             config.hidden = true;
             self.adapter.save_configs(&configs).await?;
+
+            self.record_audit(
+                YakManAuditAction::Delete,
+                YakManAuditTargetType::Config,
+                config_id,
+                before,
+                None,
+            )
+            .await;
+
+            if settings::is_notifications_enabled() {
+                if let Err(err) = self.send_config_deleted_notification(config_id).await {
+                    log::error!("Failed to queue notification, {err:?}");
+                }
+            }
+
             return Ok(());
         }
 
@@ -519,6 +744,7 @@ impl StorageService for KVStorageService {
         mut labels: Vec<YakManLabel>,
         data: &str,
         content_type: Option<String>,
+        change_details: RevisionChangeDetails,
         submitted_by_user_id: &str,
     ) -> Result<String, SaveConfigInstanceError> {
         let mut config_details = self
@@ -540,6 +766,11 @@ impl StorageService for KVStorageService {
             return Err(SaveConfigInstanceError::InvalidLabel);
         }
 
+        let policy = self.get_approval_policy(config_id).await?;
+        if policy.require_change_description && change_details.description.is_none() {
+            return Err(SaveConfigInstanceError::MissingChangeDescription);
+        }
+
         let revision_key = generate_revision_id();
         let data_key = Uuid::new_v4().to_string();
 
@@ -561,6 +792,9 @@ impl StorageService for KVStorageService {
             submitted_by_user_id: submitted_by_user_id.to_string(),
             submit_timestamp_ms: now,
             content_type: content_type.unwrap_or(String::from("text/plain")),
+            approvals: vec![],
+            comments: vec![],
+            change_details: change_details,
         };
         self.adapter.save_revision(config_id, &revision).await?;
 
@@ -582,12 +816,24 @@ impl StorageService for KVStorageService {
 
         log::info!("Updated config details for config: {config_id}");
 
+        // A schedule for the previous pending revision no longer applies
+        self.remove_scheduled_apply(config_id, instance_id).await?;
+
+        self.record_audit(
+            YakManAuditAction::Submit,
+            YakManAuditTargetType::Revision,
+            &revision.revision,
+            None,
+            revision_summary(config_id, instance_id, &revision),
+        )
+        .await;
+
         if settings::is_notifications_enabled() {
             if let Err(err) = self
-                .send_submitted_notification(config_id, instance_id, &revision.revision)
+                .send_submitted_notification(config_id, instance_id, &revision)
                 .await
             {
-                log::error!("Failed to send notification, {err:?}");
+                log::error!("Failed to queue notification, {err:?}");
             }
         }
 
@@ -645,7 +891,8 @@ impl StorageService for KVStorageService {
         instance: &str,
         revision: &str,
         approved_user_id: &str,
-    ) -> Result<(), ApproveRevisionError> {
+        comment: Option<String>,
+    ) -> Result<bool, ApproveRevisionError> {
         let Some(mut config_details) = self.adapter.get_config_details(config_id).await? else {
             return Err(ApproveRevisionError::InvalidConfig);
         };
@@ -670,9 +917,46 @@ impl StorageService for KVStorageService {
             Some(Some(revision_data)) => revision_data,
             None | Some(None) => return Err(ApproveRevisionError::InvalidRevision),
         };
+        // Approvals count per person, so another session or api key of the same user or service account is not a new approval
+        for approval in &revision_data.approvals {
+            if self
+                .is_same_principal(&approval.user_id, approved_user_id)
+                .await?
+            {
+                return Err(ApproveRevisionError::AlreadyApproved);
+            }
+        }
+        let policy = self.get_approval_policy(config_id).await?;
+        if policy.forbid_self_approval
+            && self
+                .is_same_principal(&revision_data.submitted_by_user_id, approved_user_id)
+                .await?
+        {
+            return Err(ApproveRevisionError::SelfApproval);
+        }
+        let before = revision_summary(config_id, instance_id, &revision_data);
+        let required_approvals = policy.required_approvals_for(&revision_data.labels);
 
         let now = Utc::now().timestamp_millis();
-        revision_data.review_state = RevisionReviewState::Approved;
+        revision_data.approvals.push(RevisionApproval {
+            user_id: approved_user_id.to_string(),
+            timestamp_ms: now,
+        });
+        if let Some(comment) = &comment {
+            revision_data.comments.push(RevisionComment {
+                id: generate_revision_comment_id(),
+                user_id: approved_user_id.to_string(),
+                timestamp_ms: now,
+                message: comment.to_string(),
+                kind: RevisionCommentKind::Approval,
+            });
+        }
+        let is_fully_approved = revision_data.approval_count() >= required_approvals;
+        revision_data.review_state = if is_fully_approved {
+            RevisionReviewState::Approved
+        } else {
+            RevisionReviewState::Pending
+        };
         revision_data.reviewed_by_user_id = Some(approved_user_id.to_string());
         revision_data.review_timestamp_ms = Some(now);
         self.adapter
@@ -694,16 +978,30 @@ impl StorageService for KVStorageService {
             .save_config_details(config_id, &config_details)
             .await?;
 
-        if settings::is_notifications_enabled() {
+        self.record_audit(
+            YakManAuditAction::Approve,
+            YakManAuditTargetType::Revision,
+            revision,
+            before,
+            revision_summary(config_id, instance_id, &revision_data),
+        )
+        .await;
+
+        if settings::is_notifications_enabled() && is_fully_approved {
             if let Err(err) = self
-                .send_approved_notification(config_id, instance_id, &revision_data.revision)
+                .send_approved_notification(
+                    config_id,
+                    instance_id,
+                    &revision_data.revision,
+                    comment,
+                )
                 .await
             {
-                log::error!("Failed to send notification, {err:?}");
+                log::error!("Failed to queue notification, {err:?}");
             }
         }
 
-        return Ok(());
+        return Ok(is_fully_approved);
     }
 
     async fn apply_instance_revision(
@@ -712,6 +1010,7 @@ impl StorageService for KVStorageService {
         instance: &str,
         revision: &str,
         applied_by_user_id: &str,
+        break_glass: bool,
     ) -> Result<(), ApplyRevisionError> {
         let Some(mut config_details) = self.adapter.get_config_details(config_id).await? else {
             return Err(ApplyRevisionError::InvalidConfig);
@@ -738,10 +1037,32 @@ impl StorageService for KVStorageService {
             None | Some(None) => return Err(ApplyRevisionError::InvalidRevision),
         };
 
-        if revision_data.review_state != RevisionReviewState::Approved {
-            return Err(ApplyRevisionError::NotApproved);
+        self.verify_revision_approvals(config_id, &revision_data)
+            .await?;
+
+        if let Some(reason) = self
+            .check_change_freeze(
+                config_id,
+                instance_id,
+                YakManAuditAction::Apply,
+                break_glass,
+            )
+            .await?
+        {
+            return Err(ApplyRevisionError::ChangeFreeze { reason: reason });
         }
 
+        let before = Some(json!({
+            "config_id": config_id,
+            "current_revision": instance.current_revision,
+            "labels": instance.labels,
+        }));
+        let after = Some(json!({
+            "config_id": config_id,
+            "current_revision": revision,
+            "labels": revision_data.labels,
+        }));
+
         let now = Utc::now().timestamp_millis();
         instance.changelog.push(ConfigInstanceEvent {
             event: ConfigInstanceEventData::Updated {
@@ -754,6 +1075,7 @@ impl StorageService for KVStorageService {
         instance.current_revision = String::from(revision);
         instance.pending_revision = None;
         instance.labels = revision_data.labels;
+        let change_details = revision_data.change_details;
 
         if !instance.revisions.contains(&String::from(revision)) {
             instance.revisions.push(String::from(revision));
@@ -762,13 +1084,23 @@ impl StorageService for KVStorageService {
         self.adapter
             .save_config_details(config_id, &config_details)
             .await?;
+        self.remove_scheduled_apply(config_id, instance_id).await?;
+
+        self.record_audit(
+            YakManAuditAction::Apply,
+            YakManAuditTargetType::ConfigInstance,
+            instance_id,
+            before,
+            after,
+        )
+        .await;
 
         if settings::is_notifications_enabled() {
             if let Err(err) = self
-                .send_applied_notification(config_id, instance_id, revision)
+                .send_applied_notification(config_id, instance_id, revision, change_details)
                 .await
             {
-                log::error!("Failed to send notification, {err:?}");
+                log::error!("Failed to queue notification, {err:?}");
             }
         }
 
@@ -781,6 +1113,7 @@ impl StorageService for KVStorageService {
         instance: &str,
         revision: &str,
         rejected_by_user_id: &str,
+        reason: &str,
     ) -> Result<(), ApplyRevisionError> {
         let Some(mut config_details) = self.adapter.get_config_details(config_id).await? else {
             return Err(ApplyRevisionError::InvalidConfig);
@@ -797,11 +1130,19 @@ impl StorageService for KVStorageService {
             Some(Some(revision_data)) => revision_data,
             None | Some(None) => return Err(ApplyRevisionError::InvalidRevision),
         };
+        let before = revision_summary(config_id, instance_id, &revision_data);
 
         let now = Utc::now().timestamp_millis();
         revision_data.review_state = RevisionReviewState::Rejected;
         revision_data.reviewed_by_user_id = Some(rejected_by_user_id.to_string());
         revision_data.review_timestamp_ms = Some(now);
+        revision_data.comments.push(RevisionComment {
+            id: generate_revision_comment_id(),
+            user_id: rejected_by_user_id.to_string(),
+            timestamp_ms: now,
+            message: reason.to_string(),
+            kind: RevisionCommentKind::Rejection,
+        });
 
         instance.pending_revision = None;
 
@@ -813,6 +1154,7 @@ impl StorageService for KVStorageService {
             event: ConfigInstanceEventData::NewRevisionRejected {
                 new_revision: revision.to_string(),
                 rejected_by_user_id: rejected_by_user_id.to_string(),
+                reason: Some(reason.to_string()),
             },
             timestamp_ms: now,
         });
@@ -824,19 +1166,85 @@ impl StorageService for KVStorageService {
         self.adapter
             .save_config_details(config_id, &config_details)
             .await?;
+        self.remove_scheduled_apply(config_id, instance_id).await?;
+
+        self.record_audit(
+            YakManAuditAction::Reject,
+            YakManAuditTargetType::Revision,
+            revision,
+            before,
+            revision_summary(config_id, instance_id, &revision_data),
+        )
+        .await;
 
         if settings::is_notifications_enabled() {
             if let Err(err) = self
-                .send_reject_notification(config_id, instance_id, revision)
+                .send_reject_notification(config_id, instance_id, revision, reason)
                 .await
             {
-                log::error!("Failed to send notification, {err:?}");
+                log::error!("Failed to queue notification, {err:?}");
             }
         }
 
         return Ok(());
     }
 
+    async fn add_revision_comment(
+        &self,
+        config_id: &str,
+        instance: &str,
+        revision: &str,
+        user_id: &str,
+        message: &str,
+    ) -> Result<RevisionComment, AddRevisionCommentError> {
+        let Some(config_details) = self.adapter.get_config_details(config_id).await? else {
+            return Err(AddRevisionCommentError::InvalidConfig);
+        };
+        let Some(instance_data) = config_details
+            .instances
+            .iter()
+            .find(|i| i.instance == instance)
+        else {
+            return Err(AddRevisionCommentError::InvalidInstance);
+        };
+        let is_instance_revision = instance_data.revisions.iter().any(|r| r == revision)
+            || instance_data.pending_revision.as_deref() == Some(revision);
+        if !is_instance_revision {
+            return Err(AddRevisionCommentError::InvalidRevision);
+        }
+
+        let Some(mut revision_data) = self.adapter.get_revision(config_id, revision).await? else {
+            return Err(AddRevisionCommentError::InvalidRevision);
+        };
+
+        let comment = RevisionComment {
+            id: generate_revision_comment_id(),
+            user_id: user_id.to_string(),
+            timestamp_ms: Utc::now().timestamp_millis(),
+            message: message.to_string(),
+            kind: RevisionCommentKind::Comment,
+        };
+        revision_data.comments.push(comment.clone());
+        self.adapter
+            .save_revision(config_id, &revision_data)
+            .await?;
+
+        self.record_audit(
+            YakManAuditAction::Comment,
+            YakManAuditTargetType::Revision,
+            revision,
+            None,
+            Some(json!({
+                "config_id": config_id,
+                "instance": instance,
+                "comment": comment,
+            })),
+        )
+        .await;
+
+        return Ok(comment);
+    }
+
     async fn rollback_instance_revision(
         &self,
         config_id: &str,
@@ -852,6 +1260,8 @@ impl StorageService for KVStorageService {
 
         let instances = &mut config_details.instances;
 
+        let instance_id = instance;
+        let rollback_to = revision;
         let instance = instances
             .iter_mut()
             .find(|inst| inst.instance == instance)
@@ -878,6 +1288,12 @@ impl StorageService for KVStorageService {
             submitted_by_user_id: rollback_by_user_id.to_string(),
             submit_timestamp_ms: now,
             content_type: previous_revision.content_type,
+            approvals: vec![],
+            comments: vec![],
+            change_details: RevisionChangeDetails {
+                description: Some(format!("Rollback to revision {rollback_to}")),
+                ticket: None,
+            },
         };
         self.adapter.save_revision(config_id, &revision).await?;
 
@@ -889,9 +1305,242 @@ impl StorageService for KVStorageService {
             .save_config_details(config_id, &config_details)
             .await?;
         log::info!("Updated config details for config: {config_id}");
+        self.remove_scheduled_apply(config_id, instance_id).await?;
+
+        let mut after = revision_summary(config_id, instance_id, &revision);
+        if let Some(Value::Object(summary)) = &mut after {
+            summary.insert(String::from("rollback_to"), json!(rollback_to));
+        }
+        self.record_audit(
+            YakManAuditAction::Rollback,
+            YakManAuditTargetType::Revision,
+            &revision_key,
+            None,
+            after,
+        )
+        .await;
+
+        if settings::is_notifications_enabled() {
+            if let Err(err) = self
+                .send_rollback_notification(config_id, instance_id, &revision_key, rollback_to)
+                .await
+            {
+                log::error!("Failed to queue notification, {err:?}");
+            }
+        }
+
         return Ok(revision_key);
     }
 
+    async fn get_scheduled_applies(
+        &self,
+    ) -> Result<Vec<YakManScheduledApply>, GenericStorageError> {
+        return self.adapter.get_scheduled_applies().await;
+    }
+
+    async fn get_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<Option<YakManScheduledApply>, GenericStorageError> {
+        return self.adapter.get_scheduled_apply(config_id, instance).await;
+    }
+
+    async fn schedule_instance_revision_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+        revision: &str,
+        apply_at_ms: i64,
+        scheduled_by_user_id: &str,
+    ) -> Result<YakManScheduledApply, ScheduleApplyError> {
+        let config_details = self
+            .adapter
+            .get_config_details(config_id)
+            .await?
+            .ok_or(ScheduleApplyError::InvalidConfig)?;
+        let config_instance = config_details
+            .instances
+            .iter()
+            .find(|i| i.instance == instance)
+            .ok_or(ScheduleApplyError::InvalidInstance)?;
+
+        if config_instance.pending_revision.as_deref() != Some(revision) {
+            return Err(ScheduleApplyError::InvalidRevision);
+        }
+
+        let revision_data = self
+            .adapter
+            .get_revision(config_id, revision)
+            .await?
+            .ok_or(ScheduleApplyError::InvalidRevision)?;
+        self.verify_revision_approvals(config_id, &revision_data)
+            .await?;
+
+        let now = Utc::now().timestamp_millis();
+        if apply_at_ms <= now {
+            return Err(ScheduleApplyError::InvalidApplyTime);
+        }
+
+        // The worker cannot break glass, so a scheduled apply during a freeze would always fail
+        let apply_at = DateTime::from_timestamp_millis(apply_at_ms)
+            .ok_or(ScheduleApplyError::InvalidApplyTime)?;
+        if let Some(freeze_window) = self.get_active_freeze_window(config_id, apply_at).await? {
+            return Err(ScheduleApplyError::ChangeFreeze {
+                reason: freeze_window.reason,
+            });
+        }
+
+        let scheduled = YakManScheduledApply {
+            config_id: config_id.to_string(),
+            instance: instance.to_string(),
+            revision: revision.to_string(),
+            apply_at_ms: apply_at_ms,
+            scheduled_by_user_id: scheduled_by_user_id.to_string(),
+            scheduled_at_ms: now,
+        };
+
+        if self
+            .adapter
+            .get_scheduled_apply(config_id, instance)
+            .await?
+            .is_some()
+        {
+            return Err(ScheduleApplyError::AlreadyScheduled);
+        }
+        self.adapter.save_scheduled_apply(&scheduled).await?;
+
+        self.push_instance_event(
+            config_id,
+            instance,
+            ConfigInstanceEventData::ApplyScheduled {
+                new_revision: revision.to_string(),
+                apply_at_ms: apply_at_ms,
+                scheduled_by_user_id: scheduled_by_user_id.to_string(),
+            },
+        )
+        .await?;
+
+        self.record_audit(
+            YakManAuditAction::ScheduleApply,
+            YakManAuditTargetType::Revision,
+            revision,
+            None,
+            to_summary(&scheduled),
+        )
+        .await;
+
+        return Ok(scheduled);
+    }
+
+    async fn cancel_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+        revision: &str,
+        cancelled_by_user_id: &str,
+    ) -> Result<(), ScheduleApplyError> {
+        let cancelled = match self
+            .adapter
+            .get_scheduled_apply(config_id, instance)
+            .await?
+        {
+            Some(scheduled) if scheduled.revision == revision => scheduled,
+            _ => return Err(ScheduleApplyError::NotScheduled),
+        };
+        self.adapter
+            .delete_scheduled_apply(config_id, instance)
+            .await?;
+
+        self.record_audit(
+            YakManAuditAction::CancelScheduledApply,
+            YakManAuditTargetType::Revision,
+            revision,
+            to_summary(&cancelled),
+            None,
+        )
+        .await;
+
+        self.push_instance_event(
+            config_id,
+            instance,
+            ConfigInstanceEventData::ScheduledApplyCancelled {
+                new_revision: revision.to_string(),
+                cancelled_by_user_id: cancelled_by_user_id.to_string(),
+            },
+        )
+        .await?;
+
+        return Ok(());
+    }
+
+    async fn run_scheduled_apply(
+        &self,
+        scheduled: &YakManScheduledApply,
+    ) -> Result<(), ApplyRevisionError> {
+        // Removed before applying so a revision that cannot be applied is not retried forever
+        match self
+            .remove_scheduled_apply(&scheduled.config_id, &scheduled.instance)
+            .await?
+        {
+            Some(removed) if removed.revision == scheduled.revision => {}
+            _ => return Err(ApplyRevisionError::InvalidRevision),
+        }
+
+        let result = self
+            .apply_instance_revision(
+                &scheduled.config_id,
+                &scheduled.instance,
+                &scheduled.revision,
+                &scheduled.scheduled_by_user_id,
+                false,
+            )
+            .await;
+
+        if let Err(err) = &result {
+            self.push_instance_event(
+                &scheduled.config_id,
+                &scheduled.instance,
+                ConfigInstanceEventData::ScheduledApplyFailed {
+                    new_revision: scheduled.revision.clone(),
+                    reason: err.to_string(),
+                },
+            )
+            .await?;
+        }
+
+        return result;
+    }
+
+    async fn get_audit_log(
+        &self,
+        filter: &AuditLogFilter,
+    ) -> Result<Vec<YakManAuditLogEntry>, GenericStorageError> {
+        let mut entries = vec![];
+        for day in audit::audit_log_days(filter.from_ms, filter.to_ms)
+            .into_iter()
+            .rev()
+        {
+            let mut day_entries = self.adapter.get_audit_log(&day).await?;
+            day_entries.sort_by(|a, b| {
+                a.timestamp_ms
+                    .cmp(&b.timestamp_ms)
+                    .then_with(|| a.id.cmp(&b.id))
+            });
+            entries.extend(
+                day_entries
+                    .into_iter()
+                    .rev()
+                    .filter(|entry| filter.matches(entry)),
+            );
+            if entries.len() >= filter.limit {
+                break;
+            }
+        }
+        entries.truncate(filter.limit);
+        return Ok(entries);
+    }
+
     async fn initialize_storage(&self) -> Result<(), GenericStorageError> {
         log::info!("initializing local storage adapter");
         let now = Utc::now().timestamp_millis();
@@ -907,6 +1556,7 @@ impl StorageService for KVStorageService {
                     .expect("No users found and 'YAKMAN_DEFAULT_ADMIN_USER_EMAIL' is not set"),
                 role: Some(YakManRole::Admin),
                 id: generate_user_id(),
+                deactivated_at: None,
             };
 
             let admin_user_details = YakManUserDetails {
@@ -915,6 +1565,8 @@ impl StorageService for KVStorageService {
                 roles: vec![],
                 profile_picture: None,
                 team_ids: vec![],
+                deactivated_at: None,
+                token_version: 0,
             };
 
             self.adapter
@@ -951,6 +1603,7 @@ impl StorageService for KVStorageService {
                             &YakManPassword {
                                 hash: password_hash,
                                 timestamp: now,
+                                previous_hashes: vec![],
                             },
                         )
                         .await?;
@@ -991,58 +1644,317 @@ impl StorageService for KVStorageService {
         user_id: &str,
         details: YakManUserDetails,
     ) -> Result<(), GenericStorageError> {
-        return self.adapter.save_user_details(user_id, &details).await;
+        let before = self
+            .adapter
+            .get_user_details(user_id)
+            .await?
+            .and_then(|details| to_summary(&details));
+        self.adapter.save_user_details(user_id, &details).await?;
+
+        let after = to_summary(&details);
+        if before != after {
+            self.record_audit(
+                YakManAuditAction::Update,
+                YakManAuditTargetType::User,
+                user_id,
+                before,
+                after,
+            )
+            .await;
+        }
+        return Ok(());
     }
 
-    async fn create_user(
+    async fn update_user_roles(
         &self,
-        payload: CreateYakManUserPayload,
-    ) -> Result<String, GenericStorageError> {
-        let mut users = self.get_users().await?;
-
-        // TODO: Prevent duplicate emails
+        user_id: &str,
+        payload: UpdateUserRolesPayload,
+    ) -> Result<(), UpdateUserError> {
+        let Some(mut details) = self.adapter.get_user_details(user_id).await? else {
+            return Err(UpdateUserError::UserNotFound);
+        };
 
-        let user_id = generate_user_id();
+        if !payload.global_roles.contains(&YakManRole::Admin)
+            && !self.has_admin_team(&details).await?
+            && self.is_last_active_admin(user_id).await?
+        {
+            return Err(UpdateUserError::LastActiveAdmin);
+        }
 
-        users.push(YakManUser {
-            email: payload.email.clone(),
-            id: user_id.clone(),
-            role: payload.role.clone(),
-        });
+        let before = Some(json!({ "global_roles": details.global_roles, "roles": details.roles }));
+        details.global_roles = payload.global_roles;
+        details.roles = payload.roles;
 
-        // TODO: Handle roles properly
-        let user_details = YakManUserDetails {
-            user_id: user_id.clone(),
-            profile_picture: None,
-            global_roles: vec![],
-            roles: vec![],
-            team_ids: vec![],
-        };
+        self.adapter.save_user_details(user_id, &details).await?;
 
-        self.adapter
-            .save_user_details(&user_id, &user_details)
-            .await?;
+        // Keep the role shown in the user list in sync with the details
+        let mut users = self.adapter.get_users().await?;
+        if let Some(user) = users.iter_mut().find(|u| u.id == user_id) {
+            user.role = details.global_roles.first().cloned();
+            self.adapter.save_users(&users).await?;
+        }
 
-        self.adapter.save_users(&users).await?;
+        self.record_audit(
+            YakManAuditAction::Update,
+            YakManAuditTargetType::User,
+            user_id,
+            before,
+            Some(json!({ "global_roles": details.global_roles, "roles": details.roles })),
+        )
+        .await;
 
-        Ok(user_id)
+        return Ok(());
     }
 
-    async fn get_teams(&self) -> Result<Vec<YakManTeam>, GenericStorageError> {
-        return self.adapter.get_teams().await;
+    async fn deactivate_user(&self, user_id: &str) -> Result<(), UpdateUserError> {
+        if self.is_last_active_admin(user_id).await? {
+            return Err(UpdateUserError::LastActiveAdmin);
+        }
+        self.set_user_deactivated_at(user_id, Some(Utc::now().timestamp_millis()))
+            .await?;
+        self.record_audit(
+            YakManAuditAction::Deactivate,
+            YakManAuditTargetType::User,
+            user_id,
+            None,
+            None,
+        )
+        .await;
+        return Ok(());
     }
 
-    async fn get_team_details(
-        &self,
-        team_id: &str,
-    ) -> Result<Option<YakManTeamDetails>, GenericStorageError> {
-        return self.adapter.get_team_details(team_id).await;
+    async fn reactivate_user(&self, user_id: &str) -> Result<(), UpdateUserError> {
+        self.set_user_deactivated_at(user_id, None).await?;
+        self.record_audit(
+            YakManAuditAction::Reactivate,
+            YakManAuditTargetType::User,
+            user_id,
+            None,
+            None,
+        )
+        .await;
+        return Ok(());
     }
 
-    async fn create_team(&self, payload: CreateTeamPayload) -> Result<String, CreateTeamError> {
-        let team_name = payload.name;
+    async fn revoke_user_sessions(&self, user_id: &str) -> Result<(), UpdateUserError> {
+        let Some(mut details) = self.adapter.get_user_details(user_id).await? else {
+            return Err(UpdateUserError::UserNotFound);
+        };
 
-        let mut teams = self.adapter.get_teams().await?;
+        details.token_version += 1;
+
+        self.adapter.save_user_details(user_id, &details).await?;
+
+        self.record_audit(
+            YakManAuditAction::RevokeSessions,
+            YakManAuditTargetType::User,
+            user_id,
+            None,
+            None,
+        )
+        .await;
+
+        return Ok(());
+    }
+
+    async fn revoke_refresh_token(&self, refresh_token: &str) -> Result<(), GenericStorageError> {
+        // Only the hash is stored so the revoked tokens cannot be read back from storage
+        let token_hash = sha256::digest(refresh_token);
+        let now = Utc::now();
+        let retention =
+            chrono::Duration::try_days(settings::revoked_refresh_token_retention_days())
+                .unwrap_or(chrono::Duration::days(365));
+        let revoked_token = YakManRevokedRefreshToken {
+            revoked_at: now.timestamp_millis(),
+            expires_at: (now + retention).timestamp_millis(),
+        };
+        return self
+            .adapter
+            .save_revoked_refresh_token(&token_hash, &revoked_token)
+            .await;
+    }
+
+    async fn is_refresh_token_revoked(
+        &self,
+        refresh_token: &str,
+    ) -> Result<bool, GenericStorageError> {
+        let token_hash = sha256::digest(refresh_token);
+        let now = Utc::now().timestamp_millis();
+        return Ok(self
+            .adapter
+            .get_revoked_refresh_token(&token_hash)
+            .await?
+            .is_some_and(|revoked_token| revoked_token.expires_at > now));
+    }
+
+    async fn sync_user_team_memberships(
+        &self,
+        user_id: &str,
+        managed_team_ids: &[String],
+        member_team_ids: &[String],
+    ) -> Result<(), UpdateUserError> {
+        let Some(mut details) = self.adapter.get_user_details(user_id).await? else {
+            return Err(UpdateUserError::UserNotFound);
+        };
+        let original_team_ids = details.team_ids.clone();
+        let mut joined_team_ids: Vec<String> = vec![];
+
+        for team_id in managed_team_ids {
+            let Some(mut team_details) = self.adapter.get_team_details(team_id).await? else {
+                log::warn!("Team {team_id} not found, skipping membership sync");
+                continue;
+            };
+
+            let should_be_member = member_team_ids.contains(team_id);
+            let is_member = team_details.member_user_ids.iter().any(|id| id == user_id);
+            if should_be_member {
+                joined_team_ids.push(team_id.clone());
+            }
+
+            if should_be_member && !is_member {
+                team_details.member_user_ids.push(user_id.to_string());
+            } else if !should_be_member && is_member {
+                team_details.member_user_ids.retain(|id| id != user_id);
+            } else {
+                continue;
+            }
+
+            self.adapter
+                .save_team_details(team_id, &team_details)
+                .await?;
+        }
+
+        details
+            .team_ids
+            .retain(|id| !managed_team_ids.contains(id) || joined_team_ids.contains(id));
+        for team_id in joined_team_ids {
+            if !details.team_ids.contains(&team_id) {
+                details.team_ids.push(team_id);
+            }
+        }
+
+        if details.team_ids != original_team_ids {
+            self.adapter.save_user_details(user_id, &details).await?;
+            self.record_audit(
+                YakManAuditAction::Update,
+                YakManAuditTargetType::User,
+                user_id,
+                Some(json!({ "team_ids": original_team_ids })),
+                Some(json!({ "team_ids": details.team_ids })),
+            )
+            .await;
+        }
+
+        return Ok(());
+    }
+
+    async fn delete_user(&self, user_id: &str) -> Result<(), DeleteUserError> {
+        let mut users = self.adapter.get_users().await?;
+        let Some(index) = users.iter().position(|u| u.id == user_id) else {
+            return Err(DeleteUserError::UserNotFound);
+        };
+        if self.is_last_active_admin(user_id).await? {
+            return Err(DeleteUserError::LastActiveAdmin);
+        }
+        let user = users.remove(index);
+
+        if let Some(details) = self.adapter.get_user_details(user_id).await? {
+            for team_id in &details.team_ids {
+                if let Some(mut team_details) = self.adapter.get_team_details(team_id).await? {
+                    team_details.member_user_ids.retain(|id| id != user_id);
+                    self.adapter
+                        .save_team_details(team_id, &team_details)
+                        .await?;
+                }
+            }
+            self.adapter.delete_user_details(user_id).await?;
+        }
+
+        let email_hash = sha256::digest(&user.email);
+        if self.adapter.get_password(&email_hash).await?.is_some() {
+            self.adapter.delete_password(&email_hash).await?;
+        }
+
+        if self.adapter.get_totp(user_id).await?.is_some() {
+            self.adapter.delete_totp(user_id).await?;
+        }
+
+        self.adapter.save_users(&users).await?;
+
+        self.record_audit(
+            YakManAuditAction::Delete,
+            YakManAuditTargetType::User,
+            user_id,
+            to_summary(&user),
+            None,
+        )
+        .await;
+
+        return Ok(());
+    }
+
+    async fn create_user(
+        &self,
+        payload: CreateYakManUserPayload,
+    ) -> Result<String, GenericStorageError> {
+        let mut users = self.get_users().await?;
+
+        // TODO: Prevent duplicate emails
+
+        let user_id = generate_user_id();
+
+        let user = YakManUser {
+            email: payload.email.clone(),
+            id: user_id.clone(),
+            role: payload.role.clone(),
+            deactivated_at: None,
+        };
+        let after = to_summary(&user);
+        users.push(user);
+
+        let user_details = YakManUserDetails {
+            user_id: user_id.clone(),
+            profile_picture: None,
+            global_roles: payload.role.into_iter().collect(),
+            roles: vec![],
+            team_ids: vec![],
+            deactivated_at: None,
+            token_version: 0,
+        };
+
+        self.adapter
+            .save_user_details(&user_id, &user_details)
+            .await?;
+
+        self.adapter.save_users(&users).await?;
+
+        self.record_audit(
+            YakManAuditAction::Create,
+            YakManAuditTargetType::User,
+            &user_id,
+            None,
+            after,
+        )
+        .await;
+
+        Ok(user_id)
+    }
+
+    async fn get_teams(&self) -> Result<Vec<YakManTeam>, GenericStorageError> {
+        return self.adapter.get_teams().await;
+    }
+
+    async fn get_team_details(
+        &self,
+        team_id: &str,
+    ) -> Result<Option<YakManTeamDetails>, GenericStorageError> {
+        return self.adapter.get_team_details(team_id).await;
+    }
+
+    async fn create_team(&self, payload: CreateTeamPayload) -> Result<String, CreateTeamError> {
+        let team_name = payload.name;
+
+        let mut teams = self.adapter.get_teams().await?;
         if teams.iter().any(|t| t.name == team_name) {
             return Err(CreateTeamError::DuplicateTeam);
         }
@@ -1064,17 +1976,15 @@ impl StorageService for KVStorageService {
             name: team_name.clone(),
         });
 
+        let team_details = YakManTeamDetails {
+            id: team_id.clone(),
+            name: team_name,
+            roles: payload.roles,
+            global_roles: payload.global_roles,
+            member_user_ids: payload.team_member_user_ids,
+        };
         self.adapter
-            .save_team_details(
-                &team_id.clone(),
-                &YakManTeamDetails {
-                    id: team_id.clone(),
-                    name: team_name,
-                    roles: payload.roles,
-                    global_roles: payload.global_roles,
-                    member_user_ids: payload.team_member_user_ids,
-                },
-            )
+            .save_team_details(&team_id, &team_details)
             .await?;
 
         self.adapter.save_teams(&teams).await?;
@@ -1089,6 +1999,15 @@ impl StorageService for KVStorageService {
             }
         }
 
+        self.record_audit(
+            YakManAuditAction::Create,
+            YakManAuditTargetType::Team,
+            &team_id,
+            None,
+            to_summary(&team_details),
+        )
+        .await;
+
         return Ok(team_id);
     }
 
@@ -1115,6 +2034,7 @@ impl StorageService for KVStorageService {
         let Some(mut team_details) = self.adapter.get_team_details(team_id).await? else {
             return Err(UpdateTeamError::TeamNotFound);
         };
+        let before = to_summary(&team_details);
 
         let mut user_details: Vec<YakManUserDetails> = vec![];
 
@@ -1170,6 +2090,15 @@ impl StorageService for KVStorageService {
             }
         }
 
+        self.record_audit(
+            YakManAuditAction::Update,
+            YakManAuditTargetType::Team,
+            team_id,
+            before,
+            to_summary(&team_details),
+        )
+        .await;
+
         return Ok(());
     }
 
@@ -1199,9 +2128,162 @@ impl StorageService for KVStorageService {
             }
         }
 
+        let mut service_accounts = self.adapter.get_service_accounts().await?;
+        if service_accounts
+            .iter()
+            .any(|sa| sa.team_ids.iter().any(|id| id == team_id))
+        {
+            for service_account in service_accounts.iter_mut() {
+                service_account.team_ids.retain(|id| id != team_id);
+            }
+            self.adapter
+                .save_service_accounts(&service_accounts)
+                .await?;
+        }
+
         self.adapter.delete_team_details(team_id).await?;
         self.adapter.save_teams(&teams).await?;
 
+        self.record_audit(
+            YakManAuditAction::Delete,
+            YakManAuditTargetType::Team,
+            team_id,
+            to_summary(&team_details),
+            None,
+        )
+        .await;
+
+        return Ok(());
+    }
+
+    async fn get_service_accounts(&self) -> Result<Vec<YakManServiceAccount>, GenericStorageError> {
+        return self.adapter.get_service_accounts().await;
+    }
+
+    async fn get_service_account(
+        &self,
+        id: &str,
+    ) -> Result<Option<YakManServiceAccount>, GenericStorageError> {
+        let service_accounts = self.adapter.get_service_accounts().await?;
+        return Ok(service_accounts.into_iter().find(|sa| sa.id == id));
+    }
+
+    async fn create_service_account(
+        &self,
+        payload: ServiceAccountPayload,
+        created_by_user_id: &str,
+    ) -> Result<String, CreateServiceAccountError> {
+        let mut service_accounts = self.adapter.get_service_accounts().await?;
+        if service_accounts.iter().any(|sa| sa.name == payload.name) {
+            return Err(CreateServiceAccountError::DuplicateServiceAccount);
+        }
+
+        if !self.teams_exist(&payload.team_ids).await? {
+            return Err(CreateServiceAccountError::TeamNotFound);
+        }
+
+        let id = generate_service_account_id();
+        service_accounts.push(YakManServiceAccount {
+            id: id.clone(),
+            name: payload.name,
+            global_roles: payload.global_roles,
+            roles: payload.roles,
+            team_ids: payload.team_ids,
+            created_at: Utc::now().timestamp_millis(),
+            created_by_user_id: created_by_user_id.to_string(),
+        });
+
+        self.adapter
+            .save_service_accounts(&service_accounts)
+            .await?;
+
+        self.record_audit(
+            YakManAuditAction::Create,
+            YakManAuditTargetType::ServiceAccount,
+            &id,
+            None,
+            service_accounts.last().and_then(to_summary),
+        )
+        .await;
+
+        return Ok(id);
+    }
+
+    async fn update_service_account(
+        &self,
+        id: &str,
+        payload: ServiceAccountPayload,
+    ) -> Result<(), UpdateServiceAccountError> {
+        let mut service_accounts = self.adapter.get_service_accounts().await?;
+        if service_accounts
+            .iter()
+            .any(|sa| sa.name == payload.name && sa.id != id)
+        {
+            return Err(UpdateServiceAccountError::DuplicateServiceAccount);
+        }
+
+        if !self.teams_exist(&payload.team_ids).await? {
+            return Err(UpdateServiceAccountError::TeamNotFound);
+        }
+
+        let Some(service_account) = service_accounts.iter_mut().find(|sa| sa.id == id) else {
+            return Err(UpdateServiceAccountError::ServiceAccountNotFound);
+        };
+        let before = to_summary(service_account);
+
+        service_account.name = payload.name;
+        service_account.global_roles = payload.global_roles;
+        service_account.roles = payload.roles;
+        service_account.team_ids = payload.team_ids;
+        let after = to_summary(service_account);
+
+        self.adapter
+            .save_service_accounts(&service_accounts)
+            .await?;
+
+        self.record_audit(
+            YakManAuditAction::Update,
+            YakManAuditTargetType::ServiceAccount,
+            id,
+            before,
+            after,
+        )
+        .await;
+
+        return Ok(());
+    }
+
+    async fn delete_service_account(&self, id: &str) -> Result<(), DeleteServiceAccountError> {
+        let mut service_accounts = self.adapter.get_service_accounts().await?;
+        let Some(index) = service_accounts.iter().position(|sa| sa.id == id) else {
+            return Err(DeleteServiceAccountError::ServiceAccountNotFound);
+        };
+        let service_account = service_accounts.remove(index);
+
+        // Remove the keys first so they cannot outlive the service account
+        let api_keys = self.adapter.get_api_keys().await?;
+        let (removed_keys, api_keys): (Vec<YakManApiKey>, Vec<YakManApiKey>) = api_keys
+            .into_iter()
+            .partition(|key| key.service_account_id.as_deref() == Some(id));
+        self.adapter.save_api_keys(&api_keys).await?;
+        self.put_api_keys_cache(&api_keys);
+        for api_key in removed_keys {
+            self.delete_api_key_usage(&api_key.id).await?;
+        }
+
+        self.adapter
+            .save_service_accounts(&service_accounts)
+            .await?;
+
+        self.record_audit(
+            YakManAuditAction::Delete,
+            YakManAuditTargetType::ServiceAccount,
+            id,
+            to_summary(&service_account),
+            None,
+        )
+        .await;
+
         return Ok(());
     }
 
@@ -1232,32 +2314,132 @@ impl StorageService for KVStorageService {
         }
 
         let api_keys: Vec<YakManApiKey> = self.get_api_keys().await?;
-        return Ok(api_keys.into_iter().find(|key| key.hash == hash));
+        return Ok(api_keys
+            .into_iter()
+            .find(|key| key.hash == hash || key.previous_hash.as_deref() == Some(hash)));
     }
 
     async fn save_api_key(&self, api_key: YakManApiKey) -> Result<(), GenericStorageError> {
         let mut api_keys = self.get_api_keys().await?;
 
-        if let Some(index) = api_keys.iter().position(|k| k.id == api_key.id) {
-            api_keys[index] = api_key;
-        } else {
-            api_keys.push(api_key);
-        }
+        let before = match api_keys.iter().position(|k| k.id == api_key.id) {
+            Some(index) => {
+                let before = api_key_summary(&api_keys[index]);
+                api_keys[index] = api_key.clone();
+                before
+            }
+            None => {
+                api_keys.push(api_key.clone());
+                None
+            }
+        };
+        let is_new_key = before.is_none();
 
         self.put_api_keys_cache(&api_keys);
 
-        return self.adapter.save_api_keys(&api_keys).await;
+        self.adapter.save_api_keys(&api_keys).await?;
+
+        let action = if is_new_key {
+            YakManAuditAction::Create
+        } else {
+            YakManAuditAction::Update
+        };
+        self.record_audit(
+            action,
+            YakManAuditTargetType::ApiKey,
+            &api_key.id,
+            before,
+            api_key_summary(&api_key),
+        )
+        .await;
+
+        if is_new_key && settings::is_notifications_enabled() {
+            if let Err(err) = self.send_api_key_created_notification(&api_key).await {
+                log::error!("Failed to queue notification, {err:?}");
+            }
+        }
+
+        return Ok(());
     }
 
     async fn delete_api_key(&self, id: &str) -> Result<(), GenericStorageError> {
         let mut api_keys = self.get_api_keys().await?;
 
-        if let Some(index) = api_keys.iter().position(|k| k.id == id) {
-            api_keys.remove(index);
-        }
+        let removed = api_keys
+            .iter()
+            .position(|k| k.id == id)
+            .map(|index| api_keys.remove(index));
 
         self.put_api_keys_cache(&api_keys);
-        return self.adapter.save_api_keys(&api_keys).await;
+        self.adapter.save_api_keys(&api_keys).await?;
+        self.delete_api_key_usage(id).await?;
+
+        if let Some(api_key) = removed {
+            self.record_audit(
+                YakManAuditAction::Delete,
+                YakManAuditTargetType::ApiKey,
+                id,
+                api_key_summary(&api_key),
+                None,
+            )
+            .await;
+        }
+        return Ok(());
+    }
+
+    async fn get_api_key_usages(
+        &self,
+        ids: &[String],
+    ) -> Result<HashMap<String, YakManApiKeyUsage>, GenericStorageError> {
+        // Each usage is its own record, so they are read concurrently
+        let usages: Vec<(String, Option<YakManApiKeyUsage>)> = stream::iter(ids.to_vec())
+            .map(|id| {
+                let adapter = self.adapter.clone();
+                async move {
+                    let usage = adapter.get_api_key_usage(&id).await?;
+                    return Ok::<_, GenericStorageError>((id, usage));
+                }
+            })
+            .buffer_unordered(MAX_CONCURRENT_OBJECT_READS)
+            .try_collect()
+            .await?;
+        return Ok(usages
+            .into_iter()
+            .filter_map(|(id, usage)| usage.map(|usage| (id, usage)))
+            .collect());
+    }
+
+    async fn record_api_key_usage(&self, id: &str) -> Result<(), GenericStorageError> {
+        let now = Utc::now().timestamp_millis();
+
+        // Most requests can be skipped using the cache without reading from storage
+        if let Some(last_used_at) = self.api_key_last_used_cache.get(id) {
+            if was_recently_used(last_used_at, now) {
+                return Ok(());
+            }
+        }
+
+        if let Some(usage) = self.adapter.get_api_key_usage(id).await? {
+            if was_recently_used(usage.last_used_at, now) {
+                self.api_key_last_used_cache
+                    .insert(id.to_string(), usage.last_used_at);
+                return Ok(());
+            }
+        }
+
+        // Usage is stored separately from the api keys, so this never overwrites changes to the keys
+        self.adapter
+            .save_api_key_usage(id, &YakManApiKeyUsage { last_used_at: now })
+            .await?;
+        self.api_key_last_used_cache.insert(id.to_string(), now);
+
+        // The key may have been deleted after the request was authenticated, in which case
+        // the delete already ran and would leave this usage record behind
+        let api_keys = self.adapter.get_api_keys().await?;
+        if !api_keys.iter().any(|key| key.id == id) {
+            self.delete_api_key_usage(id).await?;
+        }
+        return Ok(());
     }
 
     async fn delete_instance(
@@ -1265,34 +2447,34 @@ impl StorageService for KVStorageService {
         config_id: &str,
         instance: &str,
     ) -> Result<(), DeleteConfigInstanceError> {
-        let mut config_details = self
-            .adapter
-            .get_config_details(config_id)
+        let before = self
+            .get_config_instance(config_id, instance)
             .await?
-            .ok_or(DeleteConfigInstanceError::InvalidConfig)?;
-
-        let config_instance = config_details
-            .instances
-            .iter()
-            .find(|i| i.instance == instance)
-            .ok_or(DeleteConfigInstanceError::InvalidInstance)?
-            .clone();
-
-        let remaining_instances: Vec<_> = config_details
-            .instances
-            .into_iter()
-            .filter(|i| i.instance != instance)
-            .collect();
+            .map(|i| {
+                json!({
+                    "config_id": config_id,
+                    "labels": i.labels,
+                    "current_revision": i.current_revision,
+                })
+            });
 
-        config_details.instances = remaining_instances;
+        self.delete_instance_data(config_id, instance).await?;
 
-        self.adapter
-            .save_config_details(config_id, &config_details)
-            .await?;
+        self.record_audit(
+            YakManAuditAction::Delete,
+            YakManAuditTargetType::ConfigInstance,
+            instance,
+            before,
+            None,
+        )
+        .await;
 
-        for revision in config_instance.revisions {
-            if let Err(e) = self.adapter.delete_revision(config_id, &revision).await {
-                log::error!("Failed to delete revision ({revision}) {e:?}");
+        if settings::is_notifications_enabled() {
+            if let Err(err) = self
+                .send_instance_deleted_notification(config_id, instance)
+                .await
+            {
+                log::error!("Failed to queue notification, {err:?}");
             }
         }
 
@@ -1307,37 +2489,112 @@ impl StorageService for KVStorageService {
         return self.adapter.get_password(&email_hash).await;
     }
 
-    async fn create_password_reset_link(
+    async fn get_login_attempts(
         &self,
-        user_id: &str,
-    ) -> Result<YakManPublicPasswordResetLink, CreatePasswordResetLinkError> {
-        let user = match self.get_user_by_id(user_id).await? {
-            Some(user) => user,
-            None => return Err(CreatePasswordResetLinkError::InvalidUser),
-        };
+        key: &str,
+    ) -> Result<Option<YakManLoginAttempts>, GenericStorageError> {
+        return self.adapter.get_login_attempts(key).await;
+    }
 
-        let id = short_sha(&Uuid::new_v4().to_string());
-        let id_hash = sha256::digest(&id);
+    async fn save_login_attempts(
+        &self,
+        key: &str,
+        attempts: &YakManLoginAttempts,
+    ) -> Result<(), GenericStorageError> {
+        return self.adapter.save_login_attempts(key, attempts).await;
+    }
 
-        let email = user.email;
-        let email_hash = sha256::digest(&email);
+    async fn reset_login_attempts(&self, key: &str) -> Result<(), GenericStorageError> {
+        if self.adapter.get_login_attempts(key).await?.is_some() {
+            self.adapter.delete_login_attempts(key).await?;
+            self.record_audit(
+                YakManAuditAction::Unlock,
+                YakManAuditTargetType::LoginAttempts,
+                key,
+                None,
+                None,
+            )
+            .await;
+        }
+        return Ok(());
+    }
 
-        let expiration =
-            Utc::now() + chrono::Duration::try_days(2).expect("2 days will not be out of bounds");
+    async fn get_totp(&self, user_id: &str) -> Result<Option<YakManTotp>, GenericStorageError> {
+        return self.adapter.get_totp(user_id).await;
+    }
 
-        let password_reset_link = YakManPasswordResetLink {
-            email_hash,
-            expiration_timestamp_ms: expiration.timestamp_millis(),
-        };
+    async fn save_totp(&self, user_id: &str, totp: &YakManTotp) -> Result<(), GenericStorageError> {
+        // Only enabling the second factor is recorded, not every code that is used
+        let was_confirmed = self
+            .adapter
+            .get_totp(user_id)
+            .await?
+            .is_some_and(|totp| totp.confirmed);
+        self.adapter.save_totp(user_id, totp).await?;
+
+        if totp.confirmed && !was_confirmed {
+            self.record_audit(
+                YakManAuditAction::EnableTotp,
+                YakManAuditTargetType::User,
+                user_id,
+                None,
+                None,
+            )
+            .await;
+        }
+        return Ok(());
+    }
 
-        self.adapter
-            .save_password_reset_link(&id_hash, &password_reset_link)
-            .await?;
+    async fn delete_totp(&self, user_id: &str) -> Result<(), GenericStorageError> {
+        if self.adapter.get_totp(user_id).await?.is_some() {
+            self.adapter.delete_totp(user_id).await?;
+            self.record_audit(
+                YakManAuditAction::DisableTotp,
+                YakManAuditTargetType::User,
+                user_id,
+                None,
+                None,
+            )
+            .await;
+        }
+        return Ok(());
+    }
 
-        return Ok(YakManPublicPasswordResetLink {
-            id,
-            user_id: user_id.to_string(),
-        });
+    async fn create_password_reset_link(
+        &self,
+        user_id: &str,
+    ) -> Result<YakManPublicPasswordResetLink, CreatePasswordResetLinkError> {
+        let expiration =
+            Utc::now() + chrono::Duration::try_days(2).expect("2 days will not be out of bounds");
+        let link = self.save_password_reset_link(user_id, expiration).await?;
+        self.record_audit(
+            YakManAuditAction::CreatePasswordResetLink,
+            YakManAuditTargetType::User,
+            user_id,
+            None,
+            None,
+        )
+        .await;
+        return Ok(link);
+    }
+
+    async fn create_user_invitation(
+        &self,
+        user_id: &str,
+    ) -> Result<YakManPublicPasswordResetLink, CreatePasswordResetLinkError> {
+        let expiration = Utc::now()
+            + chrono::Duration::try_hours(settings::invitation_expiration_hours())
+                .unwrap_or(chrono::Duration::days(3));
+        let link = self.save_password_reset_link(user_id, expiration).await?;
+        self.record_audit(
+            YakManAuditAction::Invite,
+            YakManAuditTargetType::User,
+            user_id,
+            None,
+            None,
+        )
+        .await;
+        return Ok(link);
     }
 
     async fn reset_password_with_link(
@@ -1370,27 +2627,67 @@ impl StorageService for KVStorageService {
             return Err(ResetPasswordError::ResetLinkExpired);
         }
 
-        if let Err(err) = validate_password(password) {
-            return Err(ResetPasswordError::PasswordValidationError { error: err });
-        }
-
-        let password_hash = hash_password(password)
-            .map_err(|err| ResetPasswordError::PasswordHashError { error: err })?;
+        let current_password = self.adapter.get_password(&email_hash).await?;
+        let new_password = self
+            .password_policy
+            .create_password(password, current_password, now)?;
         self.adapter
-            .save_password(
-                &email_hash,
-                &YakManPassword {
-                    hash: password_hash,
-                    timestamp: now,
-                },
-            )
+            .save_password(&email_hash, &new_password)
             .await?;
 
         self.adapter.delete_password_reset_link(&id).await?;
 
+        self.record_audit(
+            YakManAuditAction::ResetPassword,
+            YakManAuditTargetType::User,
+            &user.id,
+            None,
+            None,
+        )
+        .await;
+
         Ok(())
     }
 
+    async fn change_password(
+        &self,
+        user_id: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<(), ChangePasswordError> {
+        let Some(user) = self.get_user_by_id(user_id).await? else {
+            return Err(ChangePasswordError::InvalidUser);
+        };
+        let email_hash = sha256::digest(&user.email);
+
+        let Some(password) = self.adapter.get_password(&email_hash).await? else {
+            // Users without a password (OAuth) cannot change it
+            return Err(ChangePasswordError::InvalidUser);
+        };
+        if !verify_password(current_password, password.clone()).unwrap_or(false) {
+            return Err(ChangePasswordError::IncorrectPassword);
+        }
+
+        let now = Utc::now().timestamp_millis();
+        let new_password =
+            self.password_policy
+                .create_password(new_password, Some(password), now)?;
+        self.adapter
+            .save_password(&email_hash, &new_password)
+            .await?;
+
+        self.record_audit(
+            YakManAuditAction::ChangePassword,
+            YakManAuditTargetType::User,
+            user_id,
+            None,
+            None,
+        )
+        .await;
+
+        return Ok(());
+    }
+
     async fn validate_password_reset_link(
         &self,
         id: &str,
@@ -1420,72 +2717,212 @@ impl StorageService for KVStorageService {
 }
 
 impl KVStorageService {
+    /// Appends an entry to the audit log, attributed to the actor of the current request.
+    /// The mutation has already been saved, so failures are logged rather than returned.
+    async fn record_audit(
+        &self,
+        action: YakManAuditAction,
+        target_type: YakManAuditTargetType,
+        target_id: &str,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) {
+        let now = Utc::now().timestamp_millis();
+        let entry = YakManAuditLogEntry {
+            id: generate_audit_log_entry_id(now),
+            timestamp_ms: now,
+            actor: audit::current_actor(),
+            action: action,
+            target_type: target_type,
+            target_id: target_id.to_string(),
+            before: before,
+            after: after,
+        };
+        let day = audit::audit_log_day(now);
+
+        self.audit_exporter.export(&entry);
+
+        // Every entry has its own key, so concurrent appends (including from other replicas) cannot overwrite each other
+        for attempt in 1..=AUDIT_LOG_WRITE_ATTEMPTS {
+            match self.adapter.save_audit_log_entry(&day, &entry).await {
+                Ok(_) => return,
+                Err(err) if attempt < AUDIT_LOG_WRITE_ATTEMPTS => {
+                    log::warn!("Failed to record audit log entry (attempt {attempt}), {err:?}");
+                    tokio::time::sleep(Duration::from_millis(100 * attempt as u64)).await;
+                }
+                Err(err) => {
+                    log::error!(
+                        "Failed to record audit log entry {}, giving up, {err:?}",
+                        entry.id
+                    );
+                }
+            }
+        }
+    }
+
+    /// Invitations and password reset links are both links to set the user's password
+    async fn save_password_reset_link(
+        &self,
+        user_id: &str,
+        expiration: DateTime<Utc>,
+    ) -> Result<YakManPublicPasswordResetLink, CreatePasswordResetLinkError> {
+        let user = match self.get_user_by_id(user_id).await? {
+            Some(user) => user,
+            None => return Err(CreatePasswordResetLinkError::InvalidUser),
+        };
+
+        let id = short_sha(&Uuid::new_v4().to_string());
+        let id_hash = sha256::digest(&id);
+
+        let email = user.email;
+        let email_hash = sha256::digest(&email);
+
+        let password_reset_link = YakManPasswordResetLink {
+            email_hash,
+            expiration_timestamp_ms: expiration.timestamp_millis(),
+        };
+
+        self.adapter
+            .save_password_reset_link(&id_hash, &password_reset_link)
+            .await?;
+
+        return Ok(YakManPublicPasswordResetLink {
+            id,
+            user_id: user_id.to_string(),
+        });
+    }
+
+    async fn delete_api_key_usage(&self, id: &str) -> Result<(), GenericStorageError> {
+        self.api_key_last_used_cache.invalidate(id);
+        if self.adapter.get_api_key_usage(id).await?.is_some() {
+            self.adapter.delete_api_key_usage(id).await?;
+        }
+        return Ok(());
+    }
+
     fn put_api_keys_cache(&self, api_keys: &Vec<YakManApiKey>) {
-        // Update caches
+        // Clear the caches so deleted keys and rotated secrets are not served from the cache
+        self.api_key_id_cache.invalidate_all();
+        self.api_key_hash_cache.invalidate_all();
+
         for key in api_keys {
             self.api_key_id_cache
                 .insert(key.id.to_string(), key.clone());
             self.api_key_hash_cache
                 .insert(key.hash.to_string(), key.clone());
+            if let Some(previous_hash) = &key.previous_hash {
+                self.api_key_hash_cache
+                    .insert(previous_hash.to_string(), key.clone());
+            }
         }
     }
 
-    async fn send_instance_created_notification(
+    /// Removes the instance and its revisions without sending a notification,
+    /// used directly when the whole project is being deleted.
+    async fn delete_instance_data(
         &self,
         config_id: &str,
         instance: &str,
-    ) -> anyhow::Result<()> {
-        let (project, config) = self.get_data_to_send_notification(config_id).await?;
+    ) -> Result<(), DeleteConfigInstanceError> {
+        let mut config_details = self
+            .adapter
+            .get_config_details(config_id)
+            .await?
+            .ok_or(DeleteConfigInstanceError::InvalidConfig)?;
 
-        let Some(notification_settings) = project.notification_settings else {
-            return Ok(()); // No notification settings configured for project
-        };
+        let config_instance = config_details
+            .instances
+            .iter()
+            .find(|i| i.instance == instance)
+            .ok_or(DeleteConfigInstanceError::InvalidInstance)?
+            .clone();
 
-        if !notification_settings.events.is_instance_created_enabled {
-            return Ok(()); // Project does not have this notification enabled
-        }
+        let remaining_instances: Vec<_> = config_details
+            .instances
+            .into_iter()
+            .filter(|i| i.instance != instance)
+            .collect();
 
-        let notification_adapter: Arc<dyn YakManNotificationAdapter + Send + Sync> =
-            notification_settings.settings.into();
-        notification_adapter
-            .send_notification(YakManNotificationType::InstanceCreated {
-                project_name: project.name.to_string(),
-                config_name: config.name,
-                instance: instance.to_string(),
-            })
+        config_details.instances = remaining_instances;
+
+        self.adapter
+            .save_config_details(config_id, &config_details)
             .await?;
 
+        for revision in config_instance.revisions {
+            if let Err(e) = self.adapter.delete_revision(config_id, &revision).await {
+                log::error!("Failed to delete revision ({revision}) {e:?}");
+            }
+        }
+
         return Ok(());
     }
 
-    async fn send_submitted_notification(
+    async fn set_user_deactivated_at(
+        &self,
+        user_id: &str,
+        deactivated_at: Option<i64>,
+    ) -> Result<(), UpdateUserError> {
+        let mut users = self.adapter.get_users().await?;
+        let Some(user) = users.iter_mut().find(|u| u.id == user_id) else {
+            return Err(UpdateUserError::UserNotFound);
+        };
+        user.deactivated_at = deactivated_at;
+
+        let Some(mut details) = self.adapter.get_user_details(user_id).await? else {
+            return Err(UpdateUserError::UserNotFound);
+        };
+        details.deactivated_at = deactivated_at;
+
+        // Save the details first, as they are what is checked when validating existing tokens
+        self.adapter.save_user_details(user_id, &details).await?;
+        self.adapter.save_users(&users).await?;
+
+        return Ok(());
+    }
+
+    async fn teams_exist(&self, team_ids: &[String]) -> Result<bool, GenericStorageError> {
+        let teams = self.adapter.get_teams().await?;
+        return Ok(team_ids
+            .iter()
+            .all(|team_id| teams.iter().any(|t| &t.id == team_id)));
+    }
+
+    async fn send_instance_created_notification(
         &self,
         config_id: &str,
         instance: &str,
-        revision: &str,
     ) -> anyhow::Result<()> {
         let (project, config) = self.get_data_to_send_notification(config_id).await?;
 
-        let Some(notification_settings) = project.notification_settings else {
-            return Ok(()); // No notification settings configured for project
+        let event = YakManNotificationType::InstanceCreated {
+            project_name: project.name.to_string(),
+            config_name: config.name,
+            instance: instance.to_string(),
         };
+        return self
+            .enqueue_notification(&project, |e| e.is_instance_created_enabled, event)
+            .await;
+    }
 
-        if !notification_settings.events.is_revision_submitted_enabled {
-            return Ok(()); // Project does not have this notification enabled
-        }
-
-        let notification_adapter: Arc<dyn YakManNotificationAdapter + Send + Sync> =
-            notification_settings.settings.into();
-        notification_adapter
-            .send_notification(YakManNotificationType::RevisionReviewSubmitted {
-                project_name: project.name.to_string(),
-                config_name: config.name,
-                instance: instance.to_string(),
-                revision: revision.to_string(),
-            })
-            .await?;
+    async fn send_submitted_notification(
+        &self,
+        config_id: &str,
+        instance: &str,
+        revision: &ConfigInstanceRevision,
+    ) -> anyhow::Result<()> {
+        let (project, config) = self.get_data_to_send_notification(config_id).await?;
 
-        return Ok(());
+        let event = YakManNotificationType::RevisionReviewSubmitted {
+            project_name: project.name.to_string(),
+            config_name: config.name,
+            instance: instance.to_string(),
+            revision: revision.revision.to_string(),
+            change_details: revision.change_details.clone(),
+        };
+        return self
+            .enqueue_notification(&project, |e| e.is_revision_submitted_enabled, event)
+            .await;
     }
 
     async fn send_approved_notification(
@@ -1493,29 +2930,20 @@ impl KVStorageService {
         config_id: &str,
         instance: &str,
         revision: &str,
+        comment: Option<String>,
     ) -> anyhow::Result<()> {
         let (project, config) = self.get_data_to_send_notification(config_id).await?;
 
-        let Some(notification_settings) = project.notification_settings else {
-            return Ok(()); // No notification settings configured for project
+        let event = YakManNotificationType::RevisionReviewApproved {
+            project_name: project.name.to_string(),
+            config_name: config.name,
+            instance: instance.to_string(),
+            revision: revision.to_string(),
+            comment: comment,
         };
-
-        if !notification_settings.events.is_revision_approved_enabled {
-            return Ok(()); // Project does not have this notification enabled
-        }
-
-        let notification_adapter: Arc<dyn YakManNotificationAdapter + Send + Sync> =
-            notification_settings.settings.into();
-        notification_adapter
-            .send_notification(YakManNotificationType::RevisionReviewApproved {
-                project_name: project.name.to_string(),
-                config_name: config.name,
-                instance: instance.to_string(),
-                revision: revision.to_string(),
-            })
-            .await?;
-
-        return Ok(());
+        return self
+            .enqueue_notification(&project, |e| e.is_revision_approved_enabled, event)
+            .await;
     }
 
     async fn send_applied_notification(
@@ -1523,61 +2951,277 @@ impl KVStorageService {
         config_id: &str,
         instance: &str,
         revision: &str,
+        change_details: RevisionChangeDetails,
     ) -> anyhow::Result<()> {
         let (project, config) = self.get_data_to_send_notification(config_id).await?;
 
-        let Some(notification_settings) = project.notification_settings else {
-            return Ok(()); // No notification settings configured for project
+        let event = YakManNotificationType::RevisionReviewApplied {
+            project_name: project.name.to_string(),
+            config_name: config.name,
+            instance: instance.to_string(),
+            revision: revision.to_string(),
+            change_details: change_details,
         };
+        return self
+            .enqueue_notification(&project, |e| e.is_instance_updated_enabled, event)
+            .await;
+    }
 
-        if !notification_settings.events.is_instance_updated_enabled {
-            return Ok(()); // Project does not have this notification enabled
-        }
-
-        let notification_adapter: Arc<dyn YakManNotificationAdapter + Send + Sync> =
-            notification_settings.settings.into();
-        notification_adapter
-            .send_notification(YakManNotificationType::RevisionReviewApplied {
-                project_name: project.name.to_string(),
-                config_name: config.name,
-                instance: instance.to_string(),
-                revision: revision.to_string(),
-            })
-            .await?;
+    async fn send_reject_notification(
+        &self,
+        config_id: &str,
+        instance: &str,
+        revision: &str,
+        reason: &str,
+    ) -> anyhow::Result<()> {
+        let (project, config) = self.get_data_to_send_notification(config_id).await?;
 
-        return Ok(());
+        let event = YakManNotificationType::RevisionReviewRejected {
+            project_name: project.name.to_string(),
+            config_name: config.name.to_string(),
+            instance: instance.to_string(),
+            revision: revision.to_string(),
+            reason: reason.to_string(),
+        };
+        return self
+            .enqueue_notification(&project, |e| e.is_revision_reject_enabled, event)
+            .await;
     }
 
-    async fn send_reject_notification(
+    async fn send_rollback_notification(
         &self,
         config_id: &str,
         instance: &str,
         revision: &str,
+        rollback_to_revision: &str,
     ) -> anyhow::Result<()> {
         let (project, config) = self.get_data_to_send_notification(config_id).await?;
 
-        let Some(notification_settings) = project.notification_settings else {
-            return Ok(()); // No notification settings configured for project
+        let event = YakManNotificationType::RevisionRollbackSubmitted {
+            project_name: project.name.to_string(),
+            config_name: config.name,
+            instance: instance.to_string(),
+            revision: revision.to_string(),
+            rollback_to_revision: rollback_to_revision.to_string(),
         };
+        return self
+            .enqueue_notification(&project, |e| e.is_revision_rollback_enabled, event)
+            .await;
+    }
 
-        if !notification_settings.events.is_revision_reject_enabled {
-            return Ok(()); // Project does not have this notification enabled
-        }
+    async fn send_instance_deleted_notification(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> anyhow::Result<()> {
+        let (project, config) = self.get_data_to_send_notification(config_id).await?;
 
-        let notification_adapter: Arc<dyn YakManNotificationAdapter + Send + Sync> =
-            notification_settings.settings.into();
-        notification_adapter
-            .send_notification(YakManNotificationType::RevisionReviewRejected {
-                project_name: project.name.to_string(),
-                config_name: config.name.to_string(),
-                instance: instance.to_string(),
-                revision: revision.to_string(),
-            })
-            .await?;
+        let event = YakManNotificationType::InstanceDeleted {
+            project_name: project.name.to_string(),
+            config_name: config.name,
+            instance: instance.to_string(),
+        };
+        return self
+            .enqueue_notification(&project, |e| e.is_instance_deleted_enabled, event)
+            .await;
+    }
+
+    async fn send_config_deleted_notification(&self, config_id: &str) -> anyhow::Result<()> {
+        let (project, config) = self.get_data_to_send_notification(config_id).await?;
+
+        let event = YakManNotificationType::ConfigDeleted {
+            project_name: project.name.to_string(),
+            config_name: config.name,
+        };
+        return self
+            .enqueue_notification(&project, |e| e.is_config_deleted_enabled, event)
+            .await;
+    }
+
+    async fn send_project_settings_updated_notification(
+        &self,
+        project: &YakManProjectDetails,
+        changes: Vec<String>,
+    ) -> anyhow::Result<()> {
+        let event = YakManNotificationType::ProjectSettingsUpdated {
+            project_name: project.name.to_string(),
+            changes: changes,
+        };
+        return self
+            .enqueue_notification(project, |e| e.is_project_settings_updated_enabled, event)
+            .await;
+    }
+
+    async fn send_api_key_created_notification(
+        &self,
+        api_key: &YakManApiKey,
+    ) -> anyhow::Result<()> {
+        // Service account keys do not belong to a project
+        let (Some(project_id), Some(role)) = (&api_key.project_id, &api_key.role) else {
+            return Ok(());
+        };
+
+        let Some(project) = self.adapter.get_project_details(project_id).await? else {
+            bail!("Could not find project {project_id}")
+        };
+
+        let event = YakManNotificationType::ApiKeyCreated {
+            project_name: project.name.to_string(),
+            api_key_id: api_key.id.to_string(),
+            role: role.clone(),
+        };
+        return self
+            .enqueue_notification(&project, |e| e.is_api_key_created_enabled, event)
+            .await;
+    }
+
+    /// Adds a delivery to the notification outbox for every channel of the project that has the event enabled.
+    /// The deliveries are sent by the notification delivery worker.
+    async fn enqueue_notification(
+        &self,
+        project: &YakManProjectDetails,
+        is_event_enabled: fn(&NotificationSettingEvents) -> bool,
+        event: YakManNotificationType,
+    ) -> anyhow::Result<()> {
+        let now = Utc::now().timestamp_millis();
+
+        for channel in &project.notification_settings {
+            if !is_event_enabled(&channel.events) {
+                continue; // Channel does not have this notification enabled
+            }
+
+            let settings = self
+                .resolve_notification_setting(&project.id, channel.settings.clone())
+                .await?;
+
+            let delivery = YakManNotificationDelivery {
+                id: generate_notification_delivery_id(),
+                project_id: project.id.to_string(),
+                settings: settings,
+                event: event.clone(),
+                status: NotificationDeliveryStatus::Pending,
+                attempts: 0,
+                created_at_ms: now,
+                next_attempt_at_ms: now,
+                last_attempt_at_ms: None,
+                last_error: None,
+            };
+            self.adapter
+                .save_pending_notification_delivery(&delivery)
+                .await?;
+        }
 
         return Ok(());
     }
 
+    /// Resolves the project approvers of email channels, so the delivery worker does not need to
+    /// look up role bindings and the recipients reflect the roles at the time of the event.
+    async fn resolve_notification_setting(
+        &self,
+        project_id: &str,
+        setting: NotificationSetting,
+    ) -> anyhow::Result<NotificationSetting> {
+        return match setting {
+            NotificationSetting::Email {
+                mut recipients,
+                send_to_project_approvers,
+            } => {
+                if send_to_project_approvers {
+                    recipients.extend(self.get_project_approver_emails(project_id).await?);
+                }
+                recipients.sort();
+                recipients.dedup();
+                Ok(NotificationSetting::Email {
+                    recipients: recipients,
+                    send_to_project_approvers: false,
+                })
+            }
+            setting => Ok(setting),
+        };
+    }
+
+    /// Whether the user is the only active user with the global admin role, directly or through a team
+    async fn is_last_active_admin(&self, user_id: &str) -> Result<bool, GenericStorageError> {
+        let mut is_admin = false;
+
+        for user in self.adapter.get_users().await? {
+            if user.is_deactivated() {
+                continue;
+            }
+            let Some(details) = self.adapter.get_user_details(&user.id).await? else {
+                continue;
+            };
+            if !details.global_roles.contains(&YakManRole::Admin)
+                && !self.has_admin_team(&details).await?
+            {
+                continue;
+            }
+
+            if user.id != user_id {
+                return Ok(false);
+            }
+            is_admin = true;
+        }
+
+        return Ok(is_admin);
+    }
+
+    async fn has_admin_team(
+        &self,
+        details: &YakManUserDetails,
+    ) -> Result<bool, GenericStorageError> {
+        for team_id in &details.team_ids {
+            if let Some(team) = self.adapter.get_team_details(team_id).await? {
+                if team.global_roles.contains(&YakManRole::Admin) {
+                    return Ok(true);
+                }
+            }
+        }
+        return Ok(false);
+    }
+
+    /// Gets the emails of all users who can approve revisions in a project, including
+    /// users who are granted the role through a team.
+    async fn get_project_approver_emails(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<String>, GenericStorageError> {
+        let mut team_role_bindings: HashMap<String, Vec<YakManRoleBinding>> = HashMap::new();
+        let mut emails = vec![];
+
+        for user in self.adapter.get_users().await? {
+            let Some(details) = self.adapter.get_user_details(&user.id).await? else {
+                continue;
+            };
+
+            let mut role_bindings: HashSet<YakManRoleBinding> =
+                role_bindings_from(details.global_roles, details.roles)
+                    .into_iter()
+                    .collect();
+
+            for team_id in &details.team_ids {
+                if !team_role_bindings.contains_key(team_id) {
+                    let bindings = match self.adapter.get_team_details(team_id).await? {
+                        Some(team) => role_bindings_from(team.global_roles, team.roles),
+                        None => vec![],
+                    };
+                    team_role_bindings.insert(team_id.to_string(), bindings);
+                }
+                role_bindings.extend(team_role_bindings[team_id].clone());
+            }
+
+            if YakManRoleBinding::has_any_role(
+                vec![YakManRole::Admin, YakManRole::Approver],
+                project_id,
+                &role_bindings,
+            ) {
+                emails.push(user.email);
+            }
+        }
+
+        return Ok(emails);
+    }
+
     async fn get_data_to_send_notification(
         &self,
         config_id: &str,
@@ -1601,11 +3245,22 @@ impl KVStorageService {
         let api_key_hash_cache = CacheBuilder::new(10_000)
             .time_to_live(Duration::from_secs(60))
             .build();
+        let api_key_last_used_cache = CacheBuilder::new(10_000)
+            .time_to_live(Duration::from_millis(
+                API_KEY_LAST_USED_UPDATE_INTERVAL_MS as u64,
+            ))
+            .build();
+
+        let password_policy = PasswordPolicy::from_settings()
+            .expect("Failed to load YAKMAN_PASSWORD_BREACHED_LIST_FILE");
 
         KVStorageService {
             adapter: adapter,
             api_key_id_cache,
             api_key_hash_cache,
+            api_key_last_used_cache,
+            password_policy,
+            audit_exporter: AuditExporter::from_settings(),
         }
     }
 
@@ -1641,4 +3296,196 @@ impl KVStorageService {
         }
         return Ok(true);
     }
+
+    /// Checks the revision has the approvals the current policy requires,
+    /// which may have changed since the revision was approved
+    async fn verify_revision_approvals(
+        &self,
+        config_id: &str,
+        revision: &ConfigInstanceRevision,
+    ) -> Result<(), ApplyRevisionError> {
+        if revision.review_state == RevisionReviewState::Rejected {
+            return Err(ApplyRevisionError::NotApproved);
+        }
+
+        let required_approvals = self
+            .get_approval_policy(config_id)
+            .await?
+            .required_approvals_for(&revision.labels);
+        let approvals = revision.approval_count();
+        if approvals < required_approvals {
+            return Err(ApplyRevisionError::NotEnoughApprovals {
+                required: required_approvals,
+                approvals: approvals,
+            });
+        }
+        return Ok(());
+    }
+
+    /// Removes the scheduled apply of an instance, if there is one
+    async fn remove_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<Option<YakManScheduledApply>, GenericStorageError> {
+        let Some(removed) = self
+            .adapter
+            .get_scheduled_apply(config_id, instance)
+            .await?
+        else {
+            return Ok(None);
+        };
+        self.adapter
+            .delete_scheduled_apply(config_id, instance)
+            .await?;
+        return Ok(Some(removed));
+    }
+
+    /// Adds an event to the changelog of an instance
+    async fn push_instance_event(
+        &self,
+        config_id: &str,
+        instance: &str,
+        event: ConfigInstanceEventData,
+    ) -> Result<(), GenericStorageError> {
+        let Some(mut config_details) = self.adapter.get_config_details(config_id).await? else {
+            return Ok(());
+        };
+        let Some(instance) = config_details
+            .instances
+            .iter_mut()
+            .find(|i| i.instance == instance)
+        else {
+            return Ok(());
+        };
+        instance.changelog.push(ConfigInstanceEvent {
+            event: event,
+            timestamp_ms: Utc::now().timestamp_millis(),
+        });
+        self.adapter
+            .save_config_details(config_id, &config_details)
+            .await?;
+        return Ok(());
+    }
+
+    /// The approval policy of the config's project
+    async fn get_project_details_for_config(
+        &self,
+        config_id: &str,
+    ) -> Result<Option<YakManProjectDetails>, GenericStorageError> {
+        let configs = self.adapter.get_configs().await?;
+        let Some(config) = configs.iter().find(|c| c.id == config_id) else {
+            return Ok(None);
+        };
+        return self.adapter.get_project_details(&config.project_id).await;
+    }
+
+    async fn get_approval_policy(
+        &self,
+        config_id: &str,
+    ) -> Result<YakManApprovalPolicy, GenericStorageError> {
+        let policy = self
+            .get_project_details_for_config(config_id)
+            .await?
+            .map(|details| details.approval_policy)
+            .unwrap_or_default();
+        return Ok(policy);
+    }
+
+    /// The first freeze window of the config's project that is active at `at`
+    async fn get_active_freeze_window(
+        &self,
+        config_id: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Option<YakManFreezeWindow>, GenericStorageError> {
+        let freeze_window = self
+            .get_project_details_for_config(config_id)
+            .await?
+            .and_then(|details| {
+                details
+                    .freeze_windows
+                    .into_iter()
+                    .find(|window| window.is_active_at(at))
+            });
+        return Ok(freeze_window);
+    }
+
+    /// Returns the reason the change is blocked by an active freeze window.
+    /// Overriding the freeze with `break_glass` is recorded in the audit log.
+    async fn check_change_freeze(
+        &self,
+        config_id: &str,
+        instance: &str,
+        action: YakManAuditAction,
+        break_glass: bool,
+    ) -> Result<Option<String>, GenericStorageError> {
+        let Some(freeze_window) = self.get_active_freeze_window(config_id, Utc::now()).await?
+        else {
+            return Ok(None);
+        };
+
+        if !break_glass {
+            return Ok(Some(freeze_window.reason));
+        }
+
+        log::warn!("Freeze window overridden for instance {instance} of config {config_id}");
+        self.record_audit(
+            YakManAuditAction::BreakGlass,
+            YakManAuditTargetType::ConfigInstance,
+            instance,
+            None,
+            Some(json!({
+                "config_id": config_id,
+                "action": action,
+                "freeze_window": freeze_window,
+            })),
+        )
+        .await;
+        return Ok(None);
+    }
+
+    /// The identities a user or api key acts as.
+    /// Api keys act on behalf of the user that created them and, for service account keys, the service account.
+    async fn get_principal_identities(
+        &self,
+        principal_id: &str,
+    ) -> Result<Vec<String>, GenericStorageError> {
+        let mut identities = vec![principal_id.to_string()];
+        if let Some(api_key) = self.get_api_key_by_id(principal_id).await? {
+            identities.push(api_key.created_by_user_id);
+            if let Some(service_account_id) = api_key.service_account_id {
+                identities.push(service_account_id);
+            }
+        }
+        return Ok(identities);
+    }
+
+    /// True if both principals are, or act on behalf of, the same user or service account.
+    /// Role bindings granted through teams do not matter here since identities are compared directly.
+    async fn is_same_principal(
+        &self,
+        principal_id: &str,
+        other_principal_id: &str,
+    ) -> Result<bool, GenericStorageError> {
+        let identities = self.get_principal_identities(principal_id).await?;
+        let other_identities = self.get_principal_identities(other_principal_id).await?;
+        return Ok(identities.iter().any(|id| other_identities.contains(id)));
+    }
+}
+
+fn role_bindings_from(
+    global_roles: Vec<YakManRole>,
+    project_roles: Vec<YakManProjectRole>,
+) -> Vec<YakManRoleBinding> {
+    let global_role_bindings = global_roles
+        .into_iter()
+        .map(YakManRoleBinding::GlobalRoleBinding);
+    let project_role_bindings = project_roles
+        .into_iter()
+        .map(YakManRoleBinding::ProjectRoleBinding);
+    return global_role_bindings.chain(project_role_bindings).collect();
+}
+
+fn was_recently_used(last_used_at: i64, now_ms: i64) -> bool {
+    return now_ms - last_used_at < API_KEY_LAST_USED_UPDATE_INTERVAL_MS;
 }
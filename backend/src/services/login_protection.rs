@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use crate::{adapters::errors::GenericStorageError, model::YakManLoginAttempts, settings};
+
+use super::StorageService;
+
+/// Failed attempts allowed before each attempt has to wait for a delay
+const DELAY_AFTER_FAILED_ATTEMPTS: u32 = 3;
+const BASE_DELAY_MS: i64 = 1000;
+const MAX_DELAY_MS: i64 = 60 * 1000;
+
+#[derive(Debug, PartialEq)]
+pub enum LoginThrottle {
+    Allowed,
+    /// Too many recent failures, the client must wait before trying again
+    Delayed {
+        retry_after_ms: i64,
+    },
+    Locked {
+        retry_after_ms: i64,
+    },
+}
+
+impl LoginThrottle {
+    fn retry_after_ms(&self) -> i64 {
+        return match self {
+            LoginThrottle::Allowed => 0,
+            LoginThrottle::Delayed { retry_after_ms } => *retry_after_ms,
+            LoginThrottle::Locked { retry_after_ms } => *retry_after_ms,
+        };
+    }
+}
+
+/// Brute-force protection for password logins.
+/// Failures are counted per account and per client IP in storage so the limits apply across replicas.
+/// After a few failures, attempts have to wait for an exponentially increasing delay,
+/// and after too many failures the account (or IP) is locked for the lockout duration.
+pub struct LoginProtection {
+    pub max_failed_attempts: u32,
+    pub max_failed_attempts_per_ip: u32,
+    pub lockout_duration_ms: i64,
+}
+
+impl LoginProtection {
+    pub fn from_settings() -> LoginProtection {
+        return LoginProtection {
+            max_failed_attempts: settings::login_max_failed_attempts(),
+            max_failed_attempts_per_ip: settings::login_max_failed_attempts_per_ip(),
+            lockout_duration_ms: settings::login_lockout_seconds() * 1000,
+        };
+    }
+
+    /// Returns the most restrictive throttle of the account and IP
+    pub async fn check(
+        &self,
+        storage_service: &Arc<dyn StorageService>,
+        email: &str,
+        ip: &str,
+        now: i64,
+    ) -> Result<LoginThrottle, GenericStorageError> {
+        let account_attempts = storage_service
+            .get_login_attempts(&account_key(email))
+            .await?;
+        let ip_attempts = storage_service.get_login_attempts(&ip_key(ip)).await?;
+
+        let account_throttle = self.evaluate(account_attempts.as_ref(), now);
+        let ip_throttle = self.evaluate(ip_attempts.as_ref(), now);
+
+        return Ok(match (&account_throttle, &ip_throttle) {
+            (LoginThrottle::Locked { .. }, _) => account_throttle,
+            (_, LoginThrottle::Locked { .. }) => ip_throttle,
+            _ if ip_throttle.retry_after_ms() > account_throttle.retry_after_ms() => ip_throttle,
+            _ => account_throttle,
+        });
+    }
+
+    pub async fn record_failure(
+        &self,
+        storage_service: &Arc<dyn StorageService>,
+        email: &str,
+        ip: &str,
+        now: i64,
+    ) -> Result<(), GenericStorageError> {
+        for (key, max_failed_attempts) in [
+            (account_key(email), self.max_failed_attempts),
+            (ip_key(ip), self.max_failed_attempts_per_ip),
+        ] {
+            let attempts = storage_service.get_login_attempts(&key).await?;
+            let attempts = self.next_attempts(attempts, max_failed_attempts, now);
+            if attempts.locked_until.is_some() {
+                log::warn!("Too many failed logins, locking {key}");
+            }
+            storage_service.save_login_attempts(&key, &attempts).await?;
+        }
+        return Ok(());
+    }
+
+    /// Clears the account's failures. The IP counter is left to expire
+    /// so a valid login does not reset the limit for guessing other accounts.
+    pub async fn record_success(
+        &self,
+        storage_service: &Arc<dyn StorageService>,
+        email: &str,
+    ) -> Result<(), GenericStorageError> {
+        return storage_service
+            .reset_login_attempts(&account_key(email))
+            .await;
+    }
+
+    fn evaluate(&self, attempts: Option<&YakManLoginAttempts>, now: i64) -> LoginThrottle {
+        let Some(attempts) = attempts else {
+            return LoginThrottle::Allowed;
+        };
+
+        if let Some(locked_until) = attempts.locked_until {
+            if locked_until > now {
+                return LoginThrottle::Locked {
+                    retry_after_ms: locked_until - now,
+                };
+            }
+            return LoginThrottle::Allowed;
+        }
+
+        if attempts.failed_attempts < DELAY_AFTER_FAILED_ATTEMPTS {
+            return LoginThrottle::Allowed;
+        }
+
+        let retry_at = attempts.last_failed_at + delay_ms(attempts.failed_attempts);
+        if retry_at > now {
+            return LoginThrottle::Delayed {
+                retry_after_ms: retry_at - now,
+            };
+        }
+        return LoginThrottle::Allowed;
+    }
+
+    fn next_attempts(
+        &self,
+        attempts: Option<YakManLoginAttempts>,
+        max_failed_attempts: u32,
+        now: i64,
+    ) -> YakManLoginAttempts {
+        // Failures are forgotten once the lockout duration has passed since the last one
+        let failed_attempts = match attempts {
+            Some(attempts) if attempts.last_failed_at + self.lockout_duration_ms > now => {
+                attempts.failed_attempts + 1
+            }
+            _ => 1,
+        };
+
+        return YakManLoginAttempts {
+            failed_attempts: failed_attempts,
+            last_failed_at: now,
+            locked_until: (failed_attempts >= max_failed_attempts)
+                .then_some(now + self.lockout_duration_ms),
+        };
+    }
+}
+
+fn delay_ms(failed_attempts: u32) -> i64 {
+    let exponent = failed_attempts
+        .saturating_sub(DELAY_AFTER_FAILED_ATTEMPTS)
+        .min(16);
+    return (BASE_DELAY_MS << exponent).min(MAX_DELAY_MS);
+}
+
+/// Storage keys are hashed so emails and IPs can be used as file or object names
+pub fn account_key(email: &str) -> String {
+    return sha256::digest(format!("account:{}", email.to_lowercase()));
+}
+
+fn ip_key(ip: &str) -> String {
+    return sha256::digest(format!("ip:{ip}"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protection() -> LoginProtection {
+        return LoginProtection {
+            max_failed_attempts: 5,
+            max_failed_attempts_per_ip: 20,
+            lockout_duration_ms: 60_000,
+        };
+    }
+
+    #[test]
+    fn should_delay_then_lock_after_repeated_failures() {
+        let protection = protection();
+        let mut attempts = None;
+
+        for i in 0..3 {
+            assert_eq!(
+                LoginThrottle::Allowed,
+                protection.evaluate(attempts.as_ref(), i)
+            );
+            attempts = Some(protection.next_attempts(attempts, 5, i));
+        }
+
+        // 3 failures, the next attempt has to wait 1 second
+        assert_eq!(
+            LoginThrottle::Delayed {
+                retry_after_ms: 998
+            },
+            protection.evaluate(attempts.as_ref(), 4)
+        );
+        assert_eq!(
+            LoginThrottle::Allowed,
+            protection.evaluate(attempts.as_ref(), 1002)
+        );
+
+        attempts = Some(protection.next_attempts(attempts, 5, 1002));
+        // The delay doubles with each failure
+        assert_eq!(
+            LoginThrottle::Delayed {
+                retry_after_ms: 2000
+            },
+            protection.evaluate(attempts.as_ref(), 1002)
+        );
+
+        attempts = Some(protection.next_attempts(attempts, 5, 3002));
+        assert_eq!(Some(63_002), attempts.as_ref().unwrap().locked_until);
+        assert_eq!(
+            LoginThrottle::Locked {
+                retry_after_ms: 60_000
+            },
+            protection.evaluate(attempts.as_ref(), 3002)
+        );
+        assert_eq!(
+            LoginThrottle::Allowed,
+            protection.evaluate(attempts.as_ref(), 63_002)
+        );
+
+        // After the lockout the counter starts over
+        let attempts = protection.next_attempts(attempts, 5, 63_003);
+        assert_eq!(1, attempts.failed_attempts);
+        assert_eq!(None, attempts.locked_until);
+    }
+
+    #[test]
+    fn delay_should_be_capped() {
+        assert_eq!(1000, delay_ms(3));
+        assert_eq!(4000, delay_ms(5));
+        assert_eq!(MAX_DELAY_MS, delay_ms(30));
+    }
+}
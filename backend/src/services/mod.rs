@@ -1,26 +1,45 @@
+pub mod audit;
+pub mod audit_export;
+pub mod email;
 pub mod id;
 pub mod kv_storage_service;
+pub mod login_protection;
+pub mod notification_delivery;
 pub mod password;
+pub mod refresh_token_sweep;
+pub mod scheduled_apply;
 pub mod snapshot;
+pub mod totp;
 
+use std::collections::HashMap;
+
+use self::audit::AuditLogFilter;
 use crate::{
     adapters::errors::GenericStorageError,
     api::{
         projects::ProjectNotificationSettings,
+        service_accounts::ServiceAccountPayload,
         teams::{CreateTeamPayload, UpdateTeamPayload},
+        users::UpdateUserRolesPayload,
     },
     error::{
-        ApplyRevisionError, ApproveRevisionError, CreateConfigError, CreateConfigInstanceError,
-        CreateLabelError, CreatePasswordResetLinkError, CreateProjectError, CreateTeamError,
-        DeleteConfigError, DeleteConfigInstanceError, DeleteLabelError, DeleteProjectError,
-        DeleteTeamError, ResetPasswordError, RollbackRevisionError, SaveConfigInstanceError,
-        UpdateLabelError, UpdateProjectError, UpdateTeamError,
+        AddRevisionCommentError, ApplyRevisionError, ApproveRevisionError, ChangePasswordError,
+        CreateConfigError, CreateConfigInstanceError, CreateLabelError,
+        CreatePasswordResetLinkError, CreateProjectError, CreateServiceAccountError,
+        CreateTeamError, DeleteConfigError, DeleteConfigInstanceError, DeleteLabelError,
+        DeleteProjectError, DeleteServiceAccountError, DeleteTeamError, DeleteUserError,
+        ResetPasswordError, RollbackRevisionError, SaveConfigInstanceError, ScheduleApplyError,
+        UpdateLabelError, UpdateProjectError, UpdateServiceAccountError, UpdateTeamError,
+        UpdateUserError,
     },
     model::{
         request::CreateYakManUserPayload, ConfigInstance, ConfigInstanceRevision, LabelType,
-        YakManApiKey, YakManConfig, YakManLabel, YakManPassword, YakManProject,
-        YakManProjectDetails, YakManPublicPasswordResetLink, YakManTeam, YakManTeamDetails,
-        YakManUser, YakManUserDetails,
+        RevisionChangeDetails, RevisionComment, YakManApiKey, YakManApiKeyUsage,
+        YakManApprovalPolicy, YakManAuditLogEntry, YakManConfig, YakManFreezeWindow, YakManLabel,
+        YakManLoginAttempts, YakManNotificationDelivery, YakManPassword, YakManProject,
+        YakManProjectDetails, YakManPublicPasswordResetLink, YakManScheduledApply,
+        YakManServiceAccount, YakManTeam, YakManTeamDetails, YakManTotp, YakManUser,
+        YakManUserDetails,
     },
 };
 use async_trait::async_trait;
@@ -34,17 +53,24 @@ pub trait StorageService: Sync + Send {
         project_id: &str,
     ) -> Result<Option<YakManProjectDetails>, GenericStorageError>;
 
+    async fn get_notification_deliveries(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError>;
+
     async fn create_project(
         &self,
         project_name: &str,
-        notification_settings: Option<ProjectNotificationSettings>,
+        notification_settings: Vec<ProjectNotificationSettings>,
     ) -> Result<String, CreateProjectError>;
 
     async fn update_project(
         &self,
         project_id: &str,
         project_name: &str,
-        notification_settings: Option<ProjectNotificationSettings>,
+        notification_settings: Vec<ProjectNotificationSettings>,
+        approval_policy: YakManApprovalPolicy,
+        freeze_windows: Vec<YakManFreezeWindow>,
     ) -> Result<(), UpdateProjectError>;
 
     async fn delete_project(&self, project_id: &str) -> Result<(), DeleteProjectError>;
@@ -75,6 +101,7 @@ pub trait StorageService: Sync + Send {
 
     async fn delete_config(&self, config_id: &str) -> Result<(), DeleteConfigError>;
 
+    /// `break_glass` creates the instance even if the project has an active freeze window
     async fn create_config_instance(
         &self,
         config_id: &str,
@@ -82,6 +109,7 @@ pub trait StorageService: Sync + Send {
         data: &str,
         content_type: Option<String>,
         creator_user_id: &str,
+        break_glass: bool,
     ) -> Result<String, CreateConfigInstanceError>;
 
     async fn get_config_instance(
@@ -114,6 +142,7 @@ pub trait StorageService: Sync + Send {
     ) -> Result<Option<(String, String)>, GenericStorageError>;
 
     /// Creates a new revision pending approval
+    #[allow(clippy::too_many_arguments)]
     async fn submit_new_instance_revision(
         &self,
         config_id: &str,
@@ -121,6 +150,7 @@ pub trait StorageService: Sync + Send {
         labels: Vec<YakManLabel>,
         data: &str,
         content_type: Option<String>,
+        change_details: RevisionChangeDetails,
         submitted_by_user_id: &str,
     ) -> Result<String, SaveConfigInstanceError>;
 
@@ -130,20 +160,24 @@ pub trait StorageService: Sync + Send {
         instance: &str,
     ) -> Result<Option<Vec<ConfigInstanceRevision>>, GenericStorageError>;
 
+    /// Returns true once the revision has every approval it requires
     async fn approve_instance_revision(
         &self,
         config_id: &str,
         instance: &str,
         revision: &str,
         approved_user_id: &str,
-    ) -> Result<(), ApproveRevisionError>;
+        comment: Option<String>,
+    ) -> Result<bool, ApproveRevisionError>;
 
+    /// `break_glass` applies the revision even if the project has an active freeze window
     async fn apply_instance_revision(
         &self,
         config_id: &str,
         instance: &str,
         revision: &str,
         applied_by_user_id: &str,
+        break_glass: bool,
     ) -> Result<(), ApplyRevisionError>;
 
     async fn reject_instance_revision(
@@ -152,8 +186,19 @@ pub trait StorageService: Sync + Send {
         instance: &str,
         revision: &str,
         rejected_by_user_id: &str,
+        reason: &str,
     ) -> Result<(), ApplyRevisionError>;
 
+    /// Adds a comment to a revision of the instance
+    async fn add_revision_comment(
+        &self,
+        config_id: &str,
+        instance: &str,
+        revision: &str,
+        user_id: &str,
+        message: &str,
+    ) -> Result<RevisionComment, AddRevisionCommentError>;
+
     async fn rollback_instance_revision(
         &self,
         config_id: &str,
@@ -190,6 +235,41 @@ pub trait StorageService: Sync + Send {
         details: YakManUserDetails,
     ) -> Result<(), GenericStorageError>;
 
+    async fn update_user_roles(
+        &self,
+        user_id: &str,
+        payload: UpdateUserRolesPayload,
+    ) -> Result<(), UpdateUserError>;
+
+    /// Deactivated users keep their data but can no longer login or use existing tokens
+    async fn deactivate_user(&self, user_id: &str) -> Result<(), UpdateUserError>;
+
+    async fn reactivate_user(&self, user_id: &str) -> Result<(), UpdateUserError>;
+
+    /// Invalidates all access and refresh tokens issued to the user
+    async fn revoke_user_sessions(&self, user_id: &str) -> Result<(), UpdateUserError>;
+
+    /// Invalidates a single refresh token, leaving the user's other sessions active
+    async fn revoke_refresh_token(&self, refresh_token: &str) -> Result<(), GenericStorageError>;
+
+    async fn is_refresh_token_revoked(
+        &self,
+        refresh_token: &str,
+    ) -> Result<bool, GenericStorageError>;
+
+    /// Adds the user to each team in `member_team_ids` and removes them from the other `managed_team_ids`.
+    /// Memberships of teams outside of `managed_team_ids` are left as is.
+    async fn sync_user_team_memberships(
+        &self,
+        user_id: &str,
+        managed_team_ids: &[String],
+        member_team_ids: &[String],
+    ) -> Result<(), UpdateUserError>;
+
+    /// Removes the user, their password and team memberships.
+    /// References to the user id in revisions and other history are kept as is.
+    async fn delete_user(&self, user_id: &str) -> Result<(), DeleteUserError>;
+
     async fn get_teams(&self) -> Result<Vec<YakManTeam>, GenericStorageError>;
 
     async fn get_team_details(
@@ -207,6 +287,28 @@ pub trait StorageService: Sync + Send {
 
     async fn delete_team(&self, team_id: &str) -> Result<(), DeleteTeamError>;
 
+    async fn get_service_accounts(&self) -> Result<Vec<YakManServiceAccount>, GenericStorageError>;
+
+    async fn get_service_account(
+        &self,
+        id: &str,
+    ) -> Result<Option<YakManServiceAccount>, GenericStorageError>;
+
+    async fn create_service_account(
+        &self,
+        payload: ServiceAccountPayload,
+        created_by_user_id: &str,
+    ) -> Result<String, CreateServiceAccountError>;
+
+    async fn update_service_account(
+        &self,
+        id: &str,
+        payload: ServiceAccountPayload,
+    ) -> Result<(), UpdateServiceAccountError>;
+
+    /// Deletes the service account and all of its api keys
+    async fn delete_service_account(&self, id: &str) -> Result<(), DeleteServiceAccountError>;
+
     async fn get_api_keys(&self) -> Result<Vec<YakManApiKey>, GenericStorageError>;
 
     async fn get_api_key_by_id(
@@ -223,27 +325,111 @@ pub trait StorageService: Sync + Send {
 
     async fn delete_api_key(&self, id: &str) -> Result<(), GenericStorageError>;
 
+    /// Returns the usage of the api keys that have been used, keyed by api key ID
+    async fn get_api_key_usages(
+        &self,
+        ids: &[String],
+    ) -> Result<HashMap<String, YakManApiKeyUsage>, GenericStorageError>;
+
+    /// Records that an api key was used, unless it was recorded recently
+    async fn record_api_key_usage(&self, id: &str) -> Result<(), GenericStorageError>;
+
     async fn get_password_by_email(
         &self,
         email: &str,
     ) -> Result<Option<YakManPassword>, GenericStorageError>;
 
+    async fn get_login_attempts(
+        &self,
+        key: &str,
+    ) -> Result<Option<YakManLoginAttempts>, GenericStorageError>;
+
+    async fn save_login_attempts(
+        &self,
+        key: &str,
+        attempts: &YakManLoginAttempts,
+    ) -> Result<(), GenericStorageError>;
+
+    /// Clears the failed login counter, unlocking the account or IP
+    async fn reset_login_attempts(&self, key: &str) -> Result<(), GenericStorageError>;
+
+    async fn get_totp(&self, user_id: &str) -> Result<Option<YakManTotp>, GenericStorageError>;
+
+    async fn save_totp(&self, user_id: &str, totp: &YakManTotp) -> Result<(), GenericStorageError>;
+
+    /// Removes the user's second factor, they will have to enroll again if TOTP is required
+    async fn delete_totp(&self, user_id: &str) -> Result<(), GenericStorageError>;
+
     async fn create_password_reset_link(
         &self,
         user_id: &str,
     ) -> Result<YakManPublicPasswordResetLink, CreatePasswordResetLinkError>;
 
+    /// Creates a link for a new user to set their password, it expires after `YAKMAN_INVITATION_EXPIRATION_HOURS`
+    async fn create_user_invitation(
+        &self,
+        user_id: &str,
+    ) -> Result<YakManPublicPasswordResetLink, CreatePasswordResetLinkError>;
+
     async fn reset_password_with_link(
         &self,
         reset_link: YakManPublicPasswordResetLink,
         password: &str,
     ) -> Result<(), ResetPasswordError>;
 
+    /// Changes the user's password after verifying their current password
+    async fn change_password(
+        &self,
+        user_id: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<(), ChangePasswordError>;
+
     async fn validate_password_reset_link(
         &self,
         id: &str,
         user_id: &str,
     ) -> Result<bool, GenericStorageError>;
 
+    /// Returns every pending scheduled apply, in no particular order
+    async fn get_scheduled_applies(&self)
+        -> Result<Vec<YakManScheduledApply>, GenericStorageError>;
+
+    async fn get_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+    ) -> Result<Option<YakManScheduledApply>, GenericStorageError>;
+
+    /// Schedules an approved revision to be applied by the scheduled apply worker
+    async fn schedule_instance_revision_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+        revision: &str,
+        apply_at_ms: i64,
+        scheduled_by_user_id: &str,
+    ) -> Result<YakManScheduledApply, ScheduleApplyError>;
+
+    async fn cancel_scheduled_apply(
+        &self,
+        config_id: &str,
+        instance: &str,
+        revision: &str,
+        cancelled_by_user_id: &str,
+    ) -> Result<(), ScheduleApplyError>;
+
+    /// Applies a scheduled revision on behalf of the user that scheduled it
+    async fn run_scheduled_apply(
+        &self,
+        scheduled: &YakManScheduledApply,
+    ) -> Result<(), ApplyRevisionError>;
+
+    /// Returns the matching audit log entries, newest first
+    async fn get_audit_log(
+        &self,
+        filter: &AuditLogFilter,
+    ) -> Result<Vec<YakManAuditLogEntry>, GenericStorageError>;
+
     async fn initialize_storage(&self) -> Result<(), GenericStorageError>;
 }
@@ -0,0 +1,324 @@
+use std::{collections::HashSet, sync::Arc};
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    adapters::{errors::GenericStorageError, KVStorageAdapter},
+    model::{NotificationDeliveryStatus, YakManNotificationDelivery, YakManSnapshotLock},
+    notifications::YakManNotificationAdapter,
+    settings,
+};
+
+/// How often the worker checks the outbox for deliveries that are due
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+const INITIAL_RETRY_DELAY_MS: i64 = 30 * 1000;
+const MAX_RETRY_DELAY_MS: i64 = 60 * 60 * 1000;
+/// The number of delivered and failed deliveries kept per project for the delivery log
+const MAX_COMPLETED_DELIVERIES: usize = 500;
+
+pub struct NotificationDeliveryService {
+    adapter: Arc<dyn KVStorageAdapter>,
+}
+
+impl NotificationDeliveryService {
+    pub fn new(adapter: Arc<dyn KVStorageAdapter>) -> Self {
+        Self { adapter }
+    }
+
+    /// Sends every delivery that is due. Only the replica holding the lock sends them.
+    pub async fn deliver_pending_notifications(&self) {
+        match self.get_due_deliveries().await {
+            Ok(due_deliveries) if due_deliveries.is_empty() => return,
+            Ok(_) => {}
+            Err(err) => {
+                log::error!("Failed to load pending notification deliveries, {err:?}");
+                return;
+            }
+        }
+
+        let Some(lock) = self.try_take_lock().await else {
+            log::debug!("Notification delivery lock already taken");
+            return;
+        };
+        log::debug!(
+            "Aquired notification delivery lockfile, Lock ID: {}",
+            lock.lock.unwrap().id
+        );
+
+        if let Err(err) = self.deliver_due_notifications().await {
+            log::error!("Failed to deliver notifications, {err:?}");
+        }
+
+        if let Err(err) = self
+            .adapter
+            .save_notification_delivery_lock(&YakManSnapshotLock::unlocked())
+            .await
+        {
+            log::error!("Failed to unlock notification delivery lockfile, Error: {err:?}");
+        }
+    }
+
+    async fn get_due_deliveries(
+        &self,
+    ) -> Result<Vec<YakManNotificationDelivery>, GenericStorageError> {
+        let now = Utc::now().timestamp_millis();
+        return Ok(self
+            .adapter
+            .get_pending_notification_deliveries()
+            .await?
+            .into_iter()
+            .filter(|d| d.next_attempt_at_ms <= now)
+            .collect());
+    }
+
+    async fn deliver_due_notifications(&self) -> Result<(), GenericStorageError> {
+        // Read again since another replica may have sent them while we were waiting for the lock
+        let due_deliveries = self.get_due_deliveries().await?;
+
+        let mut completed_project_ids = HashSet::new();
+        for delivery in due_deliveries {
+            let attempted = self.attempt_delivery(delivery).await;
+            if attempted.status == NotificationDeliveryStatus::Pending {
+                self.adapter
+                    .save_pending_notification_delivery(&attempted)
+                    .await?;
+                continue;
+            }
+
+            // Moved to the delivery log once it will not be attempted again
+            self.adapter
+                .save_notification_delivery(&attempted.project_id, &attempted)
+                .await?;
+            self.adapter
+                .delete_pending_notification_delivery(&attempted.id)
+                .await?;
+            completed_project_ids.insert(attempted.project_id);
+        }
+
+        for project_id in completed_project_ids {
+            self.trim_delivery_log(&project_id).await?;
+        }
+
+        return Ok(());
+    }
+
+    async fn trim_delivery_log(&self, project_id: &str) -> Result<(), GenericStorageError> {
+        let mut deliveries = self.adapter.get_notification_deliveries(project_id).await?;
+        sort_deliveries(&mut deliveries);
+        for delivery in get_deliveries_to_trim(&deliveries) {
+            self.adapter
+                .delete_notification_delivery(project_id, &delivery.id)
+                .await?;
+        }
+        return Ok(());
+    }
+
+    /// Takes the lock the same way as the snapshot worker
+    async fn try_take_lock(&self) -> Option<YakManSnapshotLock> {
+        let current_lock = self.adapter.get_notification_delivery_lock().await.ok()?;
+
+        if let Some(lock) = current_lock.lock {
+            // Allow taking an expired lock in case a previous run failed to unlock
+            let max_age_timestamp =
+                Utc::now() - Duration::try_minutes(5).expect("5 minutes will not be out of bounds");
+            if lock.timestamp_ms >= max_age_timestamp.timestamp_millis() {
+                return None;
+            }
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let taken_lock = YakManSnapshotLock::new(id.clone(), Utc::now().timestamp_millis());
+        if let Err(err) = self
+            .adapter
+            .save_notification_delivery_lock(&taken_lock)
+            .await
+        {
+            log::error!("Failed to save lock. Error: {err:?}");
+            return None;
+        }
+
+        // There is no atomic lock across storage systems, so wait and make sure
+        // the lock was not overwritten by another instance
+        tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+        match self
+            .adapter
+            .get_notification_delivery_lock()
+            .await
+            .map(|s| s.lock)
+        {
+            Ok(Some(lock)) if lock.id == id => return Some(taken_lock),
+            _ => {
+                log::warn!("Lock was overriden, bailing");
+                return None;
+            }
+        }
+    }
+
+    async fn attempt_delivery(
+        &self,
+        mut delivery: YakManNotificationDelivery,
+    ) -> YakManNotificationDelivery {
+        let notification_adapter: Arc<dyn YakManNotificationAdapter + Send + Sync> =
+            delivery.settings.clone().into();
+        let result = notification_adapter
+            .send_notification(delivery.event.clone())
+            .await;
+
+        let now = Utc::now().timestamp_millis();
+        delivery.attempts += 1;
+        delivery.last_attempt_at_ms = Some(now);
+
+        match result {
+            Ok(_) => {
+                delivery.status = NotificationDeliveryStatus::Delivered;
+                delivery.last_error = None;
+            }
+            Err(err) => {
+                log::warn!(
+                    "Notification delivery {} failed (attempt {}), {err:?}",
+                    delivery.id,
+                    delivery.attempts
+                );
+                delivery.last_error = Some(format!("{err:#}"));
+
+                if delivery.attempts >= settings::notification_delivery_max_attempts() {
+                    delivery.status = NotificationDeliveryStatus::Failed;
+                } else {
+                    delivery.next_attempt_at_ms = now + retry_delay_ms(delivery.attempts);
+                }
+            }
+        }
+
+        return delivery;
+    }
+}
+
+/// Exponential backoff, starting at 30 seconds and capped at 1 hour
+fn retry_delay_ms(attempts: u32) -> i64 {
+    let exponent = attempts.saturating_sub(1).min(20);
+    return (INITIAL_RETRY_DELAY_MS * 2_i64.pow(exponent)).min(MAX_RETRY_DELAY_MS);
+}
+
+/// Orders the deliveries by the time they were queued
+pub fn sort_deliveries(deliveries: &mut [YakManNotificationDelivery]) {
+    deliveries.sort_by(|a, b| {
+        a.created_at_ms
+            .cmp(&b.created_at_ms)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// Returns the oldest deliveries of the delivery log beyond the ones that are kept
+fn get_deliveries_to_trim(
+    deliveries: &[YakManNotificationDelivery],
+) -> &[YakManNotificationDelivery] {
+    let to_remove = deliveries.len().saturating_sub(MAX_COMPLETED_DELIVERIES);
+
+    // Deliveries are sorted oldest first
+    return &deliveries[..to_remove];
+}
+
+pub fn register_notification_delivery_worker(adapter: Arc<dyn KVStorageAdapter>) {
+    tokio::spawn(async {
+        let delivery_service = NotificationDeliveryService::new(adapter);
+
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            delivery_service.deliver_pending_notifications().await;
+        }
+    });
+
+    log::info!("Registered notification delivery worker");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        adapters::in_memory::InMemoryStorageAdapter, model::NotificationSetting,
+        notifications::YakManNotificationType,
+    };
+
+    fn pending_delivery(id: &str, attempts: u32) -> YakManNotificationDelivery {
+        return YakManNotificationDelivery {
+            id: id.to_string(),
+            project_id: "p1".to_string(),
+            settings: NotificationSetting::Slack {
+                // Nothing listens on the discard port, so the delivery fails immediately
+                webhook_url: "http://127.0.0.1:9/webhook".to_string(),
+            },
+            event: YakManNotificationType::InstanceCreated {
+                project_name: "foo".to_string(),
+                config_name: "bar".to_string(),
+                instance: "i123".to_string(),
+            },
+            status: NotificationDeliveryStatus::Pending,
+            attempts: attempts,
+            created_at_ms: 0,
+            next_attempt_at_ms: 0,
+            last_attempt_at_ms: None,
+            last_error: None,
+        };
+    }
+
+    #[test]
+    fn retry_delay_should_back_off_exponentially() {
+        assert_eq!(30_000, retry_delay_ms(1));
+        assert_eq!(60_000, retry_delay_ms(2));
+        assert_eq!(120_000, retry_delay_ms(3));
+        assert_eq!(MAX_RETRY_DELAY_MS, retry_delay_ms(10));
+        assert_eq!(MAX_RETRY_DELAY_MS, retry_delay_ms(u32::MAX));
+    }
+
+    #[actix_web::test]
+    async fn failed_delivery_should_be_rescheduled_then_marked_as_failed() -> anyhow::Result<()> {
+        let adapter: Arc<dyn KVStorageAdapter> = Arc::new(InMemoryStorageAdapter::new());
+        let max_attempts = settings::notification_delivery_max_attempts();
+        adapter
+            .save_pending_notification_delivery(&pending_delivery("n1", 0))
+            .await?;
+        adapter
+            .save_pending_notification_delivery(&pending_delivery("n2", max_attempts - 1))
+            .await?;
+
+        let service = NotificationDeliveryService::new(adapter.clone());
+        service.deliver_due_notifications().await?;
+
+        let pending = adapter.get_pending_notification_deliveries().await?;
+        assert_eq!(1, pending.len());
+        let retried = &pending[0];
+        assert_eq!("n1", retried.id);
+        assert_eq!(NotificationDeliveryStatus::Pending, retried.status);
+        assert_eq!(1, retried.attempts);
+        assert!(retried.last_error.is_some());
+        assert!(retried.next_attempt_at_ms > retried.last_attempt_at_ms.unwrap());
+
+        // Deliveries that will not be attempted again are moved to the delivery log
+        let delivery_log = adapter.get_notification_deliveries("p1").await?;
+        assert_eq!(1, delivery_log.len());
+        let failed = &delivery_log[0];
+        assert_eq!("n2", failed.id);
+        assert_eq!(NotificationDeliveryStatus::Failed, failed.status);
+        assert_eq!(max_attempts, failed.attempts);
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_deliveries_to_trim_should_keep_most_recent_deliveries() {
+        let mut deliveries = vec![];
+        for i in 0..MAX_COMPLETED_DELIVERIES + 2 {
+            let mut delivery = pending_delivery(&format!("n{i}"), 1);
+            delivery.status = NotificationDeliveryStatus::Delivered;
+            deliveries.push(delivery);
+        }
+
+        let trimmed: Vec<&str> = get_deliveries_to_trim(&deliveries)
+            .iter()
+            .map(|d| d.id.as_str())
+            .collect();
+
+        assert_eq!(vec!["n0", "n1"], trimmed);
+    }
+}
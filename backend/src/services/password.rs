@@ -1,11 +1,13 @@
-use std::fmt;
+use std::{collections::HashSet, fmt, fs};
 
 use argon2::{
     password_hash::{rand_core::OsRng, Error, PasswordHasher, SaltString},
     Argon2, PasswordHash, PasswordVerifier,
 };
 
-use crate::model::YakManPassword;
+use thiserror::Error;
+
+use crate::{model::YakManPassword, settings};
 
 #[allow(dead_code)]
 #[derive(Debug)]
@@ -37,18 +39,21 @@ pub fn verify_password(
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PasswordStrengthError {
-    TooShort,
+    TooShort { min_length: usize },
     TooLong,
     MissingUppercase,
     MissingLowercase,
     MissingDigit,
+    MissingSymbol,
+    Breached,
+    RecentlyUsed,
 }
 
 impl fmt::Display for PasswordStrengthError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            PasswordStrengthError::TooShort => {
-                write!(f, "Password must be at least 8 characters long")
+            PasswordStrengthError::TooShort { min_length } => {
+                write!(f, "Password must be at least {min_length} characters long")
             }
             PasswordStrengthError::TooLong => {
                 write!(f, "Password must be less than 100 characters long")
@@ -62,41 +67,174 @@ impl fmt::Display for PasswordStrengthError {
             PasswordStrengthError::MissingDigit => {
                 write!(f, "Password must contain at least one digit")
             }
+            PasswordStrengthError::MissingSymbol => {
+                write!(f, "Password must contain at least one symbol")
+            }
+            PasswordStrengthError::Breached => {
+                write!(
+                    f,
+                    "Password has appeared in a data breach, choose a different one"
+                )
+            }
+            PasswordStrengthError::RecentlyUsed => {
+                write!(f, "Password was used recently, choose a different one")
+            }
         }
     }
 }
 
-pub fn validate_password(password: &str) -> Result<(), PasswordStrengthError> {
-    if password.len() < 9 {
-        return Err(PasswordStrengthError::TooShort);
-    }
+#[derive(Error, Debug)]
+pub enum NewPasswordError {
+    #[error("Invalid password: {0}")]
+    Invalid(PasswordStrengthError),
+    #[error("Password could not be hashed")]
+    Hash(PasswordHashError),
+}
 
-    if password.len() > 100 {
-        return Err(PasswordStrengthError::TooLong);
-    }
+/// The rules new passwords have to follow, configured with the `YAKMAN_PASSWORD_*` env vars
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// Lowercased passwords from the breached password list
+    pub breached_passwords: HashSet<String>,
+    /// How many previous password hashes are kept to prevent reuse
+    pub history_size: usize,
+}
 
-    // Check for at least one uppercase letter
-    if !password.chars().any(char::is_uppercase) {
-        return Err(PasswordStrengthError::MissingUppercase);
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        return PasswordPolicy {
+            min_length: 9,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_symbol: false,
+            breached_passwords: HashSet::new(),
+            history_size: 5,
+        };
     }
+}
 
-    // Check for at least one lowercase letter
-    if !password.chars().any(char::is_lowercase) {
-        return Err(PasswordStrengthError::MissingLowercase);
+impl PasswordPolicy {
+    pub fn from_settings() -> Result<PasswordPolicy, std::io::Error> {
+        let classes = settings::password_required_character_classes();
+        for class in &classes {
+            if !["uppercase", "lowercase", "digit", "symbol"].contains(&class.as_str()) {
+                log::warn!("Unknown password character class '{class}', ignoring");
+            }
+        }
+
+        let breached_passwords = match settings::password_breached_list_file() {
+            Some(path) => {
+                let passwords: HashSet<String> = fs::read_to_string(&path)?
+                    .lines()
+                    .map(|line| line.trim().to_lowercase())
+                    .filter(|line| !line.is_empty())
+                    .collect();
+                log::info!("Loaded {} breached passwords from {path}", passwords.len());
+                passwords
+            }
+            None => HashSet::new(),
+        };
+
+        return Ok(PasswordPolicy {
+            min_length: settings::password_min_length(),
+            require_uppercase: classes.iter().any(|c| c == "uppercase"),
+            require_lowercase: classes.iter().any(|c| c == "lowercase"),
+            require_digit: classes.iter().any(|c| c == "digit"),
+            require_symbol: classes.iter().any(|c| c == "symbol"),
+            breached_passwords: breached_passwords,
+            history_size: settings::password_history_size(),
+        });
     }
 
-    // Check for at least one digit
-    if !password.chars().any(char::is_numeric) {
-        return Err(PasswordStrengthError::MissingDigit);
+    pub fn validate(&self, password: &str) -> Result<(), PasswordStrengthError> {
+        if password.chars().count() < self.min_length {
+            return Err(PasswordStrengthError::TooShort {
+                min_length: self.min_length,
+            });
+        }
+
+        if password.len() > 100 {
+            return Err(PasswordStrengthError::TooLong);
+        }
+
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            return Err(PasswordStrengthError::MissingUppercase);
+        }
+
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            return Err(PasswordStrengthError::MissingLowercase);
+        }
+
+        if self.require_digit && !password.chars().any(char::is_numeric) {
+            return Err(PasswordStrengthError::MissingDigit);
+        }
+
+        if self.require_symbol && password.chars().all(char::is_alphanumeric) {
+            return Err(PasswordStrengthError::MissingSymbol);
+        }
+
+        if self.breached_passwords.contains(&password.to_lowercase()) {
+            return Err(PasswordStrengthError::Breached);
+        }
+
+        return Ok(());
     }
 
-    return Ok(());
+    /// Validates and hashes the new password.
+    /// The current password is moved into the history so it cannot be reused.
+    pub fn create_password(
+        &self,
+        password: &str,
+        current: Option<YakManPassword>,
+        now: i64,
+    ) -> Result<YakManPassword, NewPasswordError> {
+        self.validate(password).map_err(NewPasswordError::Invalid)?;
+
+        let mut previous_hashes = vec![];
+        if let Some(current) = current {
+            if self.history_size > 0 {
+                previous_hashes.push(current.hash);
+                previous_hashes.extend(current.previous_hashes);
+            }
+        }
+        previous_hashes.truncate(self.history_size);
+
+        let was_used = previous_hashes.iter().any(|hash| {
+            let record = YakManPassword {
+                hash: hash.clone(),
+                timestamp: 0,
+                previous_hashes: vec![],
+            };
+            verify_password(password, record).unwrap_or(false)
+        });
+        if was_used {
+            return Err(NewPasswordError::Invalid(
+                PasswordStrengthError::RecentlyUsed,
+            ));
+        }
+
+        return Ok(YakManPassword {
+            hash: hash_password(password).map_err(NewPasswordError::Hash)?,
+            timestamp: now,
+            previous_hashes: previous_hashes,
+        });
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn validate_password(password: &str) -> Result<(), PasswordStrengthError> {
+        return PasswordPolicy::default().validate(password);
+    }
+
     #[test]
     fn test_valid_password() {
         let result = validate_password("ValidPassword123");
@@ -106,7 +244,10 @@ mod tests {
     #[test]
     fn test_too_short_password() {
         let result = validate_password("Short");
-        assert_eq!(result, Err(PasswordStrengthError::TooShort));
+        assert_eq!(
+            result,
+            Err(PasswordStrengthError::TooShort { min_length: 9 })
+        );
     }
 
     #[test]
@@ -134,6 +275,76 @@ mod tests {
         assert_eq!(result, Err(PasswordStrengthError::MissingDigit));
     }
 
+    #[test]
+    fn test_configured_character_classes() {
+        let policy = PasswordPolicy {
+            min_length: 4,
+            require_uppercase: false,
+            require_lowercase: true,
+            require_digit: false,
+            require_symbol: true,
+            ..Default::default()
+        };
+        assert_eq!(Ok(()), policy.validate("pass-word"));
+        assert_eq!(
+            Err(PasswordStrengthError::MissingSymbol),
+            policy.validate("password")
+        );
+    }
+
+    #[test]
+    fn test_breached_password() {
+        let policy = PasswordPolicy {
+            breached_passwords: HashSet::from(["password123a".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            Err(PasswordStrengthError::Breached),
+            policy.validate("Password123A")
+        );
+    }
+
+    #[test]
+    fn test_password_history_prevents_reuse() {
+        let policy = PasswordPolicy {
+            history_size: 2,
+            ..Default::default()
+        };
+
+        let first = policy.create_password("FirstPassword1", None, 0).unwrap();
+        let second = policy
+            .create_password("SecondPassword1", Some(first), 1)
+            .unwrap();
+        assert_eq!(1, second.previous_hashes.len());
+
+        let result = policy.create_password("FirstPassword1", Some(second.clone()), 2);
+        assert!(matches!(
+            result,
+            Err(NewPasswordError::Invalid(
+                PasswordStrengthError::RecentlyUsed
+            ))
+        ));
+        let result = policy.create_password("SecondPassword1", Some(second.clone()), 2);
+        assert!(matches!(
+            result,
+            Err(NewPasswordError::Invalid(
+                PasswordStrengthError::RecentlyUsed
+            ))
+        ));
+
+        // Only the most recent passwords are kept
+        let third = policy
+            .create_password("ThirdPassword1", Some(second), 2)
+            .unwrap();
+        let fourth = policy
+            .create_password("FourthPassword1", Some(third), 3)
+            .unwrap();
+        assert_eq!(2, fourth.previous_hashes.len());
+        assert!(policy
+            .create_password("FirstPassword1", Some(fourth), 4)
+            .is_ok());
+    }
+
     #[test]
     fn test_hash_password_success() {
         let password = "test_passwordA1";
@@ -150,6 +361,7 @@ mod tests {
         let yakman_password = YakManPassword {
             hash: hash_password(password).unwrap(),
             timestamp: 0,
+            previous_hashes: vec![],
         };
 
         assert!(verify_password(password, yakman_password).unwrap());
@@ -161,6 +373,7 @@ mod tests {
         let yakman_password = YakManPassword {
             hash: hash_password("correct_password").unwrap(),
             timestamp: 0,
+            previous_hashes: vec![],
         };
 
         assert!(!verify_password(password, yakman_password).unwrap());
@@ -172,6 +385,7 @@ mod tests {
         let yakman_password = YakManPassword {
             hash: "invalid_hash_format".to_string(),
             timestamp: 0,
+            previous_hashes: vec![],
         };
 
         assert!(verify_password(password, yakman_password).is_err());
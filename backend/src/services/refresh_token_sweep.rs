@@ -0,0 +1,23 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::adapters::KVStorageAdapter;
+
+const SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Periodically deletes the revoked refresh tokens that have expired.
+/// Every replica sweeps, deleting an expired record twice is harmless.
+pub fn register_refresh_token_sweep_worker(adapter: Arc<dyn KVStorageAdapter>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(SWEEP_INTERVAL).await;
+            let now = Utc::now().timestamp_millis();
+            if let Err(err) = adapter.delete_expired_revoked_refresh_tokens(now).await {
+                log::error!("Failed to delete expired revoked refresh tokens: {err:?}");
+            }
+        }
+    });
+
+    log::info!("Registered revoked refresh token sweep worker");
+}
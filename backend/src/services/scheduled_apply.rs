@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    adapters::KVStorageAdapter,
+    model::{YakManScheduledApply, YakManSnapshotLock},
+    services::{audit, StorageService},
+};
+
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+pub struct ScheduledApplyService {
+    adapter: Arc<dyn KVStorageAdapter>,
+    storage_service: Arc<dyn StorageService>,
+}
+
+impl ScheduledApplyService {
+    pub fn new(
+        adapter: Arc<dyn KVStorageAdapter>,
+        storage_service: Arc<dyn StorageService>,
+    ) -> Self {
+        Self {
+            adapter,
+            storage_service,
+        }
+    }
+
+    /// Applies every scheduled revision that is due. Only the replica holding the lock applies them.
+    pub async fn apply_due_revisions(&self) {
+        if self.get_due_applies().await.is_empty() {
+            return;
+        }
+
+        let Some(lock) = self.try_take_lock().await else {
+            log::debug!("Scheduled apply lock already taken");
+            return;
+        };
+        log::info!(
+            "Aquired scheduled apply lockfile, Lock ID: {}",
+            lock.lock.unwrap().id
+        );
+
+        // Read again since another replica may have applied them while we were waiting for the lock
+        for scheduled in self.get_due_applies().await {
+            let actor = Some(scheduled.scheduled_by_user_id.clone());
+            let result =
+                audit::with_actor(actor, self.storage_service.run_scheduled_apply(&scheduled))
+                    .await;
+            match result {
+                Ok(_) => log::info!(
+                    "Applied scheduled revision {} of instance {}",
+                    scheduled.revision,
+                    scheduled.instance
+                ),
+                Err(err) => log::error!(
+                    "Failed to apply scheduled revision {} of instance {}, Error: {err:?}",
+                    scheduled.revision,
+                    scheduled.instance
+                ),
+            }
+        }
+
+        if let Err(err) = self
+            .adapter
+            .save_scheduled_apply_lock(&YakManSnapshotLock::unlocked())
+            .await
+        {
+            log::error!("Failed to unlock scheduled apply lockfile, Error: {err:?}");
+        }
+    }
+
+    async fn get_due_applies(&self) -> Vec<YakManScheduledApply> {
+        let now = Utc::now().timestamp_millis();
+        return match self.storage_service.get_scheduled_applies().await {
+            Ok(scheduled_applies) => scheduled_applies
+                .into_iter()
+                .filter(|s| s.apply_at_ms <= now)
+                .collect(),
+            Err(err) => {
+                log::error!("Failed to load scheduled applies, Error: {err:?}");
+                vec![]
+            }
+        };
+    }
+
+    /// Takes the lock the same way as the snapshot worker
+    async fn try_take_lock(&self) -> Option<YakManSnapshotLock> {
+        let current_lock = self.adapter.get_scheduled_apply_lock().await.ok()?;
+
+        if let Some(lock) = current_lock.lock {
+            // Allow taking an expired lock in case a previous run failed to unlock
+            let max_age_timestamp =
+                Utc::now() - Duration::try_minutes(5).expect("5 minutes will not be out of bounds");
+            if lock.timestamp_ms >= max_age_timestamp.timestamp_millis() {
+                return None;
+            }
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let taken_lock = YakManSnapshotLock::new(id.clone(), Utc::now().timestamp_millis());
+        if let Err(err) = self.adapter.save_scheduled_apply_lock(&taken_lock).await {
+            log::error!("Failed to save lock. Error: {err:?}");
+            return None;
+        }
+
+        // There is no atomic lock across storage systems, so wait and make sure
+        // the lock was not overwritten by another instance
+        tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+        match self
+            .adapter
+            .get_scheduled_apply_lock()
+            .await
+            .map(|s| s.lock)
+        {
+            Ok(Some(lock)) if lock.id == id => return Some(taken_lock),
+            _ => {
+                log::warn!("Lock was overriden, bailing");
+                return None;
+            }
+        }
+    }
+}
+
+pub fn register_scheduled_apply_worker(
+    adapter: Arc<dyn KVStorageAdapter>,
+    storage_service: Arc<dyn StorageService>,
+) {
+    tokio::spawn(async {
+        let scheduled_apply_service = ScheduledApplyService::new(adapter, storage_service);
+
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            scheduled_apply_service.apply_due_revisions().await;
+        }
+    });
+
+    log::info!("Registered scheduled apply worker");
+}
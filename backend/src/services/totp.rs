@@ -0,0 +1,170 @@
+use thiserror::Error;
+use totp_rs::{Algorithm, Secret, TotpUrlError, TOTP};
+
+use crate::model::YakManTotp;
+
+const ISSUER: &str = "YakMan";
+const DIGITS: usize = 6;
+const STEP_SECONDS: u64 = 30;
+/// Codes from the previous and next time step are accepted to allow for clock drift
+const ALLOWED_SKEW_STEPS: u64 = 1;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(Error, Debug)]
+pub enum TotpError {
+    #[error("Invalid TOTP secret")]
+    InvalidSecret,
+    #[error("Failed to create TOTP: {0}")]
+    InvalidParameters(#[from] TotpUrlError),
+}
+
+/// Creates a new unconfirmed TOTP with a random secret
+pub fn generate_totp(now: i64) -> YakManTotp {
+    let secret = match Secret::generate_secret().to_encoded() {
+        Secret::Encoded(secret) => secret,
+        Secret::Raw(_) => unreachable!("to_encoded always returns an encoded secret"),
+    };
+    return YakManTotp {
+        secret: secret,
+        confirmed: false,
+        recovery_code_hashes: vec![],
+        last_used_step: None,
+        created_at: now,
+    };
+}
+
+/// The `otpauth://` URL authenticator apps can import (usually as a QR code)
+pub fn get_otpauth_url(totp: &YakManTotp, email: &str) -> Result<String, TotpError> {
+    return Ok(create_totp(totp, email)?.get_url());
+}
+
+/// Checks the code against the current time step (with skew) and returns the matching step.
+/// Codes for a step at or before `last_used_step` are rejected so a code cannot be replayed.
+pub fn verify_code(
+    totp: &YakManTotp,
+    code: &str,
+    now_seconds: u64,
+) -> Result<Option<u64>, TotpError> {
+    let generator = create_totp(totp, "")?;
+    let code = code.trim();
+    let current_step = now_seconds / STEP_SECONDS;
+
+    for step in current_step.saturating_sub(ALLOWED_SKEW_STEPS)..=current_step + ALLOWED_SKEW_STEPS
+    {
+        if totp.last_used_step.is_some_and(|last| step <= last) {
+            continue;
+        }
+        let expected = generator.generate(step * STEP_SECONDS);
+        if constant_time_eq(expected.as_bytes(), code.as_bytes()) {
+            return Ok(Some(step));
+        }
+    }
+    return Ok(None);
+}
+
+/// Generates a new set of recovery codes, replacing any existing ones.
+/// Returns the plain codes, which are only shown to the user once.
+pub fn generate_recovery_codes(totp: &mut YakManTotp) -> Vec<String> {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let Secret::Encoded(random) = Secret::generate_secret().to_encoded() else {
+                unreachable!("to_encoded always returns an encoded secret")
+            };
+            let random = random.to_lowercase();
+            format!("{}-{}", &random[0..5], &random[5..10])
+        })
+        .collect();
+    totp.recovery_code_hashes = codes.iter().map(|code| hash_recovery_code(code)).collect();
+    return codes;
+}
+
+/// Consumes the recovery code if it is valid. Returns false if the code is unknown or was already used.
+pub fn use_recovery_code(totp: &mut YakManTotp, code: &str) -> bool {
+    let hash = hash_recovery_code(code);
+    let Some(index) = totp
+        .recovery_code_hashes
+        .iter()
+        .position(|h| constant_time_eq(h.as_bytes(), hash.as_bytes()))
+    else {
+        return false;
+    };
+    totp.recovery_code_hashes.remove(index);
+    return true;
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    return sha256::digest(code.trim().to_lowercase());
+}
+
+fn create_totp(totp: &YakManTotp, email: &str) -> Result<TOTP, TotpError> {
+    let secret = Secret::Encoded(totp.secret.clone())
+        .to_bytes()
+        .map_err(|_| TotpError::InvalidSecret)?;
+    return Ok(TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        ALLOWED_SKEW_STEPS as u8,
+        STEP_SECONDS,
+        secret,
+        Some(ISSUER.to_string()),
+        email.to_string(),
+    )?);
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    return a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_accept_codes_within_skew_once() {
+        let mut totp = generate_totp(0);
+        let generator = create_totp(&totp, "").unwrap();
+        let now = 1_000_000;
+
+        let previous_code = generator.generate(now - STEP_SECONDS);
+        assert_eq!(
+            Some(now / STEP_SECONDS - 1),
+            verify_code(&totp, &previous_code, now).unwrap()
+        );
+
+        let old_code = generator.generate(now - 3 * STEP_SECONDS);
+        assert_eq!(None, verify_code(&totp, &old_code, now).unwrap());
+
+        let code = generator.generate(now);
+        let step = verify_code(&totp, &code, now).unwrap();
+        assert_eq!(Some(now / STEP_SECONDS), step);
+
+        // The same code cannot be used twice
+        totp.last_used_step = step;
+        assert_eq!(None, verify_code(&totp, &code, now).unwrap());
+        assert_eq!(None, verify_code(&totp, &previous_code, now).unwrap());
+    }
+
+    #[test]
+    fn recovery_codes_should_only_be_usable_once() {
+        let mut totp = generate_totp(0);
+        let codes = generate_recovery_codes(&mut totp);
+        assert_eq!(RECOVERY_CODE_COUNT, codes.len());
+        assert_eq!(11, codes[0].len());
+
+        assert!(use_recovery_code(&mut totp, &codes[3].to_uppercase()));
+        assert!(!use_recovery_code(&mut totp, &codes[3]));
+        assert!(!use_recovery_code(&mut totp, "aaaaa-bbbbb"));
+        assert_eq!(RECOVERY_CODE_COUNT - 1, totp.recovery_code_hashes.len());
+    }
+
+    #[test]
+    fn otpauth_url_should_contain_issuer_and_secret() {
+        let totp = generate_totp(0);
+        let url = get_otpauth_url(&totp, "test@yakman.com").unwrap();
+        assert!(url.starts_with("otpauth://totp/YakMan:test%40yakman.com?"));
+        assert!(url.contains(&format!("secret={}", totp.secret)));
+    }
+}
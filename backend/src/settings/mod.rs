@@ -6,6 +6,115 @@ pub fn is_oauth_enabled() -> bool {
         .unwrap_or_default();
 }
 
+/// The ID token claim with the user's groups. Group to team mapping is disabled if not set.
+pub fn oauth_groups_claim() -> Option<String> {
+    return std::env::var("YAKMAN_OAUTH_GROUPS_CLAIM").ok();
+}
+
+/// `group=team` pairs mapping OIDC groups to YakMan team names
+pub fn oauth_group_team_mapping() -> Vec<String> {
+    return from_comma_delimited_list("YAKMAN_OAUTH_GROUP_TEAM_MAPPING");
+}
+
+/// Create OAuth users on their first login instead of requiring an admin to add them
+pub fn is_oauth_auto_provisioning_enabled() -> bool {
+    return std::env::var("YAKMAN_OAUTH_AUTO_PROVISION_USERS")
+        .map(|v| v.parse::<bool>().ok())
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+}
+
+/// The bearer token the IdP uses to call the SCIM endpoints. SCIM is disabled if not set.
+pub fn scim_token() -> Option<String> {
+    return std::env::var("YAKMAN_SCIM_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty());
+}
+
+/// Failed password logins before an account is temporarily locked. Defaults to 10.
+pub fn login_max_failed_attempts() -> u32 {
+    return std::env::var("YAKMAN_LOGIN_MAX_FAILED_ATTEMPTS")
+        .map(|v| v.parse::<u32>().ok())
+        .ok()
+        .flatten()
+        .unwrap_or(10);
+}
+
+/// Failed password logins before a client IP is temporarily locked. Defaults to 100.
+pub fn login_max_failed_attempts_per_ip() -> u32 {
+    return std::env::var("YAKMAN_LOGIN_MAX_FAILED_ATTEMPTS_PER_IP")
+        .map(|v| v.parse::<u32>().ok())
+        .ok()
+        .flatten()
+        .unwrap_or(100);
+}
+
+/// How long an account or IP stays locked, and how long failed attempts are remembered. Defaults to 15 minutes.
+pub fn login_lockout_seconds() -> i64 {
+    return std::env::var("YAKMAN_LOGIN_LOCKOUT_SECONDS")
+        .map(|v| v.parse::<i64>().ok())
+        .ok()
+        .flatten()
+        .unwrap_or(15 * 60);
+}
+
+/// Use the `Forwarded`/`X-Forwarded-For` headers for the client IP.
+/// Only enable this when YakMan is behind a proxy (such as the YakMan frontend) that sets these headers.
+pub fn is_forwarded_for_trusted() -> bool {
+    return std::env::var("YAKMAN_TRUST_FORWARDED_FOR")
+        .map(|v| v.parse::<bool>().ok())
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+}
+
+/// The minimum password length. Defaults to 9.
+pub fn password_min_length() -> usize {
+    return std::env::var("YAKMAN_PASSWORD_MIN_LENGTH")
+        .map(|v| v.parse::<usize>().ok())
+        .ok()
+        .flatten()
+        .unwrap_or(9);
+}
+
+/// Character classes passwords must contain, any of `uppercase`, `lowercase`, `digit` and `symbol`.
+/// Defaults to `uppercase,lowercase,digit`.
+pub fn password_required_character_classes() -> Vec<String> {
+    if std::env::var("YAKMAN_PASSWORD_REQUIRED_CHARACTER_CLASSES").is_err() {
+        return vec![
+            "uppercase".to_string(),
+            "lowercase".to_string(),
+            "digit".to_string(),
+        ];
+    }
+    return from_comma_delimited_list("YAKMAN_PASSWORD_REQUIRED_CHARACTER_CLASSES");
+}
+
+/// Path to a file of breached passwords (one per line) that cannot be used
+pub fn password_breached_list_file() -> Option<String> {
+    return std::env::var("YAKMAN_PASSWORD_BREACHED_LIST_FILE").ok();
+}
+
+/// How many previous passwords cannot be reused. Defaults to 5, 0 allows reuse.
+pub fn password_history_size() -> usize {
+    return std::env::var("YAKMAN_PASSWORD_HISTORY_SIZE")
+        .map(|v| v.parse::<usize>().ok())
+        .ok()
+        .flatten()
+        .unwrap_or(5);
+}
+
+/// Require all password login users to use a TOTP second factor.
+/// Users without one are asked to enroll when they next login.
+pub fn is_totp_required() -> bool {
+    return std::env::var("YAKMAN_TOTP_REQUIRED")
+        .map(|v| v.parse::<bool>().ok())
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+}
+
 pub fn is_snapshot_backups_enabled() -> bool {
     return std::env::var("YAKMAN_SNAPSHOT_BACKUPS_ENABLED")
         .map(|v| v.parse::<bool>().ok())
@@ -20,6 +129,24 @@ pub fn snapshot_backups_cron() -> String {
         .unwrap_or("0 0 * * * *".to_string());
 }
 
+/// How long user invitation links are valid. Defaults to 72 hours.
+pub fn invitation_expiration_hours() -> i64 {
+    return std::env::var("YAKMAN_INVITATION_EXPIRATION_HOURS")
+        .map(|v| v.parse::<i64>().ok())
+        .ok()
+        .flatten()
+        .unwrap_or(72);
+}
+
+/// How long a revoked refresh token is remembered, this should outlive the refresh token. Defaults to 365 days.
+pub fn revoked_refresh_token_retention_days() -> i64 {
+    return std::env::var("YAKMAN_REVOKED_REFRESH_TOKEN_RETENTION_DAYS")
+        .map(|v| v.parse::<i64>().ok())
+        .ok()
+        .flatten()
+        .unwrap_or(365);
+}
+
 pub fn yakman_application_host() -> Option<String> {
     return std::env::var("YAKMAN_APPLICATION_HOST").ok();
 }
@@ -36,10 +163,121 @@ pub fn is_notifications_enabled() -> bool {
     return r;
 }
 
+/// The number of attempts before a notification delivery is marked as failed. Defaults to 8.
+pub fn notification_delivery_max_attempts() -> u32 {
+    return std::env::var("YAKMAN_NOTIFICATION_DELIVERY_MAX_ATTEMPTS")
+        .map(|v| v.parse::<u32>().ok())
+        .ok()
+        .flatten()
+        .unwrap_or(8);
+}
+
 pub fn notification_whitelisted_hosts() -> Vec<String> {
     return from_comma_delimited_list("YAKMAN_NOTIFICATION_WEBHOOK_HOSTS");
 }
 
+pub enum SmtpTlsMode {
+    None,
+    StartTls,
+    Tls,
+}
+
+pub fn smtp_host() -> Option<String> {
+    return std::env::var("YAKMAN_SMTP_HOST").ok();
+}
+
+pub fn smtp_port() -> Option<u16> {
+    return std::env::var("YAKMAN_SMTP_PORT")
+        .map(|v| v.parse::<u16>().ok())
+        .ok()
+        .flatten();
+}
+
+/// Supported values are `NONE`, `STARTTLS` and `TLS`. Defaults to `STARTTLS`.
+pub fn smtp_tls_mode() -> SmtpTlsMode {
+    return match std::env::var("YAKMAN_SMTP_TLS")
+        .unwrap_or_default()
+        .to_uppercase()
+        .as_str()
+    {
+        "NONE" => SmtpTlsMode::None,
+        "TLS" => SmtpTlsMode::Tls,
+        _ => SmtpTlsMode::StartTls,
+    };
+}
+
+pub fn smtp_username() -> Option<String> {
+    return std::env::var("YAKMAN_SMTP_USERNAME").ok();
+}
+
+pub fn smtp_password() -> Option<String> {
+    return std::env::var("YAKMAN_SMTP_PASSWORD").ok();
+}
+
+pub fn smtp_from_address() -> Option<String> {
+    return std::env::var("YAKMAN_SMTP_FROM").ok();
+}
+
+pub fn is_smtp_configured() -> bool {
+    return smtp_host().is_some() && smtp_from_address().is_some();
+}
+
+/// Appends each audit log entry as a line of JSON to this file
+pub fn audit_export_file() -> Option<String> {
+    return std::env::var("YAKMAN_AUDIT_EXPORT_FILE").ok();
+}
+
+/// The `host:port` of a syslog server to send audit log entries to
+pub fn audit_export_syslog_address() -> Option<String> {
+    return std::env::var("YAKMAN_AUDIT_EXPORT_SYSLOG_ADDRESS").ok();
+}
+
+pub enum SyslogProtocol {
+    Udp,
+    Tcp,
+}
+
+/// Supported values are `UDP` and `TCP`. Defaults to `UDP`.
+pub fn audit_export_syslog_protocol() -> SyslogProtocol {
+    return match std::env::var("YAKMAN_AUDIT_EXPORT_SYSLOG_PROTOCOL")
+        .unwrap_or_default()
+        .to_uppercase()
+        .as_str()
+    {
+        "TCP" => SyslogProtocol::Tcp,
+        _ => SyslogProtocol::Udp,
+    };
+}
+
+/// Audit log entries are POSTed to this URL as a JSON array
+pub fn audit_export_http_url() -> Option<String> {
+    return std::env::var("YAKMAN_AUDIT_EXPORT_HTTP_URL").ok();
+}
+
+/// Sent as the `Authorization` header to the HTTP audit export endpoint
+pub fn audit_export_http_authorization() -> Option<String> {
+    return std::env::var("YAKMAN_AUDIT_EXPORT_HTTP_AUTHORIZATION").ok();
+}
+
+/// The number of entries buffered per sink while it is unavailable, newer entries are dropped when full. Defaults to 10000.
+pub fn audit_export_buffer_size() -> usize {
+    return std::env::var("YAKMAN_AUDIT_EXPORT_BUFFER_SIZE")
+        .map(|v| v.parse::<usize>().ok())
+        .ok()
+        .flatten()
+        .filter(|size| *size > 0)
+        .unwrap_or(10_000);
+}
+
+/// The number of attempts to export a batch before it is dropped. Defaults to 8.
+pub fn audit_export_max_attempts() -> u32 {
+    return std::env::var("YAKMAN_AUDIT_EXPORT_MAX_ATTEMPTS")
+        .map(|v| v.parse::<u32>().ok())
+        .ok()
+        .flatten()
+        .unwrap_or(8);
+}
+
 fn from_comma_delimited_list(env_var_name: &str) -> Vec<String> {
     let env_var = match std::env::var(env_var_name) {
         Ok(val) => val,
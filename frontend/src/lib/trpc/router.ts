@@ -12,6 +12,8 @@ import { lifecycle } from './routes/lifecycle';
 import { users } from './routes/users';
 import { apiKeys } from './routes/api-keys';
 import { teams } from './routes/teams';
+import { serviceAccounts } from './routes/service-accounts';
+import { auditLog } from './routes/audit-log';
 
 export const router = t.router({
     configs: configs,
@@ -23,6 +25,8 @@ export const router = t.router({
     users: users,
     apiKeys: apiKeys,
     teams: teams,
+    serviceAccounts: serviceAccounts,
+    auditLog: auditLog,
     auth: auth,
     lifecycle: lifecycle,
 });
@@ -16,7 +16,16 @@ export const apiKeys = t.router({
     createApiKey: t.procedure
         .input(z.object({
             projectId: z.string(),
-            role: z.string()
+            role: z.string(),
+            expiresAt: z.number().int().nullable(),
+            scope: z.object({
+                operations: z.array(z.string()),
+                configIds: z.array(z.string()),
+                labelSelectors: z.array(z.object({
+                    labelId: z.string(),
+                    value: z.string()
+                }))
+            }).nullable()
         }))
         .mutation(async ({ input, ctx }) => {
             const response = await fetch(`${BASE_URL}/v1/api-keys`, {
@@ -27,7 +36,39 @@ export const apiKeys = t.router({
                 },
                 body: JSON.stringify({
                     'project_id': input.projectId,
-                    'role': input.role
+                    'role': input.role,
+                    'expires_at': input.expiresAt,
+                    'scope': input.scope ? {
+                        'operations': input.scope.operations,
+                        'config_ids': input.scope.configIds,
+                        'label_selectors': input.scope.labelSelectors.map(selector => ({
+                            'label_id': selector.labelId,
+                            'value': selector.value
+                        }))
+                    } : null
+                })
+            });
+            if (response.status != 200) {
+                throw new Error(await response.text())
+            }
+            const json = await response.json();
+
+            return json.api_key as string
+        }),
+    rotateApiKey: t.procedure
+        .input(z.object({
+            id: z.string(),
+            gracePeriodSeconds: z.number().int()
+        }))
+        .mutation(async ({ input, ctx }) => {
+            const response = await fetch(`${BASE_URL}/v1/api-keys/${input.id}/rotate`, {
+                method: 'POST',
+                headers: {
+                    ...createYakManAuthHeaders(ctx.accessToken),
+                    'Content-Type': 'application/json'
+                },
+                body: JSON.stringify({
+                    'grace_period_seconds': input.gracePeriodSeconds
                 })
             });
             if (response.status != 200) {
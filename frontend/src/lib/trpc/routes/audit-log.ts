@@ -0,0 +1,38 @@
+import { z } from "zod";
+import { t } from "../t";
+import { YakManAuditActionSchema, YakManAuditTargetTypeSchema, type YakManAuditLogEntry } from "$lib/types/types";
+import { createYakManAuthHeaders, getYakManBaseApiUrl } from "../helper";
+import { convertYakManErrorToTRPCError } from "$lib/utils/error-helpers";
+
+const BASE_URL = getYakManBaseApiUrl();
+
+export const auditLog = t.router({
+    fetchAuditLog: t.procedure
+        .input(z.object({
+            from: z.number().int().optional(),
+            to: z.number().int().optional(),
+            actor: z.string().optional(),
+            action: YakManAuditActionSchema.optional(),
+            targetType: YakManAuditTargetTypeSchema.optional(),
+            targetId: z.string().optional(),
+            limit: z.number().int().optional(),
+        }))
+        .query(async ({ input, ctx }): Promise<YakManAuditLogEntry[]> => {
+            const params = new URLSearchParams();
+            if (input.from) params.set('from', input.from.toString());
+            if (input.to) params.set('to', input.to.toString());
+            if (input.actor) params.set('actor', input.actor);
+            if (input.action) params.set('action', input.action);
+            if (input.targetType) params.set('target_type', input.targetType);
+            if (input.targetId) params.set('target_id', input.targetId);
+            if (input.limit) params.set('limit', input.limit.toString());
+
+            const response = await fetch(`${BASE_URL}/v1/audit-log?${params}`, {
+                headers: createYakManAuthHeaders(ctx.accessToken)
+            });
+            if (response.status != 200) {
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
+            }
+            return await response.json();
+        }),
+});
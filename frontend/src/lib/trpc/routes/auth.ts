@@ -1,6 +1,7 @@
 import { t } from "../t";
 import { createYakManAuthHeaders, getYakManBaseApiUrl } from "../helper";
 import { z } from "zod";
+import { convertYakManErrorToTRPCError } from "$lib/utils/error-helpers";
 
 const BASE_URL = getYakManBaseApiUrl();
 
@@ -9,6 +10,7 @@ export type GetUserInfoResponse = {
     profile_picture: string | null,
     global_roles: string[],
     roles: { [key: string]: string },
+    totp_enabled: boolean,
 };
 
 export const auth = t.router({
@@ -81,7 +83,28 @@ export const auth = t.router({
                 })
             });
             if (response.status != 200) {
-                throw new Error(await response.text())
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
+            }
+        }),
+    changePassword: t.procedure
+        .input(z.object({
+            currentPassword: z.string(),
+            newPassword: z.string(),
+        }))
+        .mutation(async ({ ctx, input }) => {
+            const response = await fetch(`${BASE_URL}/auth/change-password`, {
+                method: "POST",
+                headers: {
+                    ...createYakManAuthHeaders(ctx.accessToken),
+                    'Content-Type': 'application/json'
+                },
+                body: JSON.stringify({
+                    current_password: input.currentPassword,
+                    new_password: input.newPassword,
+                })
+            });
+            if (response.status != 200) {
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
             }
         }),
     fetchUserInfo: t.procedure
@@ -2,6 +2,7 @@ import { z } from "zod";
 import { t } from "../t";
 import type { YakManConfigInstance } from "$lib/types/types";
 import { createYakManAuthHeaders, getYakManBaseApiUrl } from "../helper";
+import { convertYakManErrorToTRPCError } from "$lib/utils/error-helpers";
 
 const BASE_URL = getYakManBaseApiUrl();
 
@@ -23,13 +24,15 @@ export const instances = t.router({
             configId: z.string(),
             data: z.string(),
             contentType: z.string(),
-            labels: z.record(z.string(), z.string())
+            labels: z.record(z.string(), z.string()),
+            breakGlass: z.boolean().optional(),
         }))
         .mutation(async ({ input, ctx }) => {
-            let query = '';
-            if (input.labels != null && Object.keys(input.labels).length > 0) {
-                query = '?' + new URLSearchParams(input.labels);
+            const params = new URLSearchParams(input.labels ?? {});
+            if (input.breakGlass) {
+                params.set('break_glass', 'true');
             }
+            const query = params.toString() ? '?' + params : '';
 
             const response = await fetch(`${BASE_URL}/v1/configs/${input.configId}/instances${query}`, {
                 headers: {
@@ -41,7 +44,7 @@ export const instances = t.router({
             });
 
             if (response.status != 200) {
-                throw new Error(`failed to create config instance, http-status: [${response.status}]`);
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
             }
 
             const data = await response.json() as InstanceResponse;
@@ -56,25 +59,34 @@ export const instances = t.router({
             instance: z.string(),
             data: z.string(),
             contentType: z.string(),
-            labels: z.record(z.string(), z.string())
+            labels: z.record(z.string(), z.string()),
+            description: z.string().optional(),
+            ticket: z.string().optional(),
         }))
         .mutation(async ({ input, ctx }) => {
-            let query = '';
-            if (input.labels != null && Object.keys(input.labels).length > 0) {
-                query = '?' + new URLSearchParams(input.labels);
+            const params = new URLSearchParams(input.labels ?? {});
+            const query = params.toString() ? '?' + params : '';
+
+            const headers: Record<string, string> = {
+                ...createYakManAuthHeaders(ctx.accessToken),
+                'content-type': input.contentType ?? 'text/plain'
+            };
+            // Header values must be ASCII, so the change details are percent-encoded
+            if (input.description) {
+                headers['YakMan-Change-Description'] = encodeURIComponent(input.description);
+            }
+            if (input.ticket) {
+                headers['YakMan-Change-Ticket'] = encodeURIComponent(input.ticket);
             }
 
             const response = await fetch(`${BASE_URL}/v1/configs/${input.configId}/instances/${input.instance}${query}`, {
-                headers: {
-                    ...createYakManAuthHeaders(ctx.accessToken),
-                    'content-type': input.contentType ?? 'text/plain'
-                },
+                headers: headers,
                 method: 'POST',
                 body: input.data
             });
 
             if (response.status != 200) {
-                throw new Error(`failed to update config instance, http-status: [${response.status}]`);
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
             }
         }),
     deleteConfigInstance: t.procedure
@@ -6,24 +6,68 @@ import { convertYakManErrorToTRPCError } from "$lib/utils/error-helpers";
 
 const BASE_URL = getYakManBaseApiUrl();
 
-const ModifyProjectPayloadSchema = z.object({
-    name: z.string(),
+const NotificationChannelSchema = z.object({
     slack: z.object({
         webhookUrl: z.string()
     }).optional(),
     discord: z.object({
         webhookUrl: z.string()
     }).optional(),
+    teams: z.object({
+        webhookUrl: z.string()
+    }).optional(),
+    email: z.object({
+        recipients: z.array(z.string()),
+        sendToProjectApprovers: z.boolean()
+    }).optional(),
     notificationEvents: z.object({
         isInstanceCreateEventEnabled: z.boolean(),
         isInstanceUpdateEventEnabled: z.boolean(),
         isRevisionSubmittedEventEnabled: z.boolean(),
         isRevisionApprovedEventEnabled: z.boolean(),
         isRevisionRejectedEventEnabled: z.boolean(),
-    }).optional()
+        isRevisionRollbackEventEnabled: z.boolean(),
+        isInstanceDeleteEventEnabled: z.boolean(),
+        isConfigDeleteEventEnabled: z.boolean(),
+        isProjectSettingsUpdateEventEnabled: z.boolean(),
+        isApiKeyCreateEventEnabled: z.boolean(),
+    })
+});
+
+const ApprovalPolicySchema = z.object({
+    requiredApprovals: z.number().int(),
+    labelRules: z.array(z.object({
+        labelId: z.string(),
+        value: z.string(),
+        requiredApprovals: z.number().int(),
+    })),
+    forbidSelfApproval: z.boolean(),
+    requireChangeDescription: z.boolean(),
+});
+
+const FreezeWindowSchema = z.object({
+    reason: z.string(),
+    range: z.object({
+        startMs: z.number().int(),
+        endMs: z.number().int(),
+    }).optional(),
+    cron: z.object({
+        expression: z.string(),
+        durationMinutes: z.number().int(),
+    }).optional(),
+});
+
+const ModifyProjectPayloadSchema = z.object({
+    name: z.string(),
+    notificationChannels: z.array(NotificationChannelSchema),
+    approvalPolicy: ApprovalPolicySchema.optional(),
+    freezeWindows: z.array(FreezeWindowSchema).optional()
 });
 
+export type NotificationChannelPayload = z.infer<typeof NotificationChannelSchema>;
 export type ModifyProjectPayload = z.infer<typeof ModifyProjectPayloadSchema>;
+export type ApprovalPolicyPayload = z.infer<typeof ApprovalPolicySchema>;
+export type FreezeWindowPayload = z.infer<typeof FreezeWindowSchema>;
 
 export const projects = t.router({
     fetchProjects: t.procedure
@@ -106,35 +150,86 @@ export const projects = t.router({
 
 // TODO: Improve type safety here
 function createModifyProjectPayload(request: ModifyProjectPayload): any {
-    let body: any = {
-        'project_name': request.name
-    }
+    return {
+        'project_name': request.name,
+        'notification_settings': request.notificationChannels
+            .map(createNotificationSettingsPayload)
+            .filter(settings => settings !== null),
+        'approval_policy': request.approvalPolicy ? {
+            'required_approvals': request.approvalPolicy.requiredApprovals,
+            'label_rules': request.approvalPolicy.labelRules.map(rule => ({
+                'label_id': rule.labelId,
+                'value': rule.value,
+                'required_approvals': rule.requiredApprovals
+            })),
+            'forbid_self_approval': request.approvalPolicy.forbidSelfApproval,
+            'require_change_description': request.approvalPolicy.requireChangeDescription
+        } : undefined,
+        'freeze_windows': request.freezeWindows?.map(createFreezeWindowPayload)
+    };
+}
 
-    const isNotificationEnabled = request.slack || request.discord;
-    if (request.notificationEvents && isNotificationEnabled) {
-        let type: any = null;
-        if (request.slack) {
-            type = {
-                Slack: {
-                    webhook_url: request.slack.webhookUrl
-                }
-            };
-        } else if (request.discord) {
-            type = {
-                Discord: {
-                    webhook_url: request.discord.webhookUrl
-                }
-            };
+function createFreezeWindowPayload(window: FreezeWindowPayload): any {
+    return {
+        'reason': window.reason,
+        'schedule': window.cron ? {
+            Cron: {
+                'expression': window.cron.expression,
+                'duration_minutes': window.cron.durationMinutes
+            }
+        } : {
+            Range: {
+                'start_ms': window.range?.startMs,
+                'end_ms': window.range?.endMs
+            }
         }
+    };
+}
 
-        body.notification_settings = {
-            notification_type: type,
-            is_instance_updated_enabled: request.notificationEvents.isInstanceUpdateEventEnabled,
-            is_instance_created_enabled: request.notificationEvents.isInstanceCreateEventEnabled,
-            is_revision_submitted_enabled: request.notificationEvents.isRevisionSubmittedEventEnabled,
-            is_revision_approved_enabled: request.notificationEvents.isRevisionApprovedEventEnabled,
-            is_revision_reject_enabled: request.notificationEvents.isRevisionRejectedEventEnabled,
+function createNotificationSettingsPayload(channel: NotificationChannelPayload): any {
+    let type: any = null;
+    if (channel.slack) {
+        type = {
+            Slack: {
+                webhook_url: channel.slack.webhookUrl
+            }
+        };
+    } else if (channel.discord) {
+        type = {
+            Discord: {
+                webhook_url: channel.discord.webhookUrl
+            }
+        };
+    } else if (channel.teams) {
+        type = {
+            MicrosoftTeams: {
+                webhook_url: channel.teams.webhookUrl
+            }
+        };
+    } else if (channel.email) {
+        type = {
+            Email: {
+                recipients: channel.email.recipients,
+                send_to_project_approvers: channel.email.sendToProjectApprovers
+            }
         };
     }
-    return body;
+
+    if (!type) {
+        return null;
+    }
+
+    return {
+        notification_type: type,
+        is_instance_updated_enabled: channel.notificationEvents.isInstanceUpdateEventEnabled,
+        is_instance_created_enabled: channel.notificationEvents.isInstanceCreateEventEnabled,
+        is_revision_submitted_enabled: channel.notificationEvents.isRevisionSubmittedEventEnabled,
+        is_revision_approved_enabled: channel.notificationEvents.isRevisionApprovedEventEnabled,
+        is_revision_reject_enabled: channel.notificationEvents.isRevisionRejectedEventEnabled,
+        is_revision_rollback_enabled: channel.notificationEvents.isRevisionRollbackEventEnabled,
+        is_instance_deleted_enabled: channel.notificationEvents.isInstanceDeleteEventEnabled,
+        is_config_deleted_enabled: channel.notificationEvents.isConfigDeleteEventEnabled,
+        is_project_settings_updated_enabled: channel.notificationEvents.isProjectSettingsUpdateEventEnabled,
+        is_api_key_created_enabled: channel.notificationEvents.isApiKeyCreateEventEnabled,
+    };
 }
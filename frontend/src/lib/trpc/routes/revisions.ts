@@ -1,7 +1,8 @@
 import { t } from "../t";
 import { z } from "zod";
 import { createYakManAuthHeaders, getYakManBaseApiUrl } from "../helper";
-import type { YakManInstanceRevision } from "$lib/types/types";
+import type { RevisionComment, YakManInstanceRevision, YakManScheduledApply } from "$lib/types/types";
+import { convertYakManErrorToTRPCError } from "$lib/utils/error-helpers";
 
 const BASE_URL = getYakManBaseApiUrl();
 
@@ -9,6 +10,11 @@ type RevisionPayload = {
     revision: string
 }
 
+type ReviewRevisionResponse = {
+    fully_approved: boolean,
+    applied: boolean,
+}
+
 export const revisions = t.router({
     fetchInstanceRevisions: t.procedure
         .input(z.object({
@@ -27,32 +33,68 @@ export const revisions = t.router({
             configId: z.string(),
             instance: z.string(),
             revision: z.string(),
-            reviewResult: z.enum(["Approve", "ApproveAndApply", "Reject"])
+            reviewResult: z.enum(["Approve", "ApproveAndApply", "Reject"]),
+            comment: z.string().optional(),
         }))
         .mutation(async ({ input, ctx }) => {
             const response = await fetch(`${BASE_URL}/v1/configs/${input.configId}/instances/${input.instance}/revisions/${input.revision}/review/${input.reviewResult}`, {
-                headers: createYakManAuthHeaders(ctx.accessToken),
-                method: 'POST'
+                headers: {
+                    ...createYakManAuthHeaders(ctx.accessToken),
+                    'Content-Type': 'application/json'
+                },
+                method: 'POST',
+                body: JSON.stringify({
+                    comment: input.comment || null
+                })
+            });
+
+            if (response.status != 200) {
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
+            }
+
+            return await response.json() as ReviewRevisionResponse
+        }),
+    addRevisionComment: t.procedure
+        .input(z.object({
+            configId: z.string(),
+            instance: z.string(),
+            revision: z.string(),
+            message: z.string(),
+        }))
+        .mutation(async ({ input, ctx }): Promise<RevisionComment> => {
+            const response = await fetch(`${BASE_URL}/v1/configs/${input.configId}/instances/${input.instance}/revisions/${input.revision}/comments`, {
+                headers: {
+                    ...createYakManAuthHeaders(ctx.accessToken),
+                    'Content-Type': 'application/json'
+                },
+                method: 'POST',
+                body: JSON.stringify({
+                    message: input.message
+                })
             });
 
             if (response.status != 200) {
-                throw new Error(`failed to review revision: http-status [${response.status}]`);
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
             }
+
+            return await response.json();
         }),
     applyInstanceRevision: t.procedure
         .input(z.object({
             configId: z.string(),
             instance: z.string(),
             revision: z.string(),
+            breakGlass: z.boolean().optional(),
         }))
         .mutation(async ({ input, ctx }) => {
-            const response = await fetch(`${BASE_URL}/v1/configs/${input.configId}/instances/${input.instance}/revisions/${input.revision}/apply`, {
+            const query = input.breakGlass ? '?break_glass=true' : '';
+            const response = await fetch(`${BASE_URL}/v1/configs/${input.configId}/instances/${input.instance}/revisions/${input.revision}/apply${query}`, {
                 headers: createYakManAuthHeaders(ctx.accessToken),
                 method: 'POST'
             });
 
             if (response.status != 200) {
-                throw new Error(`failed to apply revision: http-status [${response.status}]`);
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
             }
         }),
     rollbackInstanceRevision: t.procedure
@@ -73,4 +115,56 @@ export const revisions = t.router({
 
             return await response.json() as RevisionPayload
         }),
+    fetchScheduledApply: t.procedure
+        .input(z.object({
+            configId: z.string(),
+            instance: z.string(),
+        }))
+        .query(async ({ input, ctx }): Promise<YakManScheduledApply | null> => {
+            const response = await fetch(`${BASE_URL}/v1/configs/${input.configId}/instances/${input.instance}/scheduled-apply`, {
+                headers: createYakManAuthHeaders(ctx.accessToken)
+            });
+            if (response.status != 200) {
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
+            }
+            return await response.json();
+        }),
+    scheduleInstanceRevisionApply: t.procedure
+        .input(z.object({
+            configId: z.string(),
+            instance: z.string(),
+            revision: z.string(),
+            applyAtMs: z.number().int(),
+        }))
+        .mutation(async ({ input, ctx }): Promise<YakManScheduledApply> => {
+            const response = await fetch(`${BASE_URL}/v1/configs/${input.configId}/instances/${input.instance}/revisions/${input.revision}/schedule`, {
+                headers: {
+                    ...createYakManAuthHeaders(ctx.accessToken),
+                    'Content-Type': 'application/json'
+                },
+                method: 'POST',
+                body: JSON.stringify({
+                    apply_at_ms: input.applyAtMs
+                })
+            });
+            if (response.status != 200) {
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
+            }
+            return await response.json();
+        }),
+    cancelScheduledApply: t.procedure
+        .input(z.object({
+            configId: z.string(),
+            instance: z.string(),
+            revision: z.string(),
+        }))
+        .mutation(async ({ input, ctx }) => {
+            const response = await fetch(`${BASE_URL}/v1/configs/${input.configId}/instances/${input.instance}/revisions/${input.revision}/schedule`, {
+                headers: createYakManAuthHeaders(ctx.accessToken),
+                method: 'DELETE'
+            });
+            if (response.status != 200) {
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
+            }
+        }),
 });
@@ -0,0 +1,106 @@
+import { z } from "zod";
+import { t } from "../t";
+import { YakManRoleSchema, type YakManServiceAccount } from "$lib/types/types";
+import { createYakManAuthHeaders, getYakManBaseApiUrl } from "../helper";
+
+const BASE_URL = getYakManBaseApiUrl();
+
+const ServiceAccountInputSchema = z.object({
+    name: z.string(),
+    globalRole: YakManRoleSchema.optional(),
+    roles: z.array(z.object({
+        projectId: z.string(),
+        role: YakManRoleSchema
+    })),
+    teamIds: z.array(z.string())
+});
+
+function toServiceAccountPayload(input: z.infer<typeof ServiceAccountInputSchema>) {
+    return {
+        name: input.name,
+        global_roles: input.globalRole ? [input.globalRole] : [],
+        roles: input.roles.map(role => ({
+            project_id: role.projectId,
+            role: role.role,
+        })),
+        team_ids: input.teamIds
+    };
+}
+
+export const serviceAccounts = t.router({
+    fetchServiceAccounts: t.procedure
+        .query(async ({ ctx }): Promise<YakManServiceAccount[]> => {
+            const response = await fetch(`${BASE_URL}/v1/service-accounts`, {
+                headers: createYakManAuthHeaders(ctx.accessToken)
+            });
+            return await response.json();
+        }),
+    createServiceAccount: t.procedure
+        .input(ServiceAccountInputSchema)
+        .mutation(async ({ input, ctx }) => {
+            const response = await fetch(`${BASE_URL}/v1/service-accounts`, {
+                method: 'PUT',
+                headers: {
+                    ...createYakManAuthHeaders(ctx.accessToken),
+                    'Content-Type': 'application/json'
+                },
+                body: JSON.stringify(toServiceAccountPayload(input))
+            });
+            if (response.status != 200) {
+                throw new Error(await response.text())
+            }
+        }),
+    updateServiceAccount: t.procedure
+        .input(ServiceAccountInputSchema.extend({
+            id: z.string()
+        }))
+        .mutation(async ({ input, ctx }) => {
+            const response = await fetch(`${BASE_URL}/v1/service-accounts/${input.id}`, {
+                method: 'POST',
+                headers: {
+                    ...createYakManAuthHeaders(ctx.accessToken),
+                    'Content-Type': 'application/json'
+                },
+                body: JSON.stringify(toServiceAccountPayload(input))
+            });
+            if (response.status != 200) {
+                throw new Error(await response.text())
+            }
+        }),
+    deleteServiceAccount: t.procedure
+        .input(z.object({
+            id: z.string(),
+        }))
+        .mutation(async ({ input, ctx }) => {
+            const response = await fetch(`${BASE_URL}/v1/service-accounts/${input.id}`, {
+                method: 'DELETE',
+                headers: createYakManAuthHeaders(ctx.accessToken)
+            });
+            if (response.status != 200) {
+                throw new Error(await response.text())
+            }
+        }),
+    createServiceAccountApiKey: t.procedure
+        .input(z.object({
+            id: z.string(),
+            expiresAt: z.number().int().nullable(),
+        }))
+        .mutation(async ({ input, ctx }) => {
+            const response = await fetch(`${BASE_URL}/v1/service-accounts/${input.id}/api-keys`, {
+                method: 'PUT',
+                headers: {
+                    ...createYakManAuthHeaders(ctx.accessToken),
+                    'Content-Type': 'application/json'
+                },
+                body: JSON.stringify({
+                    'expires_at': input.expiresAt
+                })
+            });
+            if (response.status != 200) {
+                throw new Error(await response.text())
+            }
+            const json = await response.json();
+
+            return json.api_key as string
+        }),
+});
@@ -1,7 +1,8 @@
 import { t } from "../t";
 import { createYakManAuthHeaders, getYakManBaseApiUrl } from "../helper";
 import { z } from "zod";
-import type { YakManUser } from "$lib/types/types";
+import { convertYakManErrorToTRPCError } from "$lib/utils/error-helpers";
+import { YakManRoleSchema, type YakManRole, type YakManUser } from "$lib/types/types";
 
 const BASE_URL = getYakManBaseApiUrl();
 
@@ -10,6 +11,22 @@ export type GetUserInfoResponse = {
     profile_picture: string | null,
     global_roles: string[],
     roles: { [key: string]: string },
+    totp_enabled: boolean,
+};
+
+export type YakManUserDetails = {
+    user_id: string,
+    global_roles: YakManRole[],
+    roles: { project_id: string, role: YakManRole }[],
+    team_ids: string[],
+    deactivated_at: number | null,
+};
+
+export type InviteUserResponse = {
+    user_id: string,
+    invitation: { id: string, user_id: string },
+    invitation_url: string | null,
+    email_sent: boolean,
 };
 
 export const users = t.router({
@@ -20,10 +37,21 @@ export const users = t.router({
             });
             return await response.json();
         }),
+    fetchUserDetails: t.procedure
+        .input(z.string())
+        .query(async ({ input, ctx }): Promise<YakManUserDetails> => {
+            const response = await fetch(`${BASE_URL}/v1/users/${input}`, {
+                headers: createYakManAuthHeaders(ctx.accessToken)
+            });
+            if (response.status != 200) {
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
+            }
+            return await response.json();
+        }),
     createUser: t.procedure
         .input(z.object({
             username: z.string(),
-            role: z.string()
+            role: z.string().nullable()
         }))
         .mutation(async ({ input, ctx }) => {
             const response = await fetch(`${BASE_URL}/v1/users`, {
@@ -41,6 +69,123 @@ export const users = t.router({
                 throw new Error(await response.text())
             }
         }),
+    inviteUser: t.procedure
+        .input(z.object({
+            email: z.string(),
+            role: z.string().nullable()
+        }))
+        .mutation(async ({ input, ctx }): Promise<InviteUserResponse> => {
+            const response = await fetch(`${BASE_URL}/v1/users/invite`, {
+                method: 'POST',
+                headers: {
+                    ...createYakManAuthHeaders(ctx.accessToken),
+                    'Content-Type': 'application/json'
+                },
+                body: JSON.stringify({
+                    'email': input.email,
+                    'role': input.role
+                })
+            });
+            if (response.status != 200) {
+                throw convertYakManErrorToTRPCError(await response.text(), response.status)
+            }
+            return await response.json();
+        }),
+    updateUserRoles: t.procedure
+        .input(z.object({
+            userId: z.string(),
+            globalRole: YakManRoleSchema.optional(),
+            roles: z.array(z.object({
+                projectId: z.string(),
+                role: YakManRoleSchema
+            }))
+        }))
+        .mutation(async ({ input, ctx }) => {
+            const response = await fetch(`${BASE_URL}/v1/users/${input.userId}/roles`, {
+                method: 'POST',
+                headers: {
+                    ...createYakManAuthHeaders(ctx.accessToken),
+                    'Content-Type': 'application/json'
+                },
+                body: JSON.stringify({
+                    global_roles: input.globalRole ? [input.globalRole] : [],
+                    roles: input.roles.map(role => ({
+                        project_id: role.projectId,
+                        role: role.role,
+                    }))
+                })
+            });
+            if (response.status != 200) {
+                throw new Error(await response.text())
+            }
+        }),
+    setUserDeactivated: t.procedure
+        .input(z.object({
+            userId: z.string(),
+            deactivated: z.boolean()
+        }))
+        .mutation(async ({ input, ctx }) => {
+            const action = input.deactivated ? 'deactivate' : 'reactivate';
+            const response = await fetch(`${BASE_URL}/v1/users/${input.userId}/${action}`, {
+                method: 'POST',
+                headers: createYakManAuthHeaders(ctx.accessToken)
+            });
+            if (response.status != 200) {
+                throw new Error(await response.text())
+            }
+        }),
+    revokeUserSessions: t.procedure
+        .input(z.object({
+            userId: z.string()
+        }))
+        .mutation(async ({ input, ctx }) => {
+            const response = await fetch(`${BASE_URL}/v1/users/${input.userId}/revoke-sessions`, {
+                method: 'POST',
+                headers: createYakManAuthHeaders(ctx.accessToken)
+            });
+            if (response.status != 200) {
+                throw new Error(await response.text())
+            }
+        }),
+    unlockUser: t.procedure
+        .input(z.object({
+            userId: z.string()
+        }))
+        .mutation(async ({ input, ctx }) => {
+            const response = await fetch(`${BASE_URL}/v1/users/${input.userId}/unlock`, {
+                method: 'POST',
+                headers: createYakManAuthHeaders(ctx.accessToken)
+            });
+            if (response.status != 200) {
+                throw new Error(await response.text())
+            }
+        }),
+    resetUserTotp: t.procedure
+        .input(z.object({
+            userId: z.string()
+        }))
+        .mutation(async ({ input, ctx }) => {
+            const response = await fetch(`${BASE_URL}/v1/users/${input.userId}/reset-totp`, {
+                method: 'POST',
+                headers: createYakManAuthHeaders(ctx.accessToken)
+            });
+            if (response.status != 200) {
+                throw new Error(await response.text())
+            }
+        }),
+    deleteUser: t.procedure
+        .input(z.object({
+            userId: z.string()
+        }))
+        .mutation(async ({ input, ctx }) => {
+            const response = await fetch(`${BASE_URL}/v1/users/${input.userId}`, {
+                method: 'DELETE',
+                headers: createYakManAuthHeaders(ctx.accessToken)
+            });
+            if (response.status != 200) {
+                throw new Error(await response.text())
+            }
+        }),
     fetchUserInfo: t.procedure
         .query(async ({ ctx }) => {
             const response = await fetch(`${BASE_URL}/v1/user-info`, {
@@ -7,16 +7,52 @@ export const YakManProjectSchema = z.object({
 
 export type YakManProject = z.infer<typeof YakManProjectSchema>;
 
+export const YakManApprovalPolicySchema = z.object({
+    required_approvals: z.number().int(),
+    label_rules: z.array(z.object({
+        label_id: z.string(),
+        value: z.string(),
+        required_approvals: z.number().int(),
+    })),
+    forbid_self_approval: z.boolean(),
+    require_change_description: z.boolean().optional(),
+});
+
+export type YakManApprovalPolicy = z.infer<typeof YakManApprovalPolicySchema>;
+
+export const YakManFreezeWindowSchema = z.object({
+    reason: z.string(),
+    schedule: z.object({
+        Range: z.object({
+            start_ms: z.number().int(),
+            end_ms: z.number().int(),
+        }).optional(),
+        Cron: z.object({
+            expression: z.string(),
+            duration_minutes: z.number().int(),
+        }).optional(),
+    }),
+});
+
+export type YakManFreezeWindow = z.infer<typeof YakManFreezeWindowSchema>;
+
 export const YakManProjectDetailsSchema = z.object({
     id: z.string(),
     name: z.string(),
-    notification_settings: z.object({
+    notification_settings: z.array(z.object({
         settings: z.object({
             Slack: z.object({
                 webhook_url: z.string()
             }).optional(),
             Discord: z.object({
                 webhook_url: z.string()
+            }).optional(),
+            MicrosoftTeams: z.object({
+                webhook_url: z.string()
+            }).optional(),
+            Email: z.object({
+                recipients: z.array(z.string()),
+                send_to_project_approvers: z.boolean()
             }).optional()
         }),
         events: z.object({
@@ -24,9 +60,16 @@ export const YakManProjectDetailsSchema = z.object({
             is_instance_created_enabled: z.boolean(),
             is_revision_submitted_enabled: z.boolean(),
             is_revision_approved_enabled: z.boolean(),
-            is_revision_reject_enabled: z.boolean()
+            is_revision_reject_enabled: z.boolean(),
+            is_revision_rollback_enabled: z.boolean(),
+            is_instance_deleted_enabled: z.boolean(),
+            is_config_deleted_enabled: z.boolean(),
+            is_project_settings_updated_enabled: z.boolean(),
+            is_api_key_created_enabled: z.boolean()
         })
-    }).optional()
+    })),
+    approval_policy: YakManApprovalPolicySchema,
+    freeze_windows: z.array(YakManFreezeWindowSchema),
 });
 
 export type YakManProjectDetails = z.infer<typeof YakManProjectDetailsSchema>;
@@ -80,7 +123,21 @@ export const ConfigInstanceEventSchema = z.object({
     NewRevisionRejected: z.object({
         new_revision: z.string(),
         rejected_by_user_id: z.string(),
-    }).optional()
+        reason: z.string().nullable().optional(),
+    }).optional(),
+    ApplyScheduled: z.object({
+        new_revision: z.string(),
+        apply_at_ms: z.number().int(),
+        scheduled_by_user_id: z.string(),
+    }).optional(),
+    ScheduledApplyCancelled: z.object({
+        new_revision: z.string(),
+        cancelled_by_user_id: z.string(),
+    }).optional(),
+    ScheduledApplyFailed: z.object({
+        new_revision: z.string(),
+        reason: z.string(),
+    }).optional(),
 });
 
 export type ConfigInstanceEvent = z.infer<typeof ConfigInstanceEventSchema>;
@@ -97,6 +154,34 @@ export const YakManConfigInstanceSchema = z.object({
 
 export type YakManConfigInstance = z.infer<typeof YakManConfigInstanceSchema>;
 
+export const YakManScheduledApplySchema = z.object({
+    config_id: z.string(),
+    instance: z.string(),
+    revision: z.string(),
+    apply_at_ms: z.number().int(),
+    scheduled_by_user_id: z.string(),
+    scheduled_at_ms: z.number().int(),
+});
+
+export type YakManScheduledApply = z.infer<typeof YakManScheduledApplySchema>;
+
+export const RevisionCommentSchema = z.object({
+    id: z.string(),
+    user_id: z.string(),
+    timestamp_ms: z.number().int(),
+    message: z.string(),
+    kind: z.enum(['Comment', 'Approval', 'Rejection']),
+});
+
+export type RevisionComment = z.infer<typeof RevisionCommentSchema>;
+
+export const RevisionChangeDetailsSchema = z.object({
+    description: z.string().nullable().optional(),
+    ticket: z.string().nullable().optional(),
+});
+
+export type RevisionChangeDetails = z.infer<typeof RevisionChangeDetailsSchema>;
+
 export const YakManInstanceRevisionSchema = z.object({
     revision: z.string(), // Unique key
     data_key: z.string(), // Key to fetch data
@@ -106,6 +191,12 @@ export const YakManInstanceRevisionSchema = z.object({
     reviewed_by_user_id: z.string().nullable(),
     review_timestamp_ms: z.number().int().nullable(),
     content_type: z.string(),
+    approvals: z.array(z.object({
+        user_id: z.string(),
+        timestamp_ms: z.number().int(),
+    })),
+    comments: z.array(RevisionCommentSchema),
+    change_details: RevisionChangeDetailsSchema,
 });
 
 export type YakManInstanceRevision = z.infer<typeof YakManInstanceRevisionSchema>;
@@ -118,20 +209,74 @@ export const YakManUserSchema = z.object({
     email: z.string(),
     id: z.string(),
     role: YakManRoleSchema,
+    deactivated_at: z.number().int().nullable().optional(),
 });
 
 export type YakManUser = z.infer<typeof YakManUserSchema>;
 
 export const YakManApiKeySchema = z.object({
     id: z.string(),
-    project_id: z.string(),
-    role: z.string(),
+    project_id: z.string().nullable().optional(),
+    role: z.string().nullable().optional(),
+    service_account_id: z.string().nullable().optional(),
     created_at: z.number().int(),
     created_by_user_id: z.string(),
+    expires_at: z.number().int().nullable().optional(),
+    last_used_at: z.number().int().nullable().optional(),
+    rotated_at: z.number().int().nullable().optional(),
+    previous_hash_expires_at: z.number().int().nullable().optional(),
+    scope: z.object({
+        operations: z.array(z.string()),
+        config_ids: z.array(z.string()),
+        label_selectors: z.array(z.object({
+            label_id: z.string(),
+            value: z.string()
+        }))
+    }).nullable().optional(),
 });
 
 export type YakManApiKey = z.infer<typeof YakManApiKeySchema>;
 
+export const YakManServiceAccountSchema = z.object({
+    id: z.string(),
+    name: z.string(),
+    global_roles: z.array(YakManRoleSchema),
+    roles: z.array(z.object({
+        project_id: z.string(),
+        role: YakManRoleSchema,
+    })),
+    team_ids: z.array(z.string()),
+    created_at: z.number().int(),
+    created_by_user_id: z.string(),
+});
+
+export type YakManServiceAccount = z.infer<typeof YakManServiceAccountSchema>;
+
+export const YakManAuditActionSchema = z.enum([
+    'Create', 'Update', 'Delete', 'Submit', 'Approve', 'Apply', 'Reject', 'Rollback',
+    'Deactivate', 'Reactivate', 'RevokeSessions', 'EnableTotp', 'DisableTotp',
+    'CreatePasswordResetLink', 'Invite', 'ResetPassword', 'ChangePassword', 'Unlock',
+    'ScheduleApply', 'CancelScheduledApply', 'BreakGlass', 'Comment'
+]);
+
+export const YakManAuditTargetTypeSchema = z.enum([
+    'Project', 'Label', 'Config', 'ConfigInstance', 'Revision', 'User', 'Team',
+    'ServiceAccount', 'ApiKey', 'LoginAttempts'
+]);
+
+export const YakManAuditLogEntrySchema = z.object({
+    id: z.string(),
+    timestamp_ms: z.number().int(),
+    actor: z.string().nullable(),
+    action: YakManAuditActionSchema,
+    target_type: YakManAuditTargetTypeSchema,
+    target_id: z.string(),
+    before: z.any().nullable(),
+    after: z.any().nullable(),
+});
+
+export type YakManAuditLogEntry = z.infer<typeof YakManAuditLogEntrySchema>;
+
 export const YakManTeamSchema = z.object({
     id: z.string(),
     name: z.string(),
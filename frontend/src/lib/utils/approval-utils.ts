@@ -0,0 +1,37 @@
+import type {
+    YakManApprovalPolicy,
+    YakManInstanceRevision,
+    YakManLabel,
+} from "$lib/types/types";
+
+/**
+ * The number of approvals a revision with the given labels needs before it can be applied.
+ * Mirrors `YakManApprovalPolicy::required_approvals_for` in the backend.
+ */
+export function getRequiredApprovals(
+    policy: YakManApprovalPolicy | undefined,
+    labels: YakManLabel[],
+): number {
+    if (!policy) {
+        return 1;
+    }
+
+    let required = policy.required_approvals;
+    for (const rule of policy.label_rules) {
+        const matches = labels.some(
+            (label) => label.label_id === rule.label_id && label.value === rule.value,
+        );
+        if (matches) {
+            required = Math.max(required, rule.required_approvals);
+        }
+    }
+    return Math.max(required, 1);
+}
+
+/** Revisions approved before approvals were tracked count their reviewer as the only approval */
+export function getApprovalCount(revision: YakManInstanceRevision): number {
+    if (revision.approvals.length === 0) {
+        return revision.review_state === "Approved" && revision.reviewed_by_user_id ? 1 : 0;
+    }
+    return revision.approvals.length;
+}
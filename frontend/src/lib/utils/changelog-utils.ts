@@ -6,6 +6,9 @@ export type ChangelogEventType =
     | "SUBMITTED"
     | "APPROVED"
     | "REJECTED"
+    | "APPLY_SCHEDULED"
+    | "SCHEDULED_APPLY_CANCELLED"
+    | "SCHEDULED_APPLY_FAILED"
     | "UNKNOWN";
 
 export function getEventType(change: ConfigInstanceEvent): ChangelogEventType {
@@ -19,6 +22,12 @@ export function getEventType(change: ConfigInstanceEvent): ChangelogEventType {
         return "APPROVED";
     } else if (change.NewRevisionRejected) {
         return "REJECTED";
+    } else if (change.ApplyScheduled) {
+        return "APPLY_SCHEDULED";
+    } else if (change.ScheduledApplyCancelled) {
+        return "SCHEDULED_APPLY_CANCELLED";
+    } else if (change.ScheduledApplyFailed) {
+        return "SCHEDULED_APPLY_FAILED";
     } else {
         return "UNKNOWN";
     }
@@ -36,6 +45,12 @@ export function getEventName(type: ChangelogEventType) {
             return "Revision Approved";
         case "REJECTED":
             return "Revision Rejected";
+        case "APPLY_SCHEDULED":
+            return "Apply Scheduled";
+        case "SCHEDULED_APPLY_CANCELLED":
+            return "Scheduled Apply Cancelled";
+        case "SCHEDULED_APPLY_FAILED":
+            return "Scheduled Apply Failed";
         case "UNKNOWN":
             return "Unknown";
     }
@@ -6,12 +6,13 @@ import { hasRoles } from "$lib/utils/role-utils";
 const noRoles: GetUserInfoResponse = {
     global_roles: [],
     roles: {},
-    profile_picture: null
+    profile_picture: null,
+    totp_enabled: false,
 }
 
 export const load: LayoutServerLoad = async (event) => {
     const route = event.route.id;
-    if (route === '/login') {
+    if (route === '/login' || route === '/login/totp') {
         return { userRoles: noRoles };
     }
 
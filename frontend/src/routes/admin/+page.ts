@@ -4,9 +4,14 @@ import type { PageLoad } from "../$types";
 type ApiKeyTableRow = {
     id: string,
     projectName: string,
+    serviceAccountName: string | null,
     role: string,
     createdAt: Date,
-    createdBy: string
+    createdBy: string,
+    expiresAt: Date | null,
+    lastUsedAt: Date | null,
+    isExpired: boolean,
+    isScoped: boolean
 }
 
 export const load: PageLoad = async (event) => {
@@ -16,14 +21,20 @@ export const load: PageLoad = async (event) => {
     const apiKeys = await t.apiKeys.fetchApiKeys.query();
     const projects = await t.projects.fetchProjects.query();
     const settings = await t.lifecycle.fetchYakmanSettings.query();
+    const serviceAccounts = await t.serviceAccounts.fetchServiceAccounts.query();
 
     const apiKeyTableRows = apiKeys.map(key => {
         return {
             id: key.id,
             projectName: projects.find(p => p.id === key.project_id)?.name,
-            role: key.role,
+            serviceAccountName: serviceAccounts.find(sa => sa.id === key.service_account_id)?.name ?? null,
+            role: key.role ?? "",
             createdAt: new Date(key.created_at),
-            createdBy: users.find(u => u.id === key.created_by_user_id)?.email
+            createdBy: users.find(u => u.id === key.created_by_user_id)?.email,
+            expiresAt: key.expires_at ? new Date(key.expires_at) : null,
+            lastUsedAt: key.last_used_at ? new Date(key.last_used_at) : null,
+            isExpired: !!key.expires_at && key.expires_at <= Date.now(),
+            isScoped: !!key.scope
         } as ApiKeyTableRow
     });
 
@@ -31,14 +42,15 @@ export const load: PageLoad = async (event) => {
         users: users,
         apiKeyTableRows: apiKeyTableRows,
         projects: projects,
+        serviceAccounts: serviceAccounts,
         tab: getTab(event.url.searchParams),
         settings
     }
 };
 
-function getTab(searchParams: URLSearchParams): "Users" | "Api Keys" | null {
+function getTab(searchParams: URLSearchParams): "Users" | "Api Keys" | "Service Accounts" | "Audit Log" | null {
     let tab = searchParams.get('tab');
-    if (["Users", "Api Keys"].includes(tab as string))
-        return tab as "Users" | "Api Keys"
+    if (["Users", "Api Keys", "Service Accounts", "Audit Log"].includes(tab as string))
+        return tab as "Users" | "Api Keys" | "Service Accounts" | "Audit Log"
     return null;
 }
\ No newline at end of file
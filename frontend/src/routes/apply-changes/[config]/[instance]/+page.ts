@@ -1,5 +1,6 @@
 import { trpc } from "$lib/trpc/client";
 import type { YakManConfigInstance } from "$lib/types/types";
+import { getRequiredApprovals } from "$lib/utils/approval-utils";
 import type { PageLoad } from "./$types";
 
 export const load: PageLoad = async (event) => {
@@ -12,6 +13,10 @@ export const load: PageLoad = async (event) => {
 
     const instances = await trpc(event).instances.fetchInstancesByConfigId.query(config);
 
+    const configs = await trpc(event).configs.fetchConfigs.query();
+    const projectId = configs.find(c => c.id === config)?.project_id;
+    const project = projectId ? await trpc(event).projects.fetchProject.query(projectId) : null;
+
     let instance: YakManConfigInstance | null = null;
     let currentData: { data: string; contentType: string; } | null = null;
     let pendingData: { data: string; contentType: string; } | null = null;
@@ -44,8 +49,33 @@ export const load: PageLoad = async (event) => {
         }
     }
 
+    const scheduledApply = await trpc(event).revisions.fetchScheduledApply.query({
+        configId: config,
+        instance: instanceId,
+    });
+
+    // Fetch the emails of the users that commented on the pending revision
+    const users = new Map<string, string>();
+    if (pendingRevision && pendingRevision.comments.length > 0) {
+        try {
+            const userIds = new Set(pendingRevision.comments.map(comment => comment.user_id));
+            const allUsers = await trpc(event).users.fetchUsers.query();
+            for (const user of allUsers) {
+                if (userIds.has(user.id)) {
+                    users.set(user.id, user.email);
+                }
+            }
+        } catch {
+            console.warn('Failed to load users, this probably means the users is not an admin')
+        }
+    }
+
     return {
+        projectId: projectId,
         pendingRevision: pendingRevision,
+        scheduledApply: scheduledApply,
+        users: users,
+        requiredApprovals: getRequiredApprovals(project?.approval_policy, pendingRevision?.labels ?? []),
         currentData: currentData,
         pendingData: pendingData,
     };
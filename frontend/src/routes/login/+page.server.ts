@@ -9,8 +9,14 @@ type LoginResponse = {
     refresh_token: string | null,
 }
 
+type TwoFactorChallengeResponse = {
+    two_factor_required: true,
+    two_factor_token: string,
+    enrollment_required: boolean,
+}
+
 export const actions = {
-    default: async ({ request, cookies }) => {
+    default: async ({ request, cookies, getClientAddress }) => {
         const data = await request.formData();
         const username = data.get('username');
         const password = data.get('password');
@@ -18,7 +24,9 @@ export const actions = {
         const response = await fetch(`${BASE_URL}/auth/login`, {
             method: 'POST',
             headers: {
-                'Content-Type': 'application/x-www-form-urlencoded'
+                'Content-Type': 'application/x-www-form-urlencoded',
+                // Used by the backend to throttle failed logins per client (requires YAKMAN_TRUST_FORWARDED_FOR)
+                'X-Forwarded-For': getClientAddress()
             },
             body: `username=${username}&password=${password}`
         });
@@ -29,13 +37,33 @@ export const actions = {
             return;
         }
 
+        if (response.status == 429) {
+            const retryAfter = response.headers.get('Retry-After') ?? '60';
+            const error = encodeURI(`Too many failed login attempts, try again in ${retryAfter} seconds`)
+            redirect(303, `/login?error=${error}`)
+            return;
+        }
+
         if (response.status != 200) {
             const error = encodeURI('An error occured')
             redirect(303, `/login?error=${error}`)
             return;
         }
 
-        const { access_token, access_token_expire_timestamp, refresh_token }: LoginResponse = await response.json();
+        const body: LoginResponse | TwoFactorChallengeResponse = await response.json();
+
+        if ('two_factor_required' in body) {
+            // Short lived token proving the password was correct, exchanged for an access token on the next step
+            cookies.set('two_factor_token', body.two_factor_token, {
+                httpOnly: true,
+                path: '/login/totp',
+                maxAge: 5 * 60,
+            })
+            redirect(303, body.enrollment_required ? '/login/totp?enroll=true' : '/login/totp')
+            return;
+        }
+
+        const { access_token, access_token_expire_timestamp, refresh_token } = body;
 
         // todo: maybe refactor this to a utils class since they are also used in the oauth and refresh token flows
         cookies.set('access_token', access_token, {
@@ -0,0 +1,100 @@
+import { getYakManBaseApiUrl } from "$lib/trpc/helper";
+import { fail, redirect, type Actions } from "@sveltejs/kit";
+import type { PageServerLoad } from "./$types";
+
+const BASE_URL = getYakManBaseApiUrl()
+
+type TotpEnrollmentResponse = {
+    secret: string,
+    otpauth_url: string,
+}
+
+type TotpLoginResponse = {
+    access_token: string,
+    access_token_expire_timestamp: number,
+    refresh_token: string | null,
+    recovery_codes: string[] | null,
+}
+
+export const load: PageServerLoad = async ({ cookies, url }) => {
+    const twoFactorToken = cookies.get('two_factor_token');
+    if (!twoFactorToken) {
+        const error = encodeURI('Your login expired, please login again')
+        redirect(303, `/login?error=${error}`)
+    }
+
+    let enrollment: TotpEnrollmentResponse | null = null;
+    if (url.searchParams.get('enroll') === 'true') {
+        const response = await fetch(`${BASE_URL}/auth/login/totp/enroll`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ two_factor_token: twoFactorToken })
+        });
+        if (response.status != 200) {
+            const error = encodeURI('Failed to setup two factor authentication')
+            redirect(303, `/login?error=${error}`)
+        }
+        enrollment = await response.json();
+    }
+
+    return {
+        enrollment: enrollment,
+    };
+}
+
+export const actions = {
+    default: async ({ request, cookies, getClientAddress }) => {
+        const data = await request.formData();
+        const code = data.get('code');
+        const twoFactorToken = cookies.get('two_factor_token');
+
+        const response = await fetch(`${BASE_URL}/auth/login/totp`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+                // Used by the backend to throttle failed logins per client (requires YAKMAN_TRUST_FORWARDED_FOR)
+                'X-Forwarded-For': getClientAddress()
+            },
+            body: JSON.stringify({ two_factor_token: twoFactorToken, code: code })
+        });
+
+        if (response.status == 401) {
+            return fail(401, { error: 'The code was not correct' });
+        }
+
+        if (response.status == 429) {
+            const retryAfter = response.headers.get('Retry-After') ?? '60';
+            return fail(429, { error: `Too many failed login attempts, try again in ${retryAfter} seconds` });
+        }
+
+        if (response.status != 200) {
+            return fail(response.status, { error: 'An error occured' });
+        }
+
+        const { access_token, access_token_expire_timestamp, refresh_token, recovery_codes }: TotpLoginResponse = await response.json();
+
+        cookies.delete('two_factor_token', { path: '/login/totp' });
+        cookies.set('access_token', access_token, {
+            httpOnly: true,
+            path: '/',
+            maxAge: access_token_expire_timestamp,
+        })
+
+        if (refresh_token) {
+            cookies.set('refresh_token', refresh_token, {
+                httpOnly: true,
+                path: '/session',
+                maxAge: Date.now() + (1000 * 60 * 60 * 24 * 356) // TODO: Dynamically set from metadata
+            })
+        }
+
+        if (recovery_codes) {
+            // Shown once so the user can save them before continuing
+            return { recoveryCodes: recovery_codes };
+        }
+
+        redirect(303, '/')
+    }
+} satisfies Actions;
@@ -8,8 +8,10 @@ export const load: PageLoad = async (event) => {
     if (id) {
         project = await trpc(event).projects.fetchProject.query(id);
     }
+    const labels = await trpc(event).labels.fetchLabels.query();
 
     return {
-        project: project
+        project: project,
+        labels: labels,
     };
 };
@@ -1,7 +1,28 @@
 import type { RequestHandler } from './$types';
 import { json } from '@sveltejs/kit';
+import { getYakManBaseApiUrl } from '$lib/trpc/helper';
+
+const BASE_URL = getYakManBaseApiUrl()
 
 export const POST: RequestHandler = async function ({ cookies, fetch }) {
+    const refreshToken = cookies.get('refresh_token');
+
+    // Revoke the refresh token server side so this session cannot be refreshed
+    if (refreshToken) {
+        const response = await fetch(`${BASE_URL}/auth/logout`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json'
+            },
+            body: JSON.stringify({
+                refresh_token: refreshToken
+            })
+        });
+        if (response.status != 200) {
+            console.error('Failed to revoke refresh token', await response.text());
+        }
+    }
+
     cookies.delete('refresh_token', {
         path: '/session'
     })
@@ -5,6 +5,7 @@ import type { PageLoad } from "./$types";
 export const load: PageLoad = async (event) => {
     let id = event.url.searchParams.get('id') as string;
     let userId = event.url.searchParams.get('user_id') as string;
+    let isInvite = event.url.searchParams.get('invite') === 'true';
 
     const { valid } = await trpc(event).auth.validateResetPasswordLink.query({
         id,
@@ -14,6 +15,7 @@ export const load: PageLoad = async (event) => {
     return {
         id,
         userId,
-        isValidLink: valid
+        isValidLink: valid,
+        isInvite
     }
 };
\ No newline at end of file